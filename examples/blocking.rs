@@ -80,4 +80,11 @@ fn main() {
         )
         .unwrap();
     println!("{data:?}", data = &buf[..len]);
+
+    let result = interface.bulk_out_blocking(0x02, &[1, 2, 3, 4, 5], Duration::from_secs(1));
+    println!("{result:?}");
+
+    let mut buf = [0; 64];
+    let result = interface.bulk_in_blocking(0x81, &mut buf, Duration::from_secs(1));
+    println!("{result:?}");
 }
@@ -0,0 +1,107 @@
+//! Compare submitting bulk IN transfers with `RequestBuffer::new` and with
+//! `Queue::submit_pooled` on a virtual device.
+//!
+//! The virtual device completes transfers as they are submitted, so this
+//! measures the host-side cost per transfer without a bus in the way. The
+//! handler's response is one allocation per transfer either way. Run with
+//! `cargo run --release --example buffer_pool --features mock`.
+//!
+//! The first argument sets the transfer size, default 16384.
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use futures_lite::future::block_on;
+use nusb::{
+    mock::{MockDevice, MockHandler},
+    transfer::{Queue, RequestBuffer, TransferError},
+};
+
+/// Counts allocations made by the process.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Fills every IN transfer.
+struct Source;
+
+impl MockHandler for Source {
+    fn transfer_in(&self, _endpoint: u8, length: usize) -> Result<Vec<u8>, TransferError> {
+        Ok(vec![0xA5; length])
+    }
+}
+
+const DEPTH: usize = 8;
+const TRANSFERS: usize = 200_000;
+
+/// Keep `DEPTH` transfers pending until `TRANSFERS` have completed, and
+/// print the rate and allocations per transfer.
+fn run(
+    name: &str,
+    queue: &mut Queue<RequestBuffer>,
+    mut submit: impl FnMut(&mut Queue<RequestBuffer>),
+) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..TRANSFERS {
+        while queue.pending() < DEPTH {
+            submit(queue);
+        }
+        let completion = block_on(queue.next_complete());
+        completion.status.unwrap();
+        queue.recycle(completion.data);
+    }
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    println!(
+        "{name:>18}: {:>8.0} transfers/s, {:.2} allocations/transfer",
+        TRANSFERS as f64 / elapsed.as_secs_f64(),
+        allocations as f64 / TRANSFERS as f64,
+    );
+}
+
+fn main() {
+    let size = std::env::args()
+        .nth(1)
+        .map(|s| s.parse().expect("size should be a number"))
+        .unwrap_or(16384);
+
+    let connection = MockDevice::new(0x1234, 0x5678)
+        .configuration(&[
+            0x09, 0x02, 0x19, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32, // configuration
+            0x09, 0x04, 0x00, 0x00, 0x01, 0xFF, 0x00, 0x00, 0x00, // interface 0
+            0x07, 0x05, 0x81, 0x02, 0x00, 0x02, 0x00, // endpoint 0x81, bulk
+        ])
+        .handler(Source)
+        .connect();
+    let device = connection.device_info().open().unwrap();
+    let interface = device.claim_interface(0).unwrap();
+
+    println!("{TRANSFERS} transfers of {size} bytes, {DEPTH} pending");
+
+    let mut queue = interface.bulk_in_queue(0x81);
+    run("RequestBuffer::new", &mut queue, |q| {
+        q.submit(RequestBuffer::new(size));
+    });
+
+    let mut queue = interface.bulk_in_queue(0x81).with_buffer_pool(DEPTH, size);
+    run("submit_pooled", &mut queue, |q| {
+        q.submit_pooled();
+    });
+}
@@ -0,0 +1,38 @@
+//! Count reports per second from an interrupt IN endpoint.
+//!
+//! The first argument sets the stream's queue depth.
+use std::time::{Duration, Instant};
+
+use futures_lite::{future::block_on, StreamExt};
+
+fn main() {
+    env_logger::init();
+    let depth = std::env::args()
+        .nth(1)
+        .map(|s| s.parse().expect("depth should be a number"))
+        .unwrap_or(4);
+
+    let di = nusb::list_devices()
+        .unwrap()
+        .find(|d| d.vendor_id() == 0x59e3 && d.product_id() == 0x0a23)
+        .expect("device should be connected");
+
+    let device = di.open().unwrap();
+    let interface = device.claim_interface(0).unwrap();
+    let mut reports = interface.interrupt_in_stream(0x81, 64).with_depth(depth);
+
+    let mut count = 0;
+    let mut start = Instant::now();
+    while let Some(report) = block_on(reports.next()) {
+        if let Err(e) = report {
+            println!("{e}");
+            break;
+        }
+        count += 1;
+        if start.elapsed() >= Duration::from_secs(1) {
+            println!("{count} reports/s with depth {depth}");
+            count = 0;
+            start = Instant::now();
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Helpers for using `nusb` without an async runtime.
+//!
+//! The `*_blocking` methods on [`Device`][crate::Device] and
+//! [`Interface`][crate::Interface] submit a transfer and block the calling
+//! thread until it completes or times out. They are built on [`block_on`],
+//! which can also be used directly to wait on any other future returned by
+//! this crate, such as [`Queue::next_complete`][crate::transfer::Queue::next_complete].
+
+use std::{
+    any::Any,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Block the current thread until the future completes.
+///
+/// The thread is parked between polls, and unparked by the waker when the
+/// platform event loop signals transfer completion.
+///
+/// ### Example
+///
+/// ```no_run
+/// use nusb::{blocking::block_on, transfer::RequestBuffer};
+/// # let di = nusb::list_devices().unwrap().next().unwrap();
+/// # let device = di.open().unwrap();
+/// # let interface = device.claim_interface(0).unwrap();
+/// let mut queue = interface.bulk_in_queue(0x81);
+/// queue.submit(RequestBuffer::new(64));
+/// let completion = block_on(queue.next_complete());
+/// ```
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+struct Unblock<T> {
+    result: Option<Result<T, Box<dyn Any + Send>>>,
+    waker: Option<Waker>,
+}
+
+/// Run the blocking function `f` on a new thread, returning a future that
+/// completes with its result.
+///
+/// This keeps an async executor responsive while `f` is running. A panic in
+/// `f` is propagated to the task awaiting the future.
+pub(crate) fn unblock<T, F>(f: F) -> impl Future<Output = T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let shared = Arc::new(Mutex::new(Unblock {
+        result: None,
+        waker: None,
+    }));
+
+    thread::spawn({
+        let shared = shared.clone();
+        move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f));
+            let mut s = shared.lock().unwrap();
+            s.result = Some(result);
+            if let Some(waker) = s.waker.take() {
+                waker.wake();
+            }
+        }
+    });
+
+    std::future::poll_fn(move |cx| {
+        let mut s = shared.lock().unwrap();
+        match s.result.take() {
+            Some(Ok(v)) => Poll::Ready(v),
+            Some(Err(payload)) => panic::resume_unwind(payload),
+            None => {
+                s.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    })
+}
+
+#[test]
+fn test_block_on_wake_from_thread() {
+    use std::sync::Mutex;
+
+    struct Shared {
+        done: bool,
+        waker: Option<Waker>,
+    }
+
+    let shared = Arc::new(Mutex::new(Shared {
+        done: false,
+        waker: None,
+    }));
+
+    let fut = std::future::poll_fn({
+        let shared = shared.clone();
+        move |cx| {
+            let mut s = shared.lock().unwrap();
+            if s.done {
+                Poll::Ready(42)
+            } else {
+                s.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    });
+
+    let t = thread::spawn(move || loop {
+        let mut s = shared.lock().unwrap();
+        if let Some(waker) = s.waker.take() {
+            s.done = true;
+            waker.wake();
+            break;
+        }
+        drop(s);
+        thread::yield_now();
+    });
+
+    assert_eq!(block_on(fut), 42);
+    t.join().unwrap();
+}
+
+#[test]
+fn test_unblock() {
+    assert_eq!(block_on(unblock(|| 6 * 7)), 42);
+}
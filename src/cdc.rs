@@ -0,0 +1,279 @@
+//! Class requests for CDC-ACM serial adapters.
+//!
+//! Devices implementing the Communications Device Class Abstract Control
+//! Model, such as most USB serial adapters and microcontroller USB serial
+//! ports, are configured with class requests sent to their communications
+//! interface. [`Acm`] wraps a claimed communications interface to send them,
+//! while the data itself is sent on the bulk endpoints of the associated
+//! data interface.
+//!
+//! ### Example
+//!
+//! ```no_run
+//! use nusb::cdc::{Acm, Parity, StopBits};
+//! let device = nusb::list_devices().unwrap()
+//!     .find(|dev| dev.vendor_id() == 0xAAAA && dev.product_id() == 0xBBBB)
+//!     .expect("device not connected")
+//!     .open()
+//!     .expect("failed to open device");
+//! let acm = Acm::new(device.claim_interface(0).unwrap());
+//! acm.set_line_coding(115200, StopBits::One, Parity::None, 8).unwrap();
+//! acm.set_control_line_state(true, true).unwrap();
+//! ```
+
+use std::{io::ErrorKind, time::Duration};
+
+use crate::{
+    transfer::{Control, ControlType, Recipient},
+    Error, Interface,
+};
+
+// Class requests from the CDC PSTN subclass specification, section 6.3.
+const REQUEST_SET_LINE_CODING: u8 = 0x20;
+const REQUEST_GET_LINE_CODING: u8 = 0x21;
+const REQUEST_SET_CONTROL_LINE_STATE: u8 = 0x22;
+
+const LINE_CODING_LEN: usize = 7;
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A CDC-ACM communications interface, used to configure the serial line.
+pub struct Acm {
+    interface: Interface,
+}
+
+impl Acm {
+    /// Wrap a claimed CDC-ACM communications interface.
+    ///
+    /// This interface has class `0x02` and subclass `0x02`. The class
+    /// requests are addressed to it, not to the data interface.
+    pub fn new(interface: Interface) -> Acm {
+        Acm { interface }
+    }
+
+    /// Get the wrapped interface.
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    /// Consume the wrapper and return the interface.
+    pub fn into_interface(self) -> Interface {
+        self.interface
+    }
+
+    /// Set the baud rate and character format with the `SET_LINE_CODING`
+    /// request.
+    ///
+    /// `data_bits` must be 5, 6, 7, 8 or 16, and `baud_rate` must not be
+    /// zero. Other values return an error of kind
+    /// [`InvalidInput`][ErrorKind::InvalidInput] without sending the request.
+    #[doc(alias = "SET_LINE_CODING")]
+    pub fn set_line_coding(
+        &self,
+        baud_rate: u32,
+        stop_bits: StopBits,
+        parity: Parity,
+        data_bits: u8,
+    ) -> Result<(), Error> {
+        let coding = LineCoding {
+            baud_rate,
+            stop_bits,
+            parity,
+            data_bits,
+        };
+        coding.validate()?;
+        self.interface.control_out_blocking(
+            self.control(REQUEST_SET_LINE_CODING, 0),
+            &coding.to_bytes(),
+            TIMEOUT,
+        )?;
+        Ok(())
+    }
+
+    /// Read the current baud rate and character format with the
+    /// `GET_LINE_CODING` request.
+    #[doc(alias = "GET_LINE_CODING")]
+    pub fn line_coding(&self) -> Result<LineCoding, Error> {
+        let mut buf = [0; LINE_CODING_LEN];
+        let len = self.interface.control_in_blocking(
+            self.control(REQUEST_GET_LINE_CODING, 0),
+            &mut buf,
+            TIMEOUT,
+        )?;
+        if len < LINE_CODING_LEN {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "device returned short line coding",
+            ));
+        }
+        LineCoding::from_bytes(buf)
+    }
+
+    /// Set the DTR and RTS control signals with the
+    /// `SET_CONTROL_LINE_STATE` request.
+    ///
+    /// Many devices treat asserting DTR as the host opening the port, and
+    /// don't send data until it is set.
+    #[doc(alias = "SET_CONTROL_LINE_STATE")]
+    pub fn set_control_line_state(&self, dtr: bool, rts: bool) -> Result<(), Error> {
+        let value = u16::from(dtr) | u16::from(rts) << 1;
+        self.interface.control_out_blocking(
+            self.control(REQUEST_SET_CONTROL_LINE_STATE, value),
+            &[],
+            TIMEOUT,
+        )?;
+        Ok(())
+    }
+
+    fn control(&self, request: u8, value: u16) -> Control {
+        Control {
+            control_type: ControlType::Class,
+            recipient: Recipient::Interface,
+            request,
+            value,
+            index: self.interface.interface_number().into(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Acm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Acm")
+            .field("interface", &self.interface.interface_number())
+            .finish()
+    }
+}
+
+/// Number of stop bits, the `bCharFormat` field of the line coding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum StopBits {
+    /// 1 stop bit.
+    One = 0,
+
+    /// 1.5 stop bits.
+    OneAndHalf = 1,
+
+    /// 2 stop bits.
+    Two = 2,
+}
+
+/// Parity, the `bParityType` field of the line coding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Parity {
+    /// No parity bit.
+    None = 0,
+
+    /// Odd parity.
+    Odd = 1,
+
+    /// Even parity.
+    Even = 2,
+
+    /// Parity bit always set.
+    Mark = 3,
+
+    /// Parity bit always clear.
+    Space = 4,
+}
+
+/// Baud rate and character format of a CDC-ACM serial line, returned by
+/// [`Acm::line_coding`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LineCoding {
+    /// Data terminal rate in bits per second, `dwDTERate`.
+    pub baud_rate: u32,
+
+    /// Number of stop bits.
+    pub stop_bits: StopBits,
+
+    /// Parity.
+    pub parity: Parity,
+
+    /// Number of data bits, `bDataBits`.
+    pub data_bits: u8,
+}
+
+impl LineCoding {
+    fn validate(&self) -> Result<(), Error> {
+        if self.baud_rate == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "baud rate must not be zero",
+            ));
+        }
+        if !matches!(self.data_bits, 5..=8 | 16) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "data bits must be 5, 6, 7, 8 or 16",
+            ));
+        }
+        Ok(())
+    }
+
+    fn to_bytes(self) -> [u8; LINE_CODING_LEN] {
+        let [b0, b1, b2, b3] = self.baud_rate.to_le_bytes();
+        [
+            b0,
+            b1,
+            b2,
+            b3,
+            self.stop_bits as u8,
+            self.parity as u8,
+            self.data_bits,
+        ]
+    }
+
+    fn from_bytes(buf: [u8; LINE_CODING_LEN]) -> Result<LineCoding, Error> {
+        let invalid = |msg| Error::new(ErrorKind::InvalidData, msg);
+        let stop_bits = match buf[4] {
+            0 => StopBits::One,
+            1 => StopBits::OneAndHalf,
+            2 => StopBits::Two,
+            _ => return Err(invalid("invalid bCharFormat in line coding")),
+        };
+        let parity = match buf[5] {
+            0 => Parity::None,
+            1 => Parity::Odd,
+            2 => Parity::Even,
+            3 => Parity::Mark,
+            4 => Parity::Space,
+            _ => return Err(invalid("invalid bParityType in line coding")),
+        };
+        Ok(LineCoding {
+            baud_rate: u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]),
+            stop_bits,
+            parity,
+            data_bits: buf[6],
+        })
+    }
+}
+
+#[test]
+fn test_line_coding() {
+    let coding = LineCoding {
+        baud_rate: 115200,
+        stop_bits: StopBits::Two,
+        parity: Parity::Even,
+        data_bits: 7,
+    };
+    let bytes = coding.to_bytes();
+    assert_eq!(bytes, [0x00, 0xc2, 0x01, 0x00, 2, 2, 7]);
+    assert_eq!(LineCoding::from_bytes(bytes).unwrap(), coding);
+
+    assert!(coding.validate().is_ok());
+    assert!(LineCoding {
+        data_bits: 9,
+        ..coding
+    }
+    .validate()
+    .is_err());
+    assert!(LineCoding {
+        baud_rate: 0,
+        ..coding
+    }
+    .validate()
+    .is_err());
+    assert!(LineCoding::from_bytes([0, 0, 0, 0, 3, 0, 8]).is_err());
+}
@@ -5,7 +5,6 @@
 use std::{
     collections::BTreeMap,
     fmt::{Debug, Display},
-    io::ErrorKind,
     iter,
     ops::Deref,
 };
@@ -13,10 +12,12 @@ use std::{
 use log::warn;
 
 use crate::{
-    transfer::{Direction, EndpointType},
-    Error,
+    transfer::{endpoint_direction, Direction, EndpointType},
+    Error, Speed,
 };
 
+#[allow(dead_code)] // not used on all platforms
+pub(crate) const DESCRIPTOR_TYPE_DEVICE: u8 = 0x01;
 pub(crate) const DESCRIPTOR_LEN_DEVICE: u8 = 18;
 
 pub(crate) const DESCRIPTOR_TYPE_CONFIGURATION: u8 = 0x02;
@@ -28,8 +29,23 @@ pub(crate) const DESCRIPTOR_LEN_INTERFACE: u8 = 9;
 pub(crate) const DESCRIPTOR_TYPE_ENDPOINT: u8 = 0x05;
 pub(crate) const DESCRIPTOR_LEN_ENDPOINT: u8 = 7;
 
+pub(crate) const DESCRIPTOR_TYPE_SUPERSPEED_ENDPOINT_COMPANION: u8 = 0x30;
+pub(crate) const DESCRIPTOR_LEN_SUPERSPEED_ENDPOINT_COMPANION: u8 = 6;
+
 pub(crate) const DESCRIPTOR_TYPE_STRING: u8 = 0x03;
 
+pub(crate) const DESCRIPTOR_TYPE_INTERFACE_ASSOCIATION: u8 = 0x0B;
+pub(crate) const DESCRIPTOR_LEN_INTERFACE_ASSOCIATION: u8 = 8;
+
+pub(crate) const DESCRIPTOR_TYPE_DEVICE_QUALIFIER: u8 = 0x06;
+pub(crate) const DESCRIPTOR_LEN_DEVICE_QUALIFIER: u8 = 10;
+
+pub(crate) const DESCRIPTOR_TYPE_BOS: u8 = 0x0F;
+pub(crate) const DESCRIPTOR_LEN_BOS: u8 = 5;
+
+pub(crate) const DESCRIPTOR_TYPE_DEVICE_CAPABILITY: u8 = 0x10;
+pub(crate) const DESCRIPTOR_LEN_DEVICE_CAPABILITY: u8 = 3;
+
 /// USB defined language IDs for string descriptors.
 ///
 /// In practice, different language IDs are not used,
@@ -40,6 +56,28 @@ pub mod language_id {
     pub const US_ENGLISH: u16 = 0x0409;
 }
 
+/// USB defined device capability types found in the [BOS descriptor][BosDescriptor].
+pub mod capability_type {
+    /// Wireless USB
+    pub const WIRELESS_USB: u8 = 0x01;
+
+    /// USB 2.0 Extension, see [`Usb2Extension`][super::Usb2Extension]
+    pub const USB_2_0_EXTENSION: u8 = 0x02;
+
+    /// SuperSpeed USB, see [`SuperSpeedUsb`][super::SuperSpeedUsb]
+    pub const SUPERSPEED_USB: u8 = 0x03;
+
+    /// Container ID, see [`ContainerId`][super::ContainerId]
+    pub const CONTAINER_ID: u8 = 0x04;
+
+    /// Platform-specific capability identified by a UUID, see
+    /// [`MsOs20Platform`][super::MsOs20Platform] for the Microsoft OS 2.0 platform
+    pub const PLATFORM: u8 = 0x05;
+
+    /// SuperSpeedPlus USB
+    pub const SUPERSPEED_PLUS: u8 = 0x0A;
+}
+
 /// A raw USB descriptor.
 ///
 /// Wraps a byte slice to provide access to the bytes of a descriptor by implementing `Deref` to `[u8]`,
@@ -53,7 +91,7 @@ impl<'a> Descriptor<'a> {
     /// Returns `None` if
     ///   * the slice length is not at least 2.
     ///   * the `bLength` field (first byte) is greater than the slice length.
-    pub fn new(buf: &[u8]) -> Option<Descriptor> {
+    pub fn new(buf: &[u8]) -> Option<Descriptor<'_>> {
         if buf.len() >= 2 && buf.len() >= buf[0] as usize {
             Some(Descriptor(buf))
         } else {
@@ -182,7 +220,7 @@ macro_rules! descriptor_fields {
 
 pub(crate) fn validate_config_descriptor(buf: &[u8]) -> Option<usize> {
     if buf.len() < DESCRIPTOR_LEN_CONFIGURATION as usize {
-        if buf.len() != 0 {
+        if !buf.is_empty() {
             warn!(
                 "config descriptor buffer is {} bytes, need {}",
                 buf.len(),
@@ -231,7 +269,7 @@ impl<'a> Configuration<'a> {
     ///  * when the buffer is too short for a configuration descriptor
     ///  * when the bLength and wTotalLength fields are longer than the buffer
     ///  * when the first descriptor is not a configuration descriptor
-    pub fn new(buf: &[u8]) -> Configuration {
+    pub fn new(buf: &[u8]) -> Configuration<'_> {
         assert!(buf.len() >= DESCRIPTOR_LEN_CONFIGURATION as usize);
         assert!(buf[0] as usize >= DESCRIPTOR_LEN_CONFIGURATION as usize);
         assert!(buf[1] == DESCRIPTOR_TYPE_CONFIGURATION);
@@ -251,6 +289,21 @@ impl<'a> Configuration<'a> {
             .map(InterfaceAltSetting)
     }
 
+    /// Iterate the interface association descriptors (IADs) of this configuration.
+    ///
+    /// Composite devices use IADs to group consecutive interfaces that make
+    /// up a single function, such as the control and data interfaces of a
+    /// CDC-ACM serial port.
+    #[doc(alias = "IAD")]
+    pub fn interface_associations(&self) -> impl Iterator<Item = InterfaceAssociation<'a>> {
+        self.descriptors()
+            .filter(|d| {
+                d.descriptor_type() == DESCRIPTOR_TYPE_INTERFACE_ASSOCIATION
+                    && d.descriptor_len() >= DESCRIPTOR_LEN_INTERFACE_ASSOCIATION as usize
+            })
+            .map(|d| InterfaceAssociation(d.0))
+    }
+
     /// Iterate the interfaces of this configuration, grouping together alternate settings of the same interface.
     pub fn interfaces(&self) -> impl Iterator<Item = InterfaceGroup<'a>> {
         let mut interfaces = BTreeMap::new();
@@ -291,7 +344,9 @@ descriptor_fields! {
         #[doc(alias = "bmAttributes")]
         pub fn attributes at 7 -> u8;
 
-        /// `bMaxPower` descriptor field: Maximum power, in units of **2** milliamps.
+        /// `bMaxPower` descriptor field: Maximum power, in units of **2**
+        /// milliamps, or **8** milliamps when operating at SuperSpeed or
+        /// faster.
         #[doc(alias = "bMaxPower")]
         pub fn max_power at 8 -> u8;
     }
@@ -303,6 +358,23 @@ impl<'a> Configuration<'a> {
     pub fn string_index(&self) -> Option<u8> {
         Some(self.string_index_raw()).filter(|&i| i != 0)
     }
+
+    /// Maximum current drawn by the device in this configuration, in
+    /// milliamps.
+    ///
+    /// The unit of [`max_power`][Self::max_power] depends on the speed the
+    /// device is operating at: 8 mA for SuperSpeed and SuperSpeedPlus, and
+    /// 2 mA otherwise. Pass the speed from
+    /// [`DeviceInfo::speed`][crate::DeviceInfo::speed]. If the speed is not
+    /// known, the 2 mA unit is used.
+    #[doc(alias = "bMaxPower")]
+    pub fn max_power_milliamps(&self, speed: Option<Speed>) -> u16 {
+        let unit = match speed {
+            Some(Speed::Super | Speed::SuperPlus) => 8,
+            _ => 2,
+        };
+        u16::from(self.max_power()) * unit
+    }
 }
 
 struct DebugEntries<F>(F);
@@ -351,7 +423,7 @@ impl<'a> InterfaceGroup<'a> {
     }
 
     /// Iterator over alternate settings of the interface.
-    pub fn alt_settings(&self) -> impl Iterator<Item = InterfaceAltSetting> {
+    pub fn alt_settings(&self) -> impl Iterator<Item = InterfaceAltSetting<'_>> {
         self.interfaces.iter().cloned()
     }
 
@@ -363,6 +435,67 @@ impl<'a> InterfaceGroup<'a> {
     }
 }
 
+/// An interface association descriptor (IAD), grouping interfaces that make
+/// up a single function of a composite device.
+///
+/// Obtain these from [`Configuration::interface_associations`].
+#[derive(Clone)]
+#[doc(alias = "IAD")]
+pub struct InterfaceAssociation<'a>(&'a [u8]);
+
+descriptor_fields! {
+    impl<'a> InterfaceAssociation<'a> {
+        /// `bFirstInterface` descriptor field: Number of the first interface of the function.
+        #[doc(alias = "bFirstInterface")]
+        pub fn first_interface at 2 -> u8;
+
+        /// `bInterfaceCount` descriptor field: Number of consecutive interfaces in the function.
+        #[doc(alias = "bInterfaceCount")]
+        pub fn interface_count at 3 -> u8;
+
+        /// `bFunctionClass` descriptor field: Standard class of the function.
+        #[doc(alias = "bFunctionClass")]
+        pub fn function_class at 4 -> u8;
+
+        /// `bFunctionSubClass` descriptor field: Standard subclass of the function.
+        #[doc(alias = "bFunctionSubClass")]
+        pub fn function_subclass at 5 -> u8;
+
+        /// `bFunctionProtocol` descriptor field: Standard protocol of the function.
+        #[doc(alias = "bFunctionProtocol")]
+        pub fn function_protocol at 6 -> u8;
+
+        fn string_index_raw at 7 -> u8;
+    }
+}
+
+impl<'a> InterfaceAssociation<'a> {
+    /// Index of the string descriptor describing this function.
+    #[doc(alias = "iFunction")]
+    pub fn string_index(&self) -> Option<u8> {
+        Some(self.string_index_raw()).filter(|&i| i != 0)
+    }
+
+    /// Check whether `interface_number` is one of the interfaces of this function.
+    pub fn contains_interface(&self, interface_number: u8) -> bool {
+        let first = self.first_interface();
+        interface_number >= first && (interface_number - first) < self.interface_count()
+    }
+}
+
+impl<'a> Debug for InterfaceAssociation<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InterfaceAssociation")
+            .field("first_interface", &self.first_interface())
+            .field("interface_count", &self.interface_count())
+            .field("function_class", &self.function_class())
+            .field("function_subclass", &self.function_subclass())
+            .field("function_protocol", &self.function_protocol())
+            .field("string_index", &self.string_index())
+            .finish()
+    }
+}
+
 /// Information about a USB interface alternate setting, with access to associated endpoints and other descriptors.
 ///
 /// An interface descriptor represents a single alternate setting of
@@ -374,12 +507,12 @@ pub struct InterfaceAltSetting<'a>(&'a [u8]);
 impl<'a> InterfaceAltSetting<'a> {
     /// Get the interface descriptor followed by all trailing endpoint and other
     /// descriptors up to the next interface descriptor.
-    pub fn descriptors(&self) -> Descriptors {
+    pub fn descriptors(&self) -> Descriptors<'_> {
         Descriptors(self.0)
     }
 
     /// Get the endpoints of this interface.
-    pub fn endpoints(&self) -> impl Iterator<Item = Endpoint> {
+    pub fn endpoints(&self) -> impl Iterator<Item = Endpoint<'_>> {
         self.descriptors()
             .split_by_type(DESCRIPTOR_TYPE_ENDPOINT, DESCRIPTOR_LEN_ENDPOINT)
             .map(Endpoint)
@@ -444,20 +577,18 @@ impl<'a> Debug for InterfaceAltSetting<'a> {
 }
 
 /// Information about a USB endpoint, with access to any associated descriptors.
+#[derive(Clone)]
 pub struct Endpoint<'a>(&'a [u8]);
 
 impl<'a> Endpoint<'a> {
     /// Get the endpoint descriptor followed by all trailing descriptors up to the next endpoint or interface descriptor.
-    pub fn descriptors(&self) -> impl Iterator<Item = Descriptor> {
+    pub fn descriptors(&self) -> impl Iterator<Item = Descriptor<'_>> {
         Descriptors(self.0)
     }
 
     /// Get the endpoint's direction.
     pub fn direction(&self) -> Direction {
-        match self.address() & 0x80 {
-            0 => Direction::Out,
-            _ => Direction::In,
-        }
+        endpoint_direction(self.address())
     }
 
     /// Get the endpoint's transfer type.
@@ -480,6 +611,28 @@ impl<'a> Endpoint<'a> {
     pub fn packets_per_microframe(&self) -> u8 {
         ((self.max_packet_size_raw() >> 11) & 0b11) as u8 + 1
     }
+
+    /// Get the SuperSpeed endpoint companion descriptor following the endpoint descriptor.
+    ///
+    /// Only present when the device is operating at SuperSpeed or faster.
+    pub fn superspeed_companion(&self) -> Option<SuperSpeedEndpointCompanion<'_>> {
+        self.descriptors()
+            .skip(1)
+            .find(|d| {
+                d.descriptor_type() == DESCRIPTOR_TYPE_SUPERSPEED_ENDPOINT_COMPANION
+                    && d.descriptor_len() >= DESCRIPTOR_LEN_SUPERSPEED_ENDPOINT_COMPANION as usize
+            })
+            .map(|d| SuperSpeedEndpointCompanion(d.0))
+    }
+
+    /// Get the number of packets the endpoint can send or receive as part of a burst (1 to 16).
+    ///
+    /// This is 1 if there is no [SuperSpeed companion descriptor][Self::superspeed_companion],
+    /// as only SuperSpeed endpoints support bursts.
+    pub fn max_burst(&self) -> u8 {
+        self.superspeed_companion()
+            .map_or(1, |c| c.max_burst().min(15) + 1)
+    }
 }
 
 descriptor_fields! {
@@ -516,10 +669,979 @@ impl<'a> Debug for Endpoint<'a> {
             .field("max_packet_size", &self.max_packet_size())
             .field("packets_per_microframe", &self.packets_per_microframe())
             .field("interval", &self.interval())
+            .field("superspeed_companion", &self.superspeed_companion())
+            .finish()
+    }
+}
+
+/// SuperSpeed endpoint companion descriptor, describing additional properties
+/// of an endpoint of a device operating at SuperSpeed.
+///
+/// Obtain this from [`Endpoint::superspeed_companion`].
+#[derive(Clone)]
+pub struct SuperSpeedEndpointCompanion<'a>(&'a [u8]);
+
+descriptor_fields! {
+    impl<'a> SuperSpeedEndpointCompanion<'a> {
+        /// `bMaxBurst` descriptor field: Maximum number of packets in a burst, minus one.
+        ///
+        /// See [`Endpoint::max_burst`] for the number of packets.
+        #[doc(alias = "bMaxBurst")]
+        pub fn max_burst at 2 -> u8;
+
+        /// `bmAttributes` descriptor field: Maximum number of streams for bulk
+        /// endpoints, or `Mult` for isochronous endpoints.
+        #[doc(alias = "bmAttributes")]
+        pub fn attributes at 3 -> u8;
+
+        /// `wBytesPerInterval` descriptor field: Total bytes transferred every
+        /// service interval, for periodic endpoints.
+        #[doc(alias = "wBytesPerInterval")]
+        pub fn bytes_per_interval at 4 -> u16;
+    }
+}
+
+impl<'a> Debug for SuperSpeedEndpointCompanion<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SuperSpeedEndpointCompanion")
+            .field("max_burst", &self.max_burst())
+            .field("attributes", &self.attributes())
+            .field("bytes_per_interval", &self.bytes_per_interval())
+            .finish()
+    }
+}
+
+pub(crate) fn validate_bos_descriptor(buf: &[u8]) -> Option<usize> {
+    if buf.len() < DESCRIPTOR_LEN_BOS as usize {
+        warn!(
+            "BOS descriptor buffer is {} bytes, need {}",
+            buf.len(),
+            DESCRIPTOR_LEN_BOS
+        );
+        return None;
+    }
+
+    if buf[0] < DESCRIPTOR_LEN_BOS {
+        warn!("invalid BOS descriptor bLength");
+        return None;
+    }
+
+    if buf[1] != DESCRIPTOR_TYPE_BOS {
+        warn!("BOS bDescriptorType is {}, not a BOS descriptor", buf[1]);
+        return None;
+    }
+
+    let total_len = u16::from_le_bytes(buf[2..4].try_into().unwrap()) as usize;
+    if total_len < buf[0] as usize {
+        warn!("invalid BOS descriptor wTotalLength of {total_len}");
+        return None;
+    }
+
+    Some(total_len)
+}
+
+/// A descriptor parsed by [`ParsedDescriptors`] into a type-specific wrapper.
+///
+/// Each variant wraps the bytes of that descriptor only, not the
+/// descriptors that follow it. For example, [`InterfaceAltSetting::endpoints`]
+/// is empty on an `Interface` variant. Use [`Configuration`] to access
+/// descriptors grouped by interface and endpoint.
+#[derive(Clone, Debug)]
+pub enum ParsedDescriptor<'a> {
+    /// Configuration descriptor.
+    ///
+    /// Only the fields of the configuration descriptor itself are available:
+    /// it does not include the `wTotalLength` bytes that follow.
+    Configuration(ConfigurationFields<'a>),
+
+    /// Interface descriptor for one alternate setting.
+    Interface(InterfaceAltSetting<'a>),
+
+    /// Endpoint descriptor.
+    Endpoint(Endpoint<'a>),
+
+    /// Interface association descriptor.
+    InterfaceAssociation(InterfaceAssociation<'a>),
+
+    /// SuperSpeed endpoint companion descriptor.
+    SuperSpeedEndpointCompanion(SuperSpeedEndpointCompanion<'a>),
+
+    /// Descriptor of any other type, or a known type that is shorter than the
+    /// minimum length for that type.
+    Unknown {
+        /// `bDescriptorType` field.
+        descriptor_type: u8,
+
+        /// Bytes of the descriptor, including the `bLength` and `bDescriptorType` fields.
+        bytes: &'a [u8],
+    },
+}
+
+/// Fields of a configuration descriptor, without the descriptors that follow it.
+///
+/// Yielded by [`ParsedDescriptors`] as [`ParsedDescriptor::Configuration`].
+#[derive(Clone)]
+pub struct ConfigurationFields<'a>(&'a [u8]);
+
+descriptor_fields! {
+    impl<'a> ConfigurationFields<'a> {
+        /// `wTotalLength` descriptor field: Length of the configuration descriptor and all following descriptors.
+        #[doc(alias = "wTotalLength")]
+        pub fn total_len at 2 -> u16;
+
+        /// `bNumInterfaces` descriptor field: Number of interfaces
+        #[doc(alias = "bNumInterfaces")]
+        pub fn num_interfaces at 4 -> u8;
+
+        /// `bConfigurationValue` descriptor field: Identifier for the configuration.
+        #[doc(alias = "bConfigurationValue")]
+        pub fn configuration_value at 5 -> u8;
+
+        /// `bmAttributes` descriptor field: Bitmap of configuration attributes.
+        #[doc(alias = "bmAttributes")]
+        pub fn attributes at 7 -> u8;
+
+        /// `bMaxPower` descriptor field: Maximum power, in units of **2**
+        /// milliamps, or **8** milliamps when operating at SuperSpeed or
+        /// faster.
+        #[doc(alias = "bMaxPower")]
+        pub fn max_power at 8 -> u8;
+    }
+}
+
+impl<'a> Debug for ConfigurationFields<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigurationFields")
+            .field("total_len", &self.total_len())
+            .field("num_interfaces", &self.num_interfaces())
+            .field("configuration_value", &self.configuration_value())
+            .field("attributes", &self.attributes())
+            .field("max_power", &self.max_power())
+            .finish()
+    }
+}
+
+/// An iterator that walks a buffer of concatenated descriptors, such as a
+/// configuration descriptor read from a device, yielding each as a
+/// [`ParsedDescriptor`].
+///
+/// Unlike [`Configuration::new`], this does not require the buffer to be a
+/// complete, valid configuration descriptor. Iteration stops at the first
+/// descriptor whose `bLength` is invalid or runs past the end of the buffer,
+/// and [`remaining`][Self::remaining] returns the bytes that were not parsed.
+///
+/// ### Example
+///
+/// ```
+/// use nusb::descriptors::{ParsedDescriptor, ParsedDescriptors};
+/// let buf = [
+///     0x09, 0x02, 0x19, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32,
+///     0x09, 0x04, 0x00, 0x00, 0x01, 0xFF, 0x00, 0x00, 0x00,
+///     0x07, 0x05, 0x81, 0x02, 0x40, 0x00, // truncated
+/// ];
+/// let mut parser = ParsedDescriptors::new(&buf);
+/// assert!(matches!(parser.next(), Some(ParsedDescriptor::Configuration(_))));
+/// assert!(matches!(parser.next(), Some(ParsedDescriptor::Interface(_))));
+/// assert!(parser.next().is_none());
+/// assert_eq!(parser.remaining().len(), 6);
+/// ```
+#[derive(Clone)]
+pub struct ParsedDescriptors<'a>(Descriptors<'a>);
+
+impl<'a> ParsedDescriptors<'a> {
+    /// Create a `ParsedDescriptors` iterator over a buffer of concatenated descriptors.
+    pub fn new(buf: &'a [u8]) -> ParsedDescriptors<'a> {
+        ParsedDescriptors(Descriptors(buf))
+    }
+
+    /// Get the bytes that have not yet been parsed.
+    ///
+    /// After iteration has ended, this is empty unless the buffer contained a
+    /// truncated or invalid descriptor.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl<'a> Iterator for ParsedDescriptors<'a> {
+    type Item = ParsedDescriptor<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let desc = self.0.next()?.0;
+        let (descriptor_type, len) = (desc[1], desc.len());
+
+        Some(match descriptor_type {
+            DESCRIPTOR_TYPE_CONFIGURATION if len >= DESCRIPTOR_LEN_CONFIGURATION as usize => {
+                ParsedDescriptor::Configuration(ConfigurationFields(desc))
+            }
+            DESCRIPTOR_TYPE_INTERFACE if len >= DESCRIPTOR_LEN_INTERFACE as usize => {
+                ParsedDescriptor::Interface(InterfaceAltSetting(desc))
+            }
+            DESCRIPTOR_TYPE_ENDPOINT if len >= DESCRIPTOR_LEN_ENDPOINT as usize => {
+                ParsedDescriptor::Endpoint(Endpoint(desc))
+            }
+            DESCRIPTOR_TYPE_INTERFACE_ASSOCIATION
+                if len >= DESCRIPTOR_LEN_INTERFACE_ASSOCIATION as usize =>
+            {
+                ParsedDescriptor::InterfaceAssociation(InterfaceAssociation(desc))
+            }
+            DESCRIPTOR_TYPE_SUPERSPEED_ENDPOINT_COMPANION
+                if len >= DESCRIPTOR_LEN_SUPERSPEED_ENDPOINT_COMPANION as usize =>
+            {
+                ParsedDescriptor::SuperSpeedEndpointCompanion(SuperSpeedEndpointCompanion(desc))
+            }
+            _ => ParsedDescriptor::Unknown {
+                descriptor_type,
+                bytes: desc,
+            },
+        })
+    }
+}
+
+/// Device Qualifier descriptor, describing how a high-speed capable device
+/// would operate at its other speed.
+///
+/// Obtain this from [`Device::device_qualifier`][crate::Device::device_qualifier].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct DeviceQualifier([u8; DESCRIPTOR_LEN_DEVICE_QUALIFIER as usize]);
+
+impl DeviceQualifier {
+    /// Create a `DeviceQualifier` from the bytes of a device qualifier
+    /// descriptor.
+    ///
+    /// Returns `None` if the buffer is too short or is not a device
+    /// qualifier descriptor. Any bytes beyond the descriptor are ignored.
+    pub fn new(buf: &[u8]) -> Option<DeviceQualifier> {
+        if buf.len() < DESCRIPTOR_LEN_DEVICE_QUALIFIER as usize {
+            warn!(
+                "device qualifier descriptor buffer is {} bytes, need {}",
+                buf.len(),
+                DESCRIPTOR_LEN_DEVICE_QUALIFIER
+            );
+            return None;
+        }
+
+        if buf[0] < DESCRIPTOR_LEN_DEVICE_QUALIFIER || buf[1] != DESCRIPTOR_TYPE_DEVICE_QUALIFIER {
+            warn!("invalid device qualifier descriptor");
+            return None;
+        }
+
+        Some(DeviceQualifier(
+            buf[..DESCRIPTOR_LEN_DEVICE_QUALIFIER as usize]
+                .try_into()
+                .unwrap(),
+        ))
+    }
+
+    /// `bcdUSB` descriptor field: USB specification version, encoded as BCD.
+    #[doc(alias = "bcdUSB")]
+    pub fn usb_version(&self) -> u16 {
+        u16::from_le_bytes([self.0[2], self.0[3]])
+    }
+
+    /// `bDeviceClass` descriptor field: Device class code at the other speed.
+    #[doc(alias = "bDeviceClass")]
+    pub fn class(&self) -> u8 {
+        self.0[4]
+    }
+
+    /// `bDeviceSubClass` descriptor field: Device subclass code at the other speed.
+    #[doc(alias = "bDeviceSubClass")]
+    pub fn subclass(&self) -> u8 {
+        self.0[5]
+    }
+
+    /// `bDeviceProtocol` descriptor field: Device protocol code at the other speed.
+    #[doc(alias = "bDeviceProtocol")]
+    pub fn protocol(&self) -> u8 {
+        self.0[6]
+    }
+
+    /// `bMaxPacketSize0` descriptor field: Maximum packet size for the
+    /// control endpoint at the other speed.
+    #[doc(alias = "bMaxPacketSize0")]
+    pub fn max_packet_size_0(&self) -> u8 {
+        self.0[7]
+    }
+
+    /// `bNumConfigurations` descriptor field: Number of configurations at
+    /// the other speed.
+    #[doc(alias = "bNumConfigurations")]
+    pub fn num_configurations(&self) -> u8 {
+        self.0[8]
+    }
+}
+
+impl Deref for DeviceQualifier {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Debug for DeviceQualifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceQualifier")
+            .field("usb_version", &format_args!("0x{:04X}", self.usb_version()))
+            .field("class", &format_args!("0x{:02X}", self.class()))
+            .field("subclass", &format_args!("0x{:02X}", self.subclass()))
+            .field("protocol", &format_args!("0x{:02X}", self.protocol()))
+            .field("max_packet_size_0", &self.max_packet_size_0())
+            .field("num_configurations", &self.num_configurations())
+            .finish()
+    }
+}
+
+#[test]
+fn test_device_qualifier() {
+    let q = DeviceQualifier::new(&[0x0A, 0x06, 0x00, 0x02, 0xEF, 0x02, 0x01, 0x40, 0x01, 0x00])
+        .unwrap();
+    assert_eq!(q.usb_version(), 0x0200);
+    assert_eq!(q.class(), 0xEF);
+    assert_eq!(q.max_packet_size_0(), 64);
+    assert_eq!(q.num_configurations(), 1);
+
+    assert!(DeviceQualifier::new(&[0x0A, 0x06, 0x00, 0x02]).is_none());
+    assert!(DeviceQualifier::new(&[0x0A, 0x01, 0, 0, 0, 0, 0, 0, 0, 0]).is_none());
+}
+
+/// A version number encoded as binary-coded decimal, as in the `bcdUSB` and
+/// `bcdDevice` descriptor fields.
+///
+/// The value `0xJJMN` is version `JJ.M.N`, and is displayed as `"JJ.MN"`,
+/// so `0x0210` is `"2.10"`. The digits are not validated, so a value that
+/// is not valid BCD is displayed with hex digits.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BcdVersion(pub u16);
+
+impl BcdVersion {
+    /// Major version, from the high byte.
+    pub fn major(&self) -> u8 {
+        let [hi, _] = self.0.to_be_bytes();
+        (hi >> 4) * 10 + (hi & 0x0F)
+    }
+
+    /// Minor version, from the high nibble of the low byte.
+    pub fn minor(&self) -> u8 {
+        (self.0 >> 4) as u8 & 0x0F
+    }
+
+    /// Sub-minor version, from the low nibble.
+    pub fn sub_minor(&self) -> u8 {
+        self.0 as u8 & 0x0F
+    }
+
+    /// Raw BCD-encoded value.
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+}
+
+impl From<u16> for BcdVersion {
+    fn from(value: u16) -> Self {
+        BcdVersion(value)
+    }
+}
+
+impl From<BcdVersion> for u16 {
+    fn from(value: BcdVersion) -> Self {
+        value.0
+    }
+}
+
+impl std::fmt::Display for BcdVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:x}.{:02x}", self.0 >> 8, self.0 & 0xFF)
+    }
+}
+
+impl Debug for BcdVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "BcdVersion({self})")
+    }
+}
+
+#[test]
+fn test_bcd_version() {
+    let v = BcdVersion(0x0210);
+    assert_eq!((v.major(), v.minor(), v.sub_minor()), (2, 1, 0));
+    assert_eq!(v.to_string(), "2.10");
+    assert_eq!(BcdVersion(0x1234).major(), 12);
+    assert_eq!(BcdVersion(0x1234).to_string(), "12.34");
+    assert_eq!(BcdVersion(0x0320).to_string(), "3.20");
+    assert!(BcdVersion(0x0300) > BcdVersion(0x0210));
+}
+
+/// Binary Object Store (BOS) descriptor, describing the device-level
+/// capabilities of a device.
+///
+/// Obtain this from [`Device::bos_descriptor`][crate::Device::bos_descriptor].
+/// Unlike the other descriptor types, it owns its data because it is read
+/// from the device on request rather than cached by the OS.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BosDescriptor(Vec<u8>);
+
+impl BosDescriptor {
+    /// Create a `BosDescriptor` from the bytes of a BOS descriptor followed
+    /// by its device capability descriptors.
+    ///
+    /// Returns `None` if the buffer does not start with a valid BOS
+    /// descriptor header or is shorter than its `wTotalLength`. Any bytes
+    /// beyond `wTotalLength` are discarded.
+    pub fn new(mut buf: Vec<u8>) -> Option<BosDescriptor> {
+        let total_len = validate_bos_descriptor(&buf)?;
+        if total_len > buf.len() {
+            warn!(
+                "BOS descriptor wTotalLength of {total_len} exceeds buffer size {}",
+                buf.len()
+            );
+            return None;
+        }
+        buf.truncate(total_len);
+        Some(BosDescriptor(buf))
+    }
+
+    /// Get the BOS descriptor followed by all device capability descriptors.
+    pub fn descriptors(&self) -> Descriptors<'_> {
+        Descriptors(&self.0)
+    }
+
+    /// `wTotalLength` descriptor field: Length of this descriptor and all device capability descriptors.
+    #[doc(alias = "wTotalLength")]
+    pub fn total_len(&self) -> u16 {
+        u16::from_le_bytes(self.0[2..4].try_into().unwrap())
+    }
+
+    /// `bNumDeviceCaps` descriptor field: Number of device capability descriptors.
+    #[doc(alias = "bNumDeviceCaps")]
+    pub fn num_device_caps(&self) -> u8 {
+        self.0[4]
+    }
+
+    /// Iterate all device capability descriptors, including unknown and
+    /// vendor-specific capability types.
+    pub fn capabilities(&self) -> impl Iterator<Item = DeviceCapability<'_>> {
+        self.descriptors()
+            .split_by_type(
+                DESCRIPTOR_TYPE_DEVICE_CAPABILITY,
+                DESCRIPTOR_LEN_DEVICE_CAPABILITY,
+            )
+            .map(|d| DeviceCapability(&d[..d[0] as usize]))
+    }
+
+    /// Get the USB 2.0 Extension capability, if present.
+    pub fn usb2_extension(&self) -> Option<Usb2Extension<'_>> {
+        self.capabilities().find_map(|c| c.as_usb2_extension())
+    }
+
+    /// Get the SuperSpeed USB capability, if present.
+    pub fn superspeed_usb(&self) -> Option<SuperSpeedUsb<'_>> {
+        self.capabilities().find_map(|c| c.as_superspeed_usb())
+    }
+
+    /// Get the Container ID capability, if present.
+    pub fn container_id(&self) -> Option<ContainerId<'_>> {
+        self.capabilities().find_map(|c| c.as_container_id())
+    }
+
+    /// Get the Microsoft OS 2.0 platform capability, if present.
+    pub fn ms_os_20_platform(&self) -> Option<MsOs20Platform<'_>> {
+        self.capabilities().find_map(|c| c.as_ms_os_20_platform())
+    }
+}
+
+impl Deref for BosDescriptor {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Debug for BosDescriptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BosDescriptor")
+            .field("total_len", &self.total_len())
+            .field("num_device_caps", &self.num_device_caps())
+            .field("capabilities", &DebugEntries(|| self.capabilities()))
             .finish()
     }
 }
 
+/// A device capability descriptor from the [BOS descriptor][BosDescriptor].
+///
+/// Derefs to the raw bytes of the descriptor, so capability types not parsed
+/// by this library can be handled by the caller.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DeviceCapability<'a>(&'a [u8]);
+
+descriptor_fields! {
+    impl<'a> DeviceCapability<'a> {
+        /// `bDevCapabilityType` descriptor field: Type of capability, see [`capability_type`].
+        #[doc(alias = "bDevCapabilityType")]
+        pub fn capability_type at 2 -> u8;
+    }
+}
+
+impl<'a> DeviceCapability<'a> {
+    /// Get the capability-specific data following the `bDevCapabilityType` field.
+    pub fn data(&self) -> &'a [u8] {
+        &self.0[DESCRIPTOR_LEN_DEVICE_CAPABILITY as usize..]
+    }
+
+    /// Parse as a USB 2.0 Extension capability if it has that type.
+    pub fn as_usb2_extension(&self) -> Option<Usb2Extension<'a>> {
+        (self.capability_type() == capability_type::USB_2_0_EXTENSION && self.0.len() >= 7)
+            .then_some(Usb2Extension(self.0))
+    }
+
+    /// Parse as a SuperSpeed USB capability if it has that type.
+    pub fn as_superspeed_usb(&self) -> Option<SuperSpeedUsb<'a>> {
+        (self.capability_type() == capability_type::SUPERSPEED_USB && self.0.len() >= 10)
+            .then_some(SuperSpeedUsb(self.0))
+    }
+
+    /// Parse as a Container ID capability if it has that type.
+    pub fn as_container_id(&self) -> Option<ContainerId<'a>> {
+        (self.capability_type() == capability_type::CONTAINER_ID && self.0.len() >= 20)
+            .then_some(ContainerId(self.0))
+    }
+
+    /// Parse as a Microsoft OS 2.0 platform capability if it is a platform
+    /// capability with that UUID.
+    pub fn as_ms_os_20_platform(&self) -> Option<MsOs20Platform<'a>> {
+        (self.capability_type() == capability_type::PLATFORM
+            && self.0.len() >= 28
+            && self.0[4..20] == MS_OS_20_PLATFORM_UUID)
+            .then_some(MsOs20Platform(self.0))
+    }
+}
+
+impl<'a> Deref for DeviceCapability<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+impl<'a> Debug for DeviceCapability<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(c) = self.as_usb2_extension() {
+            c.fmt(f)
+        } else if let Some(c) = self.as_superspeed_usb() {
+            c.fmt(f)
+        } else if let Some(c) = self.as_container_id() {
+            c.fmt(f)
+        } else if let Some(c) = self.as_ms_os_20_platform() {
+            c.fmt(f)
+        } else {
+            f.debug_struct("DeviceCapability")
+                .field(
+                    "capability_type",
+                    &format_args!("0x{:02X}", self.capability_type()),
+                )
+                .field("data", &self.data())
+                .finish()
+        }
+    }
+}
+
+/// USB 2.0 Extension device capability, describing Link Power Management support.
+#[derive(Clone)]
+pub struct Usb2Extension<'a>(&'a [u8]);
+
+descriptor_fields! {
+    impl<'a> Usb2Extension<'a> {
+        /// `bmAttributes` descriptor field: Bitmap of LPM attributes.
+        #[doc(alias = "bmAttributes")]
+        pub fn attributes at 3 -> u32;
+    }
+}
+
+impl<'a> Usb2Extension<'a> {
+    /// Whether the device supports the Link Power Management protocol.
+    pub fn lpm_supported(&self) -> bool {
+        self.attributes() & (1 << 1) != 0
+    }
+
+    /// Whether the device supports Best Effort Service Latency (BESL) values for LPM.
+    pub fn besl_supported(&self) -> bool {
+        self.attributes() & (1 << 2) != 0
+    }
+
+    /// Recommended baseline BESL value, if specified by the device.
+    pub fn baseline_besl(&self) -> Option<u8> {
+        (self.attributes() & (1 << 3) != 0).then_some(((self.attributes() >> 8) & 0xF) as u8)
+    }
+
+    /// Recommended deep BESL value, if specified by the device.
+    pub fn deep_besl(&self) -> Option<u8> {
+        (self.attributes() & (1 << 4) != 0).then_some(((self.attributes() >> 12) & 0xF) as u8)
+    }
+}
+
+impl<'a> Debug for Usb2Extension<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Usb2Extension")
+            .field("attributes", &format_args!("0x{:08X}", self.attributes()))
+            .field("lpm_supported", &self.lpm_supported())
+            .field("besl_supported", &self.besl_supported())
+            .field("baseline_besl", &self.baseline_besl())
+            .field("deep_besl", &self.deep_besl())
+            .finish()
+    }
+}
+
+/// SuperSpeed USB device capability.
+#[derive(Clone)]
+pub struct SuperSpeedUsb<'a>(&'a [u8]);
+
+descriptor_fields! {
+    impl<'a> SuperSpeedUsb<'a> {
+        /// `bmAttributes` descriptor field: Bitmap of device-level attributes.
+        ///
+        /// Bit 1 indicates support for Latency Tolerance Messages.
+        #[doc(alias = "bmAttributes")]
+        pub fn attributes at 3 -> u8;
+
+        /// `wSpeedsSupported` descriptor field: Bitmap of supported speeds.
+        ///
+        /// Bit 0: low speed, bit 1: full speed, bit 2: high speed, bit 3: 5 Gbit/s.
+        #[doc(alias = "wSpeedsSupported")]
+        pub fn speeds_supported at 4 -> u16;
+
+        /// `bFunctionalitySupport` descriptor field: Lowest speed at which all functionality is available.
+        #[doc(alias = "bFunctionalitySupport")]
+        pub fn functionality_support at 6 -> u8;
+
+        /// `bU1DevExitLat` descriptor field: U1 device exit latency, in microseconds.
+        #[doc(alias = "bU1DevExitLat")]
+        pub fn u1_exit_latency at 7 -> u8;
+
+        /// `wU2DevExitLat` descriptor field: U2 device exit latency, in microseconds.
+        #[doc(alias = "wU2DevExitLat")]
+        pub fn u2_exit_latency at 8 -> u16;
+    }
+}
+
+impl<'a> SuperSpeedUsb<'a> {
+    /// Whether the device supports generating Latency Tolerance Messages.
+    pub fn ltm_capable(&self) -> bool {
+        self.attributes() & (1 << 1) != 0
+    }
+}
+
+impl<'a> Debug for SuperSpeedUsb<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SuperSpeedUsb")
+            .field("attributes", &format_args!("0x{:02X}", self.attributes()))
+            .field(
+                "speeds_supported",
+                &format_args!("0x{:04X}", self.speeds_supported()),
+            )
+            .field("functionality_support", &self.functionality_support())
+            .field("u1_exit_latency", &self.u1_exit_latency())
+            .field("u2_exit_latency", &self.u2_exit_latency())
+            .finish()
+    }
+}
+
+/// Container ID device capability, uniquely identifying a device across
+/// all of the buses it is connected to.
+#[derive(Clone)]
+pub struct ContainerId<'a>(&'a [u8]);
+
+impl<'a> ContainerId<'a> {
+    /// The 128-bit `ContainerID` UUID, in the byte order sent by the device.
+    #[doc(alias = "ContainerID")]
+    pub fn container_id(&self) -> [u8; 16] {
+        self.0[4..20].try_into().unwrap()
+    }
+}
+
+impl<'a> Debug for ContainerId<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContainerId")
+            .field("container_id", &self.container_id())
+            .finish()
+    }
+}
+
+/// `D8DD60DF-4589-4CC7-9CD2-659D9E648A9F` in the byte order sent by the device.
+const MS_OS_20_PLATFORM_UUID: [u8; 16] = [
+    0xDF, 0x60, 0xDD, 0xD8, 0x89, 0x45, 0xC7, 0x4C, 0x9C, 0xD2, 0x65, 0x9D, 0x9E, 0x64, 0x8A, 0x9F,
+];
+
+/// Microsoft OS 2.0 platform capability, describing how to request the
+/// MS OS 2.0 descriptor set.
+///
+/// Only the first descriptor set information entry is parsed, which is the
+/// one used by Windows 8.1 and later unless the device provides alternate
+/// sets for specific Windows versions.
+#[derive(Clone)]
+pub struct MsOs20Platform<'a>(&'a [u8]);
+
+descriptor_fields! {
+    impl<'a> MsOs20Platform<'a> {
+        /// `dwWindowsVersion` field: Minimum Windows version for this descriptor set.
+        #[doc(alias = "dwWindowsVersion")]
+        pub fn windows_version at 20 -> u32;
+
+        /// `wMSOSDescriptorSetTotalLength` field: Length of the descriptor set.
+        #[doc(alias = "wMSOSDescriptorSetTotalLength")]
+        pub fn descriptor_set_total_length at 24 -> u16;
+
+        /// `bMS_VendorCode` field: `bRequest` value of the vendor request
+        /// used to retrieve the descriptor set.
+        #[doc(alias = "bMS_VendorCode")]
+        pub fn vendor_code at 26 -> u8;
+
+        /// `bAltEnumCode` field: Nonzero if the device supports an alternate
+        /// enumeration.
+        #[doc(alias = "bAltEnumCode")]
+        pub fn alt_enum_code at 27 -> u8;
+    }
+}
+
+impl<'a> Debug for MsOs20Platform<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MsOs20Platform")
+            .field(
+                "windows_version",
+                &format_args!("0x{:08X}", self.windows_version()),
+            )
+            .field(
+                "descriptor_set_total_length",
+                &self.descriptor_set_total_length(),
+            )
+            .field("vendor_code", &format_args!("0x{:02X}", self.vendor_code()))
+            .field("alt_enum_code", &self.alt_enum_code())
+            .finish()
+    }
+}
+
+/// Index of the Microsoft OS string descriptor, which holds the vendor code
+/// for the Microsoft OS 1.0 feature descriptor requests.
+pub(crate) const MS_OS_10_STRING_INDEX: u8 = 0xEE;
+const MS_OS_10_SIGNATURE: &str = "MSFT100";
+
+pub(crate) const MS_OS_10_EXTENDED_COMPAT_ID_INDEX: u16 = 0x0004;
+pub(crate) const MS_OS_10_EXTENDED_PROPERTIES_INDEX: u16 = 0x0005;
+
+/// Get the vendor code from a Microsoft OS string descriptor, or `None` if
+/// the signature doesn't match.
+pub(crate) fn parse_ms_os_10_string_descriptor(data: &[u8]) -> Option<u8> {
+    if data.len() < 18 || data[1] != DESCRIPTOR_TYPE_STRING {
+        return None;
+    }
+    let signature = char::decode_utf16(
+        data[2..16]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]])),
+    )
+    .collect::<Result<String, _>>()
+    .ok()?;
+    (signature == MS_OS_10_SIGNATURE).then_some(data[16])
+}
+
+/// Microsoft OS 1.0 descriptors, used by Windows to choose a driver for
+/// devices without an INF file.
+///
+/// Obtain this from
+/// [`Device::read_ms_os_10_descriptors`][crate::Device::read_ms_os_10_descriptors].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MsOs10Descriptors {
+    pub(crate) vendor_code: u8,
+    pub(crate) compat_ids: Vec<MsOs10CompatId>,
+    pub(crate) properties: Vec<MsOs10Property>,
+}
+
+impl MsOs10Descriptors {
+    /// `bMS_VendorCode` from the Microsoft OS string descriptor: `bRequest`
+    /// value of the vendor requests used to retrieve the feature
+    /// descriptors.
+    #[doc(alias = "bMS_VendorCode")]
+    pub fn vendor_code(&self) -> u8 {
+        self.vendor_code
+    }
+
+    /// Functions in the Extended Compat ID feature descriptor, empty if the
+    /// device doesn't have one.
+    pub fn compat_ids(&self) -> &[MsOs10CompatId] {
+        &self.compat_ids
+    }
+
+    /// Registry properties from the Extended Properties feature descriptors
+    /// of the functions in [`compat_ids`][Self::compat_ids].
+    pub fn properties(&self) -> &[MsOs10Property] {
+        &self.properties
+    }
+}
+
+/// Function section of the Microsoft OS 1.0 Extended Compat ID feature
+/// descriptor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MsOs10CompatId {
+    first_interface: u8,
+    compatible_id: String,
+    sub_compatible_id: String,
+}
+
+impl MsOs10CompatId {
+    /// `bFirstInterfaceNumber` field: First interface of the function.
+    #[doc(alias = "bFirstInterfaceNumber")]
+    pub fn first_interface(&self) -> u8 {
+        self.first_interface
+    }
+
+    /// `compatibleID` field, like `WINUSB`, with trailing NULs removed.
+    #[doc(alias = "compatibleID")]
+    pub fn compatible_id(&self) -> &str {
+        &self.compatible_id
+    }
+
+    /// `subCompatibleID` field, with trailing NULs removed.
+    #[doc(alias = "subCompatibleID")]
+    pub fn sub_compatible_id(&self) -> &str {
+        &self.sub_compatible_id
+    }
+}
+
+/// Parse an Extended Compat ID feature descriptor.
+pub(crate) fn parse_ms_os_10_compat_ids(data: &[u8]) -> Option<Vec<MsOs10CompatId>> {
+    const HEADER_LEN: usize = 16;
+    const FUNCTION_LEN: usize = 24;
+
+    let header = data.get(..HEADER_LEN)?;
+    if u16::from_le_bytes([header[6], header[7]]) != MS_OS_10_EXTENDED_COMPAT_ID_INDEX {
+        return None;
+    }
+    let count = header[8] as usize;
+    let id = |b: &[u8]| {
+        let end = b.iter().position(|&c| c == 0).unwrap_or(b.len());
+        String::from_utf8_lossy(&b[..end]).into_owned()
+    };
+
+    Some(
+        data[HEADER_LEN..]
+            .chunks_exact(FUNCTION_LEN)
+            .take(count)
+            .map(|f| MsOs10CompatId {
+                first_interface: f[0],
+                compatible_id: id(&f[2..10]),
+                sub_compatible_id: id(&f[10..18]),
+            })
+            .collect(),
+    )
+}
+
+/// Registry property from a Microsoft OS 1.0 Extended Properties feature
+/// descriptor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MsOs10Property {
+    interface: u8,
+    data_type: u32,
+    name: String,
+    data: Vec<u8>,
+}
+
+impl MsOs10Property {
+    /// Interface number the property was requested for.
+    pub fn interface(&self) -> u8 {
+        self.interface
+    }
+
+    /// `dwPropertyDataType` field: Registry value type, like `1` for
+    /// `REG_SZ` or `7` for `REG_MULTI_SZ`.
+    #[doc(alias = "dwPropertyDataType")]
+    pub fn data_type(&self) -> u32 {
+        self.data_type
+    }
+
+    /// `bPropertyName` field: Name of the registry value, like
+    /// `DeviceInterfaceGUIDs`.
+    #[doc(alias = "bPropertyName")]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `bPropertyData` field: Raw registry value data.
+    #[doc(alias = "bPropertyData")]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Decode the value as strings if it is a `REG_SZ`, `REG_EXPAND_SZ`,
+    /// `REG_LINK`, or `REG_MULTI_SZ` value.
+    ///
+    /// String types return one string, and `REG_MULTI_SZ` returns each of
+    /// its strings.
+    pub fn strings(&self) -> Option<Vec<String>> {
+        const REG_SZ: u32 = 1;
+        const REG_EXPAND_SZ: u32 = 2;
+        const REG_LINK: u32 = 6;
+        const REG_MULTI_SZ: u32 = 7;
+
+        let mut strings: Vec<String> = decode_utf16_nul(&self.data)
+            .split('\0')
+            .map(String::from)
+            .collect();
+        match self.data_type {
+            REG_SZ | REG_EXPAND_SZ | REG_LINK => Some(strings.into_iter().take(1).collect()),
+            REG_MULTI_SZ => {
+                strings.retain(|s| !s.is_empty());
+                Some(strings)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn decode_utf16_nul(data: &[u8]) -> String {
+    let s = char::decode_utf16(
+        data.chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]])),
+    )
+    .map(|r| r.unwrap_or(char::REPLACEMENT_CHARACTER))
+    .collect::<String>();
+    s.trim_end_matches('\0').to_owned()
+}
+
+/// Parse an Extended Properties feature descriptor.
+pub(crate) fn parse_ms_os_10_properties(data: &[u8], interface: u8) -> Option<Vec<MsOs10Property>> {
+    const HEADER_LEN: usize = 10;
+
+    let header = data.get(..HEADER_LEN)?;
+    if u16::from_le_bytes([header[6], header[7]]) != MS_OS_10_EXTENDED_PROPERTIES_INDEX {
+        return None;
+    }
+    let count = u16::from_le_bytes([header[8], header[9]]);
+
+    let u32_at = |b: &[u8], i: usize| Some(u32::from_le_bytes(b.get(i..i + 4)?.try_into().ok()?));
+    let mut rest = &data[HEADER_LEN..];
+    let mut properties = Vec::new();
+    for _ in 0..count {
+        let size = u32_at(rest, 0)? as usize;
+        let section = rest.get(..size)?;
+        let data_type = u32_at(section, 4)?;
+        let name_len = u16::from_le_bytes(section.get(8..10)?.try_into().ok()?) as usize;
+        let name = decode_utf16_nul(section.get(10..10 + name_len)?);
+        let data_len = u32_at(section, 10 + name_len)? as usize;
+        let data_start = 14 + name_len;
+        let data = section.get(data_start..data_start + data_len)?.to_vec();
+        properties.push(MsOs10Property {
+            interface,
+            data_type,
+            name,
+            data,
+        });
+        rest = &rest[size..];
+    }
+    Some(properties)
+}
+
 /// Error from [`crate::Device::active_configuration`]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct ActiveConfigurationError {
@@ -544,7 +1666,7 @@ impl std::error::Error for ActiveConfigurationError {}
 
 impl From<ActiveConfigurationError> for Error {
     fn from(value: ActiveConfigurationError) -> Self {
-        Error::new(ErrorKind::Other, value)
+        Error::other(value)
     }
 }
 
@@ -558,6 +1680,35 @@ pub(crate) fn parse_concatenated_config_descriptors(mut buf: &[u8]) -> impl Iter
     })
 }
 
+/// A string descriptor read with
+/// [`Device::read_string_descriptor`][crate::Device::read_string_descriptor].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StringDescriptor {
+    /// String descriptor 0, listing the language IDs the device supports.
+    Languages(Vec<u16>),
+
+    /// Any other string descriptor, in the automatically selected language.
+    String(String),
+}
+
+impl StringDescriptor {
+    /// Get the string, or `None` for the language list.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            StringDescriptor::String(s) => Some(s),
+            StringDescriptor::Languages(_) => None,
+        }
+    }
+
+    /// Get the language IDs, or `None` for a string.
+    pub fn languages(&self) -> Option<&[u16]> {
+        match self {
+            StringDescriptor::Languages(l) => Some(l),
+            StringDescriptor::String(_) => None,
+        }
+    }
+}
+
 pub(crate) fn validate_string_descriptor(data: &[u8]) -> bool {
     data.len() >= 2 && data[0] as usize == data.len() && data[1] == DESCRIPTOR_TYPE_STRING
 }
@@ -792,6 +1943,8 @@ fn test_dell_webcam() {
     assert_eq!(c.configuration_value(), 1);
     assert_eq!(c.num_interfaces(), 2);
     assert_eq!(c.max_power(), 250);
+    assert_eq!(c.max_power_milliamps(Some(Speed::High)), 500);
+    assert_eq!(c.max_power_milliamps(Some(Speed::Super)), 2000);
 
     let mut interfaces = c.interfaces();
     let interface = interfaces.next().unwrap();
@@ -939,4 +2092,151 @@ fn test_dell_webcam() {
     assert!(endpoints.next().is_none());
     assert!(alts.next().is_none());
     assert!(interfaces.next().is_none());
+
+    let mut iads = c.interface_associations();
+    let iad = iads.next().unwrap();
+    assert_eq!(iad.first_interface(), 0);
+    assert_eq!(iad.interface_count(), 2);
+    assert_eq!(iad.function_class(), 14);
+    assert_eq!(iad.function_subclass(), 3);
+    assert_eq!(iad.string_index(), Some(5));
+    assert!(iad.contains_interface(1));
+    assert!(!iad.contains_interface(2));
+    assert!(iads.next().is_none());
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_superspeed_endpoint_companion() {
+    let c = Configuration(&[
+        0x09, 0x02, 0x2c, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32,
+        0x09, 0x04, 0x00, 0x00, 0x02, 0xff, 0x00, 0x00, 0x00,
+        0x07, 0x05, 0x81, 0x02, 0x00, 0x04, 0x00,
+        0x06, 0x30, 0x0f, 0x00, 0x00, 0x00,
+        0x07, 0x05, 0x02, 0x02, 0x00, 0x02, 0x00,
+    ]);
+
+    let alt = c.interface_alt_settings().next().unwrap();
+    let mut endpoints = alt.endpoints();
+
+    let ep = endpoints.next().unwrap();
+    assert_eq!(ep.max_packet_size(), 1024);
+    assert_eq!(ep.superspeed_companion().unwrap().max_burst(), 15);
+    assert_eq!(ep.max_burst(), 16);
+
+    let ep = endpoints.next().unwrap();
+    assert!(ep.superspeed_companion().is_none());
+    assert_eq!(ep.max_burst(), 1);
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_bos_superspeed() {
+    let bos = BosDescriptor::new(vec![
+        0x05, 0x0F, 0x2A, 0x00, 0x03,
+        0x07, 0x10, 0x02, 0x1E, 0x24, 0x00, 0x00,
+        0x0A, 0x10, 0x03, 0x00, 0x0E, 0x00, 0x01, 0x0A, 0xFF, 0x07,
+        0x14, 0x10, 0x04, 0x00,
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+            0x18, 0x19, 0x1A, 0x1B, 0x1C, 0x1D, 0x1E, 0x1F,
+    ]).unwrap();
+
+    assert_eq!(bos.total_len(), 42);
+    assert_eq!(bos.num_device_caps(), 3);
+    assert_eq!(bos.capabilities().count(), 3);
+
+    let usb2 = bos.usb2_extension().unwrap();
+    assert!(usb2.lpm_supported());
+    assert!(usb2.besl_supported());
+    assert_eq!(usb2.baseline_besl(), Some(4));
+    assert_eq!(usb2.deep_besl(), Some(2));
+
+    let ss = bos.superspeed_usb().unwrap();
+    assert!(!ss.ltm_capable());
+    assert_eq!(ss.speeds_supported(), 0x000E);
+    assert_eq!(ss.functionality_support(), 1);
+    assert_eq!(ss.u1_exit_latency(), 10);
+    assert_eq!(ss.u2_exit_latency(), 0x07FF);
+
+    let container_id = bos.container_id().unwrap();
+    assert_eq!(container_id.container_id()[0], 0x10);
+    assert_eq!(container_id.container_id()[15], 0x1F);
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_bos_unknown_capability() {
+    let bos = BosDescriptor::new(vec![
+        0x05, 0x0F, 0x0D, 0x00, 0x01,
+        0x08, 0x10, 0xEE, 0x01, 0x02, 0x03, 0x04, 0x05,
+        // trailing bytes beyond wTotalLength are discarded
+        0x00, 0x00,
+    ]).unwrap();
+
+    assert_eq!(bos.len(), 13);
+    let mut caps = bos.capabilities();
+    let cap = caps.next().unwrap();
+    assert_eq!(cap.capability_type(), 0xEE);
+    assert_eq!(cap.data(), &[0x01, 0x02, 0x03, 0x04, 0x05]);
+    assert_eq!(&cap[..], &[0x08, 0x10, 0xEE, 0x01, 0x02, 0x03, 0x04, 0x05]);
+    assert!(caps.next().is_none());
+    assert!(bos.usb2_extension().is_none());
+
+    assert!(BosDescriptor::new(vec![0x05, 0x0F, 0x20, 0x00, 0x01]).is_none());
+    assert!(BosDescriptor::new(vec![0x09, 0x02, 0x09, 0x00, 0x01]).is_none());
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_bos_ms_os_20_platform() {
+    let bos = BosDescriptor::new(vec![
+        0x05, 0x0F, 0x21, 0x00, 0x01,
+        0x1C, 0x10, 0x05, 0x00,
+            0xDF, 0x60, 0xDD, 0xD8, 0x89, 0x45, 0xC7, 0x4C,
+            0x9C, 0xD2, 0x65, 0x9D, 0x9E, 0x64, 0x8A, 0x9F,
+            0x00, 0x00, 0x03, 0x06, 0xB2, 0x00, 0x20, 0x00,
+    ]).unwrap();
+
+    let ms_os_20 = bos.ms_os_20_platform().unwrap();
+    assert_eq!(ms_os_20.windows_version(), 0x06030000);
+    assert_eq!(ms_os_20.descriptor_set_total_length(), 0xB2);
+    assert_eq!(ms_os_20.vendor_code(), 0x20);
+    assert_eq!(ms_os_20.alt_enum_code(), 0);
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_ms_os_10_descriptors() {
+    let string = [
+        0x12, 0x03,
+        b'M', 0, b'S', 0, b'F', 0, b'T', 0, b'1', 0, b'0', 0, b'0', 0,
+        0x20, 0x00,
+    ];
+    assert_eq!(parse_ms_os_10_string_descriptor(&string), Some(0x20));
+    assert_eq!(parse_ms_os_10_string_descriptor(&string[..16]), None);
+
+    let compat_ids = parse_ms_os_10_compat_ids(&[
+        0x28, 0x00, 0x00, 0x00, 0x00, 0x01, 0x04, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0,
+        0x00, 0x01,
+            b'W', b'I', b'N', b'U', b'S', b'B', 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0,
+    ]).unwrap();
+    assert_eq!(compat_ids.len(), 1);
+    assert_eq!(compat_ids[0].first_interface(), 0);
+    assert_eq!(compat_ids[0].compatible_id(), "WINUSB");
+    assert_eq!(compat_ids[0].sub_compatible_id(), "");
+
+    let mut properties = vec![
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x05, 0x00, 0x01, 0x00,
+        0x1E, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00,
+        0x08, 0x00, b'G', 0, b'U', 0, b'I', 0, 0, 0,
+        0x08, 0x00, 0x00, 0x00, b'{', 0, b'}', 0, 0, 0, 0, 0,
+    ];
+    properties[0] = properties.len() as u8;
+    let properties = parse_ms_os_10_properties(&properties, 2).unwrap();
+    assert_eq!(properties.len(), 1);
+    assert_eq!(properties[0].interface(), 2);
+    assert_eq!(properties[0].name(), "GUI");
+    assert_eq!(properties[0].strings(), Some(vec!["{}".to_owned()]));
 }
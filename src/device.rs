@@ -1,20 +1,49 @@
-use std::{io::ErrorKind, sync::Arc, time::Duration};
+use std::{
+    future::{poll_fn, Future},
+    io::{ErrorKind, IoSlice},
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
 
-use log::error;
+use futures_core::Stream;
+use log::{error, warn};
 
 use crate::{
+    blocking::block_on,
     descriptors::{
-        decode_string_descriptor, validate_string_descriptor, ActiveConfigurationError,
-        Configuration, InterfaceAltSetting, DESCRIPTOR_TYPE_STRING,
+        decode_string_descriptor, language_id::US_ENGLISH, parse_ms_os_10_compat_ids,
+        parse_ms_os_10_properties, parse_ms_os_10_string_descriptor, validate_bos_descriptor,
+        validate_string_descriptor, ActiveConfigurationError, BosDescriptor, Configuration,
+        DeviceQualifier, Endpoint, InterfaceAltSetting, MsOs10Descriptors, StringDescriptor,
+        DESCRIPTOR_LEN_BOS, DESCRIPTOR_LEN_DEVICE, DESCRIPTOR_LEN_DEVICE_QUALIFIER,
+        DESCRIPTOR_TYPE_BOS, DESCRIPTOR_TYPE_DEVICE, DESCRIPTOR_TYPE_DEVICE_QUALIFIER,
+        DESCRIPTOR_TYPE_STRING, MS_OS_10_EXTENDED_COMPAT_ID_INDEX,
+        MS_OS_10_EXTENDED_PROPERTIES_INDEX, MS_OS_10_STRING_INDEX,
     },
+    hotplug::HotplugEvent,
     platform,
     transfer::{
-        Control, ControlIn, ControlOut, EndpointType, Queue, RequestBuffer, TransferError,
-        TransferFuture,
+        assert_direction, check_direction, CallerBuffer, Control, ControlIn, ControlOut, Direction,
+        EndpointAddress, EndpointType, InterruptStream, Queue, RequestBuffer, ResponseBuffer,
+        TransferBuffer, TransferError, TransferFuture,
     },
-    DeviceInfo, Error,
+    DeviceId, DeviceInfo, Error, Speed,
 };
 
+/// Report an error from an operation on a device that has been unplugged as
+/// [`ErrorKind::NotConnected`], which the platforms otherwise report
+/// inconsistently.
+fn check_connected<T>(result: Result<T, Error>, device: &platform::Device) -> Result<T, Error> {
+    result.map_err(|e| {
+        if e.kind() != ErrorKind::NotConnected && device.is_disconnected() {
+            Error::new(ErrorKind::NotConnected, "device disconnected")
+        } else {
+            e
+        }
+    })
+}
+
 /// An opened USB device.
 ///
 /// Obtain a `Device` by calling [`DeviceInfo::open`]:
@@ -38,28 +67,112 @@ use crate::{
 #[derive(Clone)]
 pub struct Device {
     backend: Arc<crate::platform::Device>,
+
+    /// Speed from the `DeviceInfo` the device was opened from.
+    speed: Option<Speed>,
+
+    /// ID from the `DeviceInfo` the device was opened from, for
+    /// [`Device::wait_disconnected`].
+    id: Option<DeviceId>,
+
+    /// Device descriptor fields from the `DeviceInfo` the device was opened
+    /// from, for [`Device::verify_descriptors`].
+    enumerated: Option<[u16; DEVICE_DESCRIPTOR_FIELDS.len()]>,
 }
 
 impl Device {
     pub(crate) fn open(d: &DeviceInfo) -> Result<Device, std::io::Error> {
         let backend = platform::Device::from_device_info(d)?;
-        Ok(Device { backend })
+        Ok(Device {
+            backend,
+            speed: d.speed(),
+            id: Some(d.id()),
+            enumerated: Some(enumerated_device_fields(d)),
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn open_readonly(d: &DeviceInfo) -> Result<Device, std::io::Error> {
+        let backend = platform::Device::from_device_info_readonly(d)?;
+        Ok(Device {
+            backend,
+            speed: d.speed(),
+            id: Some(d.id()),
+            enumerated: Some(enumerated_device_fields(d)),
+        })
     }
 
     /// Open an interface of the device and claim it for exclusive use.
+    ///
+    /// If the device has been unplugged, the error kind is
+    /// [`ErrorKind::NotConnected`].
     pub fn claim_interface(&self, interface: u8) -> Result<Interface, Error> {
-        let backend = self.backend.claim_interface(interface)?;
-        Ok(Interface { backend })
+        let backend = check_connected(self.backend.claim_interface(interface), &self.backend)?;
+        Ok(Interface {
+            backend,
+            speed: self.speed,
+        })
     }
 
     /// Detach kernel drivers and open an interface of the device and claim it for exclusive use.
     ///
+    /// Kernel drivers are reattached when the returned [`Interface`] is
+    /// dropped. Failure to reattach is logged rather than reported.
+    ///
     /// ### Platform notes
     /// This function can only detach kernel drivers on Linux. Calling on other platforms has
     /// the same effect as [`claim_interface`][`Device::claim_interface`].
     pub fn detach_and_claim_interface(&self, interface: u8) -> Result<Interface, Error> {
-        let backend = self.backend.detach_and_claim_interface(interface)?;
-        Ok(Interface { backend })
+        let backend = check_connected(
+            self.backend.detach_and_claim_interface(interface),
+            &self.backend,
+        )?;
+        Ok(Interface {
+            backend,
+            speed: self.speed,
+        })
+    }
+
+    /// *(Linux-only)* Detach the kernel driver unless it is `driver`, and
+    /// claim the interface for exclusive use.
+    ///
+    /// This is [`detach_and_claim_interface`][Self::detach_and_claim_interface],
+    /// except that if the interface is bound to the kernel driver named
+    /// `driver`, such as `"cdc_acm"`, it is left attached and this fails
+    /// with `EBUSY`. The check, detach and claim are a single
+    /// `USBDEVFS_DISCONNECT_CLAIM` ioctl, so there's no window for the
+    /// kernel to bind another driver in between.
+    ///
+    /// `detach_and_claim_interface` passes `"usbfs"`, so it fails rather
+    /// than detaching another program that claimed the interface. This does
+    /// not, so another program's claim is detached unless `driver` is
+    /// `"usbfs"`. `driver` must be shorter than 256 bytes and not contain
+    /// NUL, or this returns an error of kind [`ErrorKind::InvalidInput`].
+    ///
+    /// Kernel drivers are reattached when the returned [`Interface`] is
+    /// dropped.
+    ///
+    /// ### Platform-specific notes
+    /// * On kernels older than Linux 3.8, which lack
+    ///   `USBDEVFS_DISCONNECT_CLAIM`, the driver is checked in sysfs and
+    ///   detached separately before claiming, which is not atomic. If the
+    ///   driver can't be read from sysfs, it is detached regardless.
+    #[cfg(target_os = "linux")]
+    #[doc(alias = "USBDEVFS_DISCONNECT_CLAIM")]
+    pub fn detach_and_claim_interface_except(
+        &self,
+        interface: u8,
+        driver: &str,
+    ) -> Result<Interface, Error> {
+        let backend = check_connected(
+            self.backend
+                .detach_and_claim_interface_except(interface, driver),
+            &self.backend,
+        )?;
+        Ok(Interface {
+            backend,
+            speed: self.speed,
+        })
     }
 
     /// Detach kernel drivers for the specified interface.
@@ -77,6 +190,9 @@ impl Device {
 
     /// Attach kernel drivers for the specified interface.
     ///
+    /// Succeeds without doing anything if a kernel driver is already
+    /// attached, so it is safe to call more than once.
+    ///
     /// ### Platform notes
     /// This function can only attach kernel drivers on Linux. Calling on other platforms has
     /// no effect.
@@ -88,40 +204,174 @@ impl Device {
         Ok(())
     }
 
+    /// Detach kernel drivers for the specified interface until the returned
+    /// guard is dropped.
+    ///
+    /// The guard reattaches kernel drivers when dropped, or when
+    /// [`DetachGuard::reattach`] is called. If no kernel driver was
+    /// attached, nothing is reattached.
+    ///
+    /// Use this instead of [`detach_and_claim_interface`][Self::detach_and_claim_interface]
+    /// when the driver should stay detached across claiming and releasing
+    /// the interface, or when the interface is claimed separately.
+    ///
+    /// ### Platform notes
+    /// This function can only detach kernel drivers on Linux. On other
+    /// platforms, it has no effect and returns a guard that does nothing.
+    pub fn detach_kernel_driver_guard(&self, interface: u8) -> Result<DetachGuard, Error> {
+        #[cfg(target_os = "linux")]
+        let detached = self.backend.detach_kernel_driver_if_attached(interface)?;
+        #[cfg(not(target_os = "linux"))]
+        let detached = false;
+
+        Ok(DetachGuard {
+            device: self.clone(),
+            interface,
+            detached,
+        })
+    }
+
+    /// Get the name of the kernel driver bound to the specified interface,
+    /// or `None` if no driver is bound.
+    ///
+    /// This reads the interface's `driver` symlink in sysfs. An interface
+    /// claimed by a program using usbfs, including this one, reports the
+    /// `usbfs` driver.
+    ///
+    /// ### Platform-specific notes
+    /// * Only available on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn kernel_driver(&self, interface: u8) -> Result<Option<String>, Error> {
+        self.backend.kernel_driver(interface)
+    }
+
     /// Get information about the active configuration.
     ///
     /// This returns cached data and does not perform IO. However, it can fail if the
     /// device is unconfigured, or if it can't find a configuration descriptor for
     /// the configuration reported as active by the OS.
-    pub fn active_configuration(&self) -> Result<Configuration, ActiveConfigurationError> {
+    pub fn active_configuration(&self) -> Result<Configuration<'_>, ActiveConfigurationError> {
         let active = self.backend.active_configuration_value();
 
         self.configurations()
             .find(|c| c.configuration_value() == active)
-            .ok_or_else(|| ActiveConfigurationError {
+            .ok_or(ActiveConfigurationError {
                 configuration_value: active,
             })
     }
 
+    /// Query the device's active configuration.
+    ///
+    /// Returns the `bConfigurationValue` of the active configuration, or `0`
+    /// if the device is unconfigured. Unlike
+    /// [`active_configuration`][`Device::active_configuration`], this asks
+    /// the OS or device rather than returning cached data.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this reads the `bConfigurationValue` sysfs attribute.
+    /// * On macOS, this issues a `GET_CONFIGURATION` request to the device.
+    /// * On Windows, this queries the configuration set by the hub driver,
+    ///   because WinUSB does not allow a `GET_CONFIGURATION` request.
+    #[doc(alias = "bConfigurationValue")]
+    #[doc(alias = "GET_CONFIGURATION")]
+    pub fn active_configuration_value(&self) -> Result<u8, Error> {
+        self.backend.get_configuration()
+    }
+
+    /// Check whether the device is currently suspended.
+    ///
+    /// This queries the OS's power management state for the device, and
+    /// does not perform IO or wake the device.
+    ///
+    /// ### Platform-specific notes
+    /// See [`DeviceInfo::is_suspended`].
+    pub fn is_suspended(&self) -> Result<bool, Error> {
+        self.backend.is_suspended()
+    }
+
     /// Get an iterator returning information about each configuration of the device.
     ///
+    /// This includes every configuration the device offers, in descriptor
+    /// index order, not only the active one. Each [`Configuration`] gives
+    /// access to its interfaces, alternate settings, and endpoints, so this
+    /// can be used to inspect a device without calling
+    /// [`set_configuration`][Self::set_configuration].
+    ///
     /// This returns cached data and does not perform IO.
-    pub fn configurations(&self) -> impl Iterator<Item = Configuration> {
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, the descriptors are parsed from the device's usbfs file,
+    ///   which contains all of its configuration descriptors.
+    /// * On Windows, the descriptors are requested from the hub with
+    ///   `GET_DESCRIPTOR` for each index up to `bNumConfigurations` when the
+    ///   device is opened.
+    /// * On macOS, the descriptors are the copies cached by IOKit.
+    #[doc(alias = "bNumConfigurations")]
+    pub fn configurations(&self) -> impl Iterator<Item = Configuration<'_>> {
         self.backend
             .configuration_descriptors()
             .map(Configuration::new)
     }
 
+    /// Suggest a length for each transfer on an endpoint of the active
+    /// configuration.
+    ///
+    /// For bulk endpoints, this is a size large enough to keep the bus
+    /// busy at the device's speed without excessive per-transfer overhead:
+    /// 16 KiB at full speed, 64 KiB at high speed, 256 KiB at SuperSpeed,
+    /// and 1 MiB at SuperSpeed Plus. It is a multiple of the maximum packet
+    /// size times the [burst size][EndpointInfo::max_burst], and is reduced
+    /// to any limit of the OS driver. Submitting several transfers of this
+    /// size at once, for example with a [`Queue`], is recommended for
+    /// streaming.
+    ///
+    /// For interrupt and isochronous endpoints, this is the
+    /// [data transferred in one service interval][EndpointInfo::bytes_per_interval].
+    ///
+    /// Returns an error of kind [`ErrorKind::NotFound`] if no interface of
+    /// the active configuration has an endpoint with that address.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, kernels without `USBDEVFS_CAP_BULK_CONTINUATION` or
+    ///   `USBDEVFS_CAP_NO_PACKET_SIZE_LIM` limit bulk transfers to 16 KiB.
+    pub fn recommended_max_transfer_size(&self, endpoint: u8) -> Result<usize, Error> {
+        let config = self
+            .active_configuration()
+            .map_err(|e| Error::new(ErrorKind::NotFound, e))?;
+        let info = config
+            .interface_alt_settings()
+            .find_map(|alt| {
+                alt.endpoints()
+                    .find(|ep| ep.address() == endpoint)
+                    .map(|ep| EndpointInfo::new(&ep, self.speed))
+            })
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "endpoint not found"))?;
+
+        #[cfg(target_os = "linux")]
+        let limit = self.backend.max_bulk_transfer_size();
+        #[cfg(not(target_os = "linux"))]
+        let limit = None;
+
+        Ok(recommended_transfer_size(&info, self.speed, limit))
+    }
+
     /// Set the device configuration.
     ///
     /// The argument is the desired configuration's `bConfigurationValue`
     /// descriptor field from [`Configuration::configuration_value`] or `0` to
     /// unconfigure the device.
     ///
+    /// Changing the configuration replaces the device's interfaces and
+    /// endpoints, so all interfaces must be released first. This returns an
+    /// error if any [`Interface`] of this device is still claimed.
+    ///
     /// ### Platform-specific notes
     /// * Not supported on Windows
+    /// * On Linux, this uses `USBDEVFS_SETCONFIGURATION`, which fails with
+    ///   `EBUSY` if any interface is claimed, including by a kernel driver or
+    ///   another program.
     pub fn set_configuration(&self, configuration: u8) -> Result<(), Error> {
-        self.backend.set_configuration(configuration)
+        check_connected(self.backend.set_configuration(configuration), &self.backend)
     }
 
     /// Request a descriptor from the device.
@@ -139,10 +389,25 @@ impl Device {
         desc_index: u8,
         language_id: u16,
         timeout: Duration,
+    ) -> Result<Vec<u8>, Error> {
+        self.get_descriptor_with_len(desc_type, desc_index, language_id, 4096, timeout)
+    }
+
+    /// Request a descriptor from the device, reading at most `length` bytes.
+    ///
+    /// On Windows, the hub always requests its own maximum length and
+    /// `length` is ignored.
+    fn get_descriptor_with_len(
+        &self,
+        desc_type: u8,
+        desc_index: u8,
+        language_id: u16,
+        length: usize,
+        timeout: Duration,
     ) -> Result<Vec<u8>, Error> {
         #[cfg(target_os = "windows")]
         {
-            let _ = timeout;
+            let _ = (length, timeout);
             self.backend
                 .get_descriptor(desc_type, desc_index, language_id)
         }
@@ -152,7 +417,7 @@ impl Device {
             const STANDARD_REQUEST_GET_DESCRIPTOR: u8 = 0x06;
             use crate::transfer::{ControlType, Recipient};
 
-            let mut buf = vec![0; 4096];
+            let mut buf = vec![0; length];
             let len = self.control_in_blocking(
                 Control {
                     control_type: ControlType::Standard,
@@ -223,17 +488,411 @@ impl Device {
             .map_err(|_| Error::new(ErrorKind::InvalidData, "string descriptor data was invalid"))
     }
 
+    /// Request a string descriptor from the device, selecting the language automatically.
+    ///
+    /// This first reads the list of supported languages from string
+    /// descriptor 0, and uses [`US_ENGLISH`][`crate::descriptors::language_id::US_ENGLISH`]
+    /// if supported or otherwise the first language listed. Data returned
+    /// beyond the descriptor's `bLength` is ignored.
+    ///
+    /// Index 0 is the language table rather than a string, and returns
+    /// [`StringDescriptor::Languages`] with the supported language IDs, as
+    /// from [`get_string_descriptor_supported_languages`][`Self::get_string_descriptor_supported_languages`].
+    ///
+    /// ### Platform-specific details
+    ///
+    /// See notes on [`get_descriptor`][`Self::get_descriptor`].
+    pub fn read_string_descriptor(
+        &self,
+        desc_index: u8,
+        timeout: Duration,
+    ) -> Result<StringDescriptor, Error> {
+        let languages: Vec<u16> = self
+            .get_string_descriptor_supported_languages(timeout)?
+            .collect();
+        if desc_index == 0 {
+            return Ok(StringDescriptor::Languages(languages));
+        }
+
+        let language_id = languages
+            .iter()
+            .copied()
+            .find(|&l| l == US_ENGLISH)
+            .or(languages.first().copied())
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    "device reported no string languages",
+                )
+            })?;
+
+        let mut data = self.get_descriptor_with_len(
+            DESCRIPTOR_TYPE_STRING,
+            desc_index,
+            language_id,
+            255,
+            timeout,
+        )?;
+        if let Some(&len) = data.first() {
+            data.truncate(len as usize);
+        }
+
+        decode_string_descriptor(&data)
+            .map(StringDescriptor::String)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "string descriptor data was invalid"))
+    }
+
+    /// Get the device descriptor followed by the descriptors of all
+    /// configurations, concatenated in configuration index order.
+    ///
+    /// This is the same layout as the Linux usbfs device node and sysfs
+    /// `descriptors` file, and can be parsed with
+    /// [`ParsedDescriptors`][crate::descriptors::ParsedDescriptors] after the
+    /// first 18 bytes.
+    ///
+    /// ### Platform-specific details
+    /// * On Linux, this returns the descriptors read from the device node
+    ///   when it was opened, and does not perform IO. `timeout` is unused.
+    /// * On Windows and macOS, this requests the device descriptor from the
+    ///   device, and appends the configuration descriptors cached by the OS.
+    ///   See notes on [`get_descriptor`][`Self::get_descriptor`].
+    pub fn full_descriptors(&self, timeout: Duration) -> Result<Vec<u8>, Error> {
+        #[cfg(target_os = "linux")]
+        {
+            let _ = timeout;
+            Ok(self.backend.descriptors().to_vec())
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            use crate::descriptors::{DESCRIPTOR_LEN_DEVICE, DESCRIPTOR_TYPE_DEVICE};
+
+            let mut buf = self.get_descriptor_with_len(
+                DESCRIPTOR_TYPE_DEVICE,
+                0,
+                0,
+                DESCRIPTOR_LEN_DEVICE as usize,
+                timeout,
+            )?;
+
+            if buf.len() < DESCRIPTOR_LEN_DEVICE as usize || buf[1] != DESCRIPTOR_TYPE_DEVICE {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "device descriptor data was invalid",
+                ));
+            }
+            buf.truncate(DESCRIPTOR_LEN_DEVICE as usize);
+
+            for config in self.backend.configuration_descriptors() {
+                buf.extend_from_slice(config);
+            }
+
+            Ok(buf)
+        }
+    }
+
+    /// Request the Device Qualifier descriptor from the device.
+    ///
+    /// High-speed capable devices use this descriptor to describe how they
+    /// would operate at the other speed. Returns `Ok(None)` if the device
+    /// stalls the request, which full-speed only devices are required to do.
+    ///
+    /// ### Platform-specific details
+    ///
+    /// See notes on [`get_descriptor`][`Self::get_descriptor`]. On Windows,
+    /// the hub driver doesn't distinguish a stall from other failures, so
+    /// this returns an error instead of `Ok(None)`.
+    pub fn device_qualifier(&self, timeout: Duration) -> Result<Option<DeviceQualifier>, Error> {
+        let data = match self.get_descriptor_with_len(
+            DESCRIPTOR_TYPE_DEVICE_QUALIFIER,
+            0,
+            0,
+            DESCRIPTOR_LEN_DEVICE_QUALIFIER as usize,
+            timeout,
+        ) {
+            Ok(data) => data,
+            Err(e) if is_stall(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        DeviceQualifier::new(&data).map(Some).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "device qualifier descriptor data was invalid",
+            )
+        })
+    }
+
+    /// Re-read the device descriptor from the device and compare it to the
+    /// fields of the [`DeviceInfo`] the device was opened from.
+    ///
+    /// Returns the fields that differ, which is empty if the enumerated
+    /// information is current. A mismatch means the OS cached the
+    /// descriptor before the device changed it, for example after a
+    /// firmware update that didn't cause the device to re-enumerate.
+    ///
+    /// This performs a `GET_DESCRIPTOR` control transfer.
+    ///
+    /// ### Platform-specific details
+    ///
+    /// See notes on [`get_descriptor`][`Self::get_descriptor`].
+    pub fn verify_descriptors(&self, timeout: Duration) -> Result<Vec<DescriptorMismatch>, Error> {
+        let enumerated = self.enumerated.ok_or_else(|| {
+            Error::new(
+                ErrorKind::Unsupported,
+                "device was not opened from a DeviceInfo",
+            )
+        })?;
+
+        let data = self.get_descriptor_with_len(
+            DESCRIPTOR_TYPE_DEVICE,
+            0,
+            0,
+            DESCRIPTOR_LEN_DEVICE as usize,
+            timeout,
+        )?;
+        let live = device_descriptor_fields(&data).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData, "device descriptor data was invalid")
+        })?;
+
+        Ok(compare_device_fields(&enumerated, &live))
+    }
+
+    /// Request the Binary Object Store (BOS) descriptor from the device.
+    ///
+    /// This reads the BOS descriptor header to find its total length, then
+    /// reads the full descriptor including all device capability descriptors.
+    /// Devices prior to USB 2.1 typically do not have a BOS descriptor and
+    /// will respond with a stall.
+    ///
+    /// ### Platform-specific details
+    ///
+    /// See notes on [`get_descriptor`][`Self::get_descriptor`].
+    pub fn bos_descriptor(&self, timeout: Duration) -> Result<BosDescriptor, Error> {
+        let invalid = || Error::new(ErrorKind::InvalidData, "BOS descriptor data was invalid");
+
+        let header = self.get_descriptor_with_len(
+            DESCRIPTOR_TYPE_BOS,
+            0,
+            0,
+            DESCRIPTOR_LEN_BOS as usize,
+            timeout,
+        )?;
+        let total_len = validate_bos_descriptor(&header).ok_or_else(invalid)?;
+
+        let data = if header.len() >= total_len {
+            header
+        } else {
+            self.get_descriptor_with_len(DESCRIPTOR_TYPE_BOS, 0, 0, total_len, timeout)?
+        };
+
+        BosDescriptor::new(data).ok_or_else(invalid)
+    }
+
+    /// Request the device's Container ID from its BOS descriptor.
+    ///
+    /// The Container ID is a UUID that is the same for every function of a
+    /// physical device, so it can be used to group the devices that a hub or
+    /// composite device presents on different buses, and to recognize a
+    /// device after it is re-plugged. Returns `Ok(None)` if the device has no
+    /// BOS descriptor or it does not contain a Container ID capability.
+    ///
+    /// ### Platform-specific details
+    ///
+    /// See notes on [`get_descriptor`][`Self::get_descriptor`]. On Windows,
+    /// a device without a BOS descriptor returns an error, as for
+    /// [`device_qualifier`][Self::device_qualifier].
+    #[doc(alias = "ContainerID")]
+    pub fn container_id(&self, timeout: Duration) -> Result<Option<[u8; 16]>, Error> {
+        match self.bos_descriptor(timeout) {
+            Ok(bos) => Ok(bos.container_id().map(|c| c.container_id())),
+            Err(e) if is_stall(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Request the Microsoft OS 2.0 descriptor set from the device.
+    ///
+    /// This reads the [BOS descriptor][Self::bos_descriptor] to find the
+    /// Microsoft OS 2.0 platform capability, then issues the vendor request
+    /// it specifies to read the descriptor set. The raw bytes of the
+    /// descriptor set are returned for the caller to parse.
+    ///
+    /// Returns an error of kind [`ErrorKind::NotFound`] if the device does
+    /// not have a Microsoft OS 2.0 platform capability.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * Not supported on Windows. You must [claim an interface][`Device::claim_interface`]
+    ///   and use [`Interface::read_ms_os_20_descriptor`].
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn read_ms_os_20_descriptor(&self, timeout: Duration) -> Result<Vec<u8>, Error> {
+        let bos = self.bos_descriptor(timeout)?;
+        read_ms_os_20_descriptor_set(&bos, |control, buf| {
+            self.control_in_blocking(control, buf, timeout)
+        })
+    }
+
+    /// Request the Microsoft OS 1.0 descriptors from the device.
+    ///
+    /// This reads the Microsoft OS string descriptor at index `0xEE` to get
+    /// the vendor code, then issues the vendor requests for the Extended
+    /// Compat ID feature descriptor, and the Extended Properties feature
+    /// descriptor of each function it lists. These are what older versions
+    /// of Windows use to bind WinUSB to a device automatically.
+    ///
+    /// Returns an error of kind [`ErrorKind::NotFound`] if the device does
+    /// not have a Microsoft OS string descriptor. Feature descriptors the
+    /// device stalls are returned as empty.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * Not supported on Windows. You must [claim an interface][`Device::claim_interface`]
+    ///   and use [`Interface::read_ms_os_10_descriptors`].
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn read_ms_os_10_descriptors(&self, timeout: Duration) -> Result<MsOs10Descriptors, Error> {
+        read_ms_os_10_descriptors(
+            self,
+            |control, buf| self.control_in_blocking(control, buf, timeout),
+            timeout,
+        )
+    }
+
+    /// Request the device status with a standard `GET_STATUS` request.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * Not supported on Windows.
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[doc(alias = "GET_STATUS")]
+    pub fn status(&self, timeout: Duration) -> Result<DeviceStatus, Error> {
+        use crate::transfer::{ControlType, Recipient};
+
+        let mut buf = [0; 2];
+        let len = self.control_in_blocking(
+            Control {
+                control_type: ControlType::Standard,
+                recipient: Recipient::Device,
+                request: STANDARD_REQUEST_GET_STATUS,
+                value: 0,
+                index: 0,
+            },
+            &mut buf,
+            timeout,
+        )?;
+
+        if len != buf.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "device status response was too short",
+            ));
+        }
+
+        Ok(DeviceStatus(u16::from_le_bytes(buf)))
+    }
+
+    /// Enable or disable the device's ability to request remote wakeup, with
+    /// a standard `SET_FEATURE` or `CLEAR_FEATURE` `DEVICE_REMOTE_WAKEUP`
+    /// request.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * Not supported on Windows.
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[doc(alias = "DEVICE_REMOTE_WAKEUP")]
+    pub fn set_remote_wakeup(&self, enabled: bool, timeout: Duration) -> Result<(), Error> {
+        const STANDARD_REQUEST_CLEAR_FEATURE: u8 = 0x01;
+        const STANDARD_REQUEST_SET_FEATURE: u8 = 0x03;
+        const FEATURE_DEVICE_REMOTE_WAKEUP: u16 = 1;
+        use crate::transfer::{ControlType, Recipient};
+
+        self.control_out_blocking(
+            Control {
+                control_type: ControlType::Standard,
+                recipient: Recipient::Device,
+                request: if enabled {
+                    STANDARD_REQUEST_SET_FEATURE
+                } else {
+                    STANDARD_REQUEST_CLEAR_FEATURE
+                },
+                value: FEATURE_DEVICE_REMOTE_WAKEUP,
+                index: 0,
+            },
+            &[],
+            timeout,
+        )?;
+        Ok(())
+    }
+
     /// Reset the device, forcing it to re-enumerate.
     ///
-    /// This `Device` will no longer be usable, and you should drop it and call
+    /// This can recover a device that has stopped responding. This `Device`
+    /// will no longer be usable, and you should drop it and call
     /// [`super::list_devices`] to find and re-open it again.
     ///
+    /// If the OS reports that the device has already re-enumerated and this
+    /// handle is no longer valid, the error kind is
+    /// [`ErrorKind::NotConnected`].
+    ///
     /// ### Platform-specific notes
-    /// * Not supported on Windows
+    /// * On Linux, this uses `USBDEVFS_RESET`.
+    /// * On Windows, WinUSB has no reset operation, so this power-cycles the
+    ///   device's hub port instead, which may require administrator privileges.
+    /// * On macOS, this uses `USBDeviceReEnumerate`.
     pub fn reset(&self) -> Result<(), Error> {
         self.backend.reset()
     }
 
+    /// Wait for the device to be disconnected from the system.
+    ///
+    /// The future resolves when a hotplug event reports that this device was
+    /// disconnected, or immediately if it is already gone. It does not
+    /// borrow the `Device`, but keeps it open until it resolves. Together with
+    /// [`wait_for_device`][crate::wait_for_device], this lets a test harness
+    /// that power-cycles a device await the disconnect before awaiting the
+    /// reconnect.
+    ///
+    /// ### Example
+    ///
+    /// ```no_run
+    /// use futures_lite::future::block_on;
+    /// use nusb::hotplug::HotplugFilter;
+    /// # let device = nusb::list_devices().unwrap().next().unwrap().open().unwrap();
+    /// # fn power_cycle() {}
+    /// let disconnected = device.wait_disconnected();
+    /// power_cycle();
+    /// block_on(disconnected).unwrap();
+    /// drop(device);
+    ///
+    /// let filter = HotplugFilter::new().vendor_id(0xAAAA).product_id(0xBBBB);
+    /// let device_info = block_on(nusb::wait_for_device(filter)).unwrap();
+    /// ```
+    pub fn wait_disconnected(&self) -> impl Future<Output = Result<(), Error>> + 'static {
+        let backend = self.backend.clone();
+        let id = self.id;
+        async move {
+            // Watch before checking, so a disconnect in between is not missed.
+            let mut watch = crate::watch_devices()?;
+
+            let listed = match id {
+                Some(id) => crate::list_device_ids()?.any(|d| d.id() == id),
+                None => true,
+            };
+            if !listed || backend.is_disconnected() {
+                return Ok(());
+            }
+
+            loop {
+                match poll_fn(|cx| Pin::new(&mut watch).poll_next(cx)).await {
+                    Some(HotplugEvent::Disconnected(d)) if id == Some(d) => return Ok(()),
+                    Some(_) if backend.is_disconnected() => return Ok(()),
+                    Some(_) => continue,
+                    None => return Err(Error::other("hotplug event stream ended")),
+                }
+            }
+        }
+    }
+
     /// Synchronously perform a single **IN (device-to-host)** transfer on the default **control** endpoint.
     ///
     /// ### Platform-specific notes
@@ -270,6 +929,55 @@ impl Device {
         self.backend.control_out_blocking(control, data, timeout)
     }
 
+    /// Synchronously perform a single transfer on the default **control**
+    /// endpoint in the direction specified by `setup`.
+    ///
+    /// For an IN request, up to `data.len()` bytes are read into `data`. For
+    /// an OUT request, `data` is sent. The number of bytes transferred is
+    /// returned. This corresponds to `libusb_control_transfer`.
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidInput`] if the length of
+    /// `data` differs from the [`length`][crate::transfer::ControlSetup::length]
+    /// set for `wLength`.
+    ///
+    /// ### Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use nusb::transfer::{ControlSetup, ControlType, Direction};
+    /// # let di = nusb::list_devices().unwrap().next().unwrap();
+    /// # let device = di.open().unwrap();
+    /// let setup = ControlSetup::new(Direction::In)
+    ///     .control_type(ControlType::Vendor)
+    ///     .request(0x30)
+    ///     .length(64);
+    /// let mut buf = [0; 64];
+    /// let len = device.control(setup, &mut buf, Duration::from_secs(1)).unwrap();
+    /// println!("{:02x?}", &buf[..len]);
+    /// ```
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * Not supported on Windows. You must [claim an interface][`Device::claim_interface`]
+    ///   and use the interface handle to submit transfers.
+    /// * On Linux, this takes a device-wide lock, so if you have multiple threads, you
+    ///   are better off using the async methods.
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn control(
+        &self,
+        setup: crate::transfer::ControlSetup,
+        data: &mut [u8],
+        timeout: Duration,
+    ) -> Result<usize, Error> {
+        setup.check_length(data.len())?;
+
+        let len = match setup.direction() {
+            Direction::In => self.control_in_blocking(setup.control(), data, timeout)?,
+            Direction::Out => self.control_out_blocking(setup.control(), data, timeout)?,
+        };
+        Ok(len)
+    }
+
     /// Asynchronously submit a single **IN (device-to-host)** transfer on the default **control** endpoint.
     ///
     /// ### Example
@@ -330,52 +1038,174 @@ impl Device {
     /// * Not supported on Windows. You must [claim an interface][`Device::claim_interface`]
     ///   and use the interface handle to submit transfers.
     #[cfg(any(target_os = "linux", target_os = "macos"))]
-    pub fn control_out(&self, data: ControlOut) -> TransferFuture<ControlOut> {
+    pub fn control_out(&self, data: ControlOut<'_>) -> TransferFuture<ControlOut<'_>> {
         let mut t = self.backend.make_control_transfer();
         t.submit::<ControlOut>(data);
         TransferFuture::new(t)
     }
-}
-
-/// An opened interface of a USB device.
-///
-/// Obtain an `Interface` with the [`Device::claim_interface`] method.
-///
-/// This type is reference-counted with an [`Arc`] internally, and can be cloned cheaply for
-/// use in multiple places in your program. The interface is released when all clones, and all
-/// associated [`TransferFuture`]s and [`Queue`]s are dropped.
-#[derive(Clone)]
-pub struct Interface {
-    backend: Arc<platform::Interface>,
-}
 
-impl Interface {
-    /// Select the alternate setting of this interface.
+    /// Asynchronously submit a single **IN (device-to-host)** transfer on the default **control** endpoint,
+    /// cancelling it if it does not complete within `timeout`.
     ///
-    /// An alternate setting is a mode of the interface that makes particular endpoints available
-    /// and may enable or disable functionality of the device. The OS resets the device to the default
-    /// alternate setting when the interface is released or the program exits.
-    pub fn set_alt_setting(&self, alt_setting: u8) -> Result<(), Error> {
-        self.backend.set_alt_setting(alt_setting)
-    }
-
-    /// Synchronously perform a single **IN (device-to-host)** transfer on the default **control** endpoint.
+    /// On timeout, the future resolves with [`TransferError::TimedOut`],
+    /// which converts to an [`Error`] of kind
+    /// [`ErrorKind::TimedOut`][std::io::ErrorKind::TimedOut].
     ///
     /// ### Platform-specific notes
     ///
-    /// * On Linux, this takes a device-wide lock, so if you have multiple
-    ///   threads, you are better off using the async methods.
-    /// * On Windows, if the `recipient` is `Interface`, the WinUSB driver sends
-    ///   the interface number in the least significant byte of `index`,
-    ///   overriding any value passed. A warning is logged if the passed `index`
-    ///   least significant byte differs from the interface number, and this may
-    ///   become an error in the future.
-    pub fn control_in_blocking(
+    /// * Not supported on Windows. You must [claim an interface][`Device::claim_interface`]
+    ///   and use the interface handle to submit transfers.
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn control_in_timeout(
         &self,
-        control: Control,
-        data: &mut [u8],
+        data: ControlIn,
         timeout: Duration,
-    ) -> Result<usize, TransferError> {
+    ) -> TransferFuture<ControlIn> {
+        self.control_in(data).with_timeout(timeout)
+    }
+
+    /// Asynchronously submit a single **OUT (host-to-device)** transfer on the default **control** endpoint,
+    /// cancelling it if it does not complete within `timeout`.
+    ///
+    /// On timeout, the future resolves with [`TransferError::TimedOut`],
+    /// which converts to an [`Error`] of kind
+    /// [`ErrorKind::TimedOut`][std::io::ErrorKind::TimedOut].
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * Not supported on Windows. You must [claim an interface][`Device::claim_interface`]
+    ///   and use the interface handle to submit transfers.
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn control_out_timeout(
+        &self,
+        data: ControlOut<'_>,
+        timeout: Duration,
+    ) -> TransferFuture<ControlOut<'_>> {
+        self.control_out(data).with_timeout(timeout)
+    }
+
+    /// Get the raw `IOUSBDeviceInterface500**` used to access the device.
+    ///
+    /// This allows calling IOKit functions that `nusb` doesn't wrap. The
+    /// pointer is borrowed from this `Device` and is only valid while it is
+    /// open. Do not close or release it, and avoid operations that conflict
+    /// with `nusb`'s use of the device, such as changing the configuration
+    /// while interfaces are claimed.
+    ///
+    /// ### Panics
+    /// Panics on a virtual device from the `mock` backend,
+    /// which has no IOKit device.
+    #[cfg(target_os = "macos")]
+    pub fn as_raw_iokit_device(&self) -> *mut std::ffi::c_void {
+        crate::platform::os_device(&self.backend).device.raw.cast()
+    }
+
+    /// Get the usbfs features supported by the running kernel for this
+    /// device.
+    ///
+    /// This is queried with `USBDEVFS_GET_CAPABILITIES` when the device is
+    /// opened, and does not perform IO. Kernels older than 3.6 don't support
+    /// the query, and are reported as supporting none of the features.
+    ///
+    /// ### Platform-specific notes
+    /// * Only available on Linux.
+    #[cfg(target_os = "linux")]
+    #[doc(alias = "USBDEVFS_GET_CAPABILITIES")]
+    pub fn supported_features(&self) -> SupportedFeatures {
+        SupportedFeatures(self.backend.capabilities())
+    }
+}
+
+/// Borrow the usbfs file descriptor of the device.
+///
+/// This allows issuing `ioctl`s that `nusb` doesn't wrap. The file descriptor
+/// is owned by the `Device` and must not be closed. Reading from it or
+/// reaping URBs with it will interfere with `nusb`'s own transfers.
+///
+/// Panics on a virtual device from the `mock` backend, which has no file
+/// descriptor.
+#[cfg(target_os = "linux")]
+impl std::os::fd::AsFd for Device {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.backend.as_fd()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl std::os::fd::AsRawFd for Device {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        std::os::fd::AsRawFd::as_raw_fd(&self.backend.as_fd())
+    }
+}
+
+/// An opened interface of a USB device.
+///
+/// Obtain an `Interface` with the [`Device::claim_interface`] method.
+///
+/// This type is reference-counted with an [`Arc`] internally, and can be cloned cheaply for
+/// use in multiple places in your program. The interface is released when all clones, and all
+/// associated [`TransferFuture`]s and [`Queue`]s are dropped.
+#[derive(Clone)]
+pub struct Interface {
+    backend: Arc<platform::Interface>,
+    speed: Option<Speed>,
+}
+
+impl Interface {
+    /// Select the alternate setting of this interface.
+    ///
+    /// An alternate setting is a mode of the interface that makes particular endpoints available
+    /// and may enable or disable functionality of the device. The OS resets the device to the default
+    /// alternate setting when the interface is released or the program exits.
+    ///
+    /// The `alt_setting` argument is the `bAlternateSetting` value from
+    /// [`InterfaceAltSetting::alternate_setting`]. Returns an error of kind
+    /// [`ErrorKind::InvalidInput`] if the interface's descriptors do not contain
+    /// the requested alternate setting.
+    ///
+    /// Transfers pending on endpoints of the previous alternate setting should
+    /// be cancelled and completed before calling this, because those endpoints
+    /// may no longer exist once the new setting is selected.
+    ///
+    /// Use [`current_alt_setting`][Self::current_alt_setting] to check that
+    /// the device actually switched.
+    pub fn set_alt_setting(&self, alt_setting: u8) -> Result<(), Error> {
+        let mut alt_settings = self.descriptors().peekable();
+        if alt_settings.peek().is_some()
+            && !alt_settings.any(|a| a.alternate_setting() == alt_setting)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "interface {} has no alternate setting {alt_setting}",
+                    self.interface_number()
+                ),
+            ));
+        }
+
+        check_connected(
+            self.backend.set_alt_setting(alt_setting),
+            &self.backend.device,
+        )
+    }
+
+    /// Synchronously perform a single **IN (device-to-host)** transfer on the default **control** endpoint.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Linux, this takes a device-wide lock, so if you have multiple
+    ///   threads, you are better off using the async methods.
+    /// * On Windows, if the `recipient` is `Interface`, the WinUSB driver sends
+    ///   the interface number in the least significant byte of `index`,
+    ///   overriding any value passed. A warning is logged if the passed `index`
+    ///   least significant byte differs from the interface number, and this may
+    ///   become an error in the future.
+    pub fn control_in_blocking(
+        &self,
+        control: Control,
+        data: &mut [u8],
+        timeout: Duration,
+    ) -> Result<usize, TransferError> {
         self.backend.control_in_blocking(control, data, timeout)
     }
 
@@ -463,17 +1293,181 @@ impl Interface {
     ///   overriding any value passed. A warning is logged if the passed `index`
     ///   least significant byte differs from the interface number, and this may
     ///   become an error in the future.
-    pub fn control_out(&self, data: ControlOut) -> TransferFuture<ControlOut> {
+    pub fn control_out(&self, data: ControlOut<'_>) -> TransferFuture<ControlOut<'_>> {
         let mut t = self.backend.make_transfer(0, EndpointType::Control);
         t.submit::<ControlOut>(data);
         TransferFuture::new(t)
     }
 
+    /// Create a queue for pipelining multiple **IN (device-to-host)**
+    /// transfers on the default **control** endpoint.
+    ///
+    /// Submitting several requests before waiting for the first to complete
+    /// avoids a round trip through your task between each request, which
+    /// matters for devices configured with many small control requests. The
+    /// requests are still executed on the bus one at a time, in the order
+    /// they were submitted, and completions are returned in the same order.
+    ///
+    /// ### Example
+    ///
+    /// ```no_run
+    /// use futures_lite::future::block_on;
+    /// use nusb::transfer::{ControlIn, ControlType, Recipient};
+    /// # let di = nusb::list_devices().unwrap().next().unwrap();
+    /// # let device = di.open().unwrap();
+    /// # let interface = device.claim_interface(0).unwrap();
+    /// let mut queue = interface.control_in_queue();
+    /// for register in 0..16 {
+    ///     queue.submit(ControlIn {
+    ///         control_type: ControlType::Vendor,
+    ///         recipient: Recipient::Device,
+    ///         request: 0x30,
+    ///         value: register,
+    ///         index: 0,
+    ///         length: 4,
+    ///     });
+    /// }
+    /// while queue.pending() > 0 {
+    ///     let completion = block_on(queue.next_complete());
+    ///     println!("{:?}", completion.into_result());
+    /// }
+    /// ```
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, each request is submitted as a control URB on endpoint 0.
+    /// * On Windows, the note on [`control_in`][Self::control_in] about
+    ///   `index` applies to each request.
+    pub fn control_in_queue(&self) -> Queue<ControlIn> {
+        Queue::new(self.backend.clone(), 0, EndpointType::Control)
+    }
+
+    /// Create a queue for pipelining multiple **OUT (host-to-device)**
+    /// transfers on the default **control** endpoint.
+    ///
+    /// The data of each request is copied when it is submitted. See
+    /// [`control_in_queue`][Self::control_in_queue] for details.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, each request is submitted as a control URB on endpoint 0.
+    /// * On Windows, the note on [`control_out`][Self::control_out] about
+    ///   `index` applies to each request.
+    pub fn control_out_queue<'a>(&self) -> Queue<ControlOut<'a>> {
+        Queue::new(self.backend.clone(), 0, EndpointType::Control)
+    }
+
+    /// Submit a single **IN (device-to-host)** control transfer directed at this interface.
+    ///
+    /// This is [`control_in`][Self::control_in] with the `recipient` set to
+    /// [`Recipient::Interface`][crate::transfer::Recipient::Interface] and the least significant byte of `index`
+    /// set to this interface's number, as required for class requests such
+    /// as HID `GET_REPORT`. The values passed in those fields are ignored,
+    /// except for the most significant byte of `index`, which is sent
+    /// unchanged.
+    ///
+    /// Requests directed at the device should use [`control_in`][Self::control_in]
+    /// or the methods on [`Device`] instead.
+    ///
+    /// ### Example
+    ///
+    /// ```no_run
+    /// use futures_lite::future::block_on;
+    /// use nusb::transfer::{ ControlIn, ControlType, Recipient };
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// # let di = nusb::list_devices().unwrap().next().unwrap();
+    /// # let device = di.open().unwrap();
+    /// # let interface = device.claim_interface(0).unwrap();
+    ///
+    /// const HID_GET_REPORT: u8 = 0x01;
+    /// let report: Vec<u8> = block_on(interface.interface_control_in(ControlIn {
+    ///     control_type: ControlType::Class,
+    ///     recipient: Recipient::Interface,
+    ///     request: HID_GET_REPORT,
+    ///     value: 0x0100,
+    ///     index: 0,
+    ///     length: 64,
+    /// })).into_result()?;
+    /// # Ok(()) }
+    /// ```
+    pub fn interface_control_in(&self, data: ControlIn) -> TransferFuture<ControlIn> {
+        self.control_in(ControlIn {
+            recipient: crate::transfer::Recipient::Interface,
+            index: self.interface_index(data.index),
+            ..data
+        })
+    }
+
+    /// Submit a single **OUT (host-to-device)** control transfer directed at this interface.
+    ///
+    /// This is [`control_out`][Self::control_out] with the `recipient` set to
+    /// [`Recipient::Interface`][crate::transfer::Recipient::Interface] and the least significant byte of `index`
+    /// set to this interface's number, as required for class requests such
+    /// as HID `SET_REPORT` or CDC `SET_LINE_CODING`. The values passed in
+    /// those fields are ignored, except for the most significant byte of
+    /// `index`, which is sent unchanged.
+    ///
+    /// Requests directed at the device should use [`control_out`][Self::control_out]
+    /// or the methods on [`Device`] instead.
+    pub fn interface_control_out(&self, data: ControlOut<'_>) -> TransferFuture<ControlOut<'_>> {
+        self.control_out(ControlOut {
+            recipient: crate::transfer::Recipient::Interface,
+            index: self.interface_index(data.index),
+            ..data
+        })
+    }
+
+    /// Replace the low byte of a `wIndex` value with the interface number.
+    fn interface_index(&self, index: u16) -> u16 {
+        (index & 0xFF00) | self.interface_number() as u16
+    }
+
+    /// Submit a single **IN (device-to-host)** transfer on the default **control** endpoint,
+    /// cancelling it if it does not complete within `timeout`.
+    ///
+    /// On timeout, the future resolves with [`TransferError::TimedOut`],
+    /// which converts to an [`Error`] of kind
+    /// [`ErrorKind::TimedOut`][std::io::ErrorKind::TimedOut].
+    ///
+    /// ### Platform-specific notes
+    /// * See [`control_in`][`Self::control_in`] for notes on Windows.
+    pub fn control_in_timeout(
+        &self,
+        data: ControlIn,
+        timeout: Duration,
+    ) -> TransferFuture<ControlIn> {
+        self.control_in(data).with_timeout(timeout)
+    }
+
+    /// Submit a single **OUT (host-to-device)** transfer on the default **control** endpoint,
+    /// cancelling it if it does not complete within `timeout`.
+    ///
+    /// On timeout, the future resolves with [`TransferError::TimedOut`],
+    /// which converts to an [`Error`] of kind
+    /// [`ErrorKind::TimedOut`][std::io::ErrorKind::TimedOut].
+    ///
+    /// ### Platform-specific notes
+    /// * See [`control_out`][`Self::control_out`] for notes on Windows.
+    pub fn control_out_timeout(
+        &self,
+        data: ControlOut<'_>,
+        timeout: Duration,
+    ) -> TransferFuture<ControlOut<'_>> {
+        self.control_out(data).with_timeout(timeout)
+    }
+
     /// Submit a single **IN (device-to-host)** transfer on the specified **bulk** endpoint.
     ///
     /// * The requested length must be a multiple of the endpoint's maximum packet size
-    /// * An IN endpoint address must have the top (`0x80`) bit set.
-    pub fn bulk_in(&self, endpoint: u8, buf: RequestBuffer) -> TransferFuture<RequestBuffer> {
+    /// * An IN endpoint address must have the top (`0x80`) bit set, or the transfer
+    ///   fails with [`TransferError::InvalidEndpoint`].
+    pub fn bulk_in(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+        buf: RequestBuffer,
+    ) -> TransferFuture<RequestBuffer> {
+        let endpoint = match check_direction(endpoint.into(), Direction::In, "bulk_in") {
+            Ok(endpoint) => endpoint,
+            Err(e) => return TransferFuture::rejected(buf.into_vec().0, e),
+        };
         let mut t = self.backend.make_transfer(endpoint, EndpointType::Bulk);
         t.submit(buf);
         TransferFuture::new(t)
@@ -481,32 +1475,269 @@ impl Interface {
 
     /// Submit a single **OUT (host-to-device)** transfer on the specified **bulk** endpoint.
     ///
-    /// * An OUT endpoint address must have the top (`0x80`) bit clear.
-    pub fn bulk_out(&self, endpoint: u8, buf: Vec<u8>) -> TransferFuture<Vec<u8>> {
+    /// * An OUT endpoint address must have the top (`0x80`) bit clear, or the transfer
+    ///   fails with [`TransferError::InvalidEndpoint`].
+    pub fn bulk_out(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+        buf: Vec<u8>,
+    ) -> TransferFuture<Vec<u8>> {
+        let endpoint = match check_direction(endpoint.into(), Direction::Out, "bulk_out") {
+            Ok(endpoint) => endpoint,
+            Err(e) => return TransferFuture::rejected(ResponseBuffer::from_vec(buf, 0), e),
+        };
         let mut t = self.backend.make_transfer(endpoint, EndpointType::Bulk);
         t.submit(buf);
         TransferFuture::new(t)
     }
 
+    /// Submit a single **OUT (host-to-device)** transfer on the specified
+    /// **bulk** endpoint, followed by a zero-length packet if its length is
+    /// a nonzero multiple of the endpoint's maximum packet size.
+    ///
+    /// Many protocols detect the end of a transfer by a short packet, so a
+    /// transfer that fills its last packet must be terminated with a
+    /// zero-length packet, or the device waits for more data.
+    ///
+    /// * An OUT endpoint address must have the top (`0x80`) bit clear, or the transfer
+    ///   fails with [`TransferError::InvalidEndpoint`].
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this sets `USBDEVFS_URB_ZERO_PACKET` on the transfer, and
+    ///   the kernel sends the zero-length packet.
+    /// * On Windows and macOS, `nusb` submits a separate zero-length write
+    ///   after the transfer. It is not included in the returned completion,
+    ///   and errors sending it are only logged. (WinUSB's
+    ///   `SHORT_PACKET_TERMINATE` policy isn't used because it would apply to
+    ///   every transfer on the pipe, including concurrent
+    ///   [`bulk_out`][Self::bulk_out] transfers.)
+    #[doc(alias = "ZLP")]
+    pub fn bulk_out_with_zlp(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+        buf: Vec<u8>,
+    ) -> TransferFuture<Vec<u8>> {
+        let endpoint = match check_direction(endpoint.into(), Direction::Out, "bulk_out_with_zlp") {
+            Ok(endpoint) => endpoint,
+            Err(e) => return TransferFuture::rejected(ResponseBuffer::from_vec(buf, 0), e),
+        };
+        let mut t = self.backend.make_zlp_transfer(endpoint);
+        t.submit(buf);
+        TransferFuture::new(t)
+    }
+
+    /// Submit a single **OUT (host-to-device)** transfer on the specified
+    /// **bulk** endpoint, sending the concatenation of `bufs`.
+    ///
+    /// This is useful for sending a frame built from a separate header and
+    /// payload. The data is sent as one transfer, so packet boundaries fall
+    /// as if the buffers were a single buffer.
+    ///
+    /// * An OUT endpoint address must have the top (`0x80`) bit clear, or the transfer
+    ///   fails with [`TransferError::InvalidEndpoint`].
+    ///
+    /// ### Platform-specific notes
+    /// * usbfs, WinUSB, and IOKit all require a transfer to use a single
+    ///   contiguous buffer, so on all platforms the buffers are currently
+    ///   copied into one allocation rather than sent with scatter-gather.
+    pub fn bulk_out_vectored(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+        bufs: &[IoSlice],
+    ) -> TransferFuture<Vec<u8>> {
+        let mut data = Vec::with_capacity(bufs.iter().map(|b| b.len()).sum());
+        for buf in bufs {
+            data.extend_from_slice(buf);
+        }
+        self.bulk_out(endpoint, data)
+    }
+
+    /// Allocate USB 3.0 bulk streams on the specified endpoints.
+    ///
+    /// Requests `num_streams` streams on each of the bulk `endpoints` of
+    /// this interface, and returns the number actually allocated, which may
+    /// be fewer. Transfers can then be submitted on stream IDs `1` through
+    /// the returned number with [`bulk_in_stream`][Self::bulk_in_stream] and
+    /// [`bulk_out_stream`][Self::bulk_out_stream].
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this uses `USBDEVFS_ALLOC_STREAMS`, and requires a
+    ///   SuperSpeed device on a host controller that supports streams.
+    /// * Not supported on Windows or macOS; returns an error of kind
+    ///   [`ErrorKind::Unsupported`].
+    pub fn alloc_streams(&self, endpoints: &[u8], num_streams: u32) -> Result<u32, Error> {
+        self.backend.alloc_streams(endpoints, num_streams)
+    }
+
+    /// Free the bulk streams allocated on the specified endpoints with
+    /// [`alloc_streams`][Self::alloc_streams].
+    ///
+    /// No transfers should be pending on the endpoints' streams.
+    ///
+    /// ### Platform-specific notes
+    /// * Not supported on Windows or macOS; returns an error of kind
+    ///   [`ErrorKind::Unsupported`].
+    pub fn free_streams(&self, endpoints: &[u8]) -> Result<(), Error> {
+        self.backend.free_streams(endpoints)
+    }
+
+    /// Submit a single **IN (device-to-host)** transfer on a stream of the
+    /// specified **bulk** endpoint.
+    ///
+    /// The `stream_id` must have been allocated with
+    /// [`alloc_streams`][Self::alloc_streams].
+    ///
+    /// * An IN endpoint address must have the top (`0x80`) bit set, or the transfer
+    ///   fails with [`TransferError::InvalidEndpoint`].
+    #[cfg(target_os = "linux")]
+    pub fn bulk_in_stream(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+        stream_id: u32,
+        buf: RequestBuffer,
+    ) -> TransferFuture<RequestBuffer> {
+        let endpoint = match check_direction(endpoint.into(), Direction::In, "bulk_in_stream") {
+            Ok(endpoint) => endpoint,
+            Err(e) => return TransferFuture::rejected(buf.into_vec().0, e),
+        };
+        let mut t = self.backend.make_stream_transfer(endpoint, stream_id);
+        t.submit(buf);
+        TransferFuture::new(t)
+    }
+
+    /// Submit a single **OUT (host-to-device)** transfer on a stream of the
+    /// specified **bulk** endpoint.
+    ///
+    /// The `stream_id` must have been allocated with
+    /// [`alloc_streams`][Self::alloc_streams].
+    ///
+    /// * An OUT endpoint address must have the top (`0x80`) bit clear, or the transfer
+    ///   fails with [`TransferError::InvalidEndpoint`].
+    #[cfg(target_os = "linux")]
+    pub fn bulk_out_stream(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+        stream_id: u32,
+        buf: Vec<u8>,
+    ) -> TransferFuture<Vec<u8>> {
+        let endpoint = match check_direction(endpoint.into(), Direction::Out, "bulk_out_stream") {
+            Ok(endpoint) => endpoint,
+            Err(e) => return TransferFuture::rejected(ResponseBuffer::from_vec(buf, 0), e),
+        };
+        let mut t = self.backend.make_stream_transfer(endpoint, stream_id);
+        t.submit(buf);
+        TransferFuture::new(t)
+    }
+
     /// Create a queue for managing multiple **IN (device-to-host)** transfers on a **bulk** endpoint.
     ///
-    /// * An IN endpoint address must have the top (`0x80`) bit set.
-    pub fn bulk_in_queue(&self, endpoint: u8) -> Queue<RequestBuffer> {
+    /// * An IN endpoint address must have the top (`0x80`) bit set, or this panics.
+    pub fn bulk_in_queue(&self, endpoint: impl Into<EndpointAddress>) -> Queue<RequestBuffer> {
+        let endpoint = assert_direction(endpoint.into(), Direction::In, "bulk_in_queue");
+        Queue::new(self.backend.clone(), endpoint, EndpointType::Bulk)
+    }
+
+    /// Set the size above which bulk transfers are split into multiple URBs.
+    ///
+    /// Some host controllers fail or misbehave with very large URBs. With a
+    /// split size set, larger bulk transfers are submitted as multiple URBs
+    /// flagged with `USBDEVFS_URB_BULK_CONTINUATION`. The transfer still
+    /// completes as a single transfer: a short packet ends it early, and its
+    /// length is the total received by all URBs up to that point.
+    ///
+    /// Splitting is disabled by default, and `0` disables it again. This
+    /// affects transfers submitted after the call. Transfers on a bulk
+    /// stream are never split.
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidInput`] if `size` is not
+    /// a multiple of the maximum packet size of every bulk endpoint in the
+    /// current alternate setting, because a short URB in the middle would end
+    /// the transfer. Returns [`ErrorKind::Unsupported`] if the kernel doesn't
+    /// [support bulk continuation][SupportedFeatures::bulk_continuation].
+    ///
+    /// ### Platform-specific notes
+    /// * Only available on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn set_bulk_split_size(&self, size: usize) -> Result<(), Error> {
+        if size != 0 {
+            let aligned = self.current_alt_descriptor().map_or(true, |alt| {
+                alt.endpoints()
+                    .filter(|ep| ep.transfer_type() == EndpointType::Bulk)
+                    .all(|ep| size % ep.max_packet_size().max(1) == 0)
+            });
+            if !aligned {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "bulk split size must be a multiple of the maximum packet size",
+                ));
+            }
+        }
+        self.backend.set_bulk_split_size(size)
+    }
+
+    /// Create a queue for managing multiple **IN (device-to-host)** transfers on a **bulk**
+    /// endpoint into caller-provided buffers.
+    ///
+    /// Submit buffers with [`Queue::submit_buffer`]. Each buffer's length must
+    /// be a multiple of the endpoint's maximum packet size.
+    ///
+    /// * An IN endpoint address must have the top (`0x80`) bit set, or this panics.
+    ///
+    /// ### Example
+    ///
+    /// ```no_run
+    /// use futures_lite::future::block_on;
+    /// # let di = nusb::list_devices().unwrap().next().unwrap();
+    /// # let device = di.open().unwrap();
+    /// # let interface = device.claim_interface(0).unwrap();
+    /// # fn handle_data(_: &[u8]) {}
+    /// let mut queue = interface.bulk_in_buffer_queue::<Box<[u8]>>(0x81);
+    ///
+    /// for _ in 0..8 {
+    ///     queue.submit_buffer(vec![0; 4096].into_boxed_slice());
+    /// }
+    ///
+    /// loop {
+    ///     let completion = block_on(queue.next_complete());
+    ///     handle_data(completion.data.data());
+    ///
+    ///     if completion.status.is_err() {
+    ///         break;
+    ///     }
+    ///
+    ///     queue.submit_buffer(completion.data.into_inner());
+    /// }
+    /// ```
+    pub fn bulk_in_buffer_queue<B: TransferBuffer>(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+    ) -> Queue<CallerBuffer<B>> {
+        let endpoint = assert_direction(endpoint.into(), Direction::In, "bulk_in_buffer_queue");
         Queue::new(self.backend.clone(), endpoint, EndpointType::Bulk)
     }
 
     /// Create a queue for managing multiple **OUT (host-to-device)** transfers on a **bulk** endpoint.
     ///
-    /// * An OUT endpoint address must have the top (`0x80`) bit clear.
-    pub fn bulk_out_queue(&self, endpoint: u8) -> Queue<Vec<u8>> {
+    /// * An OUT endpoint address must have the top (`0x80`) bit clear, or this panics.
+    pub fn bulk_out_queue(&self, endpoint: impl Into<EndpointAddress>) -> Queue<Vec<u8>> {
+        let endpoint = assert_direction(endpoint.into(), Direction::Out, "bulk_out_queue");
         Queue::new(self.backend.clone(), endpoint, EndpointType::Bulk)
     }
 
     /// Submit a single **IN (device-to-host)** transfer on the specified **interrupt** endpoint.
     ///
     /// * The requested length must be a multiple of the endpoint's maximum packet size
-    /// * An IN endpoint address must have the top (`0x80`) bit set.
-    pub fn interrupt_in(&self, endpoint: u8, buf: RequestBuffer) -> TransferFuture<RequestBuffer> {
+    /// * An IN endpoint address must have the top (`0x80`) bit set, or the transfer
+    ///   fails with [`TransferError::InvalidEndpoint`].
+    pub fn interrupt_in(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+        buf: RequestBuffer,
+    ) -> TransferFuture<RequestBuffer> {
+        let endpoint = match check_direction(endpoint.into(), Direction::In, "interrupt_in") {
+            Ok(endpoint) => endpoint,
+            Err(e) => return TransferFuture::rejected(buf.into_vec().0, e),
+        };
         let mut t = self
             .backend
             .make_transfer(endpoint, EndpointType::Interrupt);
@@ -516,8 +1747,17 @@ impl Interface {
 
     /// Submit a single **OUT (host-to-device)** transfer on the specified **interrupt** endpoint.
     ///
-    /// * An OUT endpoint address must have the top (`0x80`) bit clear.
-    pub fn interrupt_out(&self, endpoint: u8, buf: Vec<u8>) -> TransferFuture<Vec<u8>> {
+    /// * An OUT endpoint address must have the top (`0x80`) bit clear, or the transfer
+    ///   fails with [`TransferError::InvalidEndpoint`].
+    pub fn interrupt_out(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+        buf: Vec<u8>,
+    ) -> TransferFuture<Vec<u8>> {
+        let endpoint = match check_direction(endpoint.into(), Direction::Out, "interrupt_out") {
+            Ok(endpoint) => endpoint,
+            Err(e) => return TransferFuture::rejected(ResponseBuffer::from_vec(buf, 0), e),
+        };
         let mut t = self
             .backend
             .make_transfer(endpoint, EndpointType::Interrupt);
@@ -527,55 +1767,1173 @@ impl Interface {
 
     /// Create a queue for managing multiple **IN (device-to-host)** transfers on an **interrupt** endpoint.
     ///
-    /// * An IN endpoint address must have the top (`0x80`) bit set.
-    pub fn interrupt_in_queue(&self, endpoint: u8) -> Queue<RequestBuffer> {
+    /// * An IN endpoint address must have the top (`0x80`) bit set, or this panics.
+    pub fn interrupt_in_queue(&self, endpoint: impl Into<EndpointAddress>) -> Queue<RequestBuffer> {
+        let endpoint = assert_direction(endpoint.into(), Direction::In, "interrupt_in_queue");
         Queue::new(self.backend.clone(), endpoint, EndpointType::Interrupt)
     }
 
+    /// Create a [`Stream`][futures_core::Stream] of reports from an **IN (device-to-host)** **interrupt** endpoint.
+    ///
+    /// Each transfer requests `buffer_size` bytes, which should usually be
+    /// the endpoint's [maximum packet size][Self::endpoint_max_packet_size].
+    /// See [`InterruptStream`] for details of how transfers are re-submitted
+    /// and errors are handled, and use
+    /// [`InterruptStream::with_depth`] to change how many are kept pending.
+    ///
+    /// * An IN endpoint address must have the top (`0x80`) bit set, or this panics.
+    ///
+    /// ### Example
+    ///
+    /// ```no_run
+    /// use futures_lite::{future::block_on, StreamExt};
+    /// # let di = nusb::list_devices().unwrap().next().unwrap();
+    /// # let device = di.open().unwrap();
+    /// # let interface = device.claim_interface(0).unwrap();
+    /// # fn handle_report(_: &[u8]) {}
+    /// let mut reports = interface.interrupt_in_stream(0x81, 64);
+    /// while let Some(Ok(report)) = block_on(reports.next()) {
+    ///     handle_report(&report);
+    /// }
+    /// ```
+    pub fn interrupt_in_stream(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+        buffer_size: usize,
+    ) -> InterruptStream {
+        InterruptStream::new(self.interrupt_in_queue(endpoint), buffer_size)
+    }
+
     /// Create a queue for managing multiple **OUT (device-to-host)** transfers on an **interrupt** endpoint.
     ///
-    /// * An OUT endpoint address must have the top (`0x80`) bit clear.
-    pub fn interrupt_out_queue(&self, endpoint: u8) -> Queue<Vec<u8>> {
+    /// * An OUT endpoint address must have the top (`0x80`) bit clear, or this panics.
+    pub fn interrupt_out_queue(&self, endpoint: impl Into<EndpointAddress>) -> Queue<Vec<u8>> {
+        let endpoint = assert_direction(endpoint.into(), Direction::Out, "interrupt_out_queue");
         Queue::new(self.backend.clone(), endpoint, EndpointType::Interrupt)
     }
 
-    /// Clear a bulk or interrupt endpoint's halt / stall condition.
+    /// Create a queue for managing multiple **IN (device-to-host)** transfers on an **isochronous** endpoint.
     ///
-    /// Sends a `CLEAR_FEATURE` `ENDPOINT_HALT` control transfer to tell the
-    /// device to reset the endpoint's data toggle and clear the halt / stall
-    /// condition, and resets the host-side data toggle.
+    /// Each transfer is submitted with an
+    /// [`IsoRequestBuffer`][crate::transfer::IsoRequestBuffer] specifying the
+    /// number of packets and the size of each packet, which should normally
+    /// be the endpoint's maximum packet size. The completion reports the
+    /// length and status of each packet.
     ///
-    /// Use this after receiving [`TransferError::Stall`] to clear the error and
-    /// resume use of the endpoint.
+    /// * An IN endpoint address must have the top (`0x80`) bit set, or this panics.
+    /// * The interface's alternate setting must have been selected to one where the endpoint
+    ///   has nonzero bandwidth.
     ///
-    /// This should not be called when transfers are pending on the endpoint.
-    pub fn clear_halt(&self, endpoint: u8) -> Result<(), Error> {
-        self.backend.clear_halt(endpoint)
+    /// ### Platform-specific notes
+    /// * Only supported on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn iso_in_queue(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+    ) -> Queue<crate::transfer::IsoRequestBuffer> {
+        let endpoint = assert_direction(endpoint.into(), Direction::In, "iso_in_queue");
+        Queue::new(self.backend.clone(), endpoint, EndpointType::Isochronous)
     }
 
-    /// Get the interface number.
-    pub fn interface_number(&self) -> u8 {
-        self.backend.interface_number
+    /// Create a queue for managing multiple **OUT (host-to-device)** transfers on an **isochronous** endpoint.
+    ///
+    /// Each transfer is submitted with an
+    /// [`IsoOutBuffer`][crate::transfer::IsoOutBuffer] that splits the data
+    /// into packets. The completion reports the length and status of each
+    /// packet.
+    ///
+    /// * An OUT endpoint address must have the top (`0x80`) bit clear, or this panics.
+    /// * The interface's alternate setting must have been selected to one where the endpoint
+    ///   has nonzero bandwidth.
+    ///
+    /// ### Platform-specific notes
+    /// * Only supported on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn iso_out_queue(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+    ) -> Queue<crate::transfer::IsoOutBuffer> {
+        let endpoint = assert_direction(endpoint.into(), Direction::Out, "iso_out_queue");
+        Queue::new(self.backend.clone(), endpoint, EndpointType::Isochronous)
     }
 
-    /// Get the interface descriptors for the alternate settings of this interface.
+    /// Synchronously perform a single **IN (device-to-host)** transfer on the specified **bulk** endpoint.
     ///
-    /// This returns cached data and does not perform IO.
-    pub fn descriptors(&self) -> impl Iterator<Item = InterfaceAltSetting> {
-        let active = self.backend.device.active_configuration_value();
-
-        let configuration = self
-            .backend
-            .device
-            .configuration_descriptors()
-            .map(Configuration::new)
-            .find(|c| c.configuration_value() == active);
+    /// Blocks the calling thread until the transfer completes, or cancels it
+    /// and returns [`TransferError::TimedOut`] after `timeout`. Returns the
+    /// number of bytes read into `data`.
+    ///
+    /// * The length of `data` must be a multiple of the endpoint's maximum packet size
+    /// * An IN endpoint address must have the top (`0x80`) bit set, or this
+    ///   returns [`TransferError::InvalidEndpoint`].
+    pub fn bulk_in_blocking(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+        data: &mut [u8],
+        timeout: Duration,
+    ) -> Result<usize, TransferError> {
+        let fut = self.bulk_in(endpoint, RequestBuffer::new(data.len()));
+        read_blocking(fut.with_timeout(timeout), data)
+    }
 
-        configuration
+    /// Synchronously perform a single **OUT (host-to-device)** transfer on the specified **bulk** endpoint.
+    ///
+    /// Blocks the calling thread until the transfer completes, or cancels it
+    /// and returns [`TransferError::TimedOut`] after `timeout`. Returns the
+    /// number of bytes written.
+    ///
+    /// * An OUT endpoint address must have the top (`0x80`) bit clear, or this
+    ///   returns [`TransferError::InvalidEndpoint`].
+    pub fn bulk_out_blocking(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+        data: &[u8],
+        timeout: Duration,
+    ) -> Result<usize, TransferError> {
+        let fut = self.bulk_out(endpoint, data.to_vec());
+        write_blocking(fut.with_timeout(timeout))
+    }
+
+    /// Synchronously perform a single **IN (device-to-host)** transfer on the specified **interrupt** endpoint.
+    ///
+    /// Blocks the calling thread until the transfer completes, or cancels it
+    /// and returns [`TransferError::TimedOut`] after `timeout`. Returns the
+    /// number of bytes read into `data`.
+    ///
+    /// * The length of `data` must be a multiple of the endpoint's maximum packet size
+    /// * An IN endpoint address must have the top (`0x80`) bit set, or this
+    ///   returns [`TransferError::InvalidEndpoint`].
+    pub fn interrupt_in_blocking(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+        data: &mut [u8],
+        timeout: Duration,
+    ) -> Result<usize, TransferError> {
+        let fut = self.interrupt_in(endpoint, RequestBuffer::new(data.len()));
+        read_blocking(fut.with_timeout(timeout), data)
+    }
+
+    /// Synchronously perform a single **OUT (host-to-device)** transfer on the specified **interrupt** endpoint.
+    ///
+    /// Blocks the calling thread until the transfer completes, or cancels it
+    /// and returns [`TransferError::TimedOut`] after `timeout`. Returns the
+    /// number of bytes written.
+    ///
+    /// * An OUT endpoint address must have the top (`0x80`) bit clear, or this
+    ///   returns [`TransferError::InvalidEndpoint`].
+    pub fn interrupt_out_blocking(
+        &self,
+        endpoint: impl Into<EndpointAddress>,
+        data: &[u8],
+        timeout: Duration,
+    ) -> Result<usize, TransferError> {
+        let fut = self.interrupt_out(endpoint, data.to_vec());
+        write_blocking(fut.with_timeout(timeout))
+    }
+
+    /// Clear a bulk or interrupt endpoint's halt / stall condition.
+    ///
+    /// Sends a `CLEAR_FEATURE` `ENDPOINT_HALT` control transfer to tell the
+    /// device to reset the endpoint's data toggle and clear the halt / stall
+    /// condition, and resets the host-side data toggle.
+    ///
+    /// Use this after receiving [`TransferError::Stall`] to clear the error and
+    /// resume use of the endpoint.
+    ///
+    /// This should not be called when transfers are pending on the endpoint.
+    ///
+    /// See also [`endpoint_stalled`][Self::endpoint_stalled] to check whether
+    /// the device reports the endpoint as halted.
+    pub fn clear_halt(&self, endpoint: u8) -> Result<(), Error> {
+        check_connected(self.backend.clear_halt(endpoint), &self.backend.device)
+    }
+
+    /// Request the alternate setting the device reports as active for this
+    /// interface, with a standard `GET_INTERFACE` request.
+    ///
+    /// Unlike the setting cached by [`set_alt_setting`][Self::set_alt_setting],
+    /// this is read from the device, so it can be used to verify that the
+    /// device switched. Returns the raw `bAlternateSetting` value.
+    #[doc(alias = "GET_INTERFACE")]
+    pub fn current_alt_setting(&self, timeout: Duration) -> Result<u8, Error> {
+        const STANDARD_REQUEST_GET_INTERFACE: u8 = 0x0A;
+        use crate::transfer::{ControlType, Recipient};
+
+        let mut buf = [0; 1];
+        let len = self.control_in_blocking(
+            Control {
+                control_type: ControlType::Standard,
+                recipient: Recipient::Interface,
+                request: STANDARD_REQUEST_GET_INTERFACE,
+                value: 0,
+                index: self.interface_number().into(),
+            },
+            &mut buf,
+            timeout,
+        )?;
+
+        if len != buf.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "GET_INTERFACE response was too short",
+            ));
+        }
+
+        Ok(buf[0])
+    }
+
+    /// Cancel every pending transfer on an endpoint.
+    ///
+    /// Transfers submitted from any [`TransferFuture`] or [`Queue`] on the
+    /// endpoint complete with [`TransferError::Cancelled`]. Use this to get
+    /// the endpoint to a known state when a higher-level protocol needs to
+    /// resynchronize, before calling [`clear_halt`][Self::clear_halt].
+    ///
+    /// Cancellation is asynchronous: the transfers may complete, possibly
+    /// with data, after this returns. Await them before reusing the
+    /// endpoint.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this uses `USBDEVFS_DISCARDURB` on each pending URB of
+    ///   the endpoint.
+    /// * On Windows, this uses `WinUsb_AbortPipe`.
+    /// * On macOS, this uses `AbortPipe`.
+    #[doc(alias = "AbortPipe")]
+    pub fn cancel_all(&self, endpoint: u8) -> Result<(), Error> {
+        check_connected(self.backend.cancel_all(endpoint), &self.backend.device)
+    }
+
+    /// Check whether an endpoint is halted / stalled.
+    ///
+    /// Sends a standard `GET_STATUS` request to the endpoint and returns
+    /// whether the `ENDPOINT_HALT` feature is set. Blocks the calling thread
+    /// until the request completes or `timeout` elapses.
+    pub fn endpoint_stalled(&self, endpoint: u8, timeout: Duration) -> Result<bool, Error> {
+        const ENDPOINT_STATUS_HALT: u16 = 0x0001;
+        use crate::transfer::{ControlType, Recipient};
+
+        let mut buf = [0; 2];
+        let len = self.control_in_blocking(
+            Control {
+                control_type: ControlType::Standard,
+                recipient: Recipient::Endpoint,
+                request: STANDARD_REQUEST_GET_STATUS,
+                value: 0,
+                index: endpoint as u16,
+            },
+            &mut buf,
+            timeout,
+        )?;
+
+        if len != buf.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "endpoint status response was too short",
+            ));
+        }
+
+        Ok(u16::from_le_bytes(buf) & ENDPOINT_STATUS_HALT != 0)
+    }
+
+    /// Request the Microsoft OS 2.0 descriptor set from the device.
+    ///
+    /// This is the same as [`Device::read_ms_os_20_descriptor`], but sends
+    /// the vendor request through this interface so it also works on
+    /// Windows, where the descriptor set is read live from the device rather
+    /// than from the copy cached in the registry.
+    pub fn read_ms_os_20_descriptor(&self, timeout: Duration) -> Result<Vec<u8>, Error> {
+        let device = Device {
+            backend: self.backend.device.clone(),
+            speed: self.speed,
+            id: None,
+            enumerated: None,
+        };
+        let bos = device.bos_descriptor(timeout)?;
+        read_ms_os_20_descriptor_set(&bos, |control, buf| {
+            self.control_in_blocking(control, buf, timeout)
+        })
+    }
+
+    /// Request the Microsoft OS 1.0 descriptors from the device.
+    ///
+    /// This is the same as [`Device::read_ms_os_10_descriptors`], but sends
+    /// the vendor requests through this interface so it also works on
+    /// Windows.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Windows, the Extended Properties requests are sent to the
+    ///   device rather than the interface, because WinUSB replaces the low
+    ///   byte of `wIndex` of interface requests with the interface number.
+    /// * On Windows, a device without a Microsoft OS string descriptor
+    ///   returns an error of kind [`ErrorKind::Other`] rather than
+    ///   [`ErrorKind::NotFound`], because the hub driver doesn't distinguish
+    ///   a stall from other failures.
+    pub fn read_ms_os_10_descriptors(&self, timeout: Duration) -> Result<MsOs10Descriptors, Error> {
+        let device = Device {
+            backend: self.backend.device.clone(),
+            speed: self.speed,
+            id: None,
+            enumerated: None,
+        };
+        read_ms_os_10_descriptors(
+            &device,
+            |control, buf| self.control_in_blocking(control, buf, timeout),
+            timeout,
+        )
+    }
+
+    /// Request the HID report descriptor of this interface.
+    ///
+    /// This finds the length of the report descriptor in the HID class
+    /// descriptor following the interface descriptor, then reads it with a
+    /// standard `GET_DESCRIPTOR` request directed at the interface. The raw
+    /// bytes are returned for a HID report parser.
+    ///
+    /// Returns an error of kind [`ErrorKind::NotFound`] if the interface has
+    /// no HID descriptor with a report descriptor.
+    #[doc(alias = "GET_DESCRIPTOR")]
+    pub fn hid_report_descriptor(&self, timeout: Duration) -> Result<Vec<u8>, Error> {
+        const STANDARD_REQUEST_GET_DESCRIPTOR: u8 = 0x06;
+        use crate::transfer::{ControlType, Recipient};
+
+        let length = self
+            .current_alt_descriptor()
+            .and_then(|alt| hid_report_descriptor_len(&alt))
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    "interface has no HID report descriptor",
+                )
+            })?;
+
+        let mut buf = vec![0; length as usize];
+        let len = self.control_in_blocking(
+            Control {
+                control_type: ControlType::Standard,
+                recipient: Recipient::Interface,
+                request: STANDARD_REQUEST_GET_DESCRIPTOR,
+                value: (DESCRIPTOR_TYPE_HID_REPORT as u16) << 8,
+                index: self.interface_number() as u16,
+            },
+            &mut buf,
+            timeout,
+        )?;
+        buf.truncate(len);
+        Ok(buf)
+    }
+
+    /// Release the interface, returning any error from the OS.
+    ///
+    /// The interface is otherwise released when this `Interface`, its clones,
+    /// and all associated [`TransferFuture`]s and [`Queue`]s are dropped, and
+    /// errors are only logged. Returns an error of kind [`ErrorKind::Other`]
+    /// without releasing the interface if any of those still exist; it is
+    /// then released when they are dropped.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this uses `USBDEVFS_RELEASEINTERFACE`, and reattaches
+    ///   kernel drivers if the interface was claimed with
+    ///   [`Device::detach_and_claim_interface`].
+    /// * On Windows, this frees the WinUSB handle for the interface.
+    /// * On macOS, this uses `USBInterfaceClose`.
+    pub fn release(self) -> Result<(), Error> {
+        match Arc::try_unwrap(self.backend) {
+            Ok(backend) => backend.release(),
+            Err(_) => Err(Error::other(
+                "interface is still in use by clones, transfers, or queues",
+            )),
+        }
+    }
+
+    /// Get the interface number.
+    pub fn interface_number(&self) -> u8 {
+        self.backend.interface_number
+    }
+
+    /// Get the interface descriptors for the alternate settings of this interface.
+    ///
+    /// This returns cached data and does not perform IO.
+    pub fn descriptors(&self) -> impl Iterator<Item = InterfaceAltSetting<'_>> {
+        let active = self.backend.device.active_configuration_value();
+
+        let configuration = self
+            .backend
+            .device
+            .configuration_descriptors()
+            .map(Configuration::new)
+            .find(|c| c.configuration_value() == active);
+
+        configuration
             .into_iter()
             .flat_map(|i| i.interface_alt_settings())
             .filter(|g| g.interface_number() == self.backend.interface_number)
     }
+
+    /// Get the number of alternate settings of this interface.
+    ///
+    /// This counts the interface descriptors with this interface number in
+    /// the active configuration, so it is at least 1 unless the descriptors
+    /// are unavailable. Like [`descriptors`][Self::descriptors], this returns
+    /// cached data and does not perform IO, so it can be used to find an
+    /// alternate setting with the needed bandwidth before calling
+    /// [`set_alt_setting`][Self::set_alt_setting].
+    pub fn num_alt_settings(&self) -> usize {
+        self.descriptors().count()
+    }
+
+    /// Get the descriptor of the current alternate setting.
+    fn current_alt_descriptor(&self) -> Option<InterfaceAltSetting<'_>> {
+        let alt_setting = self.backend.get_alt_setting();
+        self.descriptors()
+            .find(|a| a.alternate_setting() == alt_setting)
+    }
+
+    /// Look up an endpoint in the descriptor of the current alternate setting.
+    fn with_endpoint<T>(&self, endpoint: u8, f: impl FnOnce(&Endpoint) -> T) -> Option<T> {
+        let alt = self.current_alt_descriptor()?;
+        let ep = alt.endpoints().find(|e| e.address() == endpoint)?;
+        Some(f(&ep))
+    }
+
+    /// Get the maximum packet size of an endpoint in the current alternate
+    /// setting.
+    ///
+    /// Bulk and interrupt IN transfers should request a multiple of this
+    /// size, otherwise a device sending a full packet that doesn't fit in
+    /// the buffer causes an overflow error.
+    ///
+    /// Returns `None` if the endpoint is not part of the current alternate
+    /// setting. Like [`descriptors`][Self::descriptors], this returns cached
+    /// data and does not perform IO.
+    #[doc(alias = "wMaxPacketSize")]
+    pub fn endpoint_max_packet_size(&self, endpoint: u8) -> Option<u16> {
+        self.with_endpoint(endpoint, |ep| ep.max_packet_size() as u16)
+    }
+
+    /// Get the transfer type of an endpoint in the current alternate setting.
+    ///
+    /// Returns `None` if the endpoint is not part of the current alternate
+    /// setting.
+    pub fn endpoint_transfer_type(&self, endpoint: u8) -> Option<EndpointType> {
+        self.with_endpoint(endpoint, |ep| ep.transfer_type())
+    }
+
+    /// Get the `bInterval` polling interval of an endpoint in the current
+    /// alternate setting.
+    ///
+    /// The unit depends on the speed and transfer type; see the USB
+    /// specification. Returns `None` if the endpoint is not part of the
+    /// current alternate setting.
+    #[doc(alias = "bInterval")]
+    pub fn endpoint_interval(&self, endpoint: u8) -> Option<u8> {
+        self.with_endpoint(endpoint, |ep| ep.interval())
+    }
+
+    /// Get the endpoints of the current alternate setting.
+    ///
+    /// This allows finding endpoints by direction and transfer type rather
+    /// than hard-coding their addresses. The endpoints are returned in
+    /// descriptor order, and the default control endpoint is not included.
+    ///
+    /// Like [`descriptors`][Self::descriptors], this returns cached data and
+    /// does not perform IO.
+    pub fn endpoints(&self) -> Vec<EndpointInfo> {
+        self.current_alt_descriptor().map_or(Vec::new(), |alt| {
+            alt.endpoints()
+                .map(|ep| EndpointInfo::new(&ep, self.speed))
+                .collect()
+        })
+    }
+
+    /// Get the raw `WINUSB_INTERFACE_HANDLE` of the interface.
+    ///
+    /// This allows calling `WinUsb_*` functions that `nusb` doesn't wrap. The
+    /// handle is borrowed from this `Interface` and is only valid while it is
+    /// claimed. Do not free it with `WinUsb_Free`.
+    ///
+    /// ### Panics
+    /// Panics on an interface of a virtual device from the
+    /// `mock` backend, which has no WinUSB handle.
+    #[cfg(target_os = "windows")]
+    pub fn as_raw_winusb_handle(&self) -> isize {
+        crate::platform::os_interface(&self.backend).winusb_handle
+    }
+
+    /// Get the raw `IOUSBInterfaceInterface500**` used to access the
+    /// interface.
+    ///
+    /// This allows calling IOKit functions that `nusb` doesn't wrap. The
+    /// pointer is borrowed from this `Interface` and is only valid while it
+    /// is claimed. Do not close or release it.
+    ///
+    /// ### Panics
+    /// Panics on an interface of a virtual device from the
+    /// `mock` backend, which has no IOKit interface.
+    #[cfg(target_os = "macos")]
+    pub fn as_raw_iokit_interface(&self) -> *mut std::ffi::c_void {
+        crate::platform::os_interface(&self.backend)
+            .interface
+            .raw
+            .cast()
+    }
+}
+
+/// Borrow the usbfs file descriptor of the device the interface belongs to.
+///
+/// On Linux, all interfaces of a device share the device's file descriptor,
+/// so this is the same as [`Device`]'s. It must not be closed.
+///
+/// Panics on an interface of a virtual device from the `mock`
+/// backend, which has no file descriptor.
+#[cfg(target_os = "linux")]
+impl std::os::fd::AsFd for Interface {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.backend.device.as_fd()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl std::os::fd::AsRawFd for Interface {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        std::os::fd::AsRawFd::as_raw_fd(&self.backend.device.as_fd())
+    }
+}
+
+/// Borrow the file handle WinUSB was opened with for the interface.
+///
+/// The handle is owned by `nusb` and must not be closed. Interfaces of a
+/// composite device that share a WinUSB function share the same handle.
+///
+/// Panics on an interface of a virtual device from the `mock`
+/// backend, which has no handle.
+#[cfg(target_os = "windows")]
+impl std::os::windows::io::AsRawHandle for Interface {
+    fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
+        crate::platform::os_interface(&self.backend).handle
+    }
+}
+
+/// Summary of an endpoint of a claimed interface, returned by
+/// [`Interface::endpoints`].
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct EndpointInfo {
+    address: u8,
+    direction: Direction,
+    transfer_type: EndpointType,
+    max_packet_size: u16,
+    max_burst: u8,
+    interval: u8,
+    interval_microframes: Option<u32>,
+    bytes_per_interval: Option<u32>,
+}
+
+impl EndpointInfo {
+    /// Summarize `ep` for a device operating at `speed`, which is needed to
+    /// interpret `bInterval`.
+    pub(crate) fn new(ep: &Endpoint<'_>, speed: Option<Speed>) -> EndpointInfo {
+        let transfer_type = ep.transfer_type();
+        let periodic = matches!(
+            transfer_type,
+            EndpointType::Interrupt | EndpointType::Isochronous
+        );
+        let companion = ep.superspeed_companion();
+
+        // The SuperSpeed companion descriptor is only present when operating
+        // at SuperSpeed, so it identifies the speed if it wasn't reported.
+        let speed = speed.or(companion.as_ref().map(|_| Speed::Super));
+
+        let interval = ep.interval();
+        let exponential = 1u32 << (interval.clamp(1, 16) - 1);
+        let interval_microframes = match (periodic, transfer_type, speed) {
+            (false, _, _) | (_, _, None) => None,
+            (_, EndpointType::Interrupt, Some(Speed::Low | Speed::Full)) => {
+                Some(u32::from(interval) * 8)
+            }
+            (_, _, Some(Speed::Low | Speed::Full)) => Some(exponential * 8),
+            (_, _, Some(_)) => Some(exponential),
+        };
+
+        let bytes_per_interval = periodic.then(|| match &companion {
+            Some(c) => u32::from(c.bytes_per_interval()),
+            None => ep.max_packet_size() as u32 * u32::from(ep.packets_per_microframe()),
+        });
+
+        EndpointInfo {
+            address: ep.address(),
+            direction: ep.direction(),
+            transfer_type,
+            max_packet_size: ep.max_packet_size() as u16,
+            max_burst: ep.max_burst(),
+            interval,
+            interval_microframes,
+            bytes_per_interval,
+        }
+    }
+
+    /// Endpoint address, from the `bEndpointAddress` descriptor field.
+    ///
+    /// Pass this to the transfer methods of [`Interface`].
+    #[doc(alias = "bEndpointAddress")]
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Direction of the endpoint.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Transfer type of the endpoint.
+    pub fn transfer_type(&self) -> EndpointType {
+        self.transfer_type
+    }
+
+    /// Maximum packet size in bytes.
+    #[doc(alias = "wMaxPacketSize")]
+    pub fn max_packet_size(&self) -> u16 {
+        self.max_packet_size
+    }
+
+    /// Number of packets in a burst on a SuperSpeed endpoint, or 1 if the
+    /// device is not operating at SuperSpeed.
+    ///
+    /// For full throughput, transfers should be a multiple of
+    /// `max_packet_size * max_burst` bytes. See [`Endpoint::max_burst`].
+    #[doc(alias = "bMaxBurst")]
+    pub fn max_burst(&self) -> u8 {
+        self.max_burst
+    }
+
+    /// Polling interval, from the `bInterval` descriptor field.
+    ///
+    /// The unit and encoding depend on the speed and transfer type. See
+    /// [`interval_microframes`][Self::interval_microframes] for the decoded
+    /// value.
+    #[doc(alias = "bInterval")]
+    pub fn interval(&self) -> u8 {
+        self.interval
+    }
+
+    /// Service interval of an interrupt or isochronous endpoint, in units of
+    /// 125 µs microframes.
+    ///
+    /// This decodes [`interval`][Self::interval] according to the device's
+    /// speed: at full and low speed, interrupt endpoints specify a number of
+    /// 1 ms frames, and all other periodic endpoints use the exponent form
+    /// `2^(bInterval - 1)`.
+    ///
+    /// Returns `None` for bulk and control endpoints, or if the speed of the
+    /// device is unknown.
+    pub fn interval_microframes(&self) -> Option<u32> {
+        self.interval_microframes
+    }
+
+    /// Maximum number of bytes the endpoint transfers in each service
+    /// interval, for interrupt or isochronous endpoints.
+    ///
+    /// At SuperSpeed, this is the `wBytesPerInterval` field of the
+    /// [SuperSpeed companion descriptor][Endpoint::superspeed_companion].
+    /// Otherwise, it is the maximum packet size multiplied by the number of
+    /// transactions per microframe from [`Endpoint::packets_per_microframe`].
+    ///
+    /// Together with [`interval_microframes`][Self::interval_microframes],
+    /// this gives the bandwidth the host controller reserves for the
+    /// endpoint. Returns `None` for bulk and control endpoints.
+    #[doc(alias = "wBytesPerInterval")]
+    pub fn bytes_per_interval(&self) -> Option<u32> {
+        self.bytes_per_interval
+    }
+}
+
+/// Summarize an endpoint without knowing the device's speed, so
+/// [`EndpointInfo::interval_microframes`] is only available for SuperSpeed
+/// endpoints.
+impl From<&Endpoint<'_>> for EndpointInfo {
+    fn from(ep: &Endpoint<'_>) -> Self {
+        EndpointInfo::new(ep, None)
+    }
+}
+
+impl std::fmt::Debug for EndpointInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EndpointInfo")
+            .field("address", &format_args!("0x{:02X}", self.address))
+            .field("direction", &self.direction)
+            .field("transfer_type", &self.transfer_type)
+            .field("max_packet_size", &self.max_packet_size)
+            .field("max_burst", &self.max_burst)
+            .field("interval", &self.interval)
+            .field("interval_microframes", &self.interval_microframes)
+            .field("bytes_per_interval", &self.bytes_per_interval)
+            .finish()
+    }
+}
+
+const STANDARD_REQUEST_GET_STATUS: u8 = 0x00;
+
+const DESCRIPTOR_TYPE_HID: u8 = 0x21;
+const DESCRIPTOR_TYPE_HID_REPORT: u8 = 0x22;
+
+/// Find the report descriptor length in the HID descriptor of an interface.
+///
+/// The HID descriptor is followed by `bNumDescriptors` pairs of
+/// `bDescriptorType` and `wDescriptorLength` for its class descriptors.
+fn hid_report_descriptor_len(alt: &InterfaceAltSetting) -> Option<u16> {
+    let hid = alt
+        .descriptors()
+        .find(|d| d.descriptor_type() == DESCRIPTOR_TYPE_HID)?;
+    let num_descriptors = *hid.get(5)? as usize;
+    hid.get(6..)?
+        .chunks_exact(3)
+        .take(num_descriptors)
+        .find(|c| c[0] == DESCRIPTOR_TYPE_HID_REPORT)
+        .map(|c| u16::from_le_bytes([c[1], c[2]]))
+}
+
+/// Reattaches kernel drivers to an interface when dropped.
+///
+/// Returned by [`Device::detach_kernel_driver_guard`]. Failure to reattach
+/// when dropped is logged rather than reported; call
+/// [`reattach`][Self::reattach] to handle the error.
+#[must_use = "kernel drivers are reattached when the guard is dropped"]
+pub struct DetachGuard {
+    device: Device,
+    interface: u8,
+    detached: bool,
+}
+
+impl DetachGuard {
+    /// Interface number the kernel drivers were detached from.
+    pub fn interface_number(&self) -> u8 {
+        self.interface
+    }
+
+    /// Whether a kernel driver was detached and will be reattached.
+    pub fn detached(&self) -> bool {
+        self.detached
+    }
+
+    /// Reattach kernel drivers now, reporting any error.
+    pub fn reattach(mut self) -> Result<(), Error> {
+        self.reattach_inner()
+    }
+
+    fn reattach_inner(&mut self) -> Result<(), Error> {
+        if std::mem::take(&mut self.detached) {
+            self.device.attach_kernel_driver(self.interface)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Drop for DetachGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.reattach_inner() {
+            warn!(
+                "Failed to reattach kernel drivers for interface {}: {e}",
+                self.interface
+            );
+        }
+    }
+}
+
+impl std::fmt::Debug for DetachGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DetachGuard")
+            .field("interface", &self.interface)
+            .field("detached", &self.detached)
+            .finish()
+    }
+}
+
+/// Implementation of [`Device::recommended_max_transfer_size`], with `limit`
+/// the largest bulk transfer the OS accepts.
+fn recommended_transfer_size(
+    info: &EndpointInfo,
+    speed: Option<Speed>,
+    limit: Option<usize>,
+) -> usize {
+    let packet = usize::from(info.max_packet_size()).max(1);
+    if info.transfer_type() != EndpointType::Bulk {
+        return info.bytes_per_interval().map_or(packet, |b| b as usize);
+    }
+
+    let chunk = packet * usize::from(info.max_burst().max(1));
+    let target = match speed {
+        Some(Speed::Low | Speed::Full) => 16 * 1024,
+        Some(Speed::High) | None => 64 * 1024,
+        Some(Speed::Super) => 256 * 1024,
+        Some(Speed::SuperPlus) => 1024 * 1024,
+    };
+    let target = limit.map_or(target, |l| target.min(l));
+
+    // Round down to whole bursts, or whole packets if a burst is too large.
+    if target >= chunk {
+        target / chunk * chunk
+    } else {
+        (target / packet).max(1) * packet
+    }
+}
+
+/// Names of the device descriptor fields compared by
+/// [`Device::verify_descriptors`].
+const DEVICE_DESCRIPTOR_FIELDS: [&str; 8] = [
+    "idVendor",
+    "idProduct",
+    "bcdDevice",
+    "bDeviceClass",
+    "bDeviceSubClass",
+    "bDeviceProtocol",
+    "bMaxPacketSize0",
+    "bNumConfigurations",
+];
+
+fn enumerated_device_fields(d: &DeviceInfo) -> [u16; DEVICE_DESCRIPTOR_FIELDS.len()] {
+    [
+        d.vendor_id(),
+        d.product_id(),
+        d.device_version(),
+        d.class().into(),
+        d.subclass().into(),
+        d.protocol().into(),
+        d.max_packet_size_0().into(),
+        d.num_configurations().into(),
+    ]
+}
+
+fn device_descriptor_fields(data: &[u8]) -> Option<[u16; DEVICE_DESCRIPTOR_FIELDS.len()]> {
+    if data.len() < DESCRIPTOR_LEN_DEVICE as usize || data[1] != DESCRIPTOR_TYPE_DEVICE {
+        return None;
+    }
+    let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+    Some([
+        u16_at(8),
+        u16_at(10),
+        u16_at(12),
+        data[4].into(),
+        data[5].into(),
+        data[6].into(),
+        data[7].into(),
+        data[17].into(),
+    ])
+}
+
+fn compare_device_fields(enumerated: &[u16], live: &[u16]) -> Vec<DescriptorMismatch> {
+    DEVICE_DESCRIPTOR_FIELDS
+        .iter()
+        .zip(enumerated.iter().zip(live))
+        .filter(|(_, (e, l))| e != l)
+        .map(|(&field, (&enumerated, &live))| DescriptorMismatch {
+            field,
+            enumerated,
+            live,
+        })
+        .collect()
+}
+
+/// A device descriptor field that differs between enumeration and the
+/// device, returned by [`Device::verify_descriptors`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DescriptorMismatch {
+    field: &'static str,
+    enumerated: u16,
+    live: u16,
+}
+
+impl DescriptorMismatch {
+    /// Name of the descriptor field, like `idProduct`.
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+
+    /// Value of the field in the [`DeviceInfo`] the device was opened from.
+    pub fn enumerated(&self) -> u16 {
+        self.enumerated
+    }
+
+    /// Value of the field in the descriptor read from the device.
+    pub fn live(&self) -> u16 {
+        self.live
+    }
+}
+
+/// Device status returned by [`Device::status`].
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct DeviceStatus(u16);
+
+impl DeviceStatus {
+    /// Whether the device is currently self-powered.
+    pub fn self_powered(&self) -> bool {
+        self.0 & (1 << 0) != 0
+    }
+
+    /// Whether the device is currently enabled to request remote wakeup.
+    pub fn remote_wakeup(&self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+
+    /// Raw status bits.
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+}
+
+impl std::fmt::Debug for DeviceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceStatus")
+            .field("self_powered", &self.self_powered())
+            .field("remote_wakeup", &self.remote_wakeup())
+            .finish()
+    }
+}
+
+/// Kernel features available for a device, returned by
+/// [`Device::supported_features`].
+///
+/// These are the `USBDEVFS_CAP_*` flags reported by the Linux kernel.
+#[cfg(target_os = "linux")]
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct SupportedFeatures(u32);
+
+#[cfg(target_os = "linux")]
+impl SupportedFeatures {
+    /// Whether OUT transfers can be terminated with a zero-length packet
+    /// when their length is a multiple of the maximum packet size.
+    #[doc(alias = "USBDEVFS_CAP_ZERO_PACKET")]
+    pub fn zero_packet(&self) -> bool {
+        self.0 & platform::USBDEVFS_CAP_ZERO_PACKET != 0
+    }
+
+    /// Whether large bulk transfers can be split across multiple URBs that
+    /// complete as one transfer.
+    ///
+    /// Without this, [`Interface::set_bulk_split_size`] fails and bulk
+    /// transfers are always submitted as a single URB.
+    #[doc(alias = "USBDEVFS_CAP_BULK_CONTINUATION")]
+    pub fn bulk_continuation(&self) -> bool {
+        self.0 & platform::USBDEVFS_CAP_BULK_CONTINUATION != 0
+    }
+
+    /// Whether the kernel accepts bulk transfers larger than 16 KiB in a
+    /// single URB.
+    #[doc(alias = "USBDEVFS_CAP_NO_PACKET_SIZE_LIM")]
+    pub fn no_packet_size_limit(&self) -> bool {
+        self.0 & platform::USBDEVFS_CAP_NO_PACKET_SIZE_LIM != 0
+    }
+
+    /// Whether the host controller supports scatter-gather for bulk
+    /// transfers, so large transfers don't require a contiguous kernel
+    /// buffer.
+    #[doc(alias = "USBDEVFS_CAP_BULK_SCATTER_GATHER")]
+    pub fn bulk_scatter_gather(&self) -> bool {
+        self.0 & platform::USBDEVFS_CAP_BULK_SCATTER_GATHER != 0
+    }
+
+    /// Whether completed transfers can still be reaped after the device is
+    /// disconnected.
+    #[doc(alias = "USBDEVFS_CAP_REAP_AFTER_DISCONNECT")]
+    pub fn reap_after_disconnect(&self) -> bool {
+        self.0 & platform::USBDEVFS_CAP_REAP_AFTER_DISCONNECT != 0
+    }
+
+    /// Whether transfer buffers can be allocated with `mmap` on the device
+    /// file for zero-copy transfers.
+    #[doc(alias = "USBDEVFS_CAP_MMAP")]
+    pub fn mmap(&self) -> bool {
+        self.0 & platform::USBDEVFS_CAP_MMAP != 0
+    }
+
+    /// Whether `USBDEVFS_DROP_PRIVILEGES` is available to restrict the file
+    /// descriptor before passing it to a less privileged process.
+    #[doc(alias = "USBDEVFS_CAP_DROP_PRIVILEGES")]
+    pub fn drop_privileges(&self) -> bool {
+        self.0 & platform::USBDEVFS_CAP_DROP_PRIVILEGES != 0
+    }
+
+    /// Whether `USBDEVFS_CONNINFO_EX` is available to read the device's
+    /// speed and port path.
+    #[doc(alias = "USBDEVFS_CAP_CONNINFO_EX")]
+    pub fn conninfo_ex(&self) -> bool {
+        self.0 & platform::USBDEVFS_CAP_CONNINFO_EX != 0
+    }
+
+    /// Whether the device can be suspended and resumed through usbfs.
+    #[doc(alias = "USBDEVFS_CAP_SUSPEND")]
+    pub fn suspend(&self) -> bool {
+        self.0 & platform::USBDEVFS_CAP_SUSPEND != 0
+    }
+
+    /// Raw `USBDEVFS_CAP_*` bits.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl std::fmt::Debug for SupportedFeatures {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SupportedFeatures")
+            .field("zero_packet", &self.zero_packet())
+            .field("bulk_continuation", &self.bulk_continuation())
+            .field("no_packet_size_limit", &self.no_packet_size_limit())
+            .field("bulk_scatter_gather", &self.bulk_scatter_gather())
+            .field("reap_after_disconnect", &self.reap_after_disconnect())
+            .field("mmap", &self.mmap())
+            .field("drop_privileges", &self.drop_privileges())
+            .field("conninfo_ex", &self.conninfo_ex())
+            .field("suspend", &self.suspend())
+            .finish()
+    }
+}
+
+/// Request the descriptor set described by the MS OS 2.0 platform capability.
+fn read_ms_os_20_descriptor_set(
+    bos: &BosDescriptor,
+    control_in: impl FnOnce(Control, &mut [u8]) -> Result<usize, TransferError>,
+) -> Result<Vec<u8>, Error> {
+    const MS_OS_20_DESCRIPTOR_INDEX: u16 = 0x07;
+    use crate::transfer::{ControlType, Recipient};
+
+    let platform = bos.ms_os_20_platform().ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            "device has no Microsoft OS 2.0 platform capability",
+        )
+    })?;
+
+    let mut buf = vec![0; platform.descriptor_set_total_length() as usize];
+    let len = control_in(
+        Control {
+            control_type: ControlType::Vendor,
+            recipient: Recipient::Device,
+            request: platform.vendor_code(),
+            value: 0,
+            index: MS_OS_20_DESCRIPTOR_INDEX,
+        },
+        &mut buf,
+    )?;
+
+    buf.truncate(len);
+    Ok(buf)
+}
+
+/// Whether a request failed because the device stalled it.
+fn is_stall(e: &Error) -> bool {
+    e.get_ref().and_then(|e| e.downcast_ref::<TransferError>()) == Some(&TransferError::Stall)
+}
+
+/// Request the Microsoft OS 1.0 string descriptor and feature descriptors.
+fn read_ms_os_10_descriptors(
+    device: &Device,
+    control_in: impl Fn(Control, &mut [u8]) -> Result<usize, TransferError>,
+    timeout: Duration,
+) -> Result<MsOs10Descriptors, Error> {
+    use crate::transfer::{ControlType, Recipient};
+
+    let not_found = || {
+        Error::new(
+            ErrorKind::NotFound,
+            "device has no Microsoft OS string descriptor",
+        )
+    };
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidData,
+            "Microsoft OS feature descriptor data was invalid",
+        )
+    };
+
+    let string = match device.get_descriptor_with_len(
+        DESCRIPTOR_TYPE_STRING,
+        MS_OS_10_STRING_INDEX,
+        0,
+        18,
+        timeout,
+    ) {
+        Ok(data) => data,
+        Err(e) if is_stall(&e) => return Err(not_found()),
+        Err(e) => return Err(e),
+    };
+    let vendor_code = parse_ms_os_10_string_descriptor(&string).ok_or_else(not_found)?;
+
+    // Read the header to find the length, then the whole descriptor.
+    // Returns `None` if the device stalls the request.
+    let read_feature = |recipient, value, index, header_len| {
+        let control = || Control {
+            control_type: ControlType::Vendor,
+            recipient,
+            request: vendor_code,
+            value,
+            index,
+        };
+        let mut header = vec![0; header_len];
+        match control_in(control(), &mut header) {
+            Ok(len) if len >= 4 => {}
+            Ok(_) => return Err(invalid()),
+            Err(TransferError::Stall) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let total_len = u32::from_le_bytes(header[..4].try_into().unwrap());
+        let mut buf = vec![0; total_len.min(u16::MAX.into()) as usize];
+        let len = control_in(control(), &mut buf)?;
+        buf.truncate(len);
+        Ok(Some(buf))
+    };
+
+    let compat_ids =
+        match read_feature(Recipient::Device, 0, MS_OS_10_EXTENDED_COMPAT_ID_INDEX, 16)? {
+            Some(data) => parse_ms_os_10_compat_ids(&data).ok_or_else(invalid)?,
+            None => Vec::new(),
+        };
+
+    // WinUSB overrides `wIndex` of interface requests.
+    let properties_recipient = if cfg!(target_os = "windows") {
+        Recipient::Device
+    } else {
+        Recipient::Interface
+    };
+
+    let mut interfaces: Vec<u8> = compat_ids.iter().map(|c| c.first_interface()).collect();
+    if interfaces.is_empty() {
+        interfaces.push(0);
+    }
+    interfaces.dedup();
+
+    let mut properties = Vec::new();
+    for interface in interfaces {
+        // Windows sends the interface number in the low byte of `wValue`.
+        if let Some(data) = read_feature(
+            properties_recipient,
+            interface.into(),
+            MS_OS_10_EXTENDED_PROPERTIES_INDEX,
+            10,
+        )? {
+            properties.extend(parse_ms_os_10_properties(&data, interface).ok_or_else(invalid)?);
+        }
+    }
+
+    Ok(MsOs10Descriptors {
+        vendor_code,
+        compat_ids,
+        properties,
+    })
+}
+
+fn read_blocking(
+    fut: TransferFuture<RequestBuffer>,
+    data: &mut [u8],
+) -> Result<usize, TransferError> {
+    let completion = block_on(fut);
+    let len = completion.data.len().min(data.len());
+    data[..len].copy_from_slice(&completion.data[..len]);
+    completion.status.map(|()| len)
+}
+
+fn write_blocking(fut: TransferFuture<Vec<u8>>) -> Result<usize, TransferError> {
+    let completion = block_on(fut);
+    completion.status.map(|()| completion.data.actual_length())
 }
 
 #[test]
@@ -584,3 +2942,108 @@ fn assert_send_sync() {
     require_send_sync::<Interface>();
     require_send_sync::<Device>();
 }
+
+#[test]
+fn test_hid_report_descriptor_len() {
+    let config = Configuration::new(&[
+        0x09, 0x02, 0x22, 0x00, 0x01, 0x01, 0x00, 0xA0, 0x32, // configuration
+        0x09, 0x04, 0x00, 0x00, 0x01, 0x03, 0x01, 0x02, 0x00, // interface
+        0x09, 0x21, 0x11, 0x01, 0x00, 0x01, 0x22, 0x3F, 0x00, // HID
+        0x07, 0x05, 0x81, 0x03, 0x08, 0x00, 0x0A, // endpoint
+    ]);
+    let alt = config.interface_alt_settings().next().unwrap();
+    assert_eq!(hid_report_descriptor_len(&alt), Some(0x3F));
+}
+
+#[test]
+fn test_endpoint_info_interval() {
+    let config = Configuration::new(&[
+        0x09, 0x02, 0x34, 0x00, 0x01, 0x01, 0x00, 0xA0, 0x32, // configuration
+        0x09, 0x04, 0x00, 0x00, 0x03, 0xFF, 0x00, 0x00, 0x00, // interface
+        0x07, 0x05, 0x81, 0x03, 0x08, 0x00, 0x0A, // interrupt IN, 8 bytes, bInterval 10
+        0x07, 0x05, 0x82, 0x01, 0x00, 0x14, 0x04, // iso IN, 3 x 1024 bytes, bInterval 4
+        0x07, 0x05, 0x03, 0x02, 0x00, 0x02, 0x00, // bulk OUT
+        0x07, 0x05, 0x84, 0x03, 0x00, 0x04, 0x01, // interrupt IN, 1024 bytes, bInterval 1
+        0x06, 0x30, 0x01, 0x00, 0x00, 0x08, // SuperSpeed companion, 2 KiB per interval
+    ]);
+    let alt = config.interface_alt_settings().next().unwrap();
+    let eps: Vec<_> = alt.endpoints().collect();
+
+    let full = EndpointInfo::new(&eps[0], Some(Speed::Full));
+    assert_eq!(full.interval_microframes(), Some(80));
+    assert_eq!(full.bytes_per_interval(), Some(8));
+
+    let high = EndpointInfo::new(&eps[0], Some(Speed::High));
+    assert_eq!(high.interval_microframes(), Some(512));
+
+    let iso = EndpointInfo::new(&eps[1], Some(Speed::High));
+    assert_eq!(iso.interval_microframes(), Some(8));
+    assert_eq!(iso.bytes_per_interval(), Some(3072));
+
+    let bulk = EndpointInfo::new(&eps[2], Some(Speed::High));
+    assert_eq!(bulk.interval_microframes(), None);
+    assert_eq!(bulk.bytes_per_interval(), None);
+
+    let ss = EndpointInfo::from(&eps[3]);
+    assert_eq!(ss.interval_microframes(), Some(1));
+    assert_eq!(ss.bytes_per_interval(), Some(2048));
+}
+
+#[test]
+fn test_compare_device_fields() {
+    #[rustfmt::skip]
+    let descriptor = [
+        0x12, 0x01, 0x00, 0x02, 0xFF, 0x00, 0x00, 0x40,
+        0x34, 0x12, 0x79, 0x56, 0x01, 0x02, 0x01, 0x02, 0x03, 0x01,
+    ];
+    let live = device_descriptor_fields(&descriptor).unwrap();
+    let enumerated = [0x1234, 0x5678, 0x0200, 0xFF, 0, 0, 64, 1];
+
+    assert_eq!(
+        compare_device_fields(&enumerated, &live),
+        [
+            DescriptorMismatch {
+                field: "idProduct",
+                enumerated: 0x5678,
+                live: 0x5679,
+            },
+            DescriptorMismatch {
+                field: "bcdDevice",
+                enumerated: 0x0200,
+                live: 0x0201,
+            },
+        ]
+    );
+    assert!(compare_device_fields(&live, &live).is_empty());
+    assert_eq!(device_descriptor_fields(&descriptor[..8]), None);
+}
+
+#[test]
+fn test_recommended_transfer_size() {
+    let config = Configuration::new(&[
+        0x09, 0x02, 0x26, 0x00, 0x01, 0x01, 0x00, 0xA0, 0x32, // configuration
+        0x09, 0x04, 0x00, 0x00, 0x03, 0xFF, 0x00, 0x00, 0x00, // interface
+        0x07, 0x05, 0x81, 0x02, 0x40, 0x00, 0x00, // bulk IN, 64 bytes
+        0x07, 0x05, 0x02, 0x02, 0x00, 0x04, 0x00, // bulk OUT, 1024 bytes
+        0x06, 0x30, 0x0F, 0x00, 0x00, 0x00, // SuperSpeed companion, burst of 16
+    ]);
+    let alt = config.interface_alt_settings().next().unwrap();
+    let eps: Vec<_> = alt.endpoints().collect();
+
+    let full = EndpointInfo::new(&eps[0], Some(Speed::Full));
+    assert_eq!(
+        recommended_transfer_size(&full, Some(Speed::Full), None),
+        16384
+    );
+
+    let ss = EndpointInfo::new(&eps[1], Some(Speed::Super));
+    assert_eq!(ss.max_burst(), 16);
+    assert_eq!(
+        recommended_transfer_size(&ss, Some(Speed::Super), None),
+        262144
+    );
+    assert_eq!(
+        recommended_transfer_size(&ss, Some(Speed::Super), Some(16384)),
+        16384
+    );
+}
@@ -1,26 +1,52 @@
+use std::{future::Future, str::FromStr};
+
 #[cfg(target_os = "windows")]
 use std::ffi::{OsStr, OsString};
 
 #[cfg(target_os = "linux")]
 use crate::platform::SysfsPath;
 
-use crate::{Device, Error};
+use crate::{
+    descriptors::{
+        parse_concatenated_config_descriptors, BcdVersion, Configuration, DESCRIPTOR_LEN_DEVICE,
+    },
+    Device, Error, Hub, Interface, PortInfo,
+};
 
 /// Opaque device identifier
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 pub struct DeviceId(pub(crate) crate::platform::DeviceId);
 
+impl DeviceId {
+    /// Wrap the ID of a device from the OS backend.
+    pub(crate) fn new(id: impl Into<crate::platform::DeviceId>) -> DeviceId {
+        DeviceId(id.into())
+    }
+}
+
 /// Information about a device that can be obtained without opening it.
 ///
 /// Found in the results of [`crate::list_devices`].
 ///
+/// With the `serde` feature enabled, `DeviceInfo` implements `Serialize` and
+/// `Deserialize` for logging or comparing device inventories. A deserialized
+/// `DeviceInfo` is for display only and may not be usable to open the device.
+///
 /// ### Platform-specific notes
 ///
 /// * Some fields are platform-specific
 ///     * Linux: `sysfs_path`
 ///     * Windows: `instance_id`, `parent_instance_id`, `port_number`, `driver`
 ///     * macOS: `registry_id`, `location_id`
+/// * A virtual device from the `mock` backend has no
+///   platform-specific properties, so their accessors return `None`, or an
+///   empty list.
+/// * On Windows, the device instance handle is not serialized. A
+///   deserialized `DeviceInfo` looks it up again by `instance_id`, and
+///   methods that need it return an error of kind `NotFound` if the device
+///   is no longer in the device tree.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeviceInfo {
     #[cfg(target_os = "linux")]
     pub(crate) path: SysfsPath,
@@ -41,24 +67,38 @@ pub struct DeviceInfo {
     pub(crate) port_number: u32,
 
     #[cfg(target_os = "windows")]
-    pub(crate) devinst: crate::platform::DevInst,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) devinst: Option<crate::platform::DevInst>,
 
     #[cfg(target_os = "windows")]
     pub(crate) driver: Option<String>,
 
+    #[cfg(target_os = "windows")]
+    pub(crate) openable: bool,
+
     #[cfg(target_os = "macos")]
     pub(crate) registry_id: u64,
 
     #[cfg(target_os = "macos")]
     pub(crate) location_id: u32,
 
+    /// ID of a virtual device from the `mock` backend. Its platform-specific
+    /// fields above are left empty, and their accessors return `None`.
+    #[cfg(feature = "mock")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) mock_id: Option<u64>,
+
     pub(crate) bus_id: String,
+    pub(crate) controller_id: Option<String>,
+    pub(crate) controller_driver: Option<String>,
     pub(crate) device_address: u8,
     pub(crate) port_chain: Vec<u8>,
+    pub(crate) root_hub: bool,
 
     pub(crate) vendor_id: u16,
     pub(crate) product_id: u16,
     pub(crate) device_version: u16,
+    pub(crate) usb_version: u16,
 
     pub(crate) class: u8,
     pub(crate) subclass: u8,
@@ -66,6 +106,10 @@ pub struct DeviceInfo {
 
     pub(crate) max_packet_size_0: u8,
 
+    pub(crate) num_configurations: u8,
+
+    pub(crate) device_descriptor: Option<[u8; DESCRIPTOR_LEN_DEVICE as usize]>,
+
     pub(crate) speed: Option<Speed>,
 
     pub(crate) manufacturer_string: Option<String>,
@@ -73,19 +117,60 @@ pub struct DeviceInfo {
     pub(crate) serial_number: Option<String>,
 
     pub(crate) interfaces: Vec<InterfaceInfo>,
+
+    /// Concatenated configuration descriptors, only read when listing with
+    /// [`ListDevices::configuration_descriptors`][crate::ListDevices::configuration_descriptors].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) configuration_descriptors: Vec<u8>,
+
+    #[cfg(target_os = "linux")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) missing_attributes: Vec<&'static str>,
 }
 
 impl DeviceInfo {
+    /// Short identification of the device for log messages.
+    pub(crate) fn log_id(&self) -> String {
+        format!(
+            "{}:{:03} ({:04x}:{:04x})",
+            self.bus_id, self.device_address, self.vendor_id, self.product_id
+        )
+    }
+
+    /// Whether this is a virtual device from the `mock` backend.
+    fn is_virtual(&self) -> bool {
+        #[cfg(feature = "mock")]
+        {
+            self.mock_id.is_some()
+        }
+
+        #[cfg(not(feature = "mock"))]
+        {
+            false
+        }
+    }
+
+    /// Sysfs path, which a virtual device doesn't have.
+    #[cfg(target_os = "linux")]
+    fn sysfs(&self) -> Option<&SysfsPath> {
+        (!self.is_virtual()).then_some(&self.path)
+    }
+
     /// Opaque identifier for the device.
     pub fn id(&self) -> DeviceId {
+        #[cfg(feature = "mock")]
+        if let Some(id) = self.mock_id {
+            return DeviceId(crate::platform::DeviceId::Mock(id));
+        }
+
         #[cfg(target_os = "windows")]
         {
-            DeviceId(self.devinst)
+            DeviceId::new(crate::platform::device_devinst(self).ok())
         }
 
         #[cfg(target_os = "linux")]
         {
-            DeviceId(crate::platform::DeviceId {
+            DeviceId::new(crate::platform::os::DeviceId {
                 bus: self.busnum,
                 addr: self.device_address,
             })
@@ -93,7 +178,7 @@ impl DeviceInfo {
 
         #[cfg(target_os = "macos")]
         {
-            DeviceId(self.registry_id)
+            DeviceId::new(self.registry_id)
         }
     }
 
@@ -101,46 +186,87 @@ impl DeviceInfo {
     #[doc(hidden)]
     #[deprecated = "use `sysfs_path()` instead"]
     #[cfg(target_os = "linux")]
-    pub fn path(&self) -> &SysfsPath {
-        &self.path
+    pub fn path(&self) -> Option<&SysfsPath> {
+        self.sysfs()
     }
 
     /// *(Linux-only)* Sysfs path for the device.
+    ///
+    /// `None` for a virtual device from the `mock` backend.
     #[cfg(target_os = "linux")]
-    pub fn sysfs_path(&self) -> &std::path::Path {
-        &self.path.0
+    pub fn sysfs_path(&self) -> Option<&std::path::Path> {
+        self.sysfs().map(|p| p.0.as_path())
     }
 
     /// *(Linux-only)* Bus number.
     ///
-    /// On Linux, the `bus_id` is an integer and this provides the value as `u8`.
+    /// On Linux, the `bus_id` is an integer and this provides the value as
+    /// `u8`. `None` for a virtual device from the `mock` backend.
+    #[cfg(target_os = "linux")]
+    pub fn busnum(&self) -> Option<u8> {
+        (!self.is_virtual()).then_some(self.busnum)
+    }
+
+    /// *(Linux-only)* Path of the usbfs device node, like
+    /// `/dev/bus/usb/001/002`.
+    ///
+    /// This is the node opened by [`open`][Self::open], and is distinct from
+    /// the [`sysfs_path`][Self::sysfs_path]. It is computed from the
+    /// [`busnum`][Self::busnum] and [`device_address`][Self::device_address]
+    /// of the current enumeration, so it changes when the device is
+    /// unplugged and reconnected or re-enumerated after a reset. `None` for
+    /// a virtual device from the `mock` backend.
     #[cfg(target_os = "linux")]
-    pub fn busnum(&self) -> u8 {
-        self.busnum
+    pub fn devnode_path(&self) -> Option<std::path::PathBuf> {
+        self.busnum()
+            .map(|busnum| crate::platform::devnode_path(busnum, self.device_address))
     }
 
     /// *(Windows-only)* Instance ID path of this device
+    ///
+    /// This is the device instance ID used by SetupAPI and the configuration
+    /// manager, like `USB\VID_1234&PID_5678\0123456789`. Pass it to
+    /// [`open_device_with_instance_id`][crate::open_device_with_instance_id]
+    /// to open the device again later. `None` for a virtual device from the
+    /// `mock` backend.
     #[cfg(target_os = "windows")]
-    pub fn instance_id(&self) -> &OsStr {
-        &self.instance_id
+    pub fn instance_id(&self) -> Option<&OsStr> {
+        (!self.is_virtual()).then_some(self.instance_id.as_os_str())
+    }
+
+    /// *(Windows-only)* Query the connection status of the device's port
+    /// from its parent hub.
+    ///
+    /// See [`Hub::connection_status`] to query a port by number, including
+    /// ports whose device failed to enumerate and so isn't listed.
+    #[cfg(target_os = "windows")]
+    pub fn connection_status(&self) -> Result<crate::ConnectionStatus, Error> {
+        crate::platform::HubPort::by_child_devinst(crate::platform::device_devinst(self)?)?
+            .connection_status()
     }
 
     /// *(Windows-only)* Location paths property
+    ///
+    /// Empty for a virtual device from the `mock` backend.
     #[cfg(target_os = "windows")]
     pub fn location_paths(&self) -> &[OsString] {
         &self.location_paths
     }
 
     /// *(Windows-only)* Instance ID path of the parent hub
+    ///
+    /// `None` for a virtual device from the `mock` backend.
     #[cfg(target_os = "windows")]
-    pub fn parent_instance_id(&self) -> &OsStr {
-        &self.parent_instance_id
+    pub fn parent_instance_id(&self) -> Option<&OsStr> {
+        (!self.is_virtual()).then_some(self.parent_instance_id.as_os_str())
     }
 
     /// *(Windows-only)* Port number
+    ///
+    /// `None` for a virtual device from the `mock` backend.
     #[cfg(target_os = "windows")]
-    pub fn port_number(&self) -> u32 {
-        self.port_number
+    pub fn port_number(&self) -> Option<u32> {
+        (!self.is_virtual()).then_some(self.port_number)
     }
 
     /// Path of port numbers identifying the port where the device is connected.
@@ -154,22 +280,120 @@ impl DeviceInfo {
         &self.port_chain
     }
 
+    /// Physical port path identifying where the device is connected.
+    ///
+    /// This combines the [`bus_id`][Self::bus_id] and
+    /// [`port_chain`][Self::port_chain] into a single identifier that is
+    /// expected to remain stable when a device is unplugged and reconnected
+    /// to the same port, and can be persisted as a string.
+    ///
+    /// ### Platform-specific notes
+    ///  * Linux: this uses the bus number without leading zeros, so it matches
+    ///    the device's name in sysfs, e.g. `1-6.4.2`.
+    pub fn port_path(&self) -> DevicePath {
+        #[cfg(target_os = "linux")]
+        let bus_id = match self.busnum() {
+            Some(busnum) => busnum.to_string(),
+            None => self.bus_id.clone(),
+        };
+
+        #[cfg(not(target_os = "linux"))]
+        let bus_id = self.bus_id.clone();
+
+        DevicePath {
+            bus_id,
+            port_chain: self.port_chain.clone(),
+        }
+    }
+
+    /// Compare by bus and then port chain, the order of the device tree.
+    ///
+    /// A hub sorts before the devices connected to it, which sort by port.
+    pub(crate) fn cmp_topology(&self, other: &DeviceInfo) -> std::cmp::Ordering {
+        #[cfg(target_os = "linux")]
+        let bus = (self.busnum().cmp(&other.busnum())).then_with(|| self.bus_id.cmp(&other.bus_id));
+
+        #[cfg(not(target_os = "linux"))]
+        let bus = self.bus_id.cmp(&other.bus_id);
+
+        bus.then_with(|| self.port_chain.cmp(&other.port_chain))
+    }
+
+    /// Information about the hub that the device is connected to.
+    ///
+    /// Returns `None` for a root hub, or if the hub could not be probed.
+    /// Together with [`list_devices`][crate::list_devices], this can be used
+    /// to build the device tree.
+    ///
+    /// This re-reads the parent's information from the OS, so it reflects the
+    /// current state rather than the time this `DeviceInfo` was created.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, the parent of a device connected directly to a root hub
+    ///   is that [root hub][Self::is_root_hub], as listed with
+    ///   [`ListDevices::root_hubs`][crate::ListDevices::root_hubs].
+    /// * On Windows and macOS, root hubs are not listed, so this returns
+    ///   `None` for a device connected directly to a root hub.
+    pub fn parent(&self) -> Option<DeviceInfo> {
+        crate::platform::probe_parent(self)
+    }
+
     /// *(Windows-only)* Driver associated with the device as a whole
     #[cfg(target_os = "windows")]
     pub fn driver(&self) -> Option<&str> {
         self.driver.as_deref()
     }
 
+    /// Whether the driver bound to the device supports claiming interfaces
+    /// with `nusb`.
+    ///
+    /// [`list_devices`][crate::list_devices] returns all USB devices,
+    /// including those that `nusb` cannot control. This can be used to
+    /// show such devices as unavailable in a UI, rather than failing when
+    /// [`Device::claim_interface`][crate::Device::claim_interface] is called.
+    ///
+    /// Permissions are not checked, so opening the device may still fail.
+    ///
+    /// ### Platform-specific notes
+    /// * On Windows, this is `true` if the device is bound to WinUSB, or if
+    ///   it is a composite device bound to `usbccgp` with at least one
+    ///   interface bound to WinUSB.
+    /// * On Linux and macOS, this is always `true`. An interface claimed by a
+    ///   kernel driver can be used after
+    ///   [`Device::detach_and_claim_interface`][crate::Device::detach_and_claim_interface].
+    pub fn openable(&self) -> bool {
+        #[cfg(target_os = "windows")]
+        {
+            self.openable
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            true
+        }
+    }
+
     /// *(macOS-only)* IOKit Location ID
+    ///
+    /// This is the `locationID` property read during enumeration, which
+    /// identifies the physical port where the device is connected. The top
+    /// byte is the bus number, and each following 4-bit nibble is the port
+    /// number on a tier of hubs, starting from the root hub, with unused
+    /// tiers zero. For example, `0x14320000` is port 2 of a hub on port 3
+    /// of bus `0x14`, which has a [`port_chain`][Self::port_chain] of
+    /// `[3, 2]`. `None` for a virtual device from the `mock` backend.
+    #[doc(alias = "locationID")]
     #[cfg(target_os = "macos")]
-    pub fn location_id(&self) -> u32 {
-        self.location_id
+    pub fn location_id(&self) -> Option<u32> {
+        (!self.is_virtual()).then_some(self.location_id)
     }
 
     /// *(macOS-only)* IOKit [Registry Entry ID](https://developer.apple.com/documentation/iokit/1514719-ioregistryentrygetregistryentryi?language=objc)
+    ///
+    /// `None` for a virtual device from the `mock` backend.
     #[cfg(target_os = "macos")]
-    pub fn registry_entry_id(&self) -> u64 {
-        self.registry_id
+    pub fn registry_entry_id(&self) -> Option<u64> {
+        (!self.is_virtual()).then_some(self.registry_id)
     }
 
     /// Identifier for the bus / host controller where the device is connected.
@@ -177,6 +401,31 @@ impl DeviceInfo {
         &self.bus_id
     }
 
+    /// Identifier of the host controller for the device's bus, if known.
+    ///
+    /// Devices with the same `controller_id` share the controller's
+    /// bandwidth, even if they are on different buses.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this is the sysfs name of the controller's device, which
+    ///   for a PCI controller is its PCI address like `0000:00:14.0`.
+    /// * On Windows, this is the device instance ID of the controller.
+    /// * On macOS, this is the name of the controller in the IORegistry, such
+    ///   as `XHC1`.
+    pub fn controller_id(&self) -> Option<&str> {
+        self.controller_id.as_deref()
+    }
+
+    /// Name of the driver of the host controller for the device's bus, if
+    /// known.
+    ///
+    /// This identifies the type of controller, for example `xhci_hcd` or
+    /// `ehci-pci` on Linux, `USBXHCI` on Windows, or the IOKit class name
+    /// like `AppleUSBXHCIPCI` on macOS.
+    pub fn controller_driver(&self) -> Option<&str> {
+        self.controller_driver.as_deref()
+    }
+
     /// Number identifying the device within the bus.
     pub fn device_address(&self) -> u8 {
         self.device_address
@@ -200,6 +449,35 @@ impl DeviceInfo {
         self.device_version
     }
 
+    /// The device version from the `bcdDevice` device descriptor field,
+    /// decoded as a [`BcdVersion`].
+    pub fn device_version_bcd(&self) -> BcdVersion {
+        BcdVersion(self.device_version)
+    }
+
+    /// The USB specification release the device complies with, encoded as
+    /// BCD, from the `bcdUSB` device descriptor field.
+    ///
+    /// For example, `0x0200` is USB 2.0 and `0x0320` is USB 3.2. This is
+    /// independent of the negotiated [`speed`][Self::speed], so a USB 3.x
+    /// device connected to a USB 2.0 port still reports `0x03xx`.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this is read from the `version` sysfs attribute.
+    /// * On macOS, this is `0` if the `bcdUSB` IOKit property is missing.
+    #[doc(alias = "bcdUSB")]
+    pub fn usb_version(&self) -> u16 {
+        self.usb_version
+    }
+
+    /// The USB specification release from the `bcdUSB` device descriptor
+    /// field, decoded as a [`BcdVersion`].
+    ///
+    /// See [`usb_version`][Self::usb_version] for platform-specific notes.
+    pub fn usb_version_bcd(&self) -> BcdVersion {
+        BcdVersion(self.usb_version)
+    }
+
     /// Code identifying the standard device class, from the `bDeviceClass` device descriptor field.
     ///
     /// `0x00`: specified at the interface level.\
@@ -221,12 +499,71 @@ impl DeviceInfo {
         self.protocol
     }
 
+    /// Whether the device is a hub, from its device class `0x09`.
+    ///
+    /// ### Platform-specific notes
+    /// * On Windows, hubs are not included in [`list_devices`][crate::list_devices],
+    ///   but can be found with [`parent`][Self::parent].
+    pub fn is_hub(&self) -> bool {
+        self.class == CLASS_HUB
+    }
+
+    /// Whether the device is the root hub of a bus.
+    ///
+    /// Root hubs are only listed with [`ListDevices::root_hubs`][crate::ListDevices::root_hubs].
+    /// They are not connected to a port, so their
+    /// [`port_chain`][Self::port_chain] is empty.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this is set for the `usbN` devices in sysfs.
+    /// * On Windows and macOS, root hubs are never listed, and this is
+    ///   always `false`.
+    pub fn is_root_hub(&self) -> bool {
+        self.root_hub
+    }
+
     /// Maximum packet size for endpoint zero.
     #[doc(alias = "bMaxPacketSize0")]
     pub fn max_packet_size_0(&self) -> u8 {
         self.max_packet_size_0
     }
 
+    /// Number of configurations supported by the device, from the `bNumConfigurations` device descriptor field.
+    ///
+    /// This is the count advertised in the device descriptor cached by the
+    /// OS, so it is available without opening the device.
+    #[doc(alias = "bNumConfigurations")]
+    pub fn num_configurations(&self) -> u8 {
+        self.num_configurations
+    }
+
+    /// The raw 18-byte device descriptor, if cached by the OS.
+    ///
+    /// When present, this is the exact wire format of the descriptor returned
+    /// by the device, suitable for parsing with another descriptor parser
+    /// or forwarding elsewhere.
+    ///
+    /// ### Platform-specific notes
+    ///  * Linux: read from the `descriptors` sysfs attribute.
+    ///  * Windows: obtained from the parent hub's cached copy.
+    ///  * macOS: reassembled from the descriptor fields in the IOKit
+    ///    registry, and `None` if any of them are missing.
+    pub fn device_descriptor(&self) -> Option<&[u8]> {
+        self.device_descriptor.as_ref().map(|d| &d[..])
+    }
+
+    /// Configuration descriptors of the device, read while listing devices.
+    ///
+    /// This is empty unless the device was listed with
+    /// [`list_devices_full`][crate::list_devices_full] or
+    /// [`ListDevices::configuration_descriptors`][crate::ListDevices::configuration_descriptors],
+    /// and allows inspecting every configuration's interfaces and endpoints
+    /// without opening the device.
+    pub fn configurations(&self) -> impl Iterator<Item = Configuration<'_>> {
+        parse_concatenated_config_descriptors(&self.configuration_descriptors)
+            .map(Configuration::new)
+    }
+
     /// Connection speed
     pub fn speed(&self) -> Option<Speed> {
         self.speed
@@ -252,14 +589,120 @@ impl DeviceInfo {
     ///
     /// ### Platform-specific notes
     /// * On Windows, this comes from a case-insensitive instance ID and may
-    /// have been converted to upper case from the descriptor string. It is
-    /// recommended to use a [case-insensitive
-    /// comparison][str::eq_ignore_ascii_case] when matching a device.
+    ///   have been converted to upper case from the descriptor string. It is
+    ///   recommended to use a [case-insensitive
+    ///   comparison][str::eq_ignore_ascii_case] when matching a device.
     #[doc(alias = "iSerial")]
     pub fn serial_number(&self) -> Option<&str> {
         self.serial_number.as_deref()
     }
 
+    /// Index of the manufacturer string descriptor, from the `iManufacturer`
+    /// device descriptor field.
+    ///
+    /// Returns `None` if the device has no manufacturer string, which it
+    /// indicates with index 0, or if the OS did not cache the
+    /// [device descriptor][Self::device_descriptor]. Pass the index to
+    /// [`Device::get_string_descriptor`] to read the string in a language
+    /// other than the one the OS used.
+    #[doc(alias = "iManufacturer")]
+    pub fn manufacturer_string_index(&self) -> Option<u8> {
+        self.string_index(14)
+    }
+
+    /// Index of the product string descriptor, from the `iProduct` device
+    /// descriptor field.
+    ///
+    /// See [`manufacturer_string_index`][Self::manufacturer_string_index].
+    #[doc(alias = "iProduct")]
+    pub fn product_string_index(&self) -> Option<u8> {
+        self.string_index(15)
+    }
+
+    /// Index of the serial number string descriptor, from the
+    /// `iSerialNumber` device descriptor field.
+    ///
+    /// See [`manufacturer_string_index`][Self::manufacturer_string_index].
+    #[doc(alias = "iSerialNumber")]
+    pub fn serial_number_string_index(&self) -> Option<u8> {
+        self.string_index(16)
+    }
+
+    fn string_index(&self, offset: usize) -> Option<u8> {
+        self.device_descriptor
+            .as_ref()
+            .map(|d| d[offset])
+            .filter(|&i| i != 0)
+    }
+
+    /// Manufacturer string, reading it now if it was not read during
+    /// enumeration.
+    ///
+    /// Returns the same value as [`manufacturer_string`][Self::manufacturer_string]
+    /// unless the device was listed with
+    /// [`ListDevices::lazy_strings`][crate::ListDevices::lazy_strings].
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this reads the string from sysfs, which does not perform
+    ///   device IO.
+    /// * On Windows and macOS, strings are never skipped, so this returns the
+    ///   cached value.
+    pub fn manufacturer_string_lazy(&self) -> Option<String> {
+        self.lazy_string(&self.manufacturer_string, "manufacturer")
+    }
+
+    /// Product string, reading it now if it was not read during enumeration.
+    ///
+    /// See [`manufacturer_string_lazy`][Self::manufacturer_string_lazy].
+    pub fn product_string_lazy(&self) -> Option<String> {
+        self.lazy_string(&self.product_string, "product")
+    }
+
+    /// Serial number string, reading it now if it was not read during
+    /// enumeration.
+    ///
+    /// See [`manufacturer_string_lazy`][Self::manufacturer_string_lazy].
+    pub fn serial_number_lazy(&self) -> Option<String> {
+        self.lazy_string(&self.serial_number, "serial")
+    }
+
+    /// String of the interface with the specified number, reading it now if
+    /// it was not read during enumeration.
+    ///
+    /// See [`manufacturer_string_lazy`][Self::manufacturer_string_lazy].
+    pub fn interface_string_lazy(&self, interface_number: u8) -> Option<String> {
+        let cached = self
+            .interfaces
+            .iter()
+            .find(|i| i.interface_number == interface_number)
+            .and_then(|i| i.interface_string.clone());
+
+        #[cfg(target_os = "linux")]
+        {
+            cached.or_else(|| self.sysfs()?.read_interface_string(interface_number))
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            cached
+        }
+    }
+
+    #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+    fn lazy_string(&self, cached: &Option<String>, attr: &str) -> Option<String> {
+        #[cfg(target_os = "linux")]
+        {
+            cached
+                .clone()
+                .or_else(|| self.sysfs()?.read_attr(attr).ok())
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            cached.clone()
+        }
+    }
+
     /// Iterator over the device's interfaces.
     ///
     /// This returns summary information about the interfaces in the device's
@@ -282,10 +725,225 @@ impl DeviceInfo {
         self.interfaces.iter()
     }
 
+    /// Check whether the device is currently suspended.
+    ///
+    /// This queries the current state from the OS rather than returning a
+    /// value cached during enumeration. It does not perform IO or wake the
+    /// device.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this reads the `power/runtime_status` sysfs attribute,
+    ///   and returns `true` if it is `suspended`.
+    /// * On Windows, this reads the most recent device power state, and
+    ///   returns `true` if it is below D0. Descriptor requests to a suspended
+    ///   device fail, because the hub driver does not wake it.
+    /// * On macOS, this reads the current power state of the
+    ///   `IOUSBHostDevice`, and returns `true` if it is not fully on.
+    pub fn is_suspended(&self) -> Result<bool, Error> {
+        #[cfg(feature = "mock")]
+        if let Some(id) = self.mock_id {
+            return crate::platform::mock::is_suspended(id);
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            crate::platform::is_suspended(&self.path)
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            crate::platform::is_suspended(crate::platform::device_devinst(self)?)
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            crate::platform::is_suspended(self.registry_id)
+        }
+    }
+
+    /// Check whether the interface number `interface` is currently claimed
+    /// by a program.
+    ///
+    /// This queries the current state from the OS, and can be used to give
+    /// a more helpful error than the one returned from
+    /// [`Device::claim_interface`][crate::Device::claim_interface] when
+    /// another program is already using the device. It returns `true` if
+    /// this program has claimed the interface itself.
+    ///
+    /// Returns `None` if the OS does not expose this, or if there is no such
+    /// interface in the active configuration.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this is `true` if the interface is bound to the `usbfs`
+    ///   driver. An interface bound to a kernel driver returns `false`, and
+    ///   can be claimed after detaching the driver with
+    ///   [`Device::detach_and_claim_interface`][crate::Device::detach_and_claim_interface].
+    /// * On Windows and macOS, this is not supported and always returns
+    ///   `None`.
+    pub fn interface_in_use(&self, interface: u8) -> Option<bool> {
+        #[cfg(target_os = "linux")]
+        {
+            self.sysfs()?.interface_in_use(interface)
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = interface;
+            None
+        }
+    }
+
+    /// Get the names of the fields that could not be read from the OS.
+    ///
+    /// This is only non-empty for devices listed with
+    /// [`ListDevices::partial`][crate::ListDevices::partial]. The names are
+    /// those of the accessor methods, such as `"device_version"`, and those
+    /// fields are zero.
+    pub fn missing_attributes(&self) -> &[&'static str] {
+        #[cfg(target_os = "linux")]
+        {
+            &self.missing_attributes
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            &[]
+        }
+    }
+
+    /// Get which link power management (LPM) states are enabled for the
+    /// device's link.
+    ///
+    /// This queries the current state from the OS. Returns `None` if the OS
+    /// does not expose it.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this reads the `power/usb2_hardware_lpm`,
+    ///   `power/usb3_hardware_lpm_u1` and `power/usb3_hardware_lpm_u2` sysfs
+    ///   attributes, which are only present if the device and its hub
+    ///   support the corresponding state.
+    /// * On Windows and macOS, this is not supported and always returns
+    ///   `None`.
+    #[doc(alias = "LPM")]
+    pub fn link_power_management(&self) -> Option<LinkPowerManagement> {
+        #[cfg(target_os = "linux")]
+        {
+            crate::platform::link_power_management(self.sysfs()?)
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            None
+        }
+    }
+
     /// Open the device
+    ///
+    /// If the user does not have permission to access the device, this
+    /// returns an error of kind
+    /// [`PermissionDenied`][std::io::ErrorKind::PermissionDenied] with a
+    /// message describing the likely cause.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, access to the usbfs device node is normally granted to
+    ///   non-root users by a udev rule matching the device.
+    /// * On Windows, the WinUSB handle is opened when claiming an interface,
+    ///   which fails with `PermissionDenied` if the device is already open
+    ///   in another program.
     pub fn open(&self) -> Result<Device, Error> {
         Device::open(self)
     }
+
+    /// *(Linux-only)* Open the device node read-only, for inspecting
+    /// descriptors without write access.
+    ///
+    /// Opening the usbfs node `O_RDONLY` only requires read permission,
+    /// which is often granted where [`open`][Self::open] is denied. usbfs
+    /// doesn't allow any requests on a read-only node, so the returned
+    /// [`Device`] only provides the descriptors the kernel cached at
+    /// enumeration:
+    ///
+    /// * [`configurations`][Device::configurations],
+    ///   [`active_configuration`][Device::active_configuration] and
+    ///   [`active_configuration_value`][Device::active_configuration_value]
+    ///   work, along with the device descriptor fields of this
+    ///   `DeviceInfo`.
+    /// * Claiming interfaces, setting the configuration, resetting, and
+    ///   detaching or attaching kernel drivers return an error of kind
+    ///   [`PermissionDenied`][std::io::ErrorKind::PermissionDenied].
+    /// * Control transfers, including `GET_DESCRIPTOR` and other standard
+    ///   `GET_*` requests, fail with
+    ///   [`TransferError::Unknown`][crate::transfer::TransferError::Unknown].
+    #[cfg(target_os = "linux")]
+    pub fn open_readonly(&self) -> Result<Device, Error> {
+        Device::open_readonly(self)
+    }
+
+    /// Open the device and claim one of its interfaces.
+    ///
+    /// This is a shortcut for [`open`][Self::open] followed by
+    /// [`Device::claim_interface`] for devices where only one interface is
+    /// needed. The returned [`Interface`] keeps the device open until it and
+    /// all of its clones are dropped.
+    ///
+    /// ### Example
+    ///
+    /// ```no_run
+    /// use nusb;
+    /// let interface = nusb::list_devices().unwrap()
+    ///     .find(|dev| dev.vendor_id() == 0xAAAA && dev.product_id() == 0xBBBB)
+    ///     .expect("device not connected")
+    ///     .open_interface(0)
+    ///     .expect("failed to claim interface");
+    /// ```
+    pub fn open_interface(&self, interface: u8) -> Result<Interface, Error> {
+        self.open()?.claim_interface(interface)
+    }
+
+    /// Open the device as a hub to control its downstream ports.
+    ///
+    /// Returns an error of kind [`InvalidInput`][std::io::ErrorKind::InvalidInput]
+    /// if the device is not a [hub][Self::is_hub]. See [`Hub`] for
+    /// platform support.
+    pub fn open_hub(&self) -> Result<Hub, Error> {
+        Hub::open(self)
+    }
+
+    /// List the downstream ports of a hub, with the device connected to
+    /// each.
+    ///
+    /// This is the same as [`Hub::ports`], but doesn't need the hub to be
+    /// opened, so it works without write access to the hub's device node.
+    /// Returns an error of kind [`InvalidInput`][std::io::ErrorKind::InvalidInput]
+    /// if the device is not a [hub][Self::is_hub].
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this only reads sysfs.
+    /// * On Windows, this opens the hub driver, as [`open_hub`][Self::open_hub]
+    ///   does.
+    /// * On macOS, this is not supported.
+    pub fn hub_ports(&self) -> Result<impl Iterator<Item = PortInfo>, Error> {
+        crate::hub::hub_ports(self)
+    }
+
+    /// Open the device without blocking the async executor.
+    ///
+    /// Opening a device can block, for example while the OS checks
+    /// permissions or loads a driver. This runs [`open`][Self::open] on a
+    /// separate thread and returns a future that resolves when it completes,
+    /// so it can be awaited from an async task without stalling other tasks.
+    ///
+    /// ### Example
+    ///
+    /// ```no_run
+    /// use futures_lite::future::block_on;
+    /// let di = nusb::list_devices().unwrap().next().unwrap();
+    /// let device = block_on(di.open_async()).unwrap();
+    /// ```
+    pub fn open_async(&self) -> impl Future<Output = Result<Device, Error>> {
+        let info = self.clone();
+        crate::blocking::unblock(move || Device::open(&info))
+    }
 }
 
 // Not derived so that we can format some fields in hex
@@ -294,6 +952,8 @@ impl std::fmt::Debug for DeviceInfo {
         let mut s = f.debug_struct("DeviceInfo");
 
         s.field("bus_id", &self.bus_id)
+            .field("controller_id", &self.controller_id)
+            .field("controller_driver", &self.controller_driver)
             .field("device_address", &self.device_address)
             .field("port_chain", &format_args!("{:?}", self.port_chain))
             .field("vendor_id", &format_args!("0x{:04X}", self.vendor_id))
@@ -302,10 +962,12 @@ impl std::fmt::Debug for DeviceInfo {
                 "device_version",
                 &format_args!("0x{:04X}", self.device_version),
             )
+            .field("usb_version", &format_args!("0x{:04X}", self.usb_version))
             .field("class", &format_args!("0x{:02X}", self.class))
             .field("subclass", &format_args!("0x{:02X}", self.subclass))
             .field("protocol", &format_args!("0x{:02X}", self.protocol))
             .field("max_packet_size_0", &self.max_packet_size_0)
+            .field("num_configurations", &self.num_configurations)
             .field("speed", &self.speed)
             .field("manufacturer_string", &self.manufacturer_string)
             .field("product_string", &self.product_string)
@@ -313,8 +975,8 @@ impl std::fmt::Debug for DeviceInfo {
 
         #[cfg(target_os = "linux")]
         {
-            s.field("sysfs_path", &self.path);
-            s.field("busnum", &self.busnum);
+            s.field("sysfs_path", &self.sysfs());
+            s.field("busnum", &self.busnum());
         }
 
         #[cfg(target_os = "windows")]
@@ -324,6 +986,7 @@ impl std::fmt::Debug for DeviceInfo {
             s.field("location_paths", &self.location_paths);
             s.field("port_number", &self.port_number);
             s.field("driver", &self.driver);
+            s.field("openable", &self.openable);
         }
 
         #[cfg(target_os = "macos")]
@@ -341,8 +1004,185 @@ impl std::fmt::Debug for DeviceInfo {
     }
 }
 
+/// Identifying information about a connected device, without the rest of
+/// the details in [`DeviceInfo`].
+///
+/// Found in the results of [`crate::list_device_ids`]. Use
+/// [`probe`][Self::probe] to get the full `DeviceInfo` for a device that
+/// matches.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DeviceSummary {
+    pub(crate) id: DeviceId,
+    pub(crate) device_address: u8,
+    pub(crate) vendor_id: u16,
+    pub(crate) product_id: u16,
+}
+
+impl DeviceSummary {
+    /// Opaque identifier for the device, equal to [`DeviceInfo::id`].
+    pub fn id(&self) -> DeviceId {
+        self.id
+    }
+
+    /// *(Linux-only)* Bus number.
+    ///
+    /// `None` for a virtual device from the `mock` backend.
+    #[cfg(target_os = "linux")]
+    pub fn busnum(&self) -> Option<u8> {
+        self.id.0.busnum()
+    }
+
+    /// Number identifying the device within the bus.
+    pub fn device_address(&self) -> u8 {
+        self.device_address
+    }
+
+    /// The 16-bit number identifying the device's vendor, from the `idVendor` device descriptor field.
+    #[doc(alias = "idVendor")]
+    pub fn vendor_id(&self) -> u16 {
+        self.vendor_id
+    }
+
+    /// The 16-bit number identifying the product, from the `idProduct` device descriptor field.
+    #[doc(alias = "idProduct")]
+    pub fn product_id(&self) -> u16 {
+        self.product_id
+    }
+
+    /// Read the full [`DeviceInfo`] for this device.
+    ///
+    /// Returns `None` if the device has been disconnected or its information
+    /// could not be read.
+    pub fn probe(&self) -> Option<DeviceInfo> {
+        crate::platform::probe_device_id(self.id.0)
+    }
+}
+
+// Not derived so that we can format some fields in hex
+impl std::fmt::Debug for DeviceSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceSummary")
+            .field("id", &self.id)
+            .field("device_address", &self.device_address)
+            .field("vendor_id", &format_args!("0x{:04X}", self.vendor_id))
+            .field("product_id", &format_args!("0x{:04X}", self.product_id))
+            .finish()
+    }
+}
+
+/// Physical location of a device, identified by its bus and the chain of
+/// hub ports leading to it.
+///
+/// Obtain a `DevicePath` from [`DeviceInfo::port_path`], or parse one
+/// previously formatted with [`Display`][std::fmt::Display]. The string
+/// form is the bus ID, a `-`, and the port numbers separated by `.`, such
+/// as `1-6.4.2`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct DevicePath {
+    bus_id: String,
+    port_chain: Vec<u8>,
+}
+
+impl DevicePath {
+    /// Create a `DevicePath` from a bus ID and port chain.
+    pub fn new(bus_id: impl Into<String>, port_chain: Vec<u8>) -> DevicePath {
+        DevicePath {
+            bus_id: bus_id.into(),
+            port_chain,
+        }
+    }
+
+    /// Identifier for the bus / host controller.
+    pub fn bus_id(&self) -> &str {
+        &self.bus_id
+    }
+
+    /// Port numbers of each hub from the root hub to the device.
+    pub fn port_chain(&self) -> &[u8] {
+        &self.port_chain
+    }
+}
+
+impl std::fmt::Display for DevicePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.bus_id)?;
+        for (i, port) in self.port_chain.iter().enumerate() {
+            let sep = if i == 0 { '-' } else { '.' };
+            write!(f, "{sep}{port}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DevicePath {
+    type Err = ParseDevicePathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (bus_id, ports) = match s.rsplit_once('-') {
+            Some((bus_id, ports)) => (bus_id, Some(ports)),
+            None => (s, None),
+        };
+
+        if bus_id.is_empty() || bus_id.contains(char::is_whitespace) {
+            return Err(ParseDevicePathError(()));
+        }
+
+        let port_chain = ports
+            .into_iter()
+            .flat_map(|p| p.split('.'))
+            .map(|p| match p.parse::<u8>() {
+                Ok(n) if n != 0 && !p.starts_with(['+', '0']) => Ok(n),
+                _ => Err(ParseDevicePathError(())),
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+
+        Ok(DevicePath {
+            bus_id: bus_id.to_owned(),
+            port_chain,
+        })
+    }
+}
+
+/// Error returned when parsing a [`DevicePath`] from a string fails.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseDevicePathError(());
+
+impl std::fmt::Display for ParseDevicePathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid device path")
+    }
+}
+
+impl std::error::Error for ParseDevicePathError {}
+
+#[test]
+fn test_device_path() {
+    let p: DevicePath = "1-6.4.2".parse().unwrap();
+    assert_eq!(p.bus_id(), "1");
+    assert_eq!(p.port_chain(), &[6, 4, 2]);
+    assert_eq!(p.to_string(), "1-6.4.2");
+    assert_eq!(p, DevicePath::new("1", vec![6, 4, 2]));
+
+    let p: DevicePath = "PCIROOT(0)#PCI(1400)#USBROOT(0)-23".parse().unwrap();
+    assert_eq!(p.bus_id(), "PCIROOT(0)#PCI(1400)#USBROOT(0)");
+    assert_eq!(p.port_chain(), &[23]);
+
+    let p = DevicePath::new("02", vec![]);
+    assert_eq!(p.to_string().parse::<DevicePath>().unwrap(), p);
+
+    for s in [
+        "", "-1", "1-", "1-6.", "1-.6", "1-6..4", "1-0", "1-06", "1-+6", "1-256", "1-a", " 1-6",
+    ] {
+        assert!(s.parse::<DevicePath>().is_err(), "{s:?} should not parse");
+    }
+}
+
+/// Device class code of a hub.
+const CLASS_HUB: u8 = 0x09;
+
 /// USB connection speed
 #[derive(Copy, Clone, Eq, PartialOrd, Ord, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum Speed {
     /// Low speed (1.5 Mbit)
@@ -357,7 +1197,8 @@ pub enum Speed {
     /// Super speed (5000 Mbit)
     Super,
 
-    /// Super speed (10000 Mbit)
+    /// Super speed plus (10000 Mbit, or 20000 Mbit for Gen 2x2)
+    #[doc(alias = "SuperSpeedPlus")]
     SuperPlus,
 }
 
@@ -369,20 +1210,80 @@ impl Speed {
             "full" | "12" => Some(Speed::Full),
             "high" | "480" => Some(Speed::High),
             "super" | "5000" => Some(Speed::Super),
-            "super+" | "10000" => Some(Speed::SuperPlus),
+            "super+" | "10000" | "20000" => Some(Speed::SuperPlus),
             _ => None,
         }
     }
+
+    /// Nominal signaling rate of the connection in bits per second.
+    ///
+    /// This is the raw line rate, not the achievable throughput, which is
+    /// lower due to encoding and protocol overhead. For
+    /// [`SuperPlus`][Speed::SuperPlus], this is the single-lane Gen 2 rate
+    /// of 10 Gbit/s because the OS does not always report whether a Gen 2x2
+    /// link is in use.
+    pub fn max_bits_per_second(&self) -> u64 {
+        match self {
+            Speed::Low => 1_500_000,
+            Speed::Full => 12_000_000,
+            Speed::High => 480_000_000,
+            Speed::Super => 5_000_000_000,
+            Speed::SuperPlus => 10_000_000_000,
+        }
+    }
+}
+
+#[test]
+fn test_speed_from_str() {
+    assert_eq!(Speed::from_str("1.5"), Some(Speed::Low));
+    assert_eq!(Speed::from_str("480"), Some(Speed::High));
+    assert_eq!(Speed::from_str("5000"), Some(Speed::Super));
+    assert_eq!(Speed::from_str("10000"), Some(Speed::SuperPlus));
+    assert_eq!(Speed::from_str("20000"), Some(Speed::SuperPlus));
+    assert_eq!(Speed::from_str("40000"), None);
+    assert_eq!(Speed::SuperPlus.max_bits_per_second(), 10_000_000_000);
+}
+
+/// Link power management states enabled for a device, returned by
+/// [`DeviceInfo::link_power_management`].
+///
+/// Each state is `None` if the OS does not report it, usually because the
+/// device or the port it is connected to does not support it.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct LinkPowerManagement {
+    pub(crate) l1: Option<bool>,
+    pub(crate) u1: Option<bool>,
+    pub(crate) u2: Option<bool>,
+}
+
+impl LinkPowerManagement {
+    /// Whether the USB 2.0 L1 (sleep) state is enabled.
+    #[doc(alias = "usb2_hardware_lpm")]
+    pub fn l1(&self) -> Option<bool> {
+        self.l1
+    }
+
+    /// Whether the USB 3.x U1 (standby) state is enabled.
+    pub fn u1(&self) -> Option<bool> {
+        self.u1
+    }
+
+    /// Whether the USB 3.x U2 (sleep) state is enabled.
+    pub fn u2(&self) -> Option<bool> {
+        self.u2
+    }
 }
 
 /// Summary information about a device's interface, available before opening a device.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InterfaceInfo {
     pub(crate) interface_number: u8,
     pub(crate) class: u8,
     pub(crate) subclass: u8,
     pub(crate) protocol: u8,
     pub(crate) interface_string: Option<String>,
+    pub(crate) function: Option<FunctionInfo>,
 }
 
 impl InterfaceInfo {
@@ -410,6 +1311,97 @@ impl InterfaceInfo {
     pub fn interface_string(&self) -> Option<&str> {
         self.interface_string.as_deref()
     }
+
+    /// Function of a composite device that this interface belongs to, from
+    /// the interface association descriptor (IAD) that covers it.
+    ///
+    /// Interfaces with the same `FunctionInfo` are used together, and a driver
+    /// for the function is expected to claim all of them. `None` if the
+    /// device has no IAD covering this interface.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux and Windows, this is parsed from the configuration
+    ///   descriptor cached by the OS.
+    /// * On macOS, this is not currently available and always returns `None`.
+    #[doc(alias = "IAD")]
+    pub fn function(&self) -> Option<&FunctionInfo> {
+        self.function.as_ref()
+    }
+}
+
+/// Information about a function of a composite device, from an interface
+/// association descriptor.
+///
+/// Found in [`InterfaceInfo::function`].
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FunctionInfo {
+    pub(crate) first_interface: u8,
+    pub(crate) interface_count: u8,
+    pub(crate) class: u8,
+    pub(crate) subclass: u8,
+    pub(crate) protocol: u8,
+}
+
+impl FunctionInfo {
+    /// Number of the first interface of the function, from the `bFirstInterface` descriptor field.
+    ///
+    /// This can be used as an identifier for the function within a device.
+    pub fn first_interface(&self) -> u8 {
+        self.first_interface
+    }
+
+    /// Number of consecutive interfaces in the function, from the `bInterfaceCount` descriptor field.
+    pub fn interface_count(&self) -> u8 {
+        self.interface_count
+    }
+
+    /// Code identifying the standard class of the function, from the `bFunctionClass` descriptor field.
+    pub fn class(&self) -> u8 {
+        self.class
+    }
+
+    /// Standard subclass, from the `bFunctionSubClass` descriptor field.
+    pub fn subclass(&self) -> u8 {
+        self.subclass
+    }
+
+    /// Standard protocol, from the `bFunctionProtocol` descriptor field.
+    pub fn protocol(&self) -> u8 {
+        self.protocol
+    }
+}
+
+// Not derived so that we can format some fields in hex
+impl std::fmt::Debug for FunctionInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FunctionInfo")
+            .field("first_interface", &self.first_interface)
+            .field("interface_count", &self.interface_count)
+            .field("class", &format_args!("0x{:02X}", self.class))
+            .field("subclass", &format_args!("0x{:02X}", self.subclass))
+            .field("protocol", &format_args!("0x{:02X}", self.protocol))
+            .finish()
+    }
+}
+
+/// Fill in [`InterfaceInfo::function`] from the interface association
+/// descriptors of `config`.
+#[allow(dead_code)] // not used on all platforms
+pub(crate) fn assign_functions(interfaces: &mut [InterfaceInfo], config: &Configuration) {
+    for iad in config.interface_associations() {
+        for intf in interfaces.iter_mut() {
+            if intf.function.is_none() && iad.contains_interface(intf.interface_number) {
+                intf.function = Some(FunctionInfo {
+                    first_interface: iad.first_interface(),
+                    interface_count: iad.interface_count(),
+                    class: iad.function_class(),
+                    subclass: iad.function_subclass(),
+                    protocol: iad.function_protocol(),
+                });
+            }
+        }
+    }
 }
 
 // Not derived so that we can format some fields in hex
@@ -421,6 +1413,7 @@ impl std::fmt::Debug for InterfaceInfo {
             .field("subclass", &format_args!("0x{:02X}", self.subclass))
             .field("protocol", &format_args!("0x{:02X}", self.protocol))
             .field("interface_string", &self.interface_string)
+            .field("function", &self.function)
             .finish()
     }
 }
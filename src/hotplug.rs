@@ -3,6 +3,8 @@
 //!
 //! See [`super::watch_devices`] for a usage example.
 
+use std::task::{ready, Context, Poll};
+
 use futures_core::Stream;
 
 use crate::{DeviceId, DeviceInfo};
@@ -11,21 +13,207 @@ use crate::{DeviceId, DeviceInfo};
 ///
 /// Call [`super::watch_devices`] to begin watching device
 /// events and create a `HotplugWatch`.
-pub struct HotplugWatch(pub(crate) crate::platform::HotplugWatch);
+///
+/// ### Filtering
+///
+/// By default, events for all devices are returned. Use the builder methods
+/// to only receive events for matching devices:
+///
+/// ```no_run
+/// use nusb::hotplug::HotplugFilter;
+/// let watch = nusb::watch_devices().unwrap()
+///     .vendor_id(0x1234)
+///     .product_id(0x5678)
+///     .or(HotplugFilter::new().class(0x03));
+/// ```
+///
+/// The criteria set by [`vendor_id`][Self::vendor_id],
+/// [`product_id`][Self::product_id], [`class`][Self::class], and
+/// [`subclass`][Self::subclass] apply to the most recently added filter,
+/// and must all match. Multiple filters added with [`or`][Self::or] match
+/// if any of them match.
+///
+/// Devices are filtered on the platform's event before probing the device
+/// where possible. The OS may not be able to provide the device's identity
+/// when it is disconnected, so `Disconnected` events are only filtered when
+/// the platform reports enough information to rule out a match.
+pub struct HotplugWatch {
+    pub(crate) inner: crate::platform::HotplugWatch,
+    pub(crate) filters: Vec<HotplugFilter>,
+}
+
+impl HotplugWatch {
+    pub(crate) fn new(inner: crate::platform::HotplugWatch) -> HotplugWatch {
+        HotplugWatch {
+            inner,
+            filters: Vec::new(),
+        }
+    }
+
+    fn last_filter(&mut self) -> &mut HotplugFilter {
+        if self.filters.is_empty() {
+            self.filters.push(HotplugFilter::new());
+        }
+        self.filters.last_mut().unwrap()
+    }
+
+    /// Only return events for devices with the specified vendor ID.
+    #[doc(alias = "idVendor")]
+    pub fn vendor_id(mut self, vendor_id: u16) -> Self {
+        self.last_filter().vendor_id = Some(vendor_id);
+        self
+    }
+
+    /// Only return events for devices with the specified product ID.
+    #[doc(alias = "idProduct")]
+    pub fn product_id(mut self, product_id: u16) -> Self {
+        self.last_filter().product_id = Some(product_id);
+        self
+    }
+
+    /// Only return events for devices with the specified class.
+    ///
+    /// See [`HotplugFilter::class`] for details.
+    pub fn class(mut self, class: u8) -> Self {
+        self.last_filter().class = Some(class);
+        self
+    }
+
+    /// Only return events for devices with the specified subclass.
+    ///
+    /// See [`HotplugFilter::subclass`] for details.
+    pub fn subclass(mut self, subclass: u8) -> Self {
+        self.last_filter().subclass = Some(subclass);
+        self
+    }
+
+    /// Also return events for devices matching `filter`.
+    pub fn or(mut self, filter: HotplugFilter) -> Self {
+        // Start an empty filter if none has been set yet so that `or` doesn't
+        // narrow the match from all devices to only `filter`.
+        self.last_filter();
+        self.filters.push(filter);
+        self
+    }
+}
 
 impl Stream for HotplugWatch {
     type Item = HotplugEvent;
 
     fn poll_next(
         mut self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Option<Self::Item>> {
-        self.0.poll_next(cx).map(Some)
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            match ready!(this.inner.poll_next(cx, &this.filters)) {
+                HotplugEvent::Connected(d) if !matches_any(&this.filters, &d) => continue,
+                event => return Poll::Ready(Some(event)),
+            }
+        }
+    }
+}
+
+/// Criteria for matching devices in a [`HotplugWatch`].
+///
+/// All criteria that are set must match. A default `HotplugFilter` matches
+/// all devices.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HotplugFilter {
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+    class: Option<u8>,
+    subclass: Option<u8>,
+}
+
+impl HotplugFilter {
+    /// Create a filter that matches all devices.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match devices with the specified vendor ID.
+    #[doc(alias = "idVendor")]
+    pub fn vendor_id(mut self, vendor_id: u16) -> Self {
+        self.vendor_id = Some(vendor_id);
+        self
+    }
+
+    /// Match devices with the specified product ID.
+    #[doc(alias = "idProduct")]
+    pub fn product_id(mut self, product_id: u16) -> Self {
+        self.product_id = Some(product_id);
+        self
+    }
+
+    /// Match devices with the specified class.
+    ///
+    /// This matches either the device class from the device descriptor, or
+    /// the class of any of the device's [interfaces][DeviceInfo::interfaces].
+    pub fn class(mut self, class: u8) -> Self {
+        self.class = Some(class);
+        self
+    }
+
+    /// Match devices with the specified subclass.
+    ///
+    /// When combined with [`class`][Self::class], both must match the
+    /// device or the same interface.
+    pub fn subclass(mut self, subclass: u8) -> Self {
+        self.subclass = Some(subclass);
+        self
+    }
+
+    /// Check whether a device matches this filter.
+    pub fn matches(&self, device: &DeviceInfo) -> bool {
+        self.vendor_id.map_or(true, |v| v == device.vendor_id())
+            && self.product_id.map_or(true, |p| p == device.product_id())
+            && (self.matches_class(device.class(), device.subclass())
+                || device
+                    .interfaces()
+                    .any(|i| self.matches_class(i.class(), i.subclass())))
+    }
+
+    fn matches_class(&self, class: u8, subclass: u8) -> bool {
+        self.class.map_or(true, |c| c == class) && self.subclass.map_or(true, |s| s == subclass)
+    }
+
+    /// Check whether a device could match this filter, given the IDs known
+    /// before probing it. `None` means unknown.
+    ///
+    /// The class is not checked, as a device whose device class doesn't
+    /// match may still have an interface that does, such as a HID interface
+    /// of a composite device with class `0xEF`, and the interfaces are only
+    /// known after probing.
+    #[allow(dead_code)] // not used on all platforms
+    pub(crate) fn may_match(&self, vendor_id: Option<u16>, product_id: Option<u16>) -> bool {
+        fn known_mismatch<T: PartialEq>(filter: Option<T>, value: Option<T>) -> bool {
+            matches!((filter, value), (Some(f), Some(v)) if f != v)
+        }
+
+        !(known_mismatch(self.vendor_id, vendor_id) || known_mismatch(self.product_id, product_id))
     }
 }
 
+fn matches_any(filters: &[HotplugFilter], device: &DeviceInfo) -> bool {
+    filters.is_empty() || filters.iter().any(|f| f.matches(device))
+}
+
+/// Check whether a device could match any of `filters` before probing it.
+///
+/// See [`HotplugFilter::may_match`].
+#[allow(dead_code)] // not used on all platforms
+pub(crate) fn may_match_any(
+    filters: &[HotplugFilter],
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+) -> bool {
+    filters.is_empty() || filters.iter().any(|f| f.may_match(vendor_id, product_id))
+}
+
 /// Event returned from the [`HotplugWatch`] stream.
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)] // boxing `DeviceInfo` would be a breaking change
 pub enum HotplugEvent {
     /// A device has been connected.
     Connected(DeviceInfo),
@@ -33,3 +221,22 @@ pub enum HotplugEvent {
     /// A device has been disconnected.
     Disconnected(DeviceId),
 }
+
+#[test]
+fn test_may_match() {
+    let filters = [
+        HotplugFilter::new().vendor_id(0x1234).product_id(0x5678),
+        HotplugFilter::new().class(0x03),
+    ];
+
+    assert!(may_match_any(&[], Some(0x1111), Some(0x2222)));
+    assert!(may_match_any(&filters, Some(0x1234), Some(0x5678)));
+    assert!(may_match_any(&filters, None, None));
+
+    // Any device may have an interface matching the class filter, such as
+    // a composite device with a HID interface.
+    assert!(may_match_any(&filters, Some(0x1111), Some(0x2222)));
+    assert!(!may_match_any(&filters[..1], Some(0x1234), Some(0x9999)));
+    assert!(!may_match_any(&filters[..1], Some(0x1111), None));
+    assert!(may_match_any(&filters[..1], Some(0x1234), None));
+}
@@ -0,0 +1,506 @@
+use std::{io::ErrorKind, time::Duration};
+
+use crate::{DeviceInfo, Error};
+
+#[cfg(target_os = "linux")]
+use crate::{
+    transfer::{Control, ControlType, Recipient},
+    Device,
+};
+
+// Hub class requests and port features, from USB 2.0 section 11.24.
+#[cfg(target_os = "linux")]
+mod consts {
+    pub const REQUEST_GET_STATUS: u8 = 0x00;
+    pub const REQUEST_CLEAR_FEATURE: u8 = 0x01;
+    pub const REQUEST_SET_FEATURE: u8 = 0x03;
+
+    pub const PORT_POWER: u16 = 8;
+    pub const PORT_INDICATOR: u16 = 22;
+
+    /// Protocol code of a SuperSpeed hub, which moves the power status bit.
+    pub const PROTOCOL_SUPERSPEED_HUB: u8 = 3;
+}
+
+#[cfg(target_os = "linux")]
+use consts::*;
+
+#[cfg(target_os = "linux")]
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// An opened USB hub, used to control the power and indicators of its
+/// downstream ports.
+///
+/// Obtain a `Hub` with [`DeviceInfo::open_hub`]. To control the port a
+/// device is connected to, open the device's [`parent`][DeviceInfo::parent]
+/// and use the last element of its [`port_chain`][DeviceInfo::port_chain]
+/// as the port number.
+///
+/// Ports are numbered from 1. Many hubs don't switch power to each port
+/// individually, and indicators are optional, so the requests may appear
+/// to succeed without having any effect. Check `wHubCharacteristics` in the
+/// hub descriptor for what the hub supports.
+///
+/// ### Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// let dut = nusb::list_devices().unwrap()
+///     .find(|dev| dev.vendor_id() == 0xAAAA && dev.product_id() == 0xBBBB)
+///     .expect("device not connected");
+/// let port = *dut.port_chain().last().expect("device is a root hub");
+/// let hub = dut.parent().expect("no parent hub").open_hub().unwrap();
+/// hub.power_cycle_port(port, Duration::from_secs(1)).unwrap();
+/// ```
+///
+/// ### Platform-specific notes
+/// * On Linux, the requests are sent on the hub's usbfs device node, which
+///   normally requires root or a udev rule granting write access to hubs.
+/// * On Windows, the hub driver does not allow sending hub class
+///   requests, so only [`power_cycle_port`][Self::power_cycle_port] is
+///   supported, using `IOCTL_USB_HUB_CYCLE_PORT`. The other methods return
+///   an error of kind [`Unsupported`][ErrorKind::Unsupported].
+/// * On macOS, opening a hub returns an error of kind
+///   [`Unsupported`][ErrorKind::Unsupported].
+pub struct Hub {
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    info: DeviceInfo,
+
+    #[cfg(target_os = "linux")]
+    device: Device,
+
+    #[cfg(target_os = "linux")]
+    superspeed: bool,
+
+    #[cfg(target_os = "windows")]
+    handle: crate::platform::HubHandle,
+}
+
+impl Hub {
+    pub(crate) fn open(info: &DeviceInfo) -> Result<Hub, Error> {
+        if !info.is_hub() {
+            return Err(Error::new(ErrorKind::InvalidInput, "device is not a hub"));
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            Ok(Hub {
+                info: info.clone(),
+                device: info.open()?,
+                superspeed: info.protocol() == PROTOCOL_SUPERSPEED_HUB,
+            })
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            let devinst = crate::platform::device_devinst(info)?;
+            let handle = crate::platform::HubHandle::by_devinst(devinst)
+                .ok_or_else(|| Error::other("failed to open hub"))?;
+            Ok(Hub {
+                info: info.clone(),
+                handle,
+            })
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            Err(unsupported())
+        }
+    }
+
+    /// Read the status of a downstream port with the hub class
+    /// `GET_STATUS` request.
+    #[doc(alias = "GET_STATUS")]
+    pub fn port_status(&self, port: u8) -> Result<PortStatus, Error> {
+        #[cfg(target_os = "linux")]
+        {
+            let mut buf = [0; 4];
+            let len = self.device.control_in_blocking(
+                port_control(REQUEST_GET_STATUS, 0, port.into()),
+                &mut buf,
+                TIMEOUT,
+            )?;
+            if len < 2 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "hub returned short port status",
+                ));
+            }
+            Ok(PortStatus {
+                status: u16::from_le_bytes([buf[0], buf[1]]),
+                superspeed: self.superspeed,
+            })
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = port;
+            Err(unsupported())
+        }
+    }
+
+    /// Switch power to a downstream port on or off with the `PORT_POWER`
+    /// feature.
+    ///
+    /// Turning off power disconnects the attached device.
+    #[doc(alias = "PORT_POWER")]
+    pub fn set_port_power(&self, port: u8, on: bool) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        {
+            let request = if on {
+                REQUEST_SET_FEATURE
+            } else {
+                REQUEST_CLEAR_FEATURE
+            };
+            self.control_out(port_control(request, PORT_POWER, port.into()))
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = (port, on);
+            Err(unsupported())
+        }
+    }
+
+    /// Set the indicator LED of a downstream port with the `PORT_INDICATOR`
+    /// feature.
+    ///
+    /// Use [`PortIndicator::Automatic`] to return the indicator to the
+    /// hub's control. SuperSpeed hubs don't have port indicators.
+    #[doc(alias = "PORT_INDICATOR")]
+    pub fn set_port_indicator(&self, port: u8, indicator: PortIndicator) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        {
+            let index = (indicator as u16) << 8 | u16::from(port);
+            self.control_out(port_control(REQUEST_SET_FEATURE, PORT_INDICATOR, index))
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = (port, indicator);
+            Err(unsupported())
+        }
+    }
+
+    /// Turn off power to a downstream port, wait for `off_time`, and turn
+    /// it back on, causing the attached device to reset and re-enumerate.
+    ///
+    /// ### Platform-specific notes
+    /// * On Windows, this uses `IOCTL_USB_HUB_CYCLE_PORT`, which ignores
+    ///   `off_time` and may only re-enumerate the device without switching
+    ///   off VBUS. It may require administrator privileges.
+    pub fn power_cycle_port(&self, port: u8, off_time: Duration) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        {
+            self.set_port_power(port, false)?;
+            std::thread::sleep(off_time);
+            self.set_port_power(port, true)
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            let _ = off_time;
+            self.handle.cycle_port(port.into())
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            let _ = (port, off_time);
+            Err(unsupported())
+        }
+    }
+
+    /// *(Windows-only)* Get the connection status the hub driver reports
+    /// for a downstream port.
+    ///
+    /// Unlike [`port_status`][Self::port_status], this includes why a
+    /// connected device is not working, such as a failed enumeration or
+    /// insufficient power, which is otherwise only shown in Device Manager.
+    /// It is read from `USB_NODE_CONNECTION_INFORMATION_EX`.
+    #[cfg(target_os = "windows")]
+    pub fn connection_status(&self, port: u8) -> Result<ConnectionStatus, Error> {
+        self.handle.connection_status(port.into())
+    }
+
+    /// List the hub's downstream ports, with the device connected to each.
+    ///
+    /// Returns one [`PortInfo`] for each port, numbered from 1. Use this
+    /// from the root hubs down to build a tree of connected devices, in the
+    /// opposite direction of [`DeviceInfo::parent`].
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, the ports are found from the hub's `maxchild` sysfs
+    ///   attribute and the children from the sysfs device names. A port is
+    ///   reported as connected when it has an enumerated device.
+    /// * On Windows, the number of ports is read from the hub descriptor
+    ///   and the connection state of each port from the hub driver, so a
+    ///   device that failed to enumerate is connected but has no
+    ///   [`DeviceInfo`]. See also `Hub::connection_status`.
+    pub fn ports(&self) -> Result<impl Iterator<Item = PortInfo>, Error> {
+        #[cfg(target_os = "linux")]
+        {
+            hub_ports(&self.info)
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            let ports = (1..=self.handle.num_ports()?)
+                .map(|port| {
+                    let status = self.handle.connection_status(port.into())?;
+                    let connected = status != ConnectionStatus::NoDeviceConnected;
+                    Ok(PortInfo {
+                        port,
+                        connected,
+                        device: connected
+                            .then(|| crate::platform::probe_hub_child(&self.info, port))
+                            .flatten(),
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            Ok(ports.into_iter())
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            Err::<std::iter::Empty<PortInfo>, _>(unsupported())
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn control_out(&self, control: Control) -> Result<(), Error> {
+        self.device.control_out_blocking(control, &[], TIMEOUT)?;
+        Ok(())
+    }
+}
+
+/// List the ports of a hub for [`DeviceInfo::hub_ports`], without
+/// opening it where the platform allows.
+pub(crate) fn hub_ports(info: &DeviceInfo) -> Result<impl Iterator<Item = PortInfo>, Error> {
+    if !info.is_hub() {
+        return Err(Error::new(ErrorKind::InvalidInput, "device is not a hub"));
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let ports = crate::platform::probe_hub_ports(info)?;
+        Ok(ports.into_iter().map(|(port, device)| PortInfo {
+            port,
+            connected: device.is_some(),
+            device,
+        }))
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        Hub::open(info)?.ports()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        Err::<std::iter::Empty<PortInfo>, _>(unsupported())
+    }
+}
+
+impl std::fmt::Debug for Hub {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hub").finish_non_exhaustive()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn port_control(request: u8, value: u16, index: u16) -> Control {
+    Control {
+        control_type: ControlType::Class,
+        recipient: Recipient::Other,
+        request,
+        value,
+        index,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unsupported() -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        "hub port control is not supported on this platform",
+    )
+}
+
+/// Port indicator LED state for [`Hub::set_port_indicator`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum PortIndicator {
+    /// Indicator is controlled by the hub.
+    Automatic = 0,
+
+    /// Amber.
+    Amber = 1,
+
+    /// Green.
+    Green = 2,
+
+    /// Off.
+    Off = 3,
+}
+
+/// A downstream port of a hub, returned by [`Hub::ports`] and
+/// [`DeviceInfo::hub_ports`].
+#[derive(Clone, Debug)]
+pub struct PortInfo {
+    port: u8,
+    connected: bool,
+    device: Option<DeviceInfo>,
+}
+
+impl PortInfo {
+    /// Port number, starting from 1.
+    ///
+    /// This is the last element of the [`port_chain`][DeviceInfo::port_chain]
+    /// of the connected device.
+    pub fn port_number(&self) -> u8 {
+        self.port
+    }
+
+    /// Whether a device is connected to the port.
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// The device connected to the port, if it has been enumerated.
+    pub fn device(&self) -> Option<&DeviceInfo> {
+        self.device.as_ref()
+    }
+
+    /// Consume the `PortInfo` and return the connected device.
+    pub fn into_device(self) -> Option<DeviceInfo> {
+        self.device
+    }
+}
+
+/// Connection status of a hub port as reported by the Windows hub driver,
+/// from the `ConnectionStatus` field of
+/// `USB_NODE_CONNECTION_INFORMATION_EX`.
+///
+/// Returned by `Hub::connection_status`, which is only available on
+/// Windows.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ConnectionStatus {
+    /// No device is connected to the port.
+    NoDeviceConnected,
+
+    /// A device is connected and working.
+    DeviceConnected,
+
+    /// A device is connected but could not be enumerated, for example
+    /// because it didn't respond to `GET_DESCRIPTOR`.
+    DeviceFailedEnumeration,
+
+    /// A device is connected but failed for another reason.
+    DeviceGeneralFailure,
+
+    /// The device drew too much current and the port was disabled.
+    DeviceCausedOvercurrent,
+
+    /// The port can't supply the power the device's configuration needs.
+    DeviceNotEnoughPower,
+
+    /// The host controller doesn't have the bandwidth the device needs.
+    DeviceNotEnoughBandwidth,
+
+    /// The device is connected through more hubs than USB allows.
+    DeviceHubNestedTooDeeply,
+
+    /// The device is a high speed device connected to a full speed hub.
+    DeviceInLegacyHub,
+
+    /// The device is being enumerated.
+    DeviceEnumerating,
+
+    /// The port is being reset.
+    DeviceReset,
+
+    /// A status value not known to this version of `nusb`.
+    Unknown(i32),
+}
+
+/// Status of a hub's downstream port, returned by [`Hub::port_status`].
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct PortStatus {
+    status: u16,
+    superspeed: bool,
+}
+
+impl PortStatus {
+    /// Whether a device is connected to the port.
+    pub fn connected(&self) -> bool {
+        self.status & (1 << 0) != 0
+    }
+
+    /// Whether the port is enabled.
+    pub fn enabled(&self) -> bool {
+        self.status & (1 << 1) != 0
+    }
+
+    /// Whether the port is suspended.
+    ///
+    /// SuperSpeed hubs report the link state instead, so this is always
+    /// `false` for them.
+    pub fn suspended(&self) -> bool {
+        !self.superspeed && self.status & (1 << 2) != 0
+    }
+
+    /// Whether the port has an over-current condition.
+    pub fn over_current(&self) -> bool {
+        self.status & (1 << 3) != 0
+    }
+
+    /// Whether the port is being reset.
+    pub fn reset(&self) -> bool {
+        self.status & (1 << 4) != 0
+    }
+
+    /// Whether the port is powered.
+    pub fn powered(&self) -> bool {
+        let bit = if self.superspeed { 9 } else { 8 };
+        self.status & (1 << bit) != 0
+    }
+
+    /// Raw `wPortStatus` bits.
+    pub fn bits(&self) -> u16 {
+        self.status
+    }
+}
+
+impl std::fmt::Debug for PortStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PortStatus")
+            .field("connected", &self.connected())
+            .field("enabled", &self.enabled())
+            .field("suspended", &self.suspended())
+            .field("over_current", &self.over_current())
+            .field("reset", &self.reset())
+            .field("powered", &self.powered())
+            .finish()
+    }
+}
+
+#[test]
+fn test_port_status_power_bit() {
+    let usb2 = PortStatus {
+        status: 0x0103,
+        superspeed: false,
+    };
+    assert!(usb2.connected() && usb2.enabled() && usb2.powered());
+
+    let usb3 = PortStatus {
+        status: 0x0203,
+        superspeed: true,
+    };
+    assert!(usb3.powered());
+    assert!(!PortStatus {
+        superspeed: true,
+        ..usb2
+    }
+    .powered());
+}
@@ -4,7 +4,8 @@
 //! `nusb` is comparable to the C library [libusb] and its Rust bindings [rusb],
 //! but written in pure Rust. It's built on and exposes async APIs by default,
 //! but can be made blocking using [`futures_lite::future::block_on`][block_on]
-//! or similar.
+//! or similar. The [`blocking`] module and the `*_blocking` methods provide
+//! this without any additional dependencies.
 //!
 //! [libusb]: https://libusb.info
 //! [rusb]: https://docs.rs/rusb/
@@ -45,6 +46,27 @@
 //! Nutshell](https://beyondlogic.org/usbnutshell/usb1.shtml) is a good
 //! overview.*
 //!
+//! ## Async runtimes
+//!
+//! `nusb` does not depend on any particular async runtime, so no feature
+//! flag is needed to use it with `tokio`, `async-std`, `smol`, or any other
+//! executor. It runs its own background thread to wait for OS events (epoll
+//! on Linux, IOCP on Windows, and a `CFRunLoop` on macOS) and completes
+//! transfers by waking the standard [`Waker`][std::task::Waker] of the task
+//! awaiting them. The futures and streams are `Send`, so they can be awaited
+//! from a multi-threaded executor's tasks.
+//!
+//! For example, a bulk read works the same way on any executor:
+//!
+//! ```no_run
+//! use nusb::transfer::RequestBuffer;
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let device = nusb::list_devices()?.next().unwrap().open()?;
+//! let interface = device.claim_interface(0)?;
+//! let data = interface.bulk_in(0x81, RequestBuffer::new(64)).await.into_result()?;
+//! # Ok(()) }
+//! ```
+//!
 //! ## Logging
 //!
 //! `nusb` uses the [`log`](https://docs.rs/log) crate to log debug and error
@@ -57,6 +79,13 @@
 //!
 //! [gh-issues]: https://github.com/kevinmehall/nusb/issues
 //!
+//! ## Testing
+//!
+//! The `mock` feature adds virtual devices that tests register with
+//! scripted descriptors and transfer responses. Once one is connected, they
+//! replace the OS's devices in listing and hotplug events. See the `mock`
+//! module for details. The rest of the API is the same with or without it.
+//!
 //! ## Platform support
 //!
 //! ### Linux
@@ -120,15 +149,31 @@ mod platform;
 
 pub mod descriptors;
 mod enumeration;
-pub use enumeration::{DeviceId, DeviceInfo, InterfaceInfo, Speed};
+pub use enumeration::{
+    DeviceId, DeviceInfo, DevicePath, DeviceSummary, FunctionInfo, InterfaceInfo,
+    LinkPowerManagement, ParseDevicePathError, Speed,
+};
 
 mod device;
-pub use device::{Device, Interface};
+pub use device::{DescriptorMismatch, DetachGuard, Device, DeviceStatus, EndpointInfo, Interface};
+
+#[cfg(target_os = "linux")]
+pub use device::SupportedFeatures;
 
 pub mod transfer;
 
 pub mod hotplug;
 
+mod hub;
+pub use hub::{ConnectionStatus, Hub, PortIndicator, PortInfo, PortStatus};
+
+pub mod blocking;
+
+pub mod cdc;
+
+#[cfg(feature = "mock")]
+pub mod mock;
+
 /// OS error returned from operations other than transfers.
 pub type Error = io::Error;
 
@@ -149,6 +194,379 @@ pub fn list_devices() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
     platform::list_devices()
 }
 
+/// Options for listing connected devices.
+///
+/// [`list_devices`] uses the default options. Use this to configure how
+/// errors reading device information are handled, or to find out which
+/// devices were skipped because of an error.
+///
+/// ### Example
+///
+/// ```no_run
+/// use nusb::ListDevices;
+/// for res in ListDevices::new().retries(5).list_with_errors().unwrap() {
+///     match res {
+///         Ok(dev) => println!("{dev:?}"),
+///         Err(e) => eprintln!("couldn't read device: {e}"),
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ListDevices {
+    retries: u8,
+    lazy_strings: bool,
+    partial: bool,
+    configuration_descriptors: bool,
+    root_hubs: bool,
+}
+
+impl ListDevices {
+    /// Create options with the defaults used by [`list_devices`].
+    pub fn new() -> ListDevices {
+        ListDevices {
+            retries: 2,
+            lazy_strings: false,
+            partial: false,
+            configuration_descriptors: false,
+            root_hubs: false,
+        }
+    }
+
+    /// Set the number of times reading a device attribute is retried after a
+    /// transient error such as [`ErrorKind::Interrupted`][io::ErrorKind::Interrupted],
+    /// with an increasing delay between attempts. The default is 2.
+    ///
+    /// ### Platform-specific notes
+    /// * Only used on Linux, where device information is read from sysfs.
+    pub fn retries(mut self, retries: u8) -> ListDevices {
+        self.retries = retries;
+        self
+    }
+
+    /// Skip reading string descriptors while listing devices.
+    ///
+    /// When set, [`DeviceInfo::manufacturer_string`],
+    /// [`DeviceInfo::product_string`], [`DeviceInfo::serial_number`] and
+    /// [`InterfaceInfo::interface_string`] return `None`. Use
+    /// [`DeviceInfo::serial_number_lazy`] and related methods to read them
+    /// for only the devices that need them. This speeds up a scan of a bus
+    /// with many devices when only the IDs are needed. The default is
+    /// `false`.
+    ///
+    /// ### Platform-specific notes
+    /// * Only used on Linux, where each string is a separate sysfs file. On
+    ///   other platforms the strings are read with the rest of the device
+    ///   properties.
+    pub fn lazy_strings(mut self, lazy_strings: bool) -> ListDevices {
+        self.lazy_strings = lazy_strings;
+        self
+    }
+
+    /// Include devices whose descriptor fields could not all be read.
+    ///
+    /// By default, a device is skipped (or reported as an error by
+    /// [`list_with_errors`][Self::list_with_errors]) if any of the fields
+    /// from its device descriptor can't be read. When set, such a device is
+    /// listed with the fields that could not be read set to zero, and named
+    /// by [`DeviceInfo::missing_attributes`]. This is useful for diagnostic
+    /// tools that want to show devices that failed to enumerate correctly.
+    /// The default is `false`.
+    ///
+    /// ### Platform-specific notes
+    /// * Only used on Linux. On other platforms, devices are always skipped
+    ///   if their properties can't be read.
+    pub fn partial(mut self, partial: bool) -> ListDevices {
+        self.partial = partial;
+        self
+    }
+
+    /// Read the configuration descriptors of each device while listing, for
+    /// [`DeviceInfo::configurations`].
+    ///
+    /// This costs more per device, but allows an inventory of every
+    /// device's interfaces and endpoints without opening any of them. The
+    /// default is `false`.
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, the descriptors are read from the `descriptors` sysfs
+    ///   attribute along with the device descriptor.
+    /// * On Windows, each configuration descriptor is requested from the
+    ///   hub, which answers from its cache or asks the device.
+    /// * On macOS, the descriptors are not available without opening the
+    ///   device, so [`DeviceInfo::configurations`] is empty.
+    pub fn configuration_descriptors(mut self, configuration_descriptors: bool) -> ListDevices {
+        self.configuration_descriptors = configuration_descriptors;
+        self
+    }
+
+    /// Include root hubs in the list.
+    ///
+    /// Root hubs are the virtual hubs of each host controller that the
+    /// devices on its bus are connected to. They are skipped by default,
+    /// as there's little reason to talk to them, but tools that show the
+    /// complete device tree or audit controllers need them. Root hubs are
+    /// marked by [`DeviceInfo::is_root_hub`]. The default is `false`.
+    ///
+    /// ### Platform-specific notes
+    /// * Only used on Linux, where root hubs are the `usbN` devices in sysfs,
+    ///   and have all the attributes of other devices. Their
+    ///   [`port_chain`][DeviceInfo::port_chain] is empty.
+    /// * On Windows and macOS, root hubs are never listed.
+    pub fn root_hubs(mut self, root_hubs: bool) -> ListDevices {
+        self.root_hubs = root_hubs;
+        self
+    }
+
+    /// Get an iterator listing the connected devices, skipping devices whose
+    /// information could not be read.
+    pub fn list(&self) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+        Ok(self
+            .list_with_errors()?
+            .filter_map(|res| res.inspect_err(|e| log::warn!("{e}; ignoring device")).ok()))
+    }
+
+    /// Get an iterator listing the connected devices, with an error for each
+    /// device that was found but whose information could not be read.
+    ///
+    /// Devices removed while listing are omitted rather than reported as
+    /// errors.
+    ///
+    /// ### Platform-specific notes
+    /// * On Windows and macOS, devices that can't be read are skipped, and
+    ///   errors are never returned.
+    pub fn list_with_errors(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<DeviceInfo, Error>>, Error> {
+        platform::list_devices_with_errors(
+            self.retries,
+            self.lazy_strings,
+            self.partial,
+            self.configuration_descriptors,
+            self.root_hubs,
+        )
+    }
+}
+
+impl Default for ListDevices {
+    fn default() -> Self {
+        ListDevices::new()
+    }
+}
+
+/// Get an iterator listing the connected devices along with their
+/// configuration descriptors.
+///
+/// This is [`list_devices`] with
+/// [`ListDevices::configuration_descriptors`] enabled, so
+/// [`DeviceInfo::configurations`] is available for each device. See that
+/// method for the platform support.
+///
+/// ### Example
+///
+/// ```no_run
+/// use nusb;
+/// for dev in nusb::list_devices_full().unwrap() {
+///     for config in dev.configurations() {
+///         println!("{:04x}:{:04x} {config:?}", dev.vendor_id(), dev.product_id());
+///     }
+/// }
+/// ```
+pub fn list_devices_full() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    ListDevices::new().configuration_descriptors(true).list()
+}
+
+/// Get an iterator listing the connected devices, including root hubs.
+///
+/// This is [`list_devices`] with [`ListDevices::root_hubs`] enabled. See
+/// that method for the platform support.
+///
+/// ### Example
+///
+/// ```no_run
+/// use nusb;
+/// for hub in nusb::list_devices_including_root_hubs().unwrap() {
+///     if hub.is_root_hub() {
+///         println!("bus {}: {:?}", hub.bus_id(), hub.controller_driver());
+///     }
+/// }
+/// ```
+pub fn list_devices_including_root_hubs() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    ListDevices::new().root_hubs(true).list()
+}
+
+/// Get an iterator listing the connected devices in the order of the
+/// device tree.
+///
+/// Devices are sorted by bus, and then by [`DeviceInfo::port_chain`]
+/// compared element by element, so each hub is followed by the devices
+/// connected to it in port order, as shown by `lsusb -t`. This makes the
+/// output deterministic for tests and user interfaces, while
+/// [`list_devices`] returns devices in the arbitrary order of the OS.
+///
+/// ### Example
+///
+/// ```no_run
+/// use nusb;
+/// for dev in nusb::list_devices_sorted().unwrap() {
+///     println!("{} {:04x}:{:04x}", dev.port_path(), dev.vendor_id(), dev.product_id());
+/// }
+/// ```
+///
+/// ### Platform-specific notes
+/// * On Linux, buses are sorted by bus number. On other platforms, they are
+///   sorted by [`DeviceInfo::bus_id`] as a string.
+/// * On Windows, hubs are not included in the list
+pub fn list_devices_sorted() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    let mut devices: Vec<DeviceInfo> = platform::list_devices()?.collect();
+    devices.sort_by(DeviceInfo::cmp_topology);
+    Ok(devices.into_iter())
+}
+
+/// Get an iterator listing only the IDs of the connected devices.
+///
+/// This is cheaper than [`list_devices`] when only the vendor and product
+/// IDs are needed, for example to count or match devices. Use
+/// [`DeviceSummary::probe`] to get the [`DeviceInfo`] of a match.
+///
+/// ### Example
+///
+/// ```no_run
+/// use nusb;
+/// let device = nusb::list_device_ids().unwrap()
+///     .find(|d| d.vendor_id() == 0xAAAA && d.product_id() == 0xBBBB)
+///     .and_then(|d| d.probe())
+///     .expect("device not connected");
+/// ```
+///
+/// ### Platform-specific notes
+/// * On Linux, this reads only four sysfs attributes per device.
+/// * On macOS, this reads only the required properties of each device.
+/// * On Windows, this is implemented with [`list_devices`] and is no
+///   cheaper, as the IDs are read from the hub along with the rest of the
+///   device information. Hubs are not included in the list.
+pub fn list_device_ids() -> Result<impl Iterator<Item = DeviceSummary>, Error> {
+    platform::list_device_ids()
+}
+
+/// Get an iterator listing the connected devices with the specified class
+/// code, either as the device class or the class of any interface.
+///
+/// This is equivalent to filtering [`list_devices`] on
+/// [`DeviceInfo::class`] and [`InterfaceInfo::class`], but on Linux it
+/// skips reading the rest of the attributes of devices that don't match.
+///
+/// ### Example
+///
+/// ```no_run
+/// use nusb;
+/// const CDC_DATA: u8 = 0x0A;
+/// for dev in nusb::list_devices_with_class(CDC_DATA).unwrap() {
+///     println!("{dev:?}");
+/// }
+/// ```
+///
+/// ### Platform-specific notes
+/// * On Windows, hubs are not included in the list
+pub fn list_devices_with_class(class: u8) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    platform::list_devices_with_class(class)
+}
+
+/// Get an iterator listing the connected devices on the bus identified by
+/// `bus_id`.
+///
+/// `bus_id` is compared with [`DeviceInfo::bus_id`], and the returned
+/// `DeviceInfo`s are the same as those from [`list_devices`]. Devices on
+/// other buses are skipped before being probed, which is cheaper than
+/// filtering `list_devices` on systems with many devices.
+///
+/// ### Example
+///
+/// ```no_run
+/// use nusb;
+/// for dev in nusb::list_devices_on_bus("003").unwrap() {
+///     println!("{dev:?}");
+/// }
+/// ```
+///
+/// ### Platform-specific notes
+/// * On Linux, the bus ID is the bus number, and leading zeros are ignored.
+/// * On Windows, hubs are not included in the list
+pub fn list_devices_on_bus(bus_id: &str) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    platform::list_devices_on_bus(bus_id)
+}
+
+/// Open the first connected device with the specified vendor ID and product ID.
+///
+/// Returns `Ok(None)` if no matching device is connected, or an error if
+/// listing devices or opening the matching device fails.
+///
+/// ### Example
+///
+/// ```no_run
+/// use nusb;
+/// let device = nusb::open_device_with_vid_pid(0xAAAA, 0xBBBB)
+///     .expect("failed to open device")
+///     .expect("device not connected");
+/// ```
+pub fn open_device_with_vid_pid(vendor_id: u16, product_id: u16) -> Result<Option<Device>, Error> {
+    list_devices()?
+        .find(|d| d.vendor_id() == vendor_id && d.product_id() == product_id)
+        .map(|d| d.open())
+        .transpose()
+}
+
+/// Open the first connected device with the specified serial number.
+///
+/// The comparison is exact and case-sensitive. Devices that do not report a
+/// serial number are skipped. If the serial numbers of your devices are
+/// only unique for a particular product, filter on the vendor and product
+/// ID too with [`list_devices`] instead.
+///
+/// Returns `Ok(None)` if no matching device is connected, or an error if
+/// listing devices or opening the matching device fails.
+///
+/// ### Example
+///
+/// ```no_run
+/// use nusb;
+/// let device = nusb::open_device_with_serial("A1B2C3")
+///     .expect("failed to open device")
+///     .expect("device not connected");
+/// ```
+pub fn open_device_with_serial(serial: &str) -> Result<Option<Device>, Error> {
+    list_devices()?
+        .find(|d| d.serial_number() == Some(serial))
+        .map(|d| d.open())
+        .transpose()
+}
+
+/// Open the connected device with the specified Windows device instance ID.
+///
+/// The instance ID is the string returned by [`DeviceInfo::instance_id`],
+/// like `USB\VID_1234&PID_5678\0123456789`, and is the same one used by
+/// SetupAPI, the configuration manager, and Device Manager. The comparison
+/// is case-insensitive.
+///
+/// Returns `Ok(None)` if no device with that instance ID is connected, or an
+/// error if opening the device fails.
+///
+/// ### Example
+///
+/// ```no_run
+/// use nusb;
+/// let device = nusb::open_device_with_instance_id("USB\\VID_1234&PID_5678\\0123456789".as_ref())
+///     .expect("failed to open device")
+///     .expect("device not connected");
+/// ```
+#[cfg(target_os = "windows")]
+pub fn open_device_with_instance_id(
+    instance_id: &std::ffi::OsStr,
+) -> Result<Option<Device>, Error> {
+    platform::probe_instance_id(instance_id)
+        .map(|d| d.open())
+        .transpose()
+}
+
 /// Get a [`Stream`][`futures_core::Stream`] that yields an
 /// [event][`hotplug::HotplugEvent`] when a USB device is connected or
 /// disconnected from the system.
@@ -178,12 +596,59 @@ pub fn list_devices() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
 /// }
 /// ```
 ///
+/// Use the methods on [`HotplugWatch`][`hotplug::HotplugWatch`] to only
+/// receive events for particular devices.
+///
+/// Each call creates an independent watch with its own registration with the
+/// OS, so any number of watches can be active at once, including from
+/// different libraries in the same process. Every watch receives all events
+/// that match its own filters. Dropping a `HotplugWatch` removes only its
+/// own registration and does not affect other watches.
+///
 /// ### Platform-specific notes:
 ///
+///   * On Linux, each watch opens its own netlink socket subscribed to the
+///     udev multicast group, and the kernel delivers a copy of each event
+///     to every socket.
 ///   * On Windows, the interfaces of a composite device might not be ready
 ///     when the `Connected` event is emitted. If you are immediately opening the device
 ///     and claiming an interface when receiving a `Connected` event,
 ///     you should retry after a short delay if opening or claiming fails.
 pub fn watch_devices() -> Result<hotplug::HotplugWatch, Error> {
-    Ok(hotplug::HotplugWatch(platform::HotplugWatch::new()?))
+    Ok(hotplug::HotplugWatch::new(platform::HotplugWatch::new()?))
+}
+
+/// Wait for a device matching `filter` to be connected.
+///
+/// If a matching device is already connected, the future resolves
+/// immediately with it. Otherwise, it resolves with the first matching
+/// device that is connected. Hotplug events are watched before listing the
+/// connected devices, so a device connected in between is not missed.
+///
+/// ### Example
+///
+/// ```no_run
+/// use futures_lite::future::block_on;
+/// use nusb::hotplug::HotplugFilter;
+/// let filter = HotplugFilter::new().vendor_id(0xAAAA).product_id(0xBBBB);
+/// let device_info = block_on(nusb::wait_for_device(filter)).unwrap();
+/// ```
+pub async fn wait_for_device(filter: hotplug::HotplugFilter) -> Result<DeviceInfo, Error> {
+    use futures_core::Stream;
+    use std::{future::poll_fn, pin::Pin};
+
+    let mut watch = watch_devices()?;
+    watch.filters.push(filter.clone());
+
+    if let Some(device) = list_devices()?.find(|d| filter.matches(d)) {
+        return Ok(device);
+    }
+
+    loop {
+        match poll_fn(|cx| Pin::new(&mut watch).poll_next(cx)).await {
+            Some(hotplug::HotplugEvent::Connected(device)) => return Ok(device),
+            Some(hotplug::HotplugEvent::Disconnected(_)) => continue,
+            None => return Err(Error::other("hotplug event stream ended")),
+        }
+    }
 }
@@ -0,0 +1,833 @@
+//! Virtual devices for testing code that uses `nusb` without hardware.
+//!
+//! With the `mock` feature enabled, tests register virtual devices with
+//! [`MockDevice`], and the normal APIs like
+//! [`list_devices`][crate::list_devices],
+//! [`DeviceInfo::open`][crate::DeviceInfo::open], and the transfer methods
+//! on [`Interface`][crate::Interface] operate on them.
+//!
+//! Standard `GET_DESCRIPTOR` requests for the device, configuration, and
+//! string descriptors are answered from the descriptors given to the
+//! builder. All other control requests, and bulk, interrupt, and
+//! isochronous transfers, are passed to the device's [`MockHandler`].
+//!
+//! ```
+//! use nusb::{mock::{MockDevice, MockHandler}, transfer::TransferError};
+//! use std::time::Duration;
+//!
+//! struct Echo;
+//!
+//! impl MockHandler for Echo {
+//!     fn transfer_in(&self, endpoint: u8, length: usize) -> Result<Vec<u8>, TransferError> {
+//!         Ok(vec![endpoint; length])
+//!     }
+//! }
+//!
+//! let connection = MockDevice::new(0x1234, 0x5678)
+//!     .product_string("Test device")
+//!     .configuration(&[
+//!         0x09, 0x02, 0x19, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32, // configuration
+//!         0x09, 0x04, 0x00, 0x00, 0x01, 0xFF, 0x00, 0x00, 0x00, // interface 0
+//!         0x07, 0x05, 0x81, 0x02, 0x40, 0x00, 0x00, // endpoint 0x81, bulk
+//!     ])
+//!     .handler(Echo)
+//!     .connect();
+//!
+//! let device_info = nusb::list_devices()
+//!     .unwrap()
+//!     .find(|d| d.id() == connection.id())
+//!     .unwrap();
+//! assert_eq!(device_info.product_string(), Some("Test device"));
+//!
+//! let interface = device_info.open().unwrap().claim_interface(0).unwrap();
+//! let mut buf = [0; 64];
+//! let len = interface.bulk_in_blocking(0x81, &mut buf, Duration::from_secs(1)).unwrap();
+//! assert_eq!(&buf[..len], &[0x81; 64]);
+//! ```
+//!
+//! Transfers complete as soon as they are submitted, with the result
+//! returned by the handler, so handlers should return promptly. IN
+//! transfers on endpoints where [`MockHandler::nak_in`] returns `true`
+//! instead stay pending until cancelled or the device is disconnected. The set of
+//! virtual devices is shared by the whole process, so tests that run in
+//! parallel should look up their device by [`MockConnection::id`] rather
+//! than assuming it is the only one connected.
+//!
+//! The feature is additive: until the first virtual device is connected,
+//! `nusb` lists and opens the OS's devices as usual. From then on, listing
+//! and hotplug watches only report virtual devices, for the rest of the
+//! process, so tests don't see whatever hardware is attached. A
+//! `DeviceInfo` obtained from the OS earlier can still be opened.
+//!
+//! Virtual devices have no OS resources. Platform-specific accessors such
+//! as `DeviceInfo::sysfs_path` return `None`, operations that need the OS
+//! return an error of kind [`Unsupported`][std::io::ErrorKind::Unsupported],
+//! and the raw handle accessors, including `AsFd` and `AsRawHandle`, panic.
+//! On Linux, a virtual device reports no `SupportedFeatures`.
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use crate::{
+    descriptors::{
+        validate_config_descriptor, Configuration, DESCRIPTOR_LEN_DEVICE, DESCRIPTOR_TYPE_DEVICE,
+    },
+    enumeration::assign_functions,
+    platform::mock::{self, MockEntry},
+    transfer::{Control, TransferError},
+    DeviceId, DeviceInfo, InterfaceInfo, Speed,
+};
+
+/// Behavior of a virtual device for requests that aren't answered from its
+/// descriptors.
+///
+/// Each method is called when a transfer is submitted, and its return value
+/// completes the transfer. The default implementations return
+/// [`TransferError::Stall`], except for [`nak_in`][Self::nak_in].
+pub trait MockHandler: Send + Sync + 'static {
+    /// Respond to a control IN request with up to `length` bytes.
+    ///
+    /// Data beyond `length` is discarded, as a device would stop sending
+    /// after the length in the SETUP packet.
+    fn control_in(&self, control: Control, length: u16) -> Result<Vec<u8>, TransferError> {
+        let _ = (control, length);
+        Err(TransferError::Stall)
+    }
+
+    /// Accept the data of a control OUT request.
+    fn control_out(&self, control: Control, data: &[u8]) -> Result<(), TransferError> {
+        let _ = (control, data);
+        Err(TransferError::Stall)
+    }
+
+    /// Respond to a bulk or interrupt IN transfer on `endpoint` with up to
+    /// `length` bytes.
+    ///
+    /// For an isochronous transfer, this is called once for each packet,
+    /// and an error fails only that packet.
+    ///
+    /// Returning more than `length` bytes completes the transfer with
+    /// [`TransferError::Overflow`].
+    fn transfer_in(&self, endpoint: u8, length: usize) -> Result<Vec<u8>, TransferError> {
+        let _ = (endpoint, length);
+        Err(TransferError::Stall)
+    }
+
+    /// Whether the device NAKs IN transfers on `endpoint`, leaving them
+    /// pending instead of calling [`transfer_in`][Self::transfer_in].
+    ///
+    /// Held transfers complete with [`TransferError::Cancelled`] when
+    /// cancelled, or with [`TransferError::Disconnected`] when the device is
+    /// disconnected. The default implementation returns `false`.
+    fn nak_in(&self, endpoint: u8) -> bool {
+        let _ = endpoint;
+        false
+    }
+
+    /// Accept the data of a bulk or interrupt OUT transfer on `endpoint`.
+    ///
+    /// For an isochronous transfer, this is called once for each packet.
+    fn transfer_out(&self, endpoint: u8, data: &[u8]) -> Result<(), TransferError> {
+        let _ = (endpoint, data);
+        Err(TransferError::Stall)
+    }
+}
+
+/// Handler used when none is set, which stalls all requests.
+struct StallHandler;
+
+impl MockHandler for StallHandler {}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Builder for a virtual device.
+///
+/// Call [`connect`][Self::connect] to make it visible to
+/// [`list_devices`][crate::list_devices] and the other enumeration
+/// functions.
+pub struct MockDevice {
+    device_descriptor: [u8; DESCRIPTOR_LEN_DEVICE as usize],
+    configurations: Vec<Vec<u8>>,
+    strings: Vec<(u8, String)>,
+    bus_id: String,
+    speed: Option<Speed>,
+    root_hub: bool,
+    handler: Arc<dyn MockHandler>,
+}
+
+impl MockDevice {
+    /// Start building a USB 2.0 high speed device with the specified vendor
+    /// and product IDs, and no configurations.
+    pub fn new(vendor_id: u16, product_id: u16) -> MockDevice {
+        let [vid_lo, vid_hi] = vendor_id.to_le_bytes();
+        let [pid_lo, pid_hi] = product_id.to_le_bytes();
+
+        #[rustfmt::skip]
+        let device_descriptor = [
+            DESCRIPTOR_LEN_DEVICE, DESCRIPTOR_TYPE_DEVICE,
+            0x00, 0x02, // bcdUSB 2.00
+            0x00, 0x00, 0x00, // class, subclass, protocol
+            64, // bMaxPacketSize0
+            vid_lo, vid_hi,
+            pid_lo, pid_hi,
+            0x00, 0x01, // bcdDevice 1.00
+            0, 0, 0, // iManufacturer, iProduct, iSerialNumber
+            0, // bNumConfigurations
+        ];
+
+        MockDevice {
+            device_descriptor,
+            configurations: Vec::new(),
+            strings: Vec::new(),
+            bus_id: "mock".into(),
+            speed: Some(Speed::High),
+            root_hub: false,
+            handler: Arc::new(StallHandler),
+        }
+    }
+
+    /// Replace the device descriptor.
+    ///
+    /// This overrides the vendor and product IDs passed to
+    /// [`new`][Self::new] and any string indexes or configuration count set
+    /// by earlier builder calls.
+    ///
+    /// ### Panics
+    /// * when `descriptor` is not 18 bytes long or is not a device descriptor
+    pub fn device_descriptor(mut self, descriptor: &[u8]) -> MockDevice {
+        assert!(descriptor.len() == DESCRIPTOR_LEN_DEVICE as usize);
+        assert!(descriptor[0] == DESCRIPTOR_LEN_DEVICE);
+        assert!(descriptor[1] == DESCRIPTOR_TYPE_DEVICE);
+        self.device_descriptor.copy_from_slice(descriptor);
+        self
+    }
+
+    /// Add a configuration descriptor, followed by all of its interface,
+    /// endpoint, and other descriptors, and update `bNumConfigurations`.
+    ///
+    /// The first configuration is active when the device is opened, and its
+    /// interfaces are listed in [`DeviceInfo::interfaces`].
+    ///
+    /// ### Panics
+    /// * when `descriptors` is not a configuration descriptor whose
+    ///   `wTotalLength` matches its length
+    pub fn configuration(mut self, descriptors: &[u8]) -> MockDevice {
+        assert_eq!(
+            validate_config_descriptor(descriptors),
+            Some(descriptors.len()),
+            "invalid configuration descriptor"
+        );
+        self.configurations.push(descriptors.to_vec());
+        self.device_descriptor[17] = self.configurations.len() as u8;
+        self
+    }
+
+    /// Add a string descriptor at `index`, for use by interface or other
+    /// descriptors that refer to it.
+    pub fn string(mut self, index: u8, value: impl Into<String>) -> MockDevice {
+        assert!(index != 0, "string descriptor 0 is the language table");
+        self.strings.retain(|(i, _)| *i != index);
+        self.strings.push((index, value.into()));
+        self
+    }
+
+    /// Set the manufacturer string, using string index 1.
+    #[doc(alias = "iManufacturer")]
+    pub fn manufacturer_string(mut self, value: impl Into<String>) -> MockDevice {
+        self.device_descriptor[14] = 1;
+        self.string(1, value)
+    }
+
+    /// Set the product string, using string index 2.
+    #[doc(alias = "iProduct")]
+    pub fn product_string(mut self, value: impl Into<String>) -> MockDevice {
+        self.device_descriptor[15] = 2;
+        self.string(2, value)
+    }
+
+    /// Set the serial number string, using string index 3.
+    #[doc(alias = "iSerialNumber")]
+    pub fn serial_number(mut self, value: impl Into<String>) -> MockDevice {
+        self.device_descriptor[16] = 3;
+        self.string(3, value)
+    }
+
+    /// Set the bus ID reported in [`DeviceInfo::bus_id`]. Defaults to `mock`.
+    pub fn bus_id(mut self, bus_id: impl Into<String>) -> MockDevice {
+        self.bus_id = bus_id.into();
+        self
+    }
+
+    /// Set the speed reported in [`DeviceInfo::speed`]. Defaults to
+    /// [`Speed::High`].
+    pub fn speed(mut self, speed: Option<Speed>) -> MockDevice {
+        self.speed = speed;
+        self
+    }
+
+    /// Make the device a root hub, setting its class to hub.
+    ///
+    /// A root hub has an empty [`port_chain`][DeviceInfo::port_chain] and
+    /// is only listed with [`ListDevices::root_hubs`][crate::ListDevices::root_hubs].
+    pub fn root_hub(mut self, root_hub: bool) -> MockDevice {
+        self.root_hub = root_hub;
+        if root_hub {
+            self.device_descriptor[4] = 0x09;
+        }
+        self
+    }
+
+    /// Set the handler for transfers and control requests.
+    ///
+    /// Without a handler, all requests other than descriptor requests
+    /// return [`TransferError::Stall`].
+    pub fn handler(mut self, handler: impl MockHandler) -> MockDevice {
+        self.handler = Arc::new(handler);
+        self
+    }
+
+    /// Connect the device, making it visible to enumeration and delivering a
+    /// [`HotplugEvent::Connected`][crate::hotplug::HotplugEvent::Connected]
+    /// to open [`HotplugWatch`][crate::hotplug::HotplugWatch]es.
+    pub fn connect(self) -> MockConnection {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let device_address = ((id - 1) % 127 + 1) as u8;
+        let d = self.device_descriptor;
+
+        let strings = &self.strings;
+        let string = |index: u8| {
+            strings
+                .iter()
+                .find(|(i, _)| *i == index)
+                .map(|(_, s)| s.clone())
+        };
+
+        let mut interfaces = Vec::new();
+        if let Some(config) = self.configurations.first() {
+            let config = Configuration::new(config);
+            interfaces = config
+                .interfaces()
+                .map(|intf| {
+                    let alt = intf.first_alt_setting();
+                    InterfaceInfo {
+                        interface_number: intf.interface_number(),
+                        class: alt.class(),
+                        subclass: alt.subclass(),
+                        protocol: alt.protocol(),
+                        interface_string: alt.string_index().and_then(string),
+                        function: None,
+                    }
+                })
+                .collect();
+            assign_functions(&mut interfaces, &config);
+        }
+
+        let info = DeviceInfo {
+            mock_id: Some(id),
+            #[cfg(target_os = "linux")]
+            path: crate::platform::SysfsPath(std::path::PathBuf::new()),
+            #[cfg(target_os = "linux")]
+            busnum: 0,
+            #[cfg(target_os = "linux")]
+            missing_attributes: Vec::new(),
+            #[cfg(target_os = "windows")]
+            instance_id: std::ffi::OsString::new(),
+            #[cfg(target_os = "windows")]
+            location_paths: Vec::new(),
+            #[cfg(target_os = "windows")]
+            parent_instance_id: std::ffi::OsString::new(),
+            #[cfg(target_os = "windows")]
+            port_number: 0,
+            #[cfg(target_os = "windows")]
+            devinst: None,
+            #[cfg(target_os = "windows")]
+            driver: None,
+            #[cfg(target_os = "windows")]
+            openable: true,
+            #[cfg(target_os = "macos")]
+            registry_id: 0,
+            #[cfg(target_os = "macos")]
+            location_id: 0,
+            bus_id: self.bus_id,
+            controller_id: None,
+            controller_driver: None,
+            device_address,
+            port_chain: if self.root_hub {
+                Vec::new()
+            } else {
+                vec![device_address]
+            },
+            root_hub: self.root_hub,
+            vendor_id: u16::from_le_bytes([d[8], d[9]]),
+            product_id: u16::from_le_bytes([d[10], d[11]]),
+            device_version: u16::from_le_bytes([d[12], d[13]]),
+            usb_version: u16::from_le_bytes([d[2], d[3]]),
+            class: d[4],
+            subclass: d[5],
+            protocol: d[6],
+            max_packet_size_0: d[7],
+            num_configurations: d[17],
+            device_descriptor: Some(d),
+            speed: self.speed,
+            manufacturer_string: string(d[14]),
+            product_string: string(d[15]),
+            serial_number: string(d[16]),
+            interfaces,
+            configuration_descriptors: Vec::new(),
+        };
+
+        mock::connect(MockEntry {
+            info: info.clone(),
+            device_descriptor: d.to_vec(),
+            configurations: self.configurations,
+            strings: self.strings,
+            handler: self.handler,
+            connected: AtomicBool::new(true),
+            held: Mutex::new(Vec::new()),
+        });
+
+        MockConnection { info }
+    }
+}
+
+/// A connected virtual device, returned by [`MockDevice::connect`].
+///
+/// The device is disconnected when this is dropped.
+pub struct MockConnection {
+    info: DeviceInfo,
+}
+
+impl MockConnection {
+    /// Identifier of the virtual device, equal to [`DeviceInfo::id`].
+    pub fn id(&self) -> DeviceId {
+        self.info.id()
+    }
+
+    /// The `DeviceInfo` returned by enumeration for this device.
+    pub fn device_info(&self) -> &DeviceInfo {
+        &self.info
+    }
+
+    /// Disconnect the device.
+    ///
+    /// Transfers on open handles fail with [`TransferError::Disconnected`],
+    /// and open [`HotplugWatch`][crate::hotplug::HotplugWatch]es receive a
+    /// [`HotplugEvent::Disconnected`][crate::hotplug::HotplugEvent::Disconnected].
+    pub fn disconnect(self) {
+        // disconnects on drop
+    }
+}
+
+impl Drop for MockConnection {
+    fn drop(&mut self) {
+        if let Some(id) = self.info.mock_id {
+            mock::disconnect(id);
+        }
+    }
+}
+
+#[cfg(test)]
+const TEST_CONFIGURATION: &[u8] = &[
+    0x09, 0x02, 0x20, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32, // configuration
+    0x09, 0x04, 0x00, 0x00, 0x02, 0xFF, 0x00, 0x00, 0x04, // interface 0
+    0x07, 0x05, 0x81, 0x02, 0x40, 0x00, 0x00, // endpoint 0x81, bulk
+    0x07, 0x05, 0x02, 0x02, 0x40, 0x00, 0x00, // endpoint 0x02, bulk
+];
+
+#[test]
+fn test_mock_descriptors_and_control() {
+    use crate::transfer::{ControlType, Recipient};
+    use std::time::Duration;
+
+    struct Vendor;
+
+    impl MockHandler for Vendor {
+        fn control_in(&self, control: Control, _length: u16) -> Result<Vec<u8>, TransferError> {
+            match (control.control_type, control.request) {
+                (ControlType::Vendor, 0x01) => Ok(control.value.to_le_bytes().to_vec()),
+                _ => Err(TransferError::Stall),
+            }
+        }
+    }
+
+    let connection = MockDevice::new(0x1234, 0xabcd)
+        .manufacturer_string("nusb")
+        .serial_number("0001")
+        .string(4, "Data interface")
+        .configuration(TEST_CONFIGURATION)
+        .handler(Vendor)
+        .connect();
+
+    let info = crate::list_devices()
+        .unwrap()
+        .find(|d| d.id() == connection.id())
+        .unwrap();
+    assert_eq!(info.vendor_id(), 0x1234);
+    assert_eq!(info.product_id(), 0xabcd);
+    assert_eq!(info.manufacturer_string(), Some("nusb"));
+    assert_eq!(info.product_string(), None);
+    assert_eq!(info.serial_number(), Some("0001"));
+    assert_eq!(info.manufacturer_string_index(), Some(1));
+    assert_eq!(info.product_string_index(), None);
+    assert_eq!(info.serial_number_string_index(), Some(3));
+    let intf = info.interfaces().next().unwrap();
+    assert_eq!(intf.class(), 0xFF);
+    assert_eq!(intf.interface_string(), Some("Data interface"));
+
+    let device = info.open().unwrap();
+    assert_eq!(device.active_configuration().unwrap().num_interfaces(), 1);
+    let timeout = Duration::from_secs(1);
+    assert_eq!(
+        device.get_string_descriptor(3, 0x0409, timeout).unwrap(),
+        "0001"
+    );
+    assert_eq!(
+        device
+            .read_string_descriptor(0, timeout)
+            .unwrap()
+            .languages(),
+        Some(&[0x0409][..])
+    );
+    assert_eq!(
+        device.read_string_descriptor(1, timeout).unwrap().as_str(),
+        Some("nusb")
+    );
+
+    // The handler stalls requests for descriptors the device doesn't have.
+    assert!(device.device_qualifier(timeout).unwrap().is_none());
+    assert!(device.container_id(timeout).unwrap().is_none());
+
+    // Windows only has control transfers on a claimed interface.
+    #[cfg(not(target_os = "windows"))]
+    {
+        assert_eq!(
+            device
+                .read_ms_os_10_descriptors(timeout)
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::NotFound
+        );
+
+        let mut buf = [0; 4];
+        let control = |request| Control {
+            control_type: ControlType::Vendor,
+            recipient: Recipient::Device,
+            request,
+            value: 0x5678,
+            index: 0,
+        };
+        assert_eq!(
+            device.control_in_blocking(control(0x01), &mut buf, timeout),
+            Ok(2)
+        );
+        assert_eq!(&buf[..2], &[0x78, 0x56]);
+        assert_eq!(
+            device.control_in_blocking(control(0x02), &mut buf, timeout),
+            Err(TransferError::Stall)
+        );
+
+        // `Device::control` requires the buffer to match `wLength`.
+        let setup = crate::transfer::ControlSetup::new(crate::transfer::Direction::In)
+            .control_type(ControlType::Vendor)
+            .request(0x01)
+            .value(0x1234)
+            .length(4);
+        assert_eq!(device.control(setup, &mut buf, timeout).unwrap(), 2);
+        assert_eq!(&buf[..2], &[0x34, 0x12]);
+        assert_eq!(
+            device
+                .control(setup, &mut buf[..3], timeout)
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+
+    let interface = device.claim_interface(0).unwrap();
+    assert_eq!(interface.num_alt_settings(), 1);
+    let mut queue = interface.control_in_queue();
+    for value in [1, 2] {
+        queue.submit(crate::transfer::ControlIn {
+            control_type: ControlType::Vendor,
+            recipient: Recipient::Device,
+            request: 0x01,
+            value,
+            index: 0,
+            length: 2,
+        });
+    }
+    let results: Vec<_> = (0..2)
+        .map(|_| futures_lite::future::block_on(queue.next_complete()).into_result())
+        .collect();
+    assert_eq!(results, [Ok(vec![1, 0]), Ok(vec![2, 0])]);
+}
+
+#[test]
+fn test_mock_bulk_and_disconnect() {
+    use std::{sync::Mutex, time::Duration};
+
+    #[derive(Default)]
+    struct Loopback(Mutex<Vec<u8>>);
+
+    impl MockHandler for Loopback {
+        fn transfer_in(&self, _endpoint: u8, length: usize) -> Result<Vec<u8>, TransferError> {
+            let mut data = self.0.lock().unwrap();
+            let len = length.min(data.len());
+            Ok(data.drain(..len).collect())
+        }
+
+        fn transfer_out(&self, _endpoint: u8, data: &[u8]) -> Result<(), TransferError> {
+            self.0.lock().unwrap().extend_from_slice(data);
+            Ok(())
+        }
+    }
+
+    let connection = MockDevice::new(0x1234, 0xabce)
+        .configuration(TEST_CONFIGURATION)
+        .handler(Loopback::default())
+        .connect();
+
+    let interface = connection
+        .device_info()
+        .open()
+        .unwrap()
+        .claim_interface(0)
+        .unwrap();
+    let timeout = Duration::from_secs(1);
+    assert_eq!(interface.bulk_out_blocking(0x02, b"hello", timeout), Ok(5));
+    let mut buf = [0; 64];
+    assert_eq!(interface.bulk_in_blocking(0x81, &mut buf, timeout), Ok(5));
+    assert_eq!(&buf[..5], b"hello");
+
+    // An endpoint of the wrong direction fails the transfer instead of panicking.
+    let ep_out = crate::transfer::EndpointAddress::new_out(2);
+    assert_eq!(
+        interface.bulk_in_blocking(ep_out, &mut buf, timeout),
+        Err(TransferError::InvalidEndpoint)
+    );
+    let completion = futures_lite::future::block_on(interface.bulk_out(0x81, b"x".to_vec()));
+    assert_eq!(completion.status, Err(TransferError::InvalidEndpoint));
+    assert_eq!(completion.data.actual_length(), 0);
+
+    let id = connection.id();
+    connection.disconnect();
+    assert!(crate::list_devices().unwrap().all(|d| d.id() != id));
+    assert_eq!(
+        interface.bulk_in_blocking(0x81, &mut buf, timeout),
+        Err(TransferError::Disconnected)
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_mock_linux_apis() {
+    use crate::transfer::{IsoOutBuffer, IsoRequestBuffer, RequestBuffer};
+    use futures_lite::future::block_on;
+    use std::sync::atomic::AtomicU8;
+
+    #[derive(Default)]
+    struct Iso(AtomicU8);
+
+    impl MockHandler for Iso {
+        fn transfer_in(&self, _endpoint: u8, _length: usize) -> Result<Vec<u8>, TransferError> {
+            match self.0.fetch_add(1, Ordering::Relaxed) {
+                2 => Err(TransferError::Fault),
+                n => Ok(vec![n; n as usize + 1]),
+            }
+        }
+
+        fn transfer_out(&self, _endpoint: u8, _data: &[u8]) -> Result<(), TransferError> {
+            Ok(())
+        }
+    }
+
+    let connection = MockDevice::new(0x1234, 0xabd4)
+        .configuration(TEST_CONFIGURATION)
+        .handler(Iso::default())
+        .connect();
+    let info = connection.device_info();
+    assert_eq!(info.sysfs_path(), None);
+    assert_eq!(info.busnum(), None);
+    assert!(info.port_path().to_string().starts_with(info.bus_id()));
+    let device = info.open_readonly().unwrap();
+    assert!(!device.supported_features().bulk_continuation());
+    assert_eq!(device.kernel_driver(0).unwrap(), None);
+    let interface = device
+        .detach_and_claim_interface_except(0, "usbfs")
+        .unwrap();
+    interface.set_bulk_split_size(0).unwrap();
+
+    // Each packet is answered by a separate call to the handler.
+    let mut queue = interface.iso_in_queue(0x81);
+    queue.submit(IsoRequestBuffer::new(4, 4));
+    let completion = block_on(queue.next_complete());
+    assert_eq!(completion.status, Ok(()));
+    let response = completion.data;
+    let packets: Vec<_> = response
+        .packets()
+        .iter()
+        .map(|p| (p.offset(), p.actual_length(), p.status()))
+        .collect();
+    assert_eq!(
+        packets,
+        [
+            (0, 1, Ok(())),
+            (4, 2, Ok(())),
+            (8, 0, Err(TransferError::Fault)),
+            (12, 4, Ok(())),
+        ]
+    );
+    assert_eq!(
+        response.reuse(),
+        [0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 3, 3, 3, 3]
+    );
+
+    let mut queue = interface.iso_out_queue(0x02);
+    queue.submit(IsoOutBuffer::new(vec![7; 10], 4));
+    let completion = block_on(queue.next_complete());
+    let lengths: Vec<_> = completion
+        .data
+        .packets()
+        .iter()
+        .map(|p| p.actual_length())
+        .collect();
+    assert_eq!(lengths, [4, 4, 2]);
+
+    // Streams can't be allocated, so stream transfers fail.
+    assert!(interface.alloc_streams(&[0x81], 4).is_err());
+    let result = block_on(interface.bulk_in_stream(0x81, 1, RequestBuffer::new(64)));
+    assert_eq!(result.status, Err(TransferError::Unknown));
+}
+
+#[test]
+fn test_mock_list_devices_sorted() {
+    let b = MockDevice::new(0x1234, 0xabcf).bus_id("sorted-b").connect();
+    let a2 = MockDevice::new(0x1234, 0xabcf).bus_id("sorted-a").connect();
+    let a1 = MockDevice::new(0x1234, 0xabcf).bus_id("sorted-a").connect();
+
+    let sorted: Vec<DeviceId> = crate::list_devices_sorted()
+        .unwrap()
+        .filter(|d| d.bus_id().starts_with("sorted-"))
+        .map(|d| d.id())
+        .collect();
+    assert_eq!(sorted, [a2.id(), a1.id(), b.id()]);
+}
+
+#[test]
+fn test_mock_list_root_hubs() {
+    let hub = MockDevice::new(0x1d6b, 0x0002)
+        .bus_id("root-hubs")
+        .root_hub(true)
+        .connect();
+    let device = MockDevice::new(0x1234, 0xabd3)
+        .bus_id("root-hubs")
+        .connect();
+
+    let listed = |root_hubs: bool| -> Vec<(DeviceId, bool)> {
+        crate::ListDevices::new()
+            .root_hubs(root_hubs)
+            .list()
+            .unwrap()
+            .filter(|d| d.bus_id() == "root-hubs")
+            .map(|d| (d.id(), d.is_root_hub()))
+            .collect()
+    };
+    assert_eq!(listed(false), [(device.id(), false)]);
+    assert_eq!(listed(true), [(hub.id(), true), (device.id(), false)]);
+
+    let info = hub.device_info();
+    assert!(info.is_hub());
+    assert!(info.port_chain().is_empty());
+    assert!(crate::list_devices().unwrap().all(|d| d.id() != hub.id()));
+}
+
+#[test]
+fn test_mock_wait_disconnected() {
+    use futures_lite::future::block_on;
+
+    let connection = MockDevice::new(0x1234, 0xabd0).connect();
+    let device = connection.device_info().open().unwrap();
+    let disconnected = device.wait_disconnected();
+    let t = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        connection.disconnect();
+    });
+    block_on(disconnected).unwrap();
+    t.join().unwrap();
+
+    // Resolves immediately once the device is gone.
+    block_on(device.wait_disconnected()).unwrap();
+}
+
+#[test]
+fn test_mock_hotplug_filter_composite() {
+    use crate::hotplug::HotplugFilter;
+
+    #[rustfmt::skip]
+    let device_descriptor = [
+        0x12, 0x01, 0x00, 0x02,
+        0xEF, 0x02, 0x01, // miscellaneous class, interface association
+        64, 0x34, 0x12, 0xd1, 0xab, 0x00, 0x01, 0, 0, 0, 1,
+    ];
+    let connection = MockDevice::new(0x1234, 0xabd1)
+        .device_descriptor(&device_descriptor)
+        .configuration(&[
+            0x09, 0x02, 0x12, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32, // configuration
+            0x09, 0x04, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, // interface 0, HID
+        ])
+        .connect();
+
+    let info = connection.device_info();
+    assert!(HotplugFilter::new().class(0x03).matches(info));
+    assert!(HotplugFilter::new().class(0xEF).matches(info));
+    assert!(!HotplugFilter::new().class(0x0A).matches(info));
+}
+
+#[test]
+fn test_mock_disconnect_in_flight() {
+    use crate::transfer::RequestBuffer;
+    use futures_lite::future::block_on;
+
+    struct Nak;
+
+    impl MockHandler for Nak {
+        fn nak_in(&self, _endpoint: u8) -> bool {
+            true
+        }
+    }
+
+    let connection = MockDevice::new(0x1234, 0xabd2)
+        .configuration(TEST_CONFIGURATION)
+        .handler(Nak)
+        .connect();
+    let interface = connection
+        .device_info()
+        .open()
+        .unwrap()
+        .claim_interface(0)
+        .unwrap();
+
+    let mut queue = interface.bulk_in_queue(0x81);
+    for _ in 0..4 {
+        queue.submit(RequestBuffer::new(64));
+    }
+    interface.cancel_all(0x81).unwrap();
+    for _ in 0..4 {
+        let completion = block_on(queue.next_complete());
+        assert_eq!(completion.status, Err(TransferError::Cancelled));
+    }
+
+    for _ in 0..32 {
+        queue.submit(RequestBuffer::new(64));
+    }
+    assert_eq!(queue.pending(), 32);
+    connection.disconnect();
+    for _ in 0..32 {
+        let completion = block_on(queue.next_complete());
+        assert_eq!(completion.status, Err(TransferError::Disconnected));
+        assert!(completion.data.is_empty());
+    }
+    assert_eq!(queue.pending(), 0);
+}
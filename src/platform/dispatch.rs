@@ -0,0 +1,523 @@
+//! Selects between the OS backend and the virtual devices of the `mock`
+//! backend.
+//!
+//! Devices are listed and watched from the OS until the first virtual
+//! device is connected, and from the mock backend from then on. Opening a
+//! `DeviceInfo` uses the backend that listed it. Items that only the OS
+//! backend provides are re-exported from it unchanged.
+
+#[cfg(target_os = "linux")]
+use std::borrow::Cow;
+use std::{
+    ffi::c_void,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use crate::{
+    hotplug::{HotplugEvent, HotplugFilter},
+    transfer::{
+        Completion, Control, EndpointType, PlatformSubmit, PlatformTransfer, Priority,
+        TransferError, TransferHandle, TransferRequest,
+    },
+    DeviceInfo, DeviceSummary, Error,
+};
+
+use super::{mock, os};
+
+pub use os::*;
+
+/// Evaluate `$e` with `$x` bound to the contents of either variant of
+/// `$value`, an `$enum` defaulting to `Self`, for methods with the same
+/// signature on both backends.
+macro_rules! dispatch {
+    ($value:expr, $x:ident => $e:expr) => {
+        match $value {
+            Self::Os($x) => $e,
+            Self::Mock($x) => $e,
+        }
+    };
+    ($enum:ident, $value:expr, $x:ident => $e:expr) => {
+        match $value {
+            $enum::Os($x) => $e,
+            $enum::Mock($x) => $e,
+        }
+    };
+}
+
+/// Message for operations on a virtual device that need an OS handle.
+const NO_OS_HANDLE: &str = "virtual devices from the mock backend have no OS handle";
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub(crate) enum DeviceId {
+    Os(os::DeviceId),
+    Mock(mock::DeviceId),
+}
+
+impl From<os::DeviceId> for DeviceId {
+    fn from(id: os::DeviceId) -> Self {
+        DeviceId::Os(id)
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl DeviceId {
+    pub(crate) fn busnum(&self) -> Option<u8> {
+        match self {
+            DeviceId::Os(id) => id.busnum(),
+            DeviceId::Mock(_) => None,
+        }
+    }
+}
+
+/// Iterator over the results of either backend.
+enum Either<A, B> {
+    Os(A),
+    Mock(B),
+}
+
+impl<T, A: Iterator<Item = T>, B: Iterator<Item = T>> Iterator for Either<A, B> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        dispatch!(self, i => i.next())
+    }
+}
+
+pub fn list_devices() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    Ok(if mock::in_use() {
+        Either::Mock(mock::list_devices()?)
+    } else {
+        Either::Os(os::list_devices()?)
+    })
+}
+
+pub fn list_devices_with_errors(
+    retries: u8,
+    lazy_strings: bool,
+    partial: bool,
+    configuration_descriptors: bool,
+    root_hubs: bool,
+) -> Result<impl Iterator<Item = Result<DeviceInfo, Error>>, Error> {
+    Ok(if mock::in_use() {
+        Either::Mock(mock::list_devices_with_errors(
+            retries,
+            lazy_strings,
+            partial,
+            configuration_descriptors,
+            root_hubs,
+        )?)
+    } else {
+        Either::Os(os::list_devices_with_errors(
+            retries,
+            lazy_strings,
+            partial,
+            configuration_descriptors,
+            root_hubs,
+        )?)
+    })
+}
+
+pub fn list_devices_with_class(class: u8) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    Ok(if mock::in_use() {
+        Either::Mock(mock::list_devices_with_class(class)?)
+    } else {
+        Either::Os(os::list_devices_with_class(class)?)
+    })
+}
+
+pub fn list_devices_on_bus(bus_id: &str) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    Ok(if mock::in_use() {
+        Either::Mock(mock::list_devices_on_bus(bus_id)?)
+    } else {
+        Either::Os(os::list_devices_on_bus(bus_id)?)
+    })
+}
+
+pub fn list_device_ids() -> Result<impl Iterator<Item = DeviceSummary>, Error> {
+    Ok(if mock::in_use() {
+        Either::Mock(mock::list_device_ids()?)
+    } else {
+        Either::Os(os::list_device_ids()?)
+    })
+}
+
+pub(crate) fn probe_device_id(id: DeviceId) -> Option<DeviceInfo> {
+    match id {
+        DeviceId::Os(id) => os::probe_device_id(id),
+        DeviceId::Mock(id) => mock::probe_device_id(id),
+    }
+}
+
+pub(crate) fn probe_parent(device: &DeviceInfo) -> Option<DeviceInfo> {
+    match device.mock_id {
+        Some(_) => mock::probe_parent(device),
+        None => os::probe_parent(device),
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn probe_hub_ports(hub: &DeviceInfo) -> Result<Vec<(u8, Option<DeviceInfo>)>, Error> {
+    match hub.mock_id {
+        Some(_) => mock::probe_hub_ports(hub),
+        None => os::probe_hub_ports(hub),
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn os_device(device: &Device) -> &os::Device {
+    match device {
+        Device::Os(d) => d,
+        Device::Mock(_) => panic!("{NO_OS_HANDLE}"),
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub(crate) fn os_interface(interface: &Interface) -> &os::Interface {
+    match &interface.backend {
+        InterfaceBackend::Os(i) => i,
+        InterfaceBackend::Mock(_) => panic!("{NO_OS_HANDLE}"),
+    }
+}
+
+pub(crate) enum Device {
+    Os(Arc<os::Device>),
+    Mock(Arc<mock::Device>),
+}
+
+impl Device {
+    pub(crate) fn from_device_info(d: &DeviceInfo) -> Result<Arc<Device>, Error> {
+        Ok(Arc::new(match d.mock_id {
+            Some(_) => Device::Mock(mock::Device::from_device_info(d)?),
+            None => Device::Os(os::Device::from_device_info(d)?),
+        }))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn from_device_info_readonly(d: &DeviceInfo) -> Result<Arc<Device>, Error> {
+        Ok(Arc::new(match d.mock_id {
+            Some(_) => Device::Mock(mock::Device::from_device_info_readonly(d)?),
+            None => Device::Os(os::Device::from_device_info_readonly(d)?),
+        }))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        match self {
+            Device::Os(d) => d.as_fd(),
+            Device::Mock(_) => panic!("{NO_OS_HANDLE}"),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn capabilities(&self) -> u32 {
+        dispatch!(self, d => d.capabilities())
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn max_bulk_transfer_size(&self) -> Option<usize> {
+        dispatch!(self, d => d.max_bulk_transfer_size())
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn descriptors(&self) -> Cow<'_, [u8]> {
+        match self {
+            Device::Os(d) => Cow::Borrowed(d.descriptors()),
+            Device::Mock(d) => Cow::Owned(d.descriptors()),
+        }
+    }
+
+    pub(crate) fn is_disconnected(&self) -> bool {
+        dispatch!(self, d => d.is_disconnected())
+    }
+
+    pub(crate) fn is_suspended(&self) -> Result<bool, Error> {
+        dispatch!(self, d => d.is_suspended())
+    }
+
+    pub(crate) fn active_configuration_value(&self) -> u8 {
+        dispatch!(self, d => d.active_configuration_value())
+    }
+
+    pub(crate) fn get_configuration(&self) -> Result<u8, Error> {
+        dispatch!(self, d => d.get_configuration())
+    }
+
+    pub(crate) fn configuration_descriptors(&self) -> impl Iterator<Item = &[u8]> {
+        match self {
+            Device::Os(d) => Either::Os(d.configuration_descriptors()),
+            Device::Mock(d) => Either::Mock(d.configuration_descriptors()),
+        }
+    }
+
+    pub(crate) fn set_configuration(&self, configuration: u8) -> Result<(), Error> {
+        dispatch!(self, d => d.set_configuration(configuration))
+    }
+
+    #[cfg(target_os = "windows")]
+    pub(crate) fn get_descriptor(
+        &self,
+        desc_type: u8,
+        desc_index: u8,
+        language_id: u16,
+    ) -> Result<Vec<u8>, Error> {
+        dispatch!(self, d => d.get_descriptor(desc_type, desc_index, language_id))
+    }
+
+    pub(crate) fn reset(&self) -> Result<(), Error> {
+        dispatch!(self, d => d.reset())
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn control_in_blocking(
+        &self,
+        control: Control,
+        data: &mut [u8],
+        timeout: Duration,
+    ) -> Result<usize, TransferError> {
+        dispatch!(self, d => d.control_in_blocking(control, data, timeout))
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn control_out_blocking(
+        &self,
+        control: Control,
+        data: &[u8],
+        timeout: Duration,
+    ) -> Result<usize, TransferError> {
+        dispatch!(self, d => d.control_out_blocking(control, data, timeout))
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub(crate) fn make_control_transfer(&self) -> TransferHandle<TransferData> {
+        match self {
+            Device::Os(d) => d.make_control_transfer().map(TransferData::Os),
+            Device::Mock(d) => d.make_control_transfer().map(TransferData::Mock),
+        }
+    }
+
+    /// Wrap an interface claimed from this device.
+    fn interface(self: &Arc<Self>, backend: InterfaceBackend) -> Arc<Interface> {
+        Arc::new(Interface {
+            interface_number: dispatch!(InterfaceBackend, &backend, i => i.interface_number),
+            device: self.clone(),
+            backend,
+        })
+    }
+
+    pub(crate) fn claim_interface(
+        self: &Arc<Self>,
+        interface_number: u8,
+    ) -> Result<Arc<Interface>, Error> {
+        let backend = match &**self {
+            Device::Os(d) => InterfaceBackend::Os(d.claim_interface(interface_number)?),
+            Device::Mock(d) => InterfaceBackend::Mock(d.claim_interface(interface_number)?),
+        };
+        Ok(self.interface(backend))
+    }
+
+    pub(crate) fn detach_and_claim_interface(
+        self: &Arc<Self>,
+        interface: u8,
+    ) -> Result<Arc<Interface>, Error> {
+        let backend = match &**self {
+            Device::Os(d) => InterfaceBackend::Os(d.detach_and_claim_interface(interface)?),
+            Device::Mock(d) => InterfaceBackend::Mock(d.detach_and_claim_interface(interface)?),
+        };
+        Ok(self.interface(backend))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn detach_and_claim_interface_except(
+        self: &Arc<Self>,
+        interface: u8,
+        except_driver: &str,
+    ) -> Result<Arc<Interface>, Error> {
+        let backend = match &**self {
+            Device::Os(d) => {
+                InterfaceBackend::Os(d.detach_and_claim_interface_except(interface, except_driver)?)
+            }
+            Device::Mock(d) => InterfaceBackend::Mock(
+                d.detach_and_claim_interface_except(interface, except_driver)?,
+            ),
+        };
+        Ok(self.interface(backend))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn detach_kernel_driver(&self, interface_number: u8) -> Result<(), Error> {
+        dispatch!(self, d => d.detach_kernel_driver(interface_number))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn detach_kernel_driver_if_attached(
+        &self,
+        interface_number: u8,
+    ) -> Result<bool, Error> {
+        dispatch!(self, d => d.detach_kernel_driver_if_attached(interface_number))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn attach_kernel_driver(&self, interface_number: u8) -> Result<(), Error> {
+        dispatch!(self, d => d.attach_kernel_driver(interface_number))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn kernel_driver(&self, interface_number: u8) -> Result<Option<String>, Error> {
+        dispatch!(self, d => d.kernel_driver(interface_number))
+    }
+}
+
+enum InterfaceBackend {
+    Os(Arc<os::Interface>),
+    Mock(Arc<mock::Interface>),
+}
+
+pub(crate) struct Interface {
+    pub(crate) interface_number: u8,
+    pub(crate) device: Arc<Device>,
+    backend: InterfaceBackend,
+}
+
+impl Interface {
+    pub(crate) fn make_transfer(
+        &self,
+        endpoint: u8,
+        ep_type: EndpointType,
+    ) -> TransferHandle<TransferData> {
+        match &self.backend {
+            InterfaceBackend::Os(i) => i.make_transfer(endpoint, ep_type).map(TransferData::Os),
+            InterfaceBackend::Mock(i) => i.make_transfer(endpoint, ep_type).map(TransferData::Mock),
+        }
+    }
+
+    pub(crate) fn make_zlp_transfer(&self, endpoint: u8) -> TransferHandle<TransferData> {
+        match &self.backend {
+            InterfaceBackend::Os(i) => i.make_zlp_transfer(endpoint).map(TransferData::Os),
+            InterfaceBackend::Mock(i) => i.make_zlp_transfer(endpoint).map(TransferData::Mock),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn make_stream_transfer(
+        &self,
+        endpoint: u8,
+        stream_id: u32,
+    ) -> TransferHandle<TransferData> {
+        match &self.backend {
+            InterfaceBackend::Os(i) => i
+                .make_stream_transfer(endpoint, stream_id)
+                .map(TransferData::Os),
+            InterfaceBackend::Mock(i) => i
+                .make_stream_transfer(endpoint, stream_id)
+                .map(TransferData::Mock),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn set_bulk_split_size(&self, size: usize) -> Result<(), Error> {
+        dispatch!(InterfaceBackend, &self.backend, i => i.set_bulk_split_size(size))
+    }
+
+    pub fn control_in_blocking(
+        &self,
+        control: Control,
+        data: &mut [u8],
+        timeout: Duration,
+    ) -> Result<usize, TransferError> {
+        dispatch!(InterfaceBackend, &self.backend, i => i.control_in_blocking(control, data, timeout))
+    }
+
+    pub fn control_out_blocking(
+        &self,
+        control: Control,
+        data: &[u8],
+        timeout: Duration,
+    ) -> Result<usize, TransferError> {
+        dispatch!(InterfaceBackend, &self.backend, i => i.control_out_blocking(control, data, timeout))
+    }
+
+    pub fn set_alt_setting(&self, alt_setting: u8) -> Result<(), Error> {
+        dispatch!(InterfaceBackend, &self.backend, i => i.set_alt_setting(alt_setting))
+    }
+
+    pub fn get_alt_setting(&self) -> u8 {
+        dispatch!(InterfaceBackend, &self.backend, i => i.get_alt_setting())
+    }
+
+    pub fn clear_halt(&self, endpoint: u8) -> Result<(), Error> {
+        dispatch!(InterfaceBackend, &self.backend, i => i.clear_halt(endpoint))
+    }
+
+    pub fn cancel_all(&self, endpoint: u8) -> Result<(), Error> {
+        dispatch!(InterfaceBackend, &self.backend, i => i.cancel_all(endpoint))
+    }
+
+    pub fn alloc_streams(&self, endpoints: &[u8], num_streams: u32) -> Result<u32, Error> {
+        dispatch!(InterfaceBackend, &self.backend, i => i.alloc_streams(endpoints, num_streams))
+    }
+
+    pub fn free_streams(&self, endpoints: &[u8]) -> Result<(), Error> {
+        dispatch!(InterfaceBackend, &self.backend, i => i.free_streams(endpoints))
+    }
+
+    /// Transfers hold the backend's interface rather than this one, so it
+    /// can still be in use after the last `Arc<Interface>` is unwrapped.
+    pub fn release(self) -> Result<(), Error> {
+        let in_use = || Error::other("interface is still in use by transfers");
+        match self.backend {
+            InterfaceBackend::Os(i) => Arc::try_unwrap(i).map_err(|_| in_use())?.release(),
+            InterfaceBackend::Mock(i) => Arc::try_unwrap(i).map_err(|_| in_use())?.release(),
+        }
+    }
+}
+
+pub enum TransferData {
+    Os(os::TransferData),
+    Mock(mock::TransferData),
+}
+
+impl PlatformTransfer for TransferData {
+    fn cancel(&self) {
+        dispatch!(self, t => t.cancel())
+    }
+
+    fn set_priority(&mut self, priority: Priority) {
+        dispatch!(self, t => t.set_priority(priority))
+    }
+}
+
+impl<D> PlatformSubmit<D> for TransferData
+where
+    D: TransferRequest,
+    os::TransferData: PlatformSubmit<D>,
+    mock::TransferData: PlatformSubmit<D>,
+{
+    unsafe fn submit(&mut self, data: D, transfer: *mut c_void) {
+        dispatch!(self, t => t.submit(data, transfer))
+    }
+
+    unsafe fn take_completed(&mut self) -> Completion<D::Response> {
+        dispatch!(self, t => t.take_completed())
+    }
+}
+
+pub(crate) enum HotplugWatch {
+    Os(os::HotplugWatch),
+    Mock(mock::HotplugWatch),
+}
+
+impl HotplugWatch {
+    pub(crate) fn new() -> Result<Self, Error> {
+        Ok(if mock::in_use() {
+            HotplugWatch::Mock(mock::HotplugWatch::new()?)
+        } else {
+            HotplugWatch::Os(os::HotplugWatch::new()?)
+        })
+    }
+
+    pub fn poll_next(&mut self, cx: &mut Context, filters: &[HotplugFilter]) -> Poll<HotplugEvent> {
+        dispatch!(self, w => w.poll_next(cx, filters))
+    }
+}
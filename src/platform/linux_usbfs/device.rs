@@ -1,12 +1,12 @@
-use std::{ffi::c_void, time::Duration};
+use std::{ffi::c_void, io::ErrorKind, time::Duration};
 use std::{
     fs::File,
     io::Read,
     mem::ManuallyDrop,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicU8, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
     },
 };
 
@@ -14,13 +14,14 @@ use log::{debug, error, warn};
 use rustix::event::epoll;
 use rustix::fd::AsFd;
 use rustix::{
-    fd::{AsRawFd, FromRawFd, OwnedFd},
+    fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
     fs::{Mode, OFlags},
     io::Errno,
 };
 
 use super::{
     errno_to_transfer_error, events,
+    transfer::urb_completed,
     usbfs::{self, Urb},
     SysfsPath,
 };
@@ -28,13 +29,15 @@ use crate::platform::linux_usbfs::events::Watch;
 use crate::{
     descriptors::{parse_concatenated_config_descriptors, DESCRIPTOR_LEN_DEVICE},
     transfer::{
-        notify_completion, Control, Direction, EndpointType, TransferError, TransferHandle,
+        Control, ControlType, Direction, EndpointType, Priority, Recipient, TransferError,
+        TransferHandle,
     },
     DeviceInfo, Error,
 };
 
 pub(crate) struct LinuxDevice {
     fd: OwnedFd,
+    log_id: String,
     events_id: usize,
 
     /// Read from the fd, consists of device descriptor followed by configuration descriptors
@@ -42,17 +45,136 @@ pub(crate) struct LinuxDevice {
 
     sysfs: Option<SysfsPath>,
     active_config: AtomicU8,
+
+    /// Set when `REAPURBNDELAY` reports that the device is gone.
+    disconnected: AtomicBool,
+
+    /// `USBDEVFS_CAP_*` bits from `USBDEVFS_GET_CAPABILITIES`.
+    capabilities: u32,
+
+    /// Address of each URB owned by the kernel, for cancelling all
+    /// transfers on an endpoint. Indexed by `endpoint_index` so that
+    /// transfers on different endpoints don't contend for a lock.
+    pending_urbs: [Mutex<Vec<usize>>; 32],
+
+    /// Holds back the URBs of `Priority::Normal` transfers while a
+    /// `Priority::High` transfer is being submitted.
+    submit_gate: SubmitGate,
+
+    /// Opened with `O_RDONLY`, so usbfs rejects every ioctl.
+    readonly: bool,
+}
+
+/// Orders URB submission by transfer priority.
+///
+/// usbfs has no priority flag, so the only control is the order of the
+/// `SUBMITURB` calls. A `Priority::High` transfer holds the gate open with a
+/// `HighPriority` guard while it submits its URBs, and each URB of a
+/// `Priority::Normal` transfer waits in `wait_normal` until no `High`
+/// transfer is being submitted.
+#[derive(Default)]
+struct SubmitGate {
+    /// Number of `High` transfers being submitted.
+    high: Mutex<usize>,
+    idle: Condvar,
+}
+
+struct HighPriority<'a>(&'a SubmitGate);
+
+impl SubmitGate {
+    fn high(&self) -> HighPriority<'_> {
+        *self.high.lock().unwrap() += 1;
+        HighPriority(self)
+    }
+
+    fn wait_normal(&self) {
+        let high = self.high.lock().unwrap();
+        drop(self.idle.wait_while(high, |n| *n > 0).unwrap());
+    }
+}
+
+impl Drop for HighPriority<'_> {
+    fn drop(&mut self) {
+        let mut high = self.0.high.lock().unwrap();
+        *high -= 1;
+        if *high == 0 {
+            self.0.idle.notify_all();
+        }
+    }
+}
+
+#[test]
+fn test_submit_gate() {
+    use std::sync::mpsc;
+
+    let gate = Arc::new(SubmitGate::default());
+    gate.wait_normal();
+
+    let high = gate.high();
+    let (tx, rx) = mpsc::channel();
+    let t = std::thread::spawn({
+        let gate = gate.clone();
+        move || {
+            gate.wait_normal();
+            tx.send(()).unwrap();
+        }
+    });
+
+    // A normal submission waits while a high priority one is in progress.
+    assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+    drop(high);
+    rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    t.join().unwrap();
+}
+
+/// Index of an endpoint address in `LinuxDevice::pending_urbs`, with IN
+/// endpoints after the 16 OUT endpoints.
+fn endpoint_index(endpoint: u8) -> usize {
+    usize::from(endpoint & 0x0f) | usize::from((endpoint & 0x80) >> 3)
+}
+
+#[test]
+fn test_endpoint_index() {
+    assert_eq!(endpoint_index(0x00), 0);
+    assert_eq!(endpoint_index(0x02), 2);
+    assert_eq!(endpoint_index(0x80), 16);
+    assert_eq!(endpoint_index(0x8f), 31);
+}
+
+pub(crate) fn devnode_path(busnum: u8, devnum: u8) -> PathBuf {
+    PathBuf::from(format!("/dev/bus/usb/{busnum:03}/{devnum:03}"))
 }
 
 impl LinuxDevice {
     pub(crate) fn from_device_info(d: &DeviceInfo) -> Result<Arc<LinuxDevice>, Error> {
-        let busnum = d.busnum();
+        Self::open(d, false)
+    }
+
+    pub(crate) fn from_device_info_readonly(d: &DeviceInfo) -> Result<Arc<LinuxDevice>, Error> {
+        Self::open(d, true)
+    }
+
+    fn open(d: &DeviceInfo, readonly: bool) -> Result<Arc<LinuxDevice>, Error> {
+        let busnum = d.busnum;
         let devnum = d.device_address();
-        let active_config = d.path.read_attr("bConfigurationValue")?;
+        let active_config = d.path.read_configuration_value()?;
 
-        let path = PathBuf::from(format!("/dev/bus/usb/{busnum:03}/{devnum:03}"));
-        let fd = rustix::fs::open(&path, OFlags::RDWR | OFlags::CLOEXEC, Mode::empty())
-            .inspect_err(|e| warn!("Failed to open device {path:?}: {e}"))?;
+        let path = devnode_path(busnum, devnum);
+        let access = if readonly {
+            OFlags::RDONLY
+        } else {
+            OFlags::RDWR
+        };
+        let fd = rustix::fs::open(&path, access | OFlags::CLOEXEC, Mode::empty())
+            .inspect_err(|e| warn!("Failed to open device {path:?}: {e}"))
+            .map_err(|e| open_error(&path, e))?;
+
+        // USBDEVFS_GET_CAPABILITIES requires Linux 3.6. Older kernels also
+        // lack every capability it reports.
+        let capabilities = usbfs::get_capabilities(&fd).unwrap_or_else(|e| {
+            debug!("USBDEVFS_GET_CAPABILITIES failed on {path:?}: {e}");
+            0
+        });
 
         let descriptors = {
             let mut file = unsafe { ManuallyDrop::new(File::from_raw_fd(fd.as_raw_fd())) };
@@ -73,44 +195,48 @@ impl LinuxDevice {
             events_err = res.err();
             LinuxDevice {
                 fd,
+                log_id: d.log_id(),
                 events_id,
                 descriptors,
                 sysfs: Some(d.path.clone()),
                 active_config: AtomicU8::new(active_config),
+                disconnected: AtomicBool::new(false),
+                capabilities,
+                pending_urbs: std::array::from_fn(|_| Mutex::new(Vec::new())),
+                submit_gate: SubmitGate::default(),
+                readonly,
             }
         });
 
         if let Some(err) = events_err {
-            error!("Failed to initialize event loop: {err}");
+            error!("Failed to initialize event loop for {}: {err}", arc.log_id);
             Err(err)
         } else {
             debug!(
-                "Opened device bus={busnum} addr={devnum} with id {}",
-                arc.events_id
+                "Opened device {} with id {}, capabilities {:#x}",
+                arc.log_id, arc.events_id, arc.capabilities
             );
             Ok(arc)
         }
     }
 
     pub(crate) fn handle_events(&self) {
-        debug!("Handling events for device {}", self.events_id);
-        match usbfs::reap_urb_ndelay(&self.fd) {
-            Ok(urb_ptr) => {
-                let user_data = {
-                    let urb = unsafe { &*urb_ptr };
-                    debug!(
-                        "URB {:?} for ep {:x} completed, status={} actual_length={}",
-                        urb_ptr, urb.endpoint, urb.status, urb.actual_length
-                    );
-                    urb.usercontext
-                };
-
-                // SAFETY: pointer came from submit via kernel an we're now done with it
-                unsafe { notify_completion::<super::TransferData>(user_data) }
-            }
-            Err(Errno::AGAIN) => {}
-            Err(Errno::NODEV) => {
-                debug!("Device {} disconnected", self.events_id);
+        debug!("Handling events for device {}", self.log_id);
+
+        // Reap every completed URB before returning to epoll, so a burst of
+        // completions, e.g. from a deep queue of small interrupt transfers,
+        // costs one wakeup rather than one per URB. This takes one
+        // REAPURBNDELAY call per URB plus one that finds no more, so the
+        // calls per completion fall from 2 towards 1 as more URBs complete
+        // between wakeups; see `test_reap_calls_per_completion`.
+        match reap_all(
+            || usbfs::reap_urb_ndelay(&self.fd),
+            |urb_ptr| self.reaped(urb_ptr),
+        ) {
+            Errno::AGAIN => {}
+            Errno::NODEV => {
+                debug!("Device {} disconnected", self.log_id);
+                self.disconnected.store(true, Ordering::SeqCst);
 
                 // epoll returns events continuously on a disconnected device, and REAPURB
                 // only returns ENODEV after all events are received, so unregister to
@@ -118,40 +244,140 @@ impl LinuxDevice {
                 // The drop impl will try to unregister again, but that's ok.
                 events::unregister_fd(self.fd.as_fd());
             }
-            Err(e) => {
-                error!("Unexpected error {e} from REAPURBNDELAY");
-            }
+            e => error!("Unexpected error {e} from REAPURBNDELAY on {}", self.log_id),
         }
     }
 
+    fn reaped(&self, urb_ptr: *mut Urb) {
+        {
+            let urb = unsafe { &*urb_ptr };
+            debug!(
+                "URB {:?} for ep {:x} on {} completed, status={} actual_length={}",
+                urb_ptr, urb.endpoint, self.log_id, urb.status, urb.actual_length
+            );
+        }
+
+        // SAFETY: not completed until below
+        unsafe { self.remove_pending(urb_ptr) };
+
+        // SAFETY: pointer came from submit via kernel an we're now done with it
+        unsafe { urb_completed(urb_ptr) }
+    }
+
+    pub(crate) fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+
+    /// Check whether the kernel has reported that the device is gone.
+    pub(crate) fn is_disconnected(&self) -> bool {
+        self.disconnected.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn is_suspended(&self) -> Result<bool, Error> {
+        match self.sysfs.as_ref() {
+            Some(sysfs) => super::is_suspended(sysfs),
+            None => Err(Error::new(
+                ErrorKind::Unsupported,
+                "device was not opened from sysfs",
+            )),
+        }
+    }
+
+    /// Return an error if the device was opened read-only.
+    fn check_writable(&self) -> Result<(), Error> {
+        if self.readonly {
+            Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "device was opened read-only",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn capabilities(&self) -> u32 {
+        self.capabilities
+    }
+
+    /// Largest bulk transfer the kernel accepts, if limited.
+    ///
+    /// Kernels before 3.3 limit each URB to 16 KiB, and without bulk
+    /// continuation a transfer can't be split into several URBs.
+    pub(crate) fn max_bulk_transfer_size(&self) -> Option<usize> {
+        let unlimited =
+            usbfs::USBDEVFS_CAP_BULK_CONTINUATION | usbfs::USBDEVFS_CAP_NO_PACKET_SIZE_LIM;
+        (self.capabilities & unlimited == 0).then_some(OLD_KERNEL_MAX_URB_SIZE)
+    }
+
+    pub(crate) fn descriptors(&self) -> &[u8] {
+        &self.descriptors
+    }
+
     pub(crate) fn configuration_descriptors(&self) -> impl Iterator<Item = &[u8]> {
         parse_concatenated_config_descriptors(&self.descriptors[DESCRIPTOR_LEN_DEVICE as usize..])
     }
 
     pub(crate) fn active_configuration_value(&self) -> u8 {
         if let Some(sysfs) = self.sysfs.as_ref() {
-            match sysfs.read_attr("bConfigurationValue") {
+            match sysfs.read_configuration_value() {
                 Ok(v) => {
                     self.active_config.store(v, Ordering::SeqCst);
                     return v;
                 }
                 Err(e) => {
-                    error!("Failed to read sysfs bConfigurationValue: {e}, using cached value");
+                    error!(
+                        "Failed to read sysfs bConfigurationValue for {}: {e}, using cached value",
+                        self.log_id
+                    );
                 }
             }
         }
         self.active_config.load(Ordering::SeqCst)
     }
 
+    pub(crate) fn get_configuration(&self) -> Result<u8, Error> {
+        let value = match self.sysfs.as_ref() {
+            Some(sysfs) => sysfs.read_configuration_value()?,
+            None => {
+                const STANDARD_REQUEST_GET_CONFIGURATION: u8 = 0x08;
+                let mut buf = [0];
+                self.control_in_blocking(
+                    Control {
+                        control_type: ControlType::Standard,
+                        recipient: Recipient::Device,
+                        request: STANDARD_REQUEST_GET_CONFIGURATION,
+                        value: 0,
+                        index: 0,
+                    },
+                    &mut buf,
+                    Duration::from_secs(1),
+                )?;
+                buf[0]
+            }
+        };
+        self.active_config.store(value, Ordering::SeqCst);
+        Ok(value)
+    }
+
     pub(crate) fn set_configuration(&self, configuration: u8) -> Result<(), Error> {
+        self.check_writable()?;
         usbfs::set_configuration(&self.fd, configuration)?;
         self.active_config.store(configuration, Ordering::SeqCst);
         Ok(())
     }
 
     pub(crate) fn reset(&self) -> Result<(), Error> {
-        usbfs::reset(&self.fd)?;
-        Ok(())
+        self.check_writable()?;
+        match usbfs::reset(&self.fd) {
+            Ok(()) => Ok(()),
+            // The kernel logically disconnects the device if its descriptors
+            // changed, so the file descriptor is no longer usable.
+            Err(Errno::NODEV) => Err(Error::new(
+                ErrorKind::NotConnected,
+                "device re-enumerated after reset",
+            )),
+            Err(e) => Err(e.into()),
+        }
     }
 
     /// SAFETY: `data` must be valid for `len` bytes to read or write, depending on `Direction`
@@ -163,6 +389,14 @@ impl LinuxDevice {
         len: usize,
         timeout: Duration,
     ) -> Result<usize, TransferError> {
+        if self.readonly {
+            warn!(
+                "Control transfer on {} failed: device was opened read-only",
+                self.log_id
+            );
+            return Err(TransferError::Unknown);
+        }
+
         let r = usbfs::control(
             &self.fd,
             usbfs::CtrlTransfer {
@@ -229,20 +463,24 @@ impl LinuxDevice {
         self: &Arc<Self>,
         interface_number: u8,
     ) -> Result<Arc<LinuxInterface>, Error> {
+        self.check_writable()?;
         usbfs::claim_interface(&self.fd, interface_number).inspect_err(|e| {
             warn!(
-                "Failed to claim interface {interface_number} on device id {dev}: {e}",
-                dev = self.events_id
+                "Failed to claim interface {interface_number} on device {}: {e}",
+                self.log_id
             )
         })?;
         debug!(
-            "Claimed interface {interface_number} on device id {dev}",
-            dev = self.events_id
+            "Claimed interface {interface_number} on device {}",
+            self.log_id
         );
         Ok(Arc::new(LinuxInterface {
             device: self.clone(),
             interface_number,
             reattach: false,
+            bulk_split_size: AtomicUsize::new(0),
+            alt_setting: AtomicU8::new(0),
+            released: false,
         }))
     }
 
@@ -250,15 +488,60 @@ impl LinuxDevice {
         self: &Arc<Self>,
         interface_number: u8,
     ) -> Result<Arc<LinuxInterface>, Error> {
-        usbfs::detach_and_claim_interface(&self.fd, interface_number)?;
+        // Don't detach another program's claim of the interface.
+        self.detach_and_claim_interface_except(interface_number, "usbfs")
+    }
+
+    pub(crate) fn detach_and_claim_interface_except(
+        self: &Arc<Self>,
+        interface_number: u8,
+        except_driver: &str,
+    ) -> Result<Arc<LinuxInterface>, Error> {
+        self.check_writable()?;
+        if except_driver.len() > 255 || except_driver.contains('\0') {
+            return Err(Error::new(ErrorKind::InvalidInput, "invalid driver name"));
+        }
+        match usbfs::detach_and_claim_interface(&self.fd, interface_number, except_driver) {
+            Ok(()) => {}
+            Err(Errno::NOTTY) => {
+                // USBDEVFS_DISCONNECT_CLAIM requires Linux 3.8. Fall back to
+                // detaching separately, which isn't atomic with the claim.
+                debug!(
+                    "USBDEVFS_DISCONNECT_CLAIM not supported, detaching before claim on {}",
+                    self.log_id
+                );
+                // If the driver can't be checked, detach whatever is attached,
+                // as happened before the exception could be specified.
+                match self.kernel_driver(interface_number) {
+                    Ok(driver) if driver.as_deref() == Some(except_driver) => {
+                        return Err(Errno::BUSY.into());
+                    }
+                    Ok(_) => {}
+                    Err(e) => debug!(
+                        "Failed to check driver of interface {interface_number} on {}: {e}",
+                        self.log_id
+                    ),
+                }
+                match usbfs::detach_kernel_driver(&self.fd, interface_number) {
+                    // ENODATA means no driver was attached
+                    Ok(()) | Err(Errno::NODATA) => {}
+                    Err(e) => return Err(e.into()),
+                }
+                usbfs::claim_interface(&self.fd, interface_number)?;
+            }
+            Err(e) => return Err(e.into()),
+        }
         debug!(
-            "Detached and claimed interface {interface_number} on device id {dev}",
-            dev = self.events_id
+            "Detached and claimed interface {interface_number} on device {}",
+            self.log_id
         );
         Ok(Arc::new(LinuxInterface {
             device: self.clone(),
             interface_number,
             reattach: true,
+            bulk_split_size: AtomicUsize::new(0),
+            alt_setting: AtomicU8::new(0),
+            released: false,
         }))
     }
 
@@ -266,40 +549,134 @@ impl LinuxDevice {
         self: &Arc<Self>,
         interface_number: u8,
     ) -> Result<(), Error> {
+        self.check_writable()?;
         usbfs::detach_kernel_driver(&self.fd, interface_number).map_err(|e| e.into())
     }
 
+    /// Detach the kernel driver of an interface, returning `false` if no
+    /// driver was attached.
+    pub(crate) fn detach_kernel_driver_if_attached(
+        self: &Arc<Self>,
+        interface_number: u8,
+    ) -> Result<bool, Error> {
+        self.check_writable()?;
+        match usbfs::detach_kernel_driver(&self.fd, interface_number) {
+            Ok(()) => Ok(true),
+            Err(Errno::NODATA) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     pub(crate) fn attach_kernel_driver(
         self: &Arc<Self>,
         interface_number: u8,
     ) -> Result<(), Error> {
-        usbfs::attach_kernel_driver(&self.fd, interface_number).map_err(|e| e.into())
+        self.check_writable()?;
+        match usbfs::attach_kernel_driver(&self.fd, interface_number) {
+            Ok(()) => Ok(()),
+            // EBUSY means a driver is already bound, which is the desired
+            // state unless that driver is usbfs because the interface is
+            // claimed.
+            Err(Errno::BUSY)
+                if self
+                    .kernel_driver(interface_number)
+                    .ok()
+                    .flatten()
+                    .is_some_and(|d| d != "usbfs") =>
+            {
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub(crate) fn kernel_driver(&self, interface_number: u8) -> Result<Option<String>, Error> {
+        match self.sysfs.as_ref() {
+            Some(sysfs) => sysfs.interface_driver(interface_number),
+            None => Err(Error::new(
+                ErrorKind::Unsupported,
+                "device was not opened from sysfs",
+            )),
+        }
+    }
+
+    /// Submit the URBs of a transfer one at a time in order with
+    /// `submit_urb`, ahead of or behind other transfers according to
+    /// `priority`.
+    ///
+    /// Stops at the first error and returns the index of the URB that failed
+    /// to submit along with the error.
+    ///
+    /// SAFETY: as for `submit_urb`, for each URB it is called with.
+    pub(crate) unsafe fn submit_urbs(
+        &self,
+        priority: Priority,
+        urbs: impl Iterator<Item = *mut Urb>,
+    ) -> Result<(), (usize, Errno)> {
+        let high = (priority == Priority::High).then(|| self.submit_gate.high());
+        for (i, urb) in urbs.enumerate() {
+            if high.is_none() {
+                self.submit_gate.wait_normal();
+            }
+            unsafe { self.submit_urb(urb) }.map_err(|e| (i, e))?;
+        }
+        Ok(())
     }
 
-    pub(crate) unsafe fn submit_urb(&self, urb: *mut Urb) {
+    /// SAFETY: `urb` must be fully prepared and not already pending. If this
+    /// returns an error, the URB was not submitted and the caller is
+    /// responsible for completing it.
+    pub(crate) unsafe fn submit_urb(&self, urb: *mut Urb) -> Result<(), Errno> {
         let ep = unsafe { (*urb).endpoint };
-        if let Err(e) = usbfs::submit_urb(&self.fd, urb) {
-            // SAFETY: Transfer was not submitted. We still own the transfer
-            // and can write to the URB and complete it in place of the handler.
-            unsafe {
-                let user_data = {
-                    let u = &mut *urb;
-                    debug!("Failed to submit URB {urb:?} on ep {ep:x}: {e} {u:?}");
-                    u.actual_length = 0;
-                    u.status = e.raw_os_error();
-                    u.usercontext
-                };
-                notify_completion::<super::TransferData>(user_data)
+
+        // Added before submitting because the event thread may reap it
+        // before this returns.
+        self.pending_urbs[endpoint_index(ep)]
+            .lock()
+            .unwrap()
+            .push(urb as usize);
+
+        let res = unsafe { usbfs::submit_urb(&self.fd, urb) };
+        match res {
+            Ok(()) => debug!("Submitted URB {urb:?} on ep {ep:x} of {}", self.log_id),
+            Err(e) => {
+                debug!(
+                    "Failed to submit URB {urb:?} on ep {ep:x} of {}: {e}",
+                    self.log_id
+                );
+                // SAFETY: not submitted, so the caller still owns it
+                unsafe { self.remove_pending(urb) };
             }
-        } else {
-            debug!("Submitted URB {urb:?} on ep {ep:x}");
+        }
+        res
+    }
+
+    /// SAFETY: `urb` must be valid, i.e. not yet completed.
+    unsafe fn remove_pending(&self, urb: *mut Urb) {
+        let ep = unsafe { (*urb).endpoint };
+        let mut pending = self.pending_urbs[endpoint_index(ep)].lock().unwrap();
+        // URBs on an endpoint usually complete in the order they were
+        // submitted, so the completed one is near the front.
+        if let Some(i) = pending.iter().position(|&u| u == urb as usize) {
+            pending.remove(i);
+        }
+    }
+
+    /// Cancel every URB pending on an endpoint.
+    pub(crate) fn cancel_endpoint(&self, endpoint: u8) {
+        // Holding the lock keeps the event thread from completing the URBs,
+        // so they remain valid. The kernel ignores any that it has already
+        // returned.
+        let pending = self.pending_urbs[endpoint_index(endpoint)].lock().unwrap();
+        for &urb in pending.iter().rev() {
+            unsafe { self.cancel_urb(urb as *mut Urb) };
         }
     }
 
     pub(crate) unsafe fn cancel_urb(&self, urb: *mut Urb) {
         unsafe {
             if let Err(e) = usbfs::discard_urb(&self.fd, urb) {
-                debug!("Failed to cancel URB {urb:?}: {e}");
+                debug!("Failed to cancel URB {urb:?} on {}: {e}", self.log_id);
             }
         }
     }
@@ -307,18 +684,43 @@ impl LinuxDevice {
 
 impl Drop for LinuxDevice {
     fn drop(&mut self) {
-        debug!("Closing device {}", self.events_id);
+        debug!("Closing device {}", self.log_id);
         events::unregister(self.fd.as_fd(), self.events_id)
     }
 }
 
+/// URB size limit of kernels without bulk continuation or unlimited URBs.
+const OLD_KERNEL_MAX_URB_SIZE: usize = 16 * 1024;
+
 pub(crate) struct LinuxInterface {
     pub(crate) interface_number: u8,
     pub(crate) device: Arc<LinuxDevice>,
     pub(crate) reattach: bool,
+
+    /// Bulk transfers larger than this are split into multiple URBs, or 0 to disable.
+    pub(crate) bulk_split_size: AtomicUsize,
+
+    /// Alternate setting most recently selected with `set_alt_setting`.
+    alt_setting: AtomicU8,
+
+    /// Set by `release` so the interface isn't released again on drop.
+    released: bool,
 }
 
 impl LinuxInterface {
+    pub(crate) fn set_bulk_split_size(&self, size: usize) -> Result<(), Error> {
+        // The pieces are only completed as one transfer if the kernel
+        // supports `USBDEVFS_URB_BULK_CONTINUATION`.
+        if size != 0 && self.device.capabilities & usbfs::USBDEVFS_CAP_BULK_CONTINUATION == 0 {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "kernel does not support bulk continuation",
+            ));
+        }
+        self.bulk_split_size.store(size, Ordering::Relaxed);
+        Ok(())
+    }
+
     pub(crate) fn make_transfer(
         self: &Arc<Self>,
         endpoint: u8,
@@ -352,36 +754,188 @@ impl LinuxInterface {
 
     pub fn set_alt_setting(&self, alt_setting: u8) -> Result<(), Error> {
         debug!(
-            "Set interface {} alt setting to {alt_setting}",
-            self.interface_number
+            "Set interface {} alt setting to {alt_setting} on {}",
+            self.interface_number, self.device.log_id
         );
-        Ok(usbfs::set_interface(
-            &self.device.fd,
-            self.interface_number,
-            alt_setting,
-        )?)
+        usbfs::set_interface(&self.device.fd, self.interface_number, alt_setting)?;
+        self.alt_setting.store(alt_setting, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn get_alt_setting(&self) -> u8 {
+        self.alt_setting.load(Ordering::Relaxed)
     }
 
     pub fn clear_halt(&self, endpoint: u8) -> Result<(), Error> {
-        debug!("Clear halt, endpoint {endpoint:02x}");
+        debug!(
+            "Clear halt, endpoint {endpoint:02x} on {}",
+            self.device.log_id
+        );
         Ok(usbfs::clear_halt(&self.device.fd, endpoint)?)
     }
+
+    pub fn cancel_all(&self, endpoint: u8) -> Result<(), Error> {
+        debug!(
+            "Cancel all transfers, endpoint {endpoint:02x} on {}",
+            self.device.log_id
+        );
+        self.device.cancel_endpoint(endpoint);
+        Ok(())
+    }
+
+    pub fn alloc_streams(&self, endpoints: &[u8], num_streams: u32) -> Result<u32, Error> {
+        debug!(
+            "Allocate {num_streams} streams on endpoints {endpoints:02x?} of {}",
+            self.device.log_id
+        );
+        let streams = usbfs::Streams::new(endpoints, num_streams)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "too many endpoints"))?;
+        Ok(usbfs::alloc_streams(&self.device.fd, streams)? as u32)
+    }
+
+    pub fn free_streams(&self, endpoints: &[u8]) -> Result<(), Error> {
+        debug!(
+            "Free streams on endpoints {endpoints:02x?} of {}",
+            self.device.log_id
+        );
+        let streams = usbfs::Streams::new(endpoints, 0)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "too many endpoints"))?;
+        Ok(usbfs::free_streams(&self.device.fd, streams)?)
+    }
+
+    pub(crate) fn make_stream_transfer(
+        self: &Arc<Self>,
+        endpoint: u8,
+        stream_id: u32,
+    ) -> TransferHandle<super::TransferData> {
+        let mut data = super::TransferData::new(
+            self.device.clone(),
+            Some(self.clone()),
+            endpoint,
+            EndpointType::Bulk,
+        );
+        data.set_stream_id(stream_id);
+        TransferHandle::new(data)
+    }
+
+    /// Make a bulk OUT transfer that ends with a zero-length packet.
+    pub(crate) fn make_zlp_transfer(
+        self: &Arc<Self>,
+        endpoint: u8,
+    ) -> TransferHandle<super::TransferData> {
+        let mut data = super::TransferData::new(
+            self.device.clone(),
+            Some(self.clone()),
+            endpoint,
+            EndpointType::Bulk,
+        );
+        data.set_zero_packet(true);
+        TransferHandle::new(data)
+    }
 }
 
-impl Drop for LinuxInterface {
-    fn drop(&mut self) {
+impl LinuxInterface {
+    pub fn release(mut self) -> Result<(), Error> {
+        self.released = true;
+        Ok(self.release_interface()?)
+    }
+
+    fn release_interface(&self) -> Result<(), Errno> {
         let res = usbfs::release_interface(&self.device.fd, self.interface_number);
         debug!(
             "Released interface {} on device {}: {res:?}",
-            self.interface_number, self.device.events_id
+            self.interface_number, self.device.log_id
         );
 
         if res.is_ok() && self.reattach {
-            let res = usbfs::attach_kernel_driver(&self.device.fd, self.interface_number);
-            debug!(
-                "Reattached kernel drivers for interface {} on device {}: {res:?}",
-                self.interface_number, self.device.events_id
+            match usbfs::attach_kernel_driver(&self.device.fd, self.interface_number) {
+                Ok(()) => debug!(
+                    "Reattached kernel drivers for interface {} on device {}",
+                    self.interface_number, self.device.log_id
+                ),
+                Err(e) => warn!(
+                    "Failed to reattach kernel drivers for interface {} on device {}: {e}",
+                    self.interface_number, self.device.log_id
+                ),
+            }
+        }
+
+        res
+    }
+}
+
+impl Drop for LinuxInterface {
+    fn drop(&mut self) {
+        if !self.released {
+            let _ = self.release_interface();
+        }
+    }
+}
+
+/// Convert an error opening the usbfs device node, explaining the usual
+/// cause of a permission error.
+fn open_error(path: &Path, e: Errno) -> Error {
+    match e {
+        Errno::ACCESS | Errno::PERM => Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "permission denied opening {}; a udev rule is usually needed to allow access to the device without root",
+                path.display()
+            ),
+        ),
+        e => e.into(),
+    }
+}
+
+/// Pass URBs from `reap` to `reaped` until `reap` fails, and return the
+/// error that ended the loop.
+fn reap_all(
+    mut reap: impl FnMut() -> Result<*mut Urb, Errno>,
+    mut reaped: impl FnMut(*mut Urb),
+) -> Errno {
+    loop {
+        match reap() {
+            Ok(urb_ptr) => reaped(urb_ptr),
+            Err(e) => return e,
+        }
+    }
+}
+
+#[test]
+fn test_reap_calls_per_completion() {
+    /// Count the reap calls for 1600 completions with `depth` URBs pending,
+    /// when all of them complete between wakeups of the event thread.
+    fn calls_per_completion(depth: usize) -> f64 {
+        const COMPLETIONS: usize = 1600;
+        let (mut calls, mut reaped) = (0, 0);
+        while reaped < COMPLETIONS {
+            let mut ready = depth;
+            let e = reap_all(
+                || {
+                    calls += 1;
+                    if ready == 0 {
+                        return Err(Errno::AGAIN);
+                    }
+                    ready -= 1;
+                    Ok(std::ptr::null_mut())
+                },
+                |_| reaped += 1,
             );
+            assert_eq!(e, Errno::AGAIN);
         }
+        calls as f64 / COMPLETIONS as f64
     }
+
+    assert_eq!(calls_per_completion(1), 2.0);
+    assert_eq!(calls_per_completion(4), 1.25);
+    assert_eq!(calls_per_completion(16), 1.0625);
+}
+
+#[test]
+fn test_open_error() {
+    let path = Path::new("/dev/bus/usb/001/002");
+    let e = open_error(path, Errno::ACCESS);
+    assert_eq!(e.kind(), ErrorKind::PermissionDenied);
+    assert!(e.to_string().contains("udev"));
+    assert_eq!(open_error(path, Errno::NOENT).kind(), ErrorKind::NotFound);
 }
@@ -3,16 +3,27 @@ use std::io;
 use std::num::ParseIntError;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
 
 use log::debug;
 use log::warn;
 
-use crate::enumeration::InterfaceInfo;
+use crate::descriptors::{
+    parse_concatenated_config_descriptors, Configuration, DESCRIPTOR_LEN_DEVICE,
+    DESCRIPTOR_TYPE_DEVICE,
+};
+use crate::enumeration::{assign_functions, InterfaceInfo};
 use crate::DeviceInfo;
+use crate::DeviceSummary;
 use crate::Error;
+use crate::LinkPowerManagement;
 use crate::Speed;
 
+use super::DeviceId;
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SysfsPath(pub(crate) PathBuf);
 
 #[derive(Debug)]
@@ -49,36 +60,169 @@ impl From<SysfsError> for io::Error {
     }
 }
 
+impl SysfsError {
+    /// Whether the attribute doesn't exist because the device was removed.
+    fn is_not_found(&self) -> bool {
+        matches!(&self.1, SysfsErrorKind::Io(e) if e.kind() == io::ErrorKind::NotFound)
+    }
+}
+
+/// Default number of retries after a transient error reading a sysfs attribute.
+const DEFAULT_ATTR_RETRIES: u8 = 2;
+
+fn is_transient(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
 impl SysfsPath {
+    /// Read and parse an attribute, retrying up to `retries` times after a
+    /// transient error.
     fn parse_attr<T, E>(
         &self,
         attr: &str,
+        mut retries: u8,
         parse: impl FnOnce(&str) -> Result<T, E>,
     ) -> Result<T, SysfsError> {
         let attr_path = self.0.join(attr);
-        fs::read_to_string(&attr_path)
-            .map_err(SysfsErrorKind::Io)
+
+        let mut delay = Duration::from_millis(1);
+        let res = loop {
+            match fs::read_to_string(&attr_path) {
+                Err(e) if retries > 0 && is_transient(&e) => {
+                    debug!("Retrying read of {attr_path:?} after {e}");
+                    thread::sleep(delay);
+                    delay *= 2;
+                    retries -= 1;
+                }
+                res => break res,
+            }
+        };
+
+        res.map_err(SysfsErrorKind::Io)
             .and_then(|v| parse(v.trim()).map_err(|_| SysfsErrorKind::Parse(v)))
             .map_err(|e| SysfsError(attr_path, e))
     }
 
     pub(crate) fn read_attr<T: FromStr>(&self, attr: &str) -> Result<T, SysfsError> {
-        self.parse_attr(attr, |s| s.parse())
+        self.read_attr_retrying(attr, DEFAULT_ATTR_RETRIES)
+    }
+
+    fn read_attr_retrying<T: FromStr>(&self, attr: &str, retries: u8) -> Result<T, SysfsError> {
+        self.parse_attr(attr, retries, |s| s.parse())
     }
 
-    fn read_attr_hex<T: FromHexStr>(&self, attr: &str) -> Result<T, SysfsError> {
-        self.parse_attr(attr, |s| T::from_hex_str(s))
+    /// Read `bConfigurationValue`, which is empty if the device is unconfigured.
+    pub(crate) fn read_configuration_value(&self) -> Result<u8, SysfsError> {
+        self.parse_attr("bConfigurationValue", DEFAULT_ATTR_RETRIES, |s| {
+            if s.is_empty() {
+                Ok(0)
+            } else {
+                s.parse()
+            }
+        })
+    }
+
+    fn read_attr_hex<T: FromHexStr>(&self, attr: &str, retries: u8) -> Result<T, SysfsError> {
+        self.parse_attr(attr, retries, |s| T::from_hex_str(s))
+    }
+
+    /// Read a BCD version attribute such as `version`, which the kernel
+    /// formats as the two bytes in hex separated by a dot, like `" 2.00"`.
+    fn read_attr_bcd(&self, attr: &str, retries: u8) -> Result<u16, SysfsError> {
+        self.parse_attr(attr, retries, |s| parse_bcd_version(s).ok_or(()))
+    }
+
+    /// Read the `descriptors` attribute, containing the device descriptor
+    /// followed by all configuration descriptors.
+    fn read_descriptors(&self) -> Option<Vec<u8>> {
+        fs::read(self.0.join("descriptors"))
+            .map_err(|e| debug!("Failed to read descriptors for {:?}: {e}", self.0))
+            .ok()
+    }
+
+    /// Get the device descriptor from the start of the `descriptors` attribute.
+    fn device_descriptor(&self, buf: &[u8]) -> Option<[u8; DESCRIPTOR_LEN_DEVICE as usize]> {
+        let desc = buf.get(..DESCRIPTOR_LEN_DEVICE as usize)?;
+        if desc[0] != DESCRIPTOR_LEN_DEVICE || desc[1] != DESCRIPTOR_TYPE_DEVICE {
+            debug!("Invalid device descriptor in sysfs for {:?}", self.0);
+            return None;
+        }
+        desc.try_into().ok()
+    }
+
+    /// Find the active configuration in the `descriptors` attribute.
+    fn active_configuration<'a>(&self, buf: &'a [u8]) -> Option<Configuration<'a>> {
+        let value = self.read_configuration_value().ok()?;
+        parse_concatenated_config_descriptors(buf.get(DESCRIPTOR_LEN_DEVICE as usize..)?)
+            .map(Configuration::new)
+            .find(|c| c.configuration_value() == value)
     }
 
     fn children(&self) -> impl Iterator<Item = SysfsPath> {
         fs::read_dir(&self.0)
             .ok()
             .into_iter()
-            .flat_map(|x| x)
+            .flatten()
             .filter_map(|f| f.ok())
             .filter(|f| f.file_type().ok().is_some_and(|t| t.is_dir()))
             .map(|f| SysfsPath(f.path()))
     }
+
+    /// Read the `interface` string attribute of one of the device's interfaces.
+    pub(crate) fn read_interface_string(&self, interface_number: u8) -> Option<String> {
+        self.interface_paths()
+            .find(|i| {
+                i.read_attr_hex::<u8>("bInterfaceNumber", DEFAULT_ATTR_RETRIES)
+                    .ok()
+                    == Some(interface_number)
+            })?
+            .read_attr("interface")
+            .ok()
+    }
+
+    /// Check whether an interface is bound to the `usbfs` driver, which
+    /// means a program has claimed it.
+    pub(crate) fn interface_in_use(&self, interface_number: u8) -> Option<bool> {
+        let driver = self.interface_driver(interface_number).ok()?;
+        Some(driver.as_deref() == Some("usbfs"))
+    }
+
+    /// Read the name of the driver bound to an interface from its `driver`
+    /// symlink, or `None` if no driver is bound.
+    pub(crate) fn interface_driver(&self, interface_number: u8) -> Result<Option<String>, Error> {
+        let path = self
+            .interface_paths()
+            .find(|i| {
+                i.read_attr_hex::<u8>("bInterfaceNumber", DEFAULT_ATTR_RETRIES)
+                    .ok()
+                    == Some(interface_number)
+            })
+            .ok_or_else(|| Error::new(io::ErrorKind::NotFound, "interface not found"))?;
+
+        match fs::read_link(path.0.join("driver")) {
+            Ok(driver) => Ok(driver.file_name().map(|n| n.to_string_lossy().into_owned())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => {
+                debug!("Failed to read driver of {:?}: {e}", path.0);
+                Err(e)
+            }
+        }
+    }
+
+    fn interface_paths(&self) -> impl Iterator<Item = SysfsPath> {
+        self.children().filter(|i| {
+            // Skip subdirectories like `power` that aren't interfaces
+            // (they would be skipped when missing required properties,
+            // but might as well not open them)
+            i.0.file_name()
+                .unwrap_or_default()
+                .as_encoded_bytes()
+                .contains(&b':')
+        })
+    }
 }
 
 trait FromHexStr: Sized {
@@ -97,40 +241,343 @@ impl FromHexStr for u16 {
     }
 }
 
-const SYSFS_PREFIX: &'static str = "/sys/bus/usb/devices/";
+fn parse_bcd_version(s: &str) -> Option<u16> {
+    let (major, minor) = s.trim().split_once('.')?;
+    let major = u8::from_str_radix(major, 16).ok()?;
+    let minor = u8::from_str_radix(minor, 16).ok()?;
+    Some(u16::from_be_bytes([major, minor]))
+}
 
-pub fn list_devices() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
-    Ok(fs::read_dir(SYSFS_PREFIX)?.flat_map(|entry| {
+#[test]
+fn test_parse_bcd_version() {
+    assert_eq!(parse_bcd_version(" 2.00"), Some(0x0200));
+    assert_eq!(parse_bcd_version(" 3.20"), Some(0x0320));
+    assert_eq!(parse_bcd_version(" 2.10"), Some(0x0210));
+    assert_eq!(parse_bcd_version("2"), None);
+}
+
+const SYSFS_PREFIX: &str = "/sys/bus/usb/devices/";
+
+fn device_paths() -> Result<impl Iterator<Item = SysfsPath>, Error> {
+    device_paths_with(false)
+}
+
+fn device_paths_with(root_hubs: bool) -> Result<impl Iterator<Item = SysfsPath>, Error> {
+    Ok(fs::read_dir(SYSFS_PREFIX)?.flat_map(move |entry| {
         let path = entry.ok()?.path();
         let name = path.file_name()?;
 
         // Device names look like `1-6` or `1-6.4.2`
         // We'll ignore:
-        //  * root hubs (`usb1`) -- they're not useful to talk to and are not exposed on other platforms
+        //  * root hubs (`usb1`) unless requested -- they're not useful to talk to and are not
+        //    exposed on other platforms
         //  * interfaces (`1-6:1.0`)
-        if !name
-            .as_encoded_bytes()
-            .iter()
-            .all(|c| matches!(c, b'0'..=b'9' | b'-' | b'.'))
-        {
+        let name = name.as_encoded_bytes();
+        let is_device = name.iter().all(|c| matches!(c, b'0'..=b'9' | b'-' | b'.'));
+        if !(is_device || root_hubs && is_root_hub_name(name)) {
             return None;
         }
 
-        probe_device(SysfsPath(path))
+        Some(SysfsPath(path))
+    }))
+}
+
+/// Check for a root hub's sysfs name, `usb` followed by the bus number.
+fn is_root_hub_name(name: &[u8]) -> bool {
+    name.strip_prefix(b"usb")
+        .is_some_and(|bus| !bus.is_empty() && bus.iter().all(u8::is_ascii_digit))
+}
+
+#[test]
+fn test_is_root_hub_name() {
+    assert!(is_root_hub_name(b"usb1"));
+    assert!(is_root_hub_name(b"usb12"));
+    assert!(!is_root_hub_name(b"usb"));
+    assert!(!is_root_hub_name(b"1-6"));
+    assert!(!is_root_hub_name(b"usb1x"));
+}
+
+pub fn list_devices() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    Ok(device_paths()?.flat_map(|path| {
+        probe_device(path)
             .inspect_err(|e| warn!("{e}; ignoring device"))
             .ok()
     }))
 }
 
+pub fn list_devices_with_errors(
+    retries: u8,
+    lazy_strings: bool,
+    partial: bool,
+    configuration_descriptors: bool,
+    root_hubs: bool,
+) -> Result<impl Iterator<Item = Result<DeviceInfo, Error>>, Error> {
+    Ok(device_paths_with(root_hubs)?.filter_map(move |path| {
+        match probe_device_with(
+            path,
+            retries,
+            lazy_strings,
+            partial,
+            configuration_descriptors,
+        ) {
+            Ok(d) => Some(Ok(d)),
+            // Removed while listing, so not an error
+            Err(e) if e.is_not_found() => None,
+            Err(e) => Some(Err(e.into())),
+        }
+    }))
+}
+
+pub fn list_device_ids() -> Result<impl Iterator<Item = DeviceSummary>, Error> {
+    Ok(device_paths()?.flat_map(|path| {
+        let summary = || -> Result<DeviceSummary, SysfsError> {
+            let bus = path.read_attr("busnum")?;
+            let addr = path.read_attr("devnum")?;
+            Ok(DeviceSummary {
+                id: crate::DeviceId::new(DeviceId { bus, addr }),
+                device_address: addr,
+                vendor_id: path.read_attr_hex("idVendor", DEFAULT_ATTR_RETRIES)?,
+                product_id: path.read_attr_hex("idProduct", DEFAULT_ATTR_RETRIES)?,
+            })
+        };
+        summary()
+            .inspect_err(|e| debug!("{e}; ignoring device"))
+            .ok()
+    }))
+}
+
+/// Check the runtime power management status of the device.
+pub(crate) fn is_suspended(path: &SysfsPath) -> Result<bool, Error> {
+    let status = path.read_attr::<String>("power/runtime_status")?;
+    Ok(status == "suspended")
+}
+
+/// Read which LPM states are enabled from the `power/` sysfs attributes.
+///
+/// The attributes only exist for the states that the device and its hub
+/// both support.
+pub(crate) fn link_power_management(path: &SysfsPath) -> Option<LinkPowerManagement> {
+    let enabled = |attr: &str| path.read_attr::<String>(attr).ok().map(|v| v == "enabled");
+
+    let lpm = LinkPowerManagement {
+        l1: enabled("power/usb2_hardware_lpm"),
+        u1: enabled("power/usb3_hardware_lpm_u1"),
+        u2: enabled("power/usb3_hardware_lpm_u2"),
+    };
+    Some(lpm).filter(|lpm| *lpm != LinkPowerManagement::default())
+}
+
+/// Character device major number of usbfs device nodes.
+const USB_DEVICE_MAJOR: u32 = 189;
+
+pub(crate) fn probe_device_id(id: DeviceId) -> Option<DeviceInfo> {
+    // The sysfs name of the device can't be derived from the bus and address,
+    // but the kernel links the device node number to the sysfs directory.
+    let minor = (id.bus as u32).checked_sub(1)? * 128 + (id.addr as u32).checked_sub(1)?;
+    let path = fs::canonicalize(format!("/sys/dev/char/{USB_DEVICE_MAJOR}:{minor}"))
+        .inspect_err(|e| debug!("Failed to find sysfs path for {id:?}: {e}"))
+        .ok()?;
+
+    probe_device(SysfsPath(path))
+        .inspect_err(|e| debug!("Failed to probe {id:?}: {e}"))
+        .ok()
+}
+
+pub fn list_devices_with_class(class: u8) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    Ok(device_paths()?
+        .filter(move |path| has_class(path, class))
+        .flat_map(|path| {
+            probe_device(path)
+                .inspect_err(|e| warn!("{e}; ignoring device"))
+                .ok()
+        }))
+}
+
+pub fn list_devices_on_bus(bus_id: &str) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    // Device names start with the bus number, e.g. `3-1.2` on bus 3.
+    let prefix = bus_id.parse::<u8>().ok().map(|busnum| format!("{busnum}-"));
+
+    Ok(device_paths()?
+        .filter(move |path| {
+            let Some(prefix) = &prefix else {
+                return false;
+            };
+            path.0
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(prefix.as_str()))
+        })
+        .flat_map(|path| {
+            probe_device(path)
+                .inspect_err(|e| warn!("{e}; ignoring device"))
+                .ok()
+        }))
+}
+
+/// Check the device and interface class attributes without probing the
+/// whole device.
+fn has_class(path: &SysfsPath, class: u8) -> bool {
+    path.read_attr_hex::<u8>("bDeviceClass", DEFAULT_ATTR_RETRIES)
+        .ok()
+        == Some(class)
+        || path.interface_paths().any(|i| {
+            i.read_attr_hex::<u8>("bInterfaceClass", DEFAULT_ATTR_RETRIES)
+                .ok()
+                == Some(class)
+        })
+}
+
+/// Probe the hub that `device` is connected to.
+pub(crate) fn probe_parent(device: &DeviceInfo) -> Option<DeviceInfo> {
+    let name = device.path.0.file_name()?.to_str()?;
+    let parent_name = parent_name(name)?;
+
+    probe_device(SysfsPath(PathBuf::from(SYSFS_PREFIX).join(parent_name)))
+        .inspect_err(|e| debug!("Failed to probe parent of {name}: {e}"))
+        .ok()
+}
+
+/// Sysfs name of the hub that the device named `name` is connected to.
+///
+/// This is the name with the last port removed, e.g. `1-6.4` for `1-6.4.2`,
+/// or the root hub `usbN` for a device `N-P` on one of its ports. The
+/// reverse of the child names in [`probe_hub_ports`]. Root hubs have no
+/// parent.
+fn parent_name(name: &str) -> Option<String> {
+    if let Some((parent, _)) = name.rsplit_once('.') {
+        return Some(parent.to_owned());
+    }
+    let (bus, _) = name.split_once('-')?;
+    Some(format!("usb{bus}"))
+}
+
+#[test]
+fn test_parent_name() {
+    assert_eq!(parent_name("1-6.4.2").as_deref(), Some("1-6.4"));
+    assert_eq!(parent_name("1-6.4").as_deref(), Some("1-6"));
+    assert_eq!(parent_name("1-6").as_deref(), Some("usb1"));
+    assert_eq!(parent_name("12-3").as_deref(), Some("usb12"));
+    assert_eq!(parent_name("usb1"), None);
+}
+
+/// Probe the device connected to each downstream port of a hub.
+///
+/// Returns the port numbers from 1 to the hub's `maxchild`, and each port's
+/// device if one is connected and enumerated. The children of root hub
+/// `usbN` are `N-P`, and those of hub `N-X` are `N-X.P`.
+pub(crate) fn probe_hub_ports(hub: &DeviceInfo) -> Result<Vec<(u8, Option<DeviceInfo>)>, Error> {
+    let num_ports: u8 = hub.path.read_attr("maxchild")?;
+    let name = hub
+        .path
+        .0
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| Error::other("invalid hub sysfs path"))?;
+
+    Ok((1..=num_ports)
+        .map(|port| {
+            let child = match name.strip_prefix("usb") {
+                Some(bus) => format!("{bus}-{port}"),
+                None => format!("{name}.{port}"),
+            };
+            let path = PathBuf::from(SYSFS_PREFIX).join(child);
+            let device = path
+                .exists()
+                .then(|| {
+                    probe_device(SysfsPath(path))
+                        .inspect_err(|e| debug!("Failed to probe port {port} of {name}: {e}"))
+                        .ok()
+                })
+                .flatten();
+            (port, device)
+        })
+        .collect())
+}
+
+/// Find the host controller of a bus and its driver name.
+///
+/// The root hub `usbN` is a child of the controller's device, e.g.
+/// `/sys/devices/pci0000:00/0000:00:14.0/usb1`, so the controller's sysfs
+/// name is its PCI address.
+fn controller_info(busnum: u8) -> (Option<String>, Option<String>) {
+    let Ok(root_hub) = fs::canonicalize(format!("{SYSFS_PREFIX}usb{busnum}")) else {
+        debug!("Failed to find root hub for bus {busnum}");
+        return (None, None);
+    };
+    let Some(controller) = root_hub.parent() else {
+        return (None, None);
+    };
+
+    let id = controller
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(str::to_owned);
+    let driver = fs::read_link(controller.join("driver"))
+        .ok()
+        .and_then(|d| d.file_name()?.to_str().map(str::to_owned));
+    (id, driver)
+}
+
 pub fn probe_device(path: SysfsPath) -> Result<DeviceInfo, SysfsError> {
+    probe_device_with(path, DEFAULT_ATTR_RETRIES, false, false, false)
+}
+
+/// Return the default value and add `name` to `missing` if reading an
+/// attribute failed and `missing` is `Some`.
+fn or_missing<T: Default>(
+    res: Result<T, SysfsError>,
+    name: &'static str,
+    missing: &mut Option<Vec<&'static str>>,
+) -> Result<T, SysfsError> {
+    match (res, missing) {
+        (Err(e), Some(missing)) => {
+            debug!("{e}; leaving {name} unset");
+            missing.push(name);
+            Ok(T::default())
+        }
+        (res, _) => res,
+    }
+}
+
+/// Probe a device, skipping the string attributes if `lazy_strings` is set.
+///
+/// Each attribute read is retried up to `retries` times after a transient
+/// error.
+///
+/// If `partial` is set, descriptor fields that can't be read are set to zero
+/// and listed in `missing_attributes` rather than failing the probe. If
+/// `configuration_descriptors` is set, the configuration descriptors from
+/// the `descriptors` attribute are kept.
+fn probe_device_with(
+    path: SysfsPath,
+    retries: u8,
+    lazy_strings: bool,
+    partial: bool,
+    configuration_descriptors: bool,
+) -> Result<DeviceInfo, SysfsError> {
     debug!("Probing device {:?}", path.0);
 
-    let busnum = path.read_attr("busnum")?;
-    let device_address = path.read_attr("devnum")?;
+    let root_hub = path
+        .0
+        .file_name()
+        .is_some_and(|name| is_root_hub_name(name.as_encoded_bytes()));
 
+    let read_string = |path: &SysfsPath, attr: &str| -> Option<String> {
+        if lazy_strings {
+            None
+        } else {
+            path.read_attr_retrying(attr, retries).ok()
+        }
+    };
+
+    let busnum = path.read_attr_retrying("busnum", retries)?;
+    let device_address = path.read_attr_retrying("devnum", retries)?;
+
+    // Root hubs have a `devpath` of `0`, and no ports above them.
     let port_chain = path
-        .read_attr::<String>("devpath")
+        .read_attr_retrying::<String>("devpath", retries)
         .ok()
+        .filter(|p| p != "0")
         .and_then(|p| {
             p.split('.')
                 .map(|v| v.parse::<u8>().ok())
@@ -138,51 +585,131 @@ pub fn probe_device(path: SysfsPath) -> Result<DeviceInfo, SysfsError> {
         })
         .unwrap_or_default();
 
+    let num_configurations = path
+        .read_attr_retrying("bNumConfigurations", retries)
+        .unwrap_or_else(|e| {
+            debug!(
+                "Failed to read bNumConfigurations for {:?}, assuming 1: {e}",
+                path.0
+            );
+            1
+        });
+
+    let descriptors = path.read_descriptors();
+    let device_descriptor = descriptors
+        .as_deref()
+        .and_then(|d| path.device_descriptor(d));
+
+    let (controller_id, controller_driver) = controller_info(busnum);
+
+    let mut missing = partial.then(Vec::new);
+    let vendor_id = or_missing(
+        path.read_attr_hex("idVendor", retries),
+        "vendor_id",
+        &mut missing,
+    )?;
+    let product_id = or_missing(
+        path.read_attr_hex("idProduct", retries),
+        "product_id",
+        &mut missing,
+    )?;
+    let device_version = or_missing(
+        path.read_attr_hex("bcdDevice", retries),
+        "device_version",
+        &mut missing,
+    )?;
+    let usb_version = or_missing(
+        path.read_attr_bcd("version", retries),
+        "usb_version",
+        &mut missing,
+    )?;
+    let class = or_missing(
+        path.read_attr_hex("bDeviceClass", retries),
+        "class",
+        &mut missing,
+    )?;
+    let subclass = or_missing(
+        path.read_attr_hex("bDeviceSubClass", retries),
+        "subclass",
+        &mut missing,
+    )?;
+    let protocol = or_missing(
+        path.read_attr_hex("bDeviceProtocol", retries),
+        "protocol",
+        &mut missing,
+    )?;
+    let max_packet_size_0 = or_missing(
+        path.read_attr_retrying("bMaxPacketSize0", retries),
+        "max_packet_size_0",
+        &mut missing,
+    )?;
+    let missing_attributes = missing.unwrap_or_default();
+
+    // The attributes are all missing if the device was removed.
+    if !missing_attributes.is_empty() && !path.0.exists() {
+        return Err(SysfsError(
+            path.0.clone(),
+            SysfsErrorKind::Io(io::ErrorKind::NotFound.into()),
+        ));
+    }
+
     Ok(DeviceInfo {
         busnum,
         bus_id: format!("{busnum:03}"),
+        controller_id,
+        controller_driver,
         device_address,
         port_chain,
-        vendor_id: path.read_attr_hex("idVendor")?,
-        product_id: path.read_attr_hex("idProduct")?,
-        device_version: path.read_attr_hex("bcdDevice")?,
-        class: path.read_attr_hex("bDeviceClass")?,
-        subclass: path.read_attr_hex("bDeviceSubClass")?,
-        protocol: path.read_attr_hex("bDeviceProtocol")?,
-        max_packet_size_0: path.read_attr("bMaxPacketSize0")?,
+        root_hub,
+        vendor_id,
+        product_id,
+        device_version,
+        usb_version,
+        class,
+        subclass,
+        protocol,
+        max_packet_size_0,
+        num_configurations,
+        device_descriptor,
         speed: path
-            .read_attr::<String>("speed")
+            .read_attr_retrying::<String>("speed", retries)
             .ok()
             .as_deref()
             .and_then(Speed::from_str),
-        manufacturer_string: path.read_attr("manufacturer").ok(),
-        product_string: path.read_attr("product").ok(),
-        serial_number: path.read_attr("serial").ok(),
+        manufacturer_string: read_string(&path, "manufacturer"),
+        product_string: read_string(&path, "product"),
+        serial_number: read_string(&path, "serial"),
         interfaces: {
             let mut interfaces: Vec<_> = path
-                .children()
-                .filter(|i| {
-                    // Skip subdirectories like `power` that aren't interfaces
-                    // (they would be skipped when missing required properties,
-                    // but might as well not open them)
-                    i.0.file_name()
-                        .unwrap_or_default()
-                        .as_encoded_bytes()
-                        .contains(&b':')
-                })
+                .interface_paths()
                 .flat_map(|i| {
                     Some(InterfaceInfo {
-                        interface_number: i.read_attr_hex("bInterfaceNumber").ok()?,
-                        class: i.read_attr_hex("bInterfaceClass").ok()?,
-                        subclass: i.read_attr_hex("bInterfaceSubClass").ok()?,
-                        protocol: i.read_attr_hex("bInterfaceProtocol").ok()?,
-                        interface_string: i.read_attr("interface").ok(),
+                        interface_number: i.read_attr_hex("bInterfaceNumber", retries).ok()?,
+                        class: i.read_attr_hex("bInterfaceClass", retries).ok()?,
+                        subclass: i.read_attr_hex("bInterfaceSubClass", retries).ok()?,
+                        protocol: i.read_attr_hex("bInterfaceProtocol", retries).ok()?,
+                        interface_string: read_string(&i, "interface"),
+                        function: None,
                     })
                 })
                 .collect();
             interfaces.sort_unstable_by_key(|i| i.interface_number);
+            if let Some(config) = descriptors
+                .as_deref()
+                .and_then(|d| path.active_configuration(d))
+            {
+                assign_functions(&mut interfaces, &config);
+            }
             interfaces
         },
+        configuration_descriptors: descriptors
+            .as_deref()
+            .filter(|_| configuration_descriptors)
+            .and_then(|d| d.get(DESCRIPTOR_LEN_DEVICE as usize..))
+            .map_or(Vec::new(), |d| d.to_vec()),
+        missing_attributes,
         path,
+        #[cfg(feature = "mock")]
+        mock_id: None,
     })
 }
@@ -12,7 +12,10 @@ use std::{
     task::Poll,
 };
 
-use crate::{hotplug::HotplugEvent, Error};
+use crate::{
+    hotplug::{may_match_any, HotplugEvent, HotplugFilter},
+    Error,
+};
 
 use super::{enumeration::probe_device, events::Async, SysfsPath};
 
@@ -53,9 +56,15 @@ impl LinuxHotplugWatch {
         })
     }
 
-    pub(crate) fn poll_next(&mut self, cx: &mut std::task::Context<'_>) -> Poll<HotplugEvent> {
-        if let Some(event) = try_receive_event(self.fd.inner.as_fd()) {
-            return Poll::Ready(event);
+    pub(crate) fn poll_next(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+        filters: &[HotplugFilter],
+    ) -> Poll<HotplugEvent> {
+        while let Some(event) = try_receive_event(self.fd.inner.as_fd(), filters) {
+            if let Some(event) = event {
+                return Poll::Ready(event);
+            }
         }
 
         if let Err(e) = self.fd.register(cx.waker()) {
@@ -66,7 +75,11 @@ impl LinuxHotplugWatch {
     }
 }
 
-fn try_receive_event(fd: BorrowedFd) -> Option<HotplugEvent> {
+/// Receive a udev message if available.
+///
+/// Returns `None` when no more messages are available, or `Some(None)` if a
+/// message was received but ignored.
+fn try_receive_event(fd: BorrowedFd, filters: &[HotplugFilter]) -> Option<Option<HotplugEvent>> {
     let mut buf = [0; 8192];
 
     let received = unsafe {
@@ -90,10 +103,12 @@ fn try_receive_event(fd: BorrowedFd) -> Option<HotplugEvent> {
     match received {
         // udev messages will normally be sent to a multicast group, which only
         // root can send to. Reject unicast messages that may be from anywhere.
-        Ok((size, groups)) if groups == UDEV_MULTICAST_GROUP => parse_packet(&buf[..size]),
+        Ok((size, groups)) if groups == UDEV_MULTICAST_GROUP => {
+            Some(parse_packet(&buf[..size], filters))
+        }
         Ok((_, src)) => {
             warn!("udev netlink socket received message from {src:?}");
-            None
+            Some(None)
         }
         Err(e) if e.kind() == ErrorKind::WouldBlock => None,
         Err(e) => {
@@ -103,7 +118,7 @@ fn try_receive_event(fd: BorrowedFd) -> Option<HotplugEvent> {
     }
 }
 
-fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
+fn parse_packet(buf: &[u8], filters: &[HotplugFilter]) -> Option<HotplugEvent> {
     if buf.len() < 24 {
         error!("packet too short: {buf:x?}");
         return None;
@@ -125,6 +140,7 @@ fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
     let mut busnum = None;
     let mut devnum = None;
     let mut devpath = None;
+    let mut product = None;
 
     for (k, v) in parse_properties(properties_buf) {
         trace!("uevent property {k} = {v}");
@@ -147,6 +163,9 @@ fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
             "DEVPATH" => {
                 devpath = Some(v);
             }
+            "PRODUCT" => {
+                product = parse_product_property(v);
+            }
             _ => {}
         }
     }
@@ -156,6 +175,17 @@ fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
     let devnum = devnum?;
     let devpath = devpath?;
 
+    // Check the IDs from the uevent before reading anything from sysfs. The
+    // class can only be checked after probing, as an interface may match.
+    if !may_match_any(
+        filters,
+        product.map(|(vid, _)| vid),
+        product.map(|(_, pid)| pid),
+    ) {
+        trace!("uevent for {devpath} does not match filter");
+        return None;
+    }
+
     if is_add {
         let path = Path::new("/sys/").join(devpath.trim_start_matches('/'));
         match probe_device(SysfsPath(path.clone())) {
@@ -166,7 +196,7 @@ fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
             }
         }
     } else {
-        Some(HotplugEvent::Disconnected(crate::DeviceId(
+        Some(HotplugEvent::Disconnected(crate::DeviceId::new(
             super::DeviceId {
                 bus: busnum,
                 addr: devnum,
@@ -175,6 +205,21 @@ fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
     }
 }
 
+/// Parse vendor and product ID from the `PRODUCT` property, formatted as `vid/pid/bcdDevice` in hex.
+fn parse_product_property(v: &str) -> Option<(u16, u16)> {
+    let mut parts = v.split('/');
+    let vid = u16::from_str_radix(parts.next()?, 16).ok()?;
+    let pid = u16::from_str_radix(parts.next()?, 16).ok()?;
+    Some((vid, pid))
+}
+
+#[test]
+fn test_parse_uevent_ids() {
+    assert_eq!(parse_product_property("1d6b/3/606"), Some((0x1d6b, 0x0003)));
+    assert_eq!(parse_product_property("59e3/a23/0"), Some((0x59e3, 0x0a23)));
+    assert_eq!(parse_product_property("zzzz"), None);
+}
+
 /// Split nul-separated key=value pairs
 fn parse_properties(buf: &[u8]) -> impl Iterator<Item = (&str, &str)> + '_ {
     buf.split(|b| b == &0)
@@ -2,12 +2,24 @@ mod transfer;
 use rustix::io::Errno;
 pub(crate) use transfer::TransferData;
 mod usbfs;
+pub(crate) use usbfs::{
+    USBDEVFS_CAP_BULK_CONTINUATION, USBDEVFS_CAP_BULK_SCATTER_GATHER, USBDEVFS_CAP_CONNINFO_EX,
+    USBDEVFS_CAP_DROP_PRIVILEGES, USBDEVFS_CAP_MMAP, USBDEVFS_CAP_NO_PACKET_SIZE_LIM,
+    USBDEVFS_CAP_REAP_AFTER_DISCONNECT, USBDEVFS_CAP_SUSPEND, USBDEVFS_CAP_ZERO_PACKET,
+};
 
 mod enumeration;
 mod events;
-pub use enumeration::{list_devices, SysfsPath};
+pub(crate) use enumeration::{
+    is_suspended, link_power_management, probe_device_id, probe_hub_ports, probe_parent,
+};
+pub use enumeration::{
+    list_device_ids, list_devices, list_devices_on_bus, list_devices_with_class,
+    list_devices_with_errors, SysfsPath,
+};
 
 mod device;
+pub(crate) use device::devnode_path;
 pub(crate) use device::LinuxDevice as Device;
 pub(crate) use device::LinuxInterface as Interface;
 
@@ -22,12 +34,20 @@ pub struct DeviceId {
     pub(crate) addr: u8,
 }
 
+impl DeviceId {
+    pub(crate) fn busnum(&self) -> Option<u8> {
+        Some(self.bus)
+    }
+}
+
 fn errno_to_transfer_error(e: Errno) -> TransferError {
     match e {
         Errno::NODEV | Errno::SHUTDOWN => TransferError::Disconnected,
         Errno::PIPE => TransferError::Stall,
-        Errno::NOENT | Errno::CONNRESET | Errno::TIMEDOUT => TransferError::Cancelled,
-        Errno::PROTO | Errno::ILSEQ | Errno::OVERFLOW | Errno::COMM | Errno::TIME => {
+        Errno::NOENT | Errno::CONNRESET => TransferError::Cancelled,
+        Errno::TIMEDOUT => TransferError::TimedOut,
+        Errno::OVERFLOW => TransferError::Overflow,
+        Errno::PROTO | Errno::ILSEQ | Errno::COMM | Errno::TIME | Errno::XDEV => {
             TransferError::Fault
         }
         _ => TransferError::Unknown,
@@ -1,43 +1,117 @@
 use std::{
+    alloc::{self, Layout},
     ffi::c_void,
     mem::{self, ManuallyDrop},
     ptr::null_mut,
-    sync::Arc,
+    slice,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
 use rustix::io::Errno;
 
 use crate::transfer::{
-    Completion, ControlIn, ControlOut, EndpointType, PlatformSubmit, PlatformTransfer,
-    RequestBuffer, ResponseBuffer, TransferError, SETUP_PACKET_SIZE,
+    drop_transfer_buffer_parts, drop_vec_parts, notify_completion, CallerBuffer, Completion,
+    ControlIn, ControlOut, EndpointType, FilledBuffer, IsoOutBuffer, IsoPacket, IsoRequestBuffer,
+    IsoResponse, PlatformSubmit, PlatformTransfer, Priority, RequestBuffer, ResponseBuffer,
+    TransferBuffer, TransferError, SETUP_PACKET_SIZE,
 };
 
 use super::{
     errno_to_transfer_error,
     usbfs::{
-        Urb, USBDEVFS_URB_TYPE_BULK, USBDEVFS_URB_TYPE_CONTROL, USBDEVFS_URB_TYPE_INTERRUPT,
-        USBDEVFS_URB_TYPE_ISO,
+        IsoPacketDesc, Urb, USBDEVFS_URB_BULK_CONTINUATION, USBDEVFS_URB_ISO_ASAP,
+        USBDEVFS_URB_SHORT_NOT_OK, USBDEVFS_URB_TYPE_BULK, USBDEVFS_URB_TYPE_CONTROL,
+        USBDEVFS_URB_TYPE_INTERRUPT, USBDEVFS_URB_TYPE_ISO, USBDEVFS_URB_ZERO_PACKET,
     },
 };
 
 /// Linux-specific transfer state.
 ///
-/// This logically contains a `Vec` with urb.buffer and capacity.
+/// This logically contains a `Vec` with urb.buffer and capacity, or a
+/// caller-provided buffer freed by `buf_drop`.
 /// It also owns the `urb` allocation itself, which is stored out-of-line
 /// to avoid violating noalias when submitting the transfer while holding
-/// `&mut TransferData`.
+/// `&mut TransferData`. For isochronous transfers, the allocation is
+/// followed by the variable-length array of packet descriptors.
+///
+/// Large bulk transfers are split across `urb` and additional URBs in
+/// `continuation` that point into the same buffer. The transfer completes
+/// when all of them have been reaped, tracked by the out-of-line `group`
+/// that every URB's `usercontext` points to.
 pub struct TransferData {
     urb: *mut Urb,
+    iso_capacity: usize,
     capacity: usize,
+    buf_drop: unsafe fn(*mut u8, usize),
+
+    /// Allocated on demand and re-used. Only the first `num_continuation` are
+    /// part of the current transfer.
+    continuation: Vec<*mut Urb>,
+    num_continuation: usize,
+
+    group: *mut UrbGroup,
     device: Arc<super::Device>,
 
-    /// Not directly used, exists just to keep the interface from being released
-    /// while active.
-    _interface: Option<Arc<super::Interface>>,
+    /// Keeps the interface from being released while active, and provides
+    /// the bulk split size.
+    interface: Option<Arc<super::Interface>>,
+
+    /// Flag the last URB of OUT transfers with `USBDEVFS_URB_ZERO_PACKET`.
+    zero_packet: bool,
+
+    priority: Priority,
+}
+
+/// Completion state shared by the URBs of a transfer.
+struct UrbGroup {
+    remaining: AtomicUsize,
+    user_data: *mut c_void,
+}
+
+/// Called when the kernel returns a URB, or when it failed to submit.
+///
+/// SAFETY: `urb` must belong to a submitted `TransferData`, and must not
+/// be used again by the caller.
+pub(super) unsafe fn urb_completed(urb: *mut Urb) {
+    unsafe {
+        let group = (*urb).usercontext as *const UrbGroup;
+        if (*group).remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // Copy out before notifying, because notification allows the
+            // transfer to be freed.
+            let user_data = (*group).user_data;
+            notify_completion::<crate::platform::TransferData>(user_data)
+        }
+    }
 }
 
 unsafe impl Send for TransferData {}
 
+/// Number of URBs to submit a bulk transfer of `len` bytes as.
+///
+/// Transfers on a USB 3.0 stream are always submitted as a single URB.
+fn num_bulk_urbs(len: usize, split_size: usize, stream_id: u32) -> usize {
+    if split_size == 0 || stream_id != 0 || len <= split_size {
+        1
+    } else {
+        len.div_ceil(split_size)
+    }
+}
+
+#[test]
+fn test_num_bulk_urbs() {
+    assert_eq!(num_bulk_urbs(64 * 1024, 0, 0), 1);
+    assert_eq!(num_bulk_urbs(16 * 1024, 16 * 1024, 0), 1);
+    assert_eq!(num_bulk_urbs(16 * 1024 + 1, 16 * 1024, 0), 2);
+    assert_eq!(num_bulk_urbs(64 * 1024, 16 * 1024, 0), 4);
+    assert_eq!(num_bulk_urbs(0, 16 * 1024, 0), 1);
+
+    // Stream transfers are submitted as a single URB.
+    assert_eq!(num_bulk_urbs(64 * 1024, 16 * 1024, 1), 1);
+}
+
 impl TransferData {
     pub(super) fn new(
         device: Arc<super::Device>,
@@ -53,26 +127,32 @@ impl TransferData {
         };
 
         TransferData {
-            urb: Box::into_raw(Box::new(Urb {
-                ep_type,
-                endpoint,
-                status: 0,
-                flags: 0,
-                buffer: null_mut(),
-                buffer_length: 0,
-                actual_length: 0,
-                start_frame: 0,
-                number_of_packets_or_stream_id: 0,
-                error_count: 0,
-                signr: 0,
-                usercontext: null_mut(),
-            })),
+            urb: alloc_urb(ep_type, endpoint, 0),
+            iso_capacity: 0,
             capacity: 0,
+            buf_drop: drop_vec_parts,
+            continuation: Vec::new(),
+            num_continuation: 0,
+            group: Box::into_raw(Box::new(UrbGroup {
+                remaining: AtomicUsize::new(0),
+                user_data: null_mut(),
+            })),
             device,
-            _interface: interface,
+            interface,
+            zero_packet: false,
+            priority: Priority::Normal,
         }
     }
 
+    pub(super) fn set_zero_packet(&mut self, zero_packet: bool) {
+        self.zero_packet = zero_packet;
+    }
+
+    /// Tag the bulk transfer's URBs with a USB 3.0 stream ID.
+    pub(super) fn set_stream_id(&mut self, stream_id: u32) {
+        self.urb_mut().number_of_packets_or_stream_id = stream_id;
+    }
+
     fn urb_mut(&mut self) -> &mut Urb {
         // SAFETY: if we have `&mut`, the transfer is not pending
         unsafe { &mut *self.urb }
@@ -80,12 +160,133 @@ impl TransferData {
 
     fn fill(&mut self, v: Vec<u8>, len: usize, user_data: *mut c_void) {
         let mut v = ManuallyDrop::new(v);
+        self.fill_raw(v.as_mut_ptr(), len, user_data);
+        self.capacity = v.capacity();
+        self.buf_drop = drop_vec_parts;
+    }
+
+    fn fill_raw(&mut self, buf: *mut u8, len: usize, user_data: *mut c_void) {
+        let group = self.group;
+        // SAFETY: if we have `&mut`, the transfer is not pending
+        unsafe { (*group).user_data = user_data };
+
         let urb = self.urb_mut();
-        urb.buffer = v.as_mut_ptr();
+        urb.buffer = buf;
         urb.buffer_length = len.try_into().expect("buffer size should fit in i32");
-        urb.usercontext = user_data;
+        urb.usercontext = group.cast();
         urb.actual_length = 0;
-        self.capacity = v.capacity();
+        urb.flags = 0;
+        self.num_continuation = 0;
+    }
+
+    /// Split a filled bulk transfer into multiple URBs if it is larger than
+    /// the interface's split size.
+    ///
+    /// The URBs after the first are flagged with `BULK_CONTINUATION`, and
+    /// for IN transfers all but the last are flagged `SHORT_NOT_OK`, so that
+    /// a short packet ends the transfer and makes the kernel cancel the
+    /// remaining URBs rather than reading the next transfer's data into them.
+    fn split_bulk(&mut self) {
+        let split_size = match &self.interface {
+            Some(intf) => intf.bulk_split_size.load(Ordering::Relaxed),
+            None => 0,
+        };
+        let urb = self.urb_mut();
+        if urb.ep_type != USBDEVFS_URB_TYPE_BULK {
+            return;
+        }
+        let len = urb.buffer_length as usize;
+        let num_urbs = num_bulk_urbs(len, split_size, urb.number_of_packets_or_stream_id);
+        if num_urbs == 1 {
+            return;
+        }
+
+        let (ep_type, endpoint, buf) = (urb.ep_type, urb.endpoint, urb.buffer);
+        let is_in = endpoint & 0x80 != 0;
+
+        urb.buffer_length = split_size as i32;
+        if is_in {
+            urb.flags |= USBDEVFS_URB_SHORT_NOT_OK;
+        }
+
+        while self.continuation.len() < num_urbs - 1 {
+            self.continuation.push(alloc_urb(ep_type, endpoint, 0));
+        }
+        self.num_continuation = num_urbs - 1;
+
+        for (i, &urb) in self.continuation[..num_urbs - 1].iter().enumerate() {
+            let offset = (i + 1) * split_size;
+            let last = i == num_urbs - 2;
+
+            // SAFETY: not pending, so we have exclusive access to the URB
+            let urb = unsafe { &mut *urb };
+            // SAFETY: offset is within the buffer
+            urb.buffer = unsafe { buf.add(offset) };
+            urb.buffer_length = split_size.min(len - offset) as i32;
+            urb.actual_length = 0;
+            urb.status = 0;
+            urb.usercontext = self.group.cast();
+            urb.flags = USBDEVFS_URB_BULK_CONTINUATION;
+            urb.number_of_packets_or_stream_id = 0;
+            if is_in && !last {
+                urb.flags |= USBDEVFS_URB_SHORT_NOT_OK;
+            }
+        }
+    }
+
+    /// Get the `i`th URB of the current transfer.
+    fn urb_at(&self, i: usize) -> *mut Urb {
+        if i == 0 {
+            self.urb
+        } else {
+            self.continuation[i - 1]
+        }
+    }
+
+    /// Submit the filled URB(s).
+    ///
+    /// SAFETY: the transfer must be fully prepared and not already pending
+    unsafe fn submit_urbs(&mut self) {
+        let num_urbs = 1 + self.num_continuation;
+
+        // SAFETY: not pending
+        unsafe { (*self.group).remaining.store(num_urbs, Ordering::Release) };
+
+        let urbs = (0..num_urbs).map(|i| self.urb_at(i));
+        if let Err((i, e)) = unsafe { self.device.submit_urbs(self.priority, urbs) } {
+            // Cancel the URBs that were submitted, and complete the rest
+            // in place of the event thread.
+            for j in (0..i).rev() {
+                unsafe { self.device.cancel_urb(self.urb_at(j)) };
+            }
+            for j in i..num_urbs {
+                let urb = self.urb_at(j);
+                unsafe {
+                    (*urb).actual_length = 0;
+                    (*urb).status = e.raw_os_error();
+                    urb_completed(urb);
+                }
+            }
+        }
+    }
+
+    /// Get the total length and status of the URBs of a possibly-split transfer.
+    fn bulk_result(&mut self) -> (usize, Result<(), TransferError>) {
+        let mut len = 0;
+        for i in 0..1 + self.num_continuation {
+            // SAFETY: if we have `&mut`, the transfer is not pending
+            let urb = unsafe { &*self.urb_at(i) };
+            len += urb.actual_length as usize;
+            match urb.status {
+                0 => {}
+                // A short packet in a `SHORT_NOT_OK` URB ends the transfer successfully
+                s if s.abs() == Errno::REMOTEIO.raw_os_error() && self.num_continuation > 0 => {
+                    return (len, Ok(()))
+                }
+                _ => return (len, urb_status(urb)),
+            }
+        }
+        (len, Ok(()))
     }
 
     /// SAFETY: requires that the transfer has completed and `length` bytes are initialized
@@ -97,25 +298,178 @@ impl TransferData {
         assert!(length <= capacity);
         Vec::from_raw_parts(ptr, length, capacity)
     }
+
+    /// Fill an isochronous transfer with a packet descriptor for each of `lengths`.
+    fn fill_iso(
+        &mut self,
+        v: Vec<u8>,
+        lengths: impl ExactSizeIterator<Item = usize>,
+        user_data: *mut c_void,
+    ) {
+        let num_packets = lengths.len();
+
+        if num_packets > self.iso_capacity {
+            let (ep_type, endpoint) = (self.urb_mut().ep_type, self.urb_mut().endpoint);
+            let old = mem::replace(&mut self.urb, alloc_urb(ep_type, endpoint, num_packets));
+            // SAFETY: not pending, and allocated by `alloc_urb` with the old capacity
+            unsafe { free_urb(old, self.iso_capacity) };
+            self.iso_capacity = num_packets;
+        }
+
+        let len = v.len();
+        self.fill(v, len, user_data);
+
+        let urb = self.urb_mut();
+        urb.flags = USBDEVFS_URB_ISO_ASAP;
+        urb.start_frame = 0;
+        urb.error_count = 0;
+        urb.number_of_packets_or_stream_id = num_packets
+            .try_into()
+            .expect("number of packets should fit in u32");
+
+        let mut total = 0;
+        for (desc, length) in self.iso_packets_mut().iter_mut().zip(lengths) {
+            desc.length = length.try_into().expect("packet size should fit in u32");
+            desc.actual_length = 0;
+            desc.status = 0;
+            total += length;
+        }
+        assert!(total <= len, "packets should fit in buffer");
+    }
+
+    fn iso_packets_mut(&mut self) -> &mut [IsoPacketDesc] {
+        let num_packets = self.urb_mut().number_of_packets_or_stream_id as usize;
+        assert!(num_packets <= self.iso_capacity);
+        let (_, offset) = urb_layout(self.iso_capacity);
+
+        // SAFETY: if we have `&mut`, the transfer is not pending, and the
+        // allocation has room for `iso_capacity` descriptors at `offset`.
+        unsafe {
+            let descs = self.urb.cast::<u8>().add(offset).cast::<IsoPacketDesc>();
+            slice::from_raw_parts_mut(descs, num_packets)
+        }
+    }
+
+    /// SAFETY: requires that the transfer has completed
+    unsafe fn take_completed_iso(&mut self) -> Completion<IsoResponse> {
+        let status = iso_urb_status(self.urb_mut());
+        let packets = iso_packets(self.iso_packets_mut());
+
+        // SAFETY: self is completed (precondition), and the whole buffer
+        // was initialized before submitting.
+        let len = self.urb_mut().buffer_length as usize;
+        let buf = unsafe { self.take_buf(len) };
+        Completion {
+            data: IsoResponse { buf, packets },
+            status,
+        }
+    }
+}
+
+/// Packet lengths for an isochronous OUT transfer of `len` bytes, where only
+/// the last packet may be short.
+fn iso_out_packet_lengths(len: usize, packet_size: usize) -> impl ExactSizeIterator<Item = usize> {
+    (0..len.div_ceil(packet_size)).map(move |i| packet_size.min(len - i * packet_size))
+}
+
+/// Per-packet results from completed descriptors. Packets are laid out
+/// back to back in the buffer by requested length.
+fn iso_packets(descs: &[IsoPacketDesc]) -> Vec<IsoPacket> {
+    let mut offset = 0;
+    descs
+        .iter()
+        .map(|desc| {
+            let packet = IsoPacket {
+                offset,
+                requested_length: desc.length as usize,
+                actual_length: desc.actual_length as usize,
+                status: iso_packet_status(desc),
+            };
+            offset += desc.length as usize;
+            packet
+        })
+        .collect()
+}
+
+fn iso_urb_status(urb: &Urb) -> Result<(), TransferError> {
+    // Some host controllers report EXDEV for the whole URB if any packet
+    // failed, but that is already reflected in the per-packet status.
+    if urb.status.abs() == Errno::XDEV.raw_os_error() {
+        Ok(())
+    } else {
+        urb_status(urb)
+    }
+}
+
+fn urb_layout(iso_packets: usize) -> (Layout, usize) {
+    Layout::new::<Urb>()
+        .extend(Layout::array::<IsoPacketDesc>(iso_packets).expect("too many iso packets"))
+        .expect("too many iso packets")
+}
+
+fn alloc_urb(ep_type: u8, endpoint: u8, iso_packets: usize) -> *mut Urb {
+    let (layout, _) = urb_layout(iso_packets);
+
+    // SAFETY: layout is nonzero size because it contains `Urb`
+    unsafe {
+        let urb = alloc::alloc_zeroed(layout).cast::<Urb>();
+        if urb.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+        urb.write(Urb {
+            ep_type,
+            endpoint,
+            status: 0,
+            flags: 0,
+            buffer: null_mut(),
+            buffer_length: 0,
+            actual_length: 0,
+            start_frame: 0,
+            number_of_packets_or_stream_id: 0,
+            error_count: 0,
+            signr: 0,
+            usercontext: null_mut(),
+        });
+        urb
+    }
+}
+
+/// SAFETY: `urb` must have been allocated by `alloc_urb` with `iso_packets`
+/// and not be in use by the kernel.
+unsafe fn free_urb(urb: *mut Urb, iso_packets: usize) {
+    let (layout, _) = urb_layout(iso_packets);
+    unsafe { alloc::dealloc(urb.cast(), layout) }
 }
 
 impl Drop for TransferData {
     fn drop(&mut self) {
         unsafe {
             if !self.urb_mut().buffer.is_null() {
-                drop(Vec::from_raw_parts(self.urb_mut().buffer, 0, self.capacity));
+                (self.buf_drop)(self.urb_mut().buffer, self.capacity);
             }
-            drop(Box::from_raw(self.urb));
+            free_urb(self.urb, self.iso_capacity);
+            for &urb in &self.continuation {
+                free_urb(urb, 0);
+            }
+            drop(Box::from_raw(self.group));
         }
     }
 }
 
 impl PlatformTransfer for TransferData {
     fn cancel(&self) {
+        // Cancel in reverse order so the kernel can't start a later URB
+        // after an earlier one is cancelled.
         unsafe {
-            self.device.cancel_urb(self.urb);
+            for i in (0..1 + self.num_continuation).rev() {
+                self.device.cancel_urb(self.urb_at(i));
+            }
         }
     }
+
+    fn set_priority(&mut self, priority: Priority) {
+        self.priority = priority;
+    }
 }
 
 impl PlatformSubmit<Vec<u8>> for TransferData {
@@ -124,14 +478,19 @@ impl PlatformSubmit<Vec<u8>> for TransferData {
         assert!(ep & 0x80 == 0);
         let len = data.len();
         self.fill(data, len, user_data);
+        self.split_bulk();
+
+        if self.zero_packet {
+            // SAFETY: not pending, so we have exclusive access to the URB
+            unsafe { (*self.urb_at(self.num_continuation)).flags |= USBDEVFS_URB_ZERO_PACKET };
+        }
 
         // SAFETY: we just properly filled the buffer and it is not already pending
-        unsafe { self.device.submit_urb(self.urb) }
+        unsafe { self.submit_urbs() }
     }
 
     unsafe fn take_completed(&mut self) -> Completion<ResponseBuffer> {
-        let status = urb_status(self.urb_mut());
-        let len = self.urb_mut().actual_length as usize;
+        let (len, status) = self.bulk_result();
 
         // SAFETY: self is completed (precondition)
         let data = ResponseBuffer::from_vec(self.take_buf(0), len);
@@ -148,21 +507,52 @@ impl PlatformSubmit<RequestBuffer> for TransferData {
 
         let (data, len) = data.into_vec();
         self.fill(data, len, user_data);
+        self.split_bulk();
 
         // SAFETY: we just properly filled the buffer and it is not already pending
-        unsafe { self.device.submit_urb(self.urb) }
+        unsafe { self.submit_urbs() }
     }
 
     unsafe fn take_completed(&mut self) -> Completion<Vec<u8>> {
-        let status = urb_status(self.urb_mut());
-        let len = self.urb_mut().actual_length as usize;
+        let (len, status) = self.bulk_result();
 
-        // SAFETY: self is completed (precondition) and `actual_length` bytes were initialized.
+        // SAFETY: self is completed (precondition) and the first `len` bytes
+        // were initialized, because a split transfer stops at the first short URB.
         let data = unsafe { self.take_buf(len) };
         Completion { data, status }
     }
 }
 
+impl<B: TransferBuffer> PlatformSubmit<CallerBuffer<B>> for TransferData {
+    unsafe fn submit(&mut self, data: CallerBuffer<B>, user_data: *mut c_void) {
+        let ep = self.urb_mut().endpoint;
+        let ty = self.urb_mut().ep_type;
+        assert!(ep & 0x80 == 0x80);
+        assert!(ty == USBDEVFS_URB_TYPE_BULK || ty == USBDEVFS_URB_TYPE_INTERRUPT);
+
+        let (ptr, len) = data.into_raw_parts();
+        self.fill_raw(ptr, len, user_data);
+        self.capacity = len;
+        self.buf_drop = drop_transfer_buffer_parts::<B>;
+        self.split_bulk();
+
+        // SAFETY: we just properly filled the buffer and it is not already pending
+        unsafe { self.submit_urbs() }
+    }
+
+    unsafe fn take_completed(&mut self) -> Completion<FilledBuffer<B>> {
+        let (actual_len, status) = self.bulk_result();
+
+        let ptr = mem::replace(&mut self.urb_mut().buffer, null_mut());
+        let len = mem::replace(&mut self.capacity, 0);
+        self.buf_drop = drop_vec_parts;
+
+        // SAFETY: self is completed (precondition) and the parts came from `submit`
+        let data = unsafe { FilledBuffer::from_raw_parts(ptr, len, actual_len) };
+        Completion { data, status }
+    }
+}
+
 impl PlatformSubmit<ControlIn> for TransferData {
     unsafe fn submit(&mut self, data: ControlIn, user_data: *mut c_void) {
         let buf_len = SETUP_PACKET_SIZE + data.length as usize;
@@ -171,7 +561,7 @@ impl PlatformSubmit<ControlIn> for TransferData {
         self.fill(buf, buf_len, user_data);
 
         // SAFETY: we just properly filled the buffer and it is not already pending
-        unsafe { self.device.submit_urb(self.urb) }
+        unsafe { self.submit_urbs() }
     }
 
     unsafe fn take_completed(&mut self) -> Completion<Vec<u8>> {
@@ -199,7 +589,7 @@ impl PlatformSubmit<ControlOut<'_>> for TransferData {
         self.fill(buf, buf_len, user_data);
 
         // SAFETY: we just properly filled the buffer and it is not already pending
-        unsafe { self.device.submit_urb(self.urb) }
+        unsafe { self.submit_urbs() }
     }
 
     unsafe fn take_completed(&mut self) -> Completion<ResponseBuffer> {
@@ -210,6 +600,54 @@ impl PlatformSubmit<ControlOut<'_>> for TransferData {
     }
 }
 
+impl PlatformSubmit<IsoRequestBuffer> for TransferData {
+    unsafe fn submit(&mut self, data: IsoRequestBuffer, user_data: *mut c_void) {
+        let ep = self.urb_mut().endpoint;
+        let ty = self.urb_mut().ep_type;
+        assert!(ep & 0x80 == 0x80);
+        assert!(ty == USBDEVFS_URB_TYPE_ISO);
+
+        let lengths = std::iter::repeat(data.packet_size).take(data.num_packets);
+        self.fill_iso(data.buf, lengths, user_data);
+
+        // SAFETY: we just properly filled the buffer and it is not already pending
+        unsafe { self.submit_urbs() }
+    }
+
+    unsafe fn take_completed(&mut self) -> Completion<IsoResponse> {
+        // SAFETY: self is completed (precondition)
+        unsafe { self.take_completed_iso() }
+    }
+}
+
+impl PlatformSubmit<IsoOutBuffer> for TransferData {
+    unsafe fn submit(&mut self, data: IsoOutBuffer, user_data: *mut c_void) {
+        let ep = self.urb_mut().endpoint;
+        let ty = self.urb_mut().ep_type;
+        assert!(ep & 0x80 == 0);
+        assert!(ty == USBDEVFS_URB_TYPE_ISO);
+
+        let lengths = iso_out_packet_lengths(data.buf.len(), data.packet_size);
+        self.fill_iso(data.buf, lengths, user_data);
+
+        // SAFETY: we just properly filled the buffer and it is not already pending
+        unsafe { self.submit_urbs() }
+    }
+
+    unsafe fn take_completed(&mut self) -> Completion<IsoResponse> {
+        // SAFETY: self is completed (precondition)
+        unsafe { self.take_completed_iso() }
+    }
+}
+
+fn iso_packet_status(desc: &IsoPacketDesc) -> Result<(), TransferError> {
+    // Negative errno stored in an unsigned field.
+    match desc.status as i32 {
+        0 => Ok(()),
+        s => Err(errno_to_transfer_error(Errno::from_raw_os_error(s.abs()))),
+    }
+}
+
 fn urb_status(urb: &Urb) -> Result<(), TransferError> {
     if urb.status == 0 {
         return Ok(());
@@ -220,3 +658,76 @@ fn urb_status(urb: &Urb) -> Result<(), TransferError> {
         urb.status.abs(),
     )))
 }
+
+#[test]
+fn test_iso_packets() {
+    let errno = |e: Errno| (-e.raw_os_error()) as u32;
+
+    assert_eq!(iso_out_packet_lengths(10, 4).collect::<Vec<_>>(), [4, 4, 2]);
+    assert_eq!(iso_out_packet_lengths(8, 4).collect::<Vec<_>>(), [4, 4]);
+    assert_eq!(iso_out_packet_lengths(0, 4).len(), 0);
+
+    let descs = [
+        IsoPacketDesc {
+            length: 192,
+            actual_length: 192,
+            status: 0,
+        },
+        IsoPacketDesc {
+            length: 192,
+            actual_length: 100,
+            status: 0,
+        },
+        IsoPacketDesc {
+            length: 192,
+            actual_length: 0,
+            status: errno(Errno::PROTO),
+        },
+        IsoPacketDesc {
+            length: 96,
+            actual_length: 0,
+            status: errno(Errno::OVERFLOW),
+        },
+    ];
+    let packets = iso_packets(&descs);
+    let layout: Vec<_> = packets
+        .iter()
+        .map(|p| (p.offset(), p.requested_length(), p.actual_length()))
+        .collect();
+    assert_eq!(
+        layout,
+        [(0, 192, 192), (192, 192, 100), (384, 192, 0), (576, 96, 0)]
+    );
+    let statuses: Vec<_> = packets.iter().map(|p| p.status()).collect();
+    assert_eq!(
+        statuses,
+        [
+            Ok(()),
+            Ok(()),
+            Err(TransferError::Fault),
+            Err(TransferError::Overflow)
+        ]
+    );
+
+    let urb = |status: i32| Urb {
+        ep_type: USBDEVFS_URB_TYPE_ISO,
+        endpoint: 0x81,
+        status,
+        flags: 0,
+        buffer: null_mut(),
+        buffer_length: 0,
+        actual_length: 0,
+        start_frame: 0,
+        number_of_packets_or_stream_id: 0,
+        error_count: 0,
+        signr: 0,
+        usercontext: null_mut(),
+    };
+    // EXDEV for the URB only means some packets failed.
+    assert_eq!(iso_urb_status(&urb(-Errno::XDEV.raw_os_error())), Ok(()));
+    assert_eq!(
+        iso_urb_status(&urb(-Errno::NODEV.raw_os_error())),
+        Err(TransferError::Disconnected)
+    );
+    assert_eq!(iso_urb_status(&urb(0)), Ok(()));
+}
@@ -46,7 +46,13 @@ struct DetachAndClaim {
     driver: [c_uchar; 255 + 1],
 }
 
-pub fn detach_and_claim_interface<Fd: AsFd>(fd: Fd, interface: u8) -> io::Result<()> {
+/// Detach the kernel driver unless it is `except_driver`, and claim the
+/// interface. `except_driver` must be shorter than 256 bytes.
+pub fn detach_and_claim_interface<Fd: AsFd>(
+    fd: Fd,
+    interface: u8,
+    except_driver: &str,
+) -> io::Result<()> {
     const USBDEVFS_DISCONNECT_CLAIM_EXCEPT_DRIVER: c_uint = 0x02;
     unsafe {
         let mut dc = DetachAndClaim {
@@ -55,7 +61,7 @@ pub fn detach_and_claim_interface<Fd: AsFd>(fd: Fd, interface: u8) -> io::Result
             driver: [0; 256],
         };
 
-        dc.driver[0..6].copy_from_slice(b"usbfs\0");
+        dc.driver[..except_driver.len()].copy_from_slice(except_driver.as_bytes());
 
         let ctl = ioctl::Setter::<opcodes::USBDEVFS_DISCONNECT_CLAIM, DetachAndClaim>::new(dc);
 
@@ -82,6 +88,8 @@ mod opcodes {
 
     pub type USBDEVFS_IOCTL = ioctl::ReadWriteOpcode<b'U', 18, UsbFsIoctl>;
     pub type USBDEVFS_DISCONNECT_CLAIM = ioctl::ReadOpcode<b'U', 27, DetachAndClaim>;
+    pub type USBDEVFS_ALLOC_STREAMS = ioctl::ReadOpcode<b'U', 28, StreamsHeader>;
+    pub type USBDEVFS_FREE_STREAMS = ioctl::ReadOpcode<b'U', 29, StreamsHeader>;
 
     /// These opcodes are nested inside a [`USBDEVFS_IOCTL`] operation.
     pub mod nested {
@@ -192,6 +200,23 @@ pub unsafe fn discard_urb<Fd: AsFd>(fd: Fd, urb: *mut Urb) -> io::Result<()> {
     }
 }
 
+pub fn get_capabilities<Fd: AsFd>(fd: Fd) -> io::Result<u32> {
+    unsafe {
+        let ctl = ioctl::Getter::<ioctl::ReadOpcode<b'U', 26, u32>, u32>::new();
+        ioctl::ioctl(fd, ctl)
+    }
+}
+
+pub const USBDEVFS_CAP_ZERO_PACKET: u32 = 0x01;
+pub const USBDEVFS_CAP_BULK_CONTINUATION: u32 = 0x02;
+pub const USBDEVFS_CAP_NO_PACKET_SIZE_LIM: u32 = 0x04;
+pub const USBDEVFS_CAP_BULK_SCATTER_GATHER: u32 = 0x08;
+pub const USBDEVFS_CAP_REAP_AFTER_DISCONNECT: u32 = 0x10;
+pub const USBDEVFS_CAP_MMAP: u32 = 0x20;
+pub const USBDEVFS_CAP_DROP_PRIVILEGES: u32 = 0x40;
+pub const USBDEVFS_CAP_CONNINFO_EX: u32 = 0x80;
+pub const USBDEVFS_CAP_SUSPEND: u32 = 0x100;
+
 pub fn reset<Fd: AsFd>(fd: Fd) -> io::Result<()> {
     unsafe {
         let ctl = ioctl::NoArg::<ioctl::NoneOpcode<b'U', 20, ()>>::new();
@@ -199,10 +224,10 @@ pub fn reset<Fd: AsFd>(fd: Fd) -> io::Result<()> {
     }
 }
 
-const USBDEVFS_URB_SHORT_NOT_OK: c_uint = 0x01;
-const USBDEVFS_URB_ISO_ASAP: c_uint = 0x02;
-const USBDEVFS_URB_BULK_CONTINUATION: c_uint = 0x04;
-const USBDEVFS_URB_ZERO_PACKET: c_uint = 0x40;
+pub const USBDEVFS_URB_SHORT_NOT_OK: c_uint = 0x01;
+pub const USBDEVFS_URB_ISO_ASAP: c_uint = 0x02;
+pub const USBDEVFS_URB_BULK_CONTINUATION: c_uint = 0x04;
+pub const USBDEVFS_URB_ZERO_PACKET: c_uint = 0x40;
 const USBDEVFS_URB_NO_INTERRUPT: c_uint = 0x80;
 
 pub const USBDEVFS_URB_TYPE_ISO: c_uchar = 0;
@@ -228,6 +253,14 @@ pub struct Urb {
     // + variable size array of iso_packet_desc
 }
 
+#[repr(C)]
+#[derive(Debug)]
+pub struct IsoPacketDesc {
+    pub length: c_uint,
+    pub actual_length: c_uint,
+    pub status: c_uint,
+}
+
 pub struct Transfer<Opcode, Input> {
     input: Input,
     _opcode: PhantomData<Opcode>,
@@ -278,6 +311,50 @@ pub fn control<Fd: AsFd>(fd: Fd, transfer: CtrlTransfer) -> io::Result<usize> {
     }
 }
 
+/// Header of `struct usbdevfs_streams`, used for the size in the opcode.
+#[repr(C)]
+pub struct StreamsHeader {
+    num_streams: c_uint,
+    num_eps: c_uint,
+}
+
+/// `struct usbdevfs_streams` with space for every endpoint of an interface.
+#[repr(C)]
+pub struct Streams {
+    header: StreamsHeader,
+    eps: [c_uchar; 32],
+}
+
+impl Streams {
+    /// Returns `None` if there are too many endpoints.
+    pub fn new(endpoints: &[u8], num_streams: u32) -> Option<Streams> {
+        let mut eps = [0; 32];
+        eps.get_mut(..endpoints.len())?.copy_from_slice(endpoints);
+        Some(Streams {
+            header: StreamsHeader {
+                num_streams,
+                num_eps: endpoints.len() as c_uint,
+            },
+            eps,
+        })
+    }
+}
+
+/// Allocate streams on bulk endpoints, returning the number allocated.
+pub fn alloc_streams<Fd: AsFd>(fd: Fd, streams: Streams) -> io::Result<usize> {
+    unsafe {
+        let ctl = Transfer::<opcodes::USBDEVFS_ALLOC_STREAMS, Streams>::new(streams);
+        ioctl::ioctl(fd, ctl)
+    }
+}
+
+pub fn free_streams<Fd: AsFd>(fd: Fd, streams: Streams) -> io::Result<()> {
+    unsafe {
+        let ctl = Transfer::<opcodes::USBDEVFS_FREE_STREAMS, Streams>::new(streams);
+        ioctl::ioctl(fd, ctl).map(|_| ())
+    }
+}
+
 pub fn clear_halt<Fd: AsFd>(fd: Fd, endpoint: u8) -> io::Result<()> {
     unsafe {
         let ctl =
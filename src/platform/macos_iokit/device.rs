@@ -1,15 +1,21 @@
 use std::{
     collections::BTreeMap,
-    ffi::c_void,
+    ffi::{c_char, c_void},
     io::ErrorKind,
     sync::{
-        atomic::{AtomicU8, Ordering},
+        atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
         Arc, Mutex,
     },
     time::Duration,
 };
 
+use core_foundation::{base::TCFType, runloop::CFRunLoopSource};
+use io_kit_sys::{
+    keys::kIOGeneralInterest, ret::kIOReturnSuccess, types::io_service_t,
+    IONotificationPortGetRunLoopSource, IOServiceAddInterestNotification,
+};
 use log::{debug, error};
+use slab::Slab;
 
 use crate::{
     platform::macos_iokit::events::add_event_source,
@@ -20,16 +26,100 @@ use crate::{
 use super::{
     enumeration::service_by_registry_id,
     events::EventRegistration,
-    iokit::{call_iokit_function, check_iokit_return},
-    iokit_c::IOUSBDevRequestTO,
+    hotplug::NotificationPort,
+    iokit::{call_iokit_function, check_iokit_return, IoObject, IoService},
+    iokit_c::{kIOMessageServiceIsTerminated, IOUSBDevRequestTO},
     iokit_usb::{EndpointInfo, IoKitDevice, IoKitInterface},
     status_to_transfer_result,
 };
 
 pub(crate) struct MacDevice {
     _event_registration: EventRegistration,
-    pub(super) device: IoKitDevice,
+    termination: TerminationWatch,
+    registry_id: u64,
+    pub(crate) log_id: String,
+    pub(crate) device: IoKitDevice,
     active_config: AtomicU8,
+
+    /// Number of `MacInterface`s that have not been dropped.
+    claimed_interfaces: AtomicUsize,
+}
+
+// Flags are owned by a global slab, like the hotplug wakers, so that a
+// notification delivered while the device is being dropped finds no entry
+// rather than a freed flag.
+static TERMINATED: Mutex<Slab<Arc<AtomicBool>>> = Mutex::new(Slab::new());
+
+/// Interest notification that sets a flag when the device is terminated, so
+/// transfer completions can check for removal without searching the
+/// IORegistry.
+struct TerminationWatch {
+    key: usize,
+    terminated: Arc<AtomicBool>,
+    _notification: IoObject,
+    _registration: EventRegistration,
+    _port: NotificationPort,
+}
+
+impl TerminationWatch {
+    fn new(service: &IoService) -> Result<TerminationWatch, Error> {
+        let terminated = Arc::new(AtomicBool::new(false));
+        let key = TERMINATED.lock().unwrap().insert(terminated.clone());
+
+        let port = NotificationPort::new();
+        let mut notification = 0;
+        let r = unsafe {
+            IOServiceAddInterestNotification(
+                port.0,
+                service.get(),
+                kIOGeneralInterest as *mut c_char,
+                termination_callback,
+                key as *mut c_void,
+                &mut notification,
+            )
+        };
+        if r != kIOReturnSuccess {
+            TERMINATED.lock().unwrap().remove(key);
+            return Err(Error::other(
+                "Failed to register device interest notification",
+            ));
+        }
+
+        let source = unsafe {
+            CFRunLoopSource::wrap_under_get_rule(IONotificationPortGetRunLoopSource(port.0))
+        };
+
+        Ok(TerminationWatch {
+            key,
+            terminated,
+            _notification: unsafe { IoObject::new(notification) },
+            _registration: add_event_source(source),
+            _port: port,
+        })
+    }
+}
+
+// The notification port is only used when creating and dropping the watch.
+unsafe impl Sync for TerminationWatch {}
+
+impl Drop for TerminationWatch {
+    fn drop(&mut self) {
+        TERMINATED.lock().unwrap().remove(self.key);
+    }
+}
+
+unsafe extern "C" fn termination_callback(
+    refcon: *mut c_void,
+    _service: io_service_t,
+    message_type: u32,
+    _message_argument: *mut c_void,
+) {
+    if message_type == kIOMessageServiceIsTerminated {
+        debug!("device interest callback: terminated");
+        if let Some(terminated) = TERMINATED.lock().unwrap().get(refcon as usize) {
+            terminated.store(true, Ordering::Release);
+        }
+    }
 }
 
 // `get_configuration` does IO, so avoid it in the common case that:
@@ -47,37 +137,64 @@ fn guess_active_config(dev: &IoKitDevice) -> Option<u8> {
 
 impl MacDevice {
     pub(crate) fn from_device_info(d: &DeviceInfo) -> Result<Arc<MacDevice>, Error> {
-        log::info!("Opening device from registry id {}", d.registry_id);
+        let log_id = d.log_id();
+        log::info!("Opening device {log_id} from registry id {}", d.registry_id);
         let service = service_by_registry_id(d.registry_id)?;
+        let termination = TerminationWatch::new(&service)?;
         let device = IoKitDevice::new(service)?;
         let _event_registration = add_event_source(device.create_async_event_source()?);
 
         let active_config = if let Some(active_config) = guess_active_config(&device) {
-            log::debug!("Active config from single descriptor is {}", active_config);
+            log::debug!("Active config of {log_id} from single descriptor is {active_config}");
             active_config
         } else {
             let res = device.get_configuration();
-            log::debug!("Active config from request is {:?}", res);
+            log::debug!("Active config of {log_id} from request is {res:?}");
             res.unwrap_or(0)
         };
 
         Ok(Arc::new(MacDevice {
             _event_registration,
+            termination,
+            registry_id: d.registry_id,
+            log_id,
             device,
             active_config: AtomicU8::new(active_config),
+            claimed_interfaces: AtomicUsize::new(0),
         }))
     }
 
+    /// Check whether IOKit has reported that the device was terminated.
+    pub(crate) fn is_disconnected(&self) -> bool {
+        self.termination.terminated.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn is_suspended(&self) -> Result<bool, Error> {
+        super::is_suspended(self.registry_id)
+    }
+
     pub(crate) fn active_configuration_value(&self) -> u8 {
         self.active_config.load(Ordering::SeqCst)
     }
 
+    pub(crate) fn get_configuration(&self) -> Result<u8, Error> {
+        let value = self.device.get_configuration()?;
+        self.active_config.store(value, Ordering::SeqCst);
+        Ok(value)
+    }
+
     pub(crate) fn configuration_descriptors(&self) -> impl Iterator<Item = &[u8]> {
         let num_configs = self.device.get_number_of_configurations().unwrap_or(0);
         (0..num_configs).flat_map(|i| self.device.get_configuration_descriptor(i).ok())
     }
 
     pub(crate) fn set_configuration(&self, configuration: u8) -> Result<(), Error> {
+        if self.claimed_interfaces.load(Ordering::SeqCst) != 0 {
+            return Err(Error::other(
+                "cannot set configuration while interfaces are claimed",
+            ));
+        }
+
         unsafe {
             check_iokit_return(call_iokit_function!(
                 self.device.raw,
@@ -178,13 +295,19 @@ impl MacDevice {
         interface.open()?;
 
         let endpoints = interface.endpoints()?;
-        debug!("Found endpoints: {endpoints:?}");
+        debug!(
+            "Found endpoints of interface {interface_number} on {}: {endpoints:?}",
+            self.log_id
+        );
 
+        self.claimed_interfaces.fetch_add(1, Ordering::SeqCst);
         Ok(Arc::new(MacInterface {
             device: self.clone(),
             interface_number,
             interface,
             endpoints: Mutex::new(endpoints),
+            alt_setting: AtomicU8::new(0),
+            released: false,
             _event_registration,
         }))
     }
@@ -205,6 +328,12 @@ pub(crate) struct MacInterface {
 
     /// Map from address to a structure that contains the `pipe_ref` used by iokit
     pub(crate) endpoints: Mutex<BTreeMap<u8, EndpointInfo>>,
+
+    /// Alternate setting most recently selected with `set_alt_setting`.
+    alt_setting: AtomicU8,
+
+    /// Set by `release` so the interface isn't closed again on drop.
+    released: bool,
 }
 
 impl MacInterface {
@@ -232,6 +361,21 @@ impl MacInterface {
         }
     }
 
+    /// Make a bulk OUT transfer that ends with a zero-length packet.
+    pub(crate) fn make_zlp_transfer(
+        self: &Arc<Self>,
+        endpoint: u8,
+    ) -> TransferHandle<super::TransferData> {
+        let pipe_ref = {
+            let endpoints = self.endpoints.lock().unwrap();
+            endpoints.get(&endpoint).map(|e| e.pipe_ref).unwrap_or(0)
+        };
+        let mut data =
+            super::TransferData::new(self.device.clone(), self.clone(), endpoint, pipe_ref);
+        data.set_zero_packet(true);
+        TransferHandle::new(data)
+    }
+
     pub fn control_in_blocking(
         &self,
         control: Control,
@@ -252,8 +396,8 @@ impl MacInterface {
 
     pub fn set_alt_setting(&self, alt_setting: u8) -> Result<(), Error> {
         debug!(
-            "Set interface {} alt setting to {alt_setting}",
-            self.interface_number
+            "Set interface {} alt setting to {alt_setting} on {}",
+            self.interface_number, self.device.log_id
         );
 
         let mut endpoints = self.endpoints.lock().unwrap();
@@ -267,12 +411,20 @@ impl MacInterface {
 
         *endpoints = self.interface.endpoints()?;
         debug!("Found endpoints: {endpoints:?}");
+        self.alt_setting.store(alt_setting, Ordering::Relaxed);
 
         Ok(())
     }
 
+    pub fn get_alt_setting(&self) -> u8 {
+        self.alt_setting.load(Ordering::Relaxed)
+    }
+
     pub fn clear_halt(&self, endpoint: u8) -> Result<(), Error> {
-        debug!("Clear halt, endpoint {endpoint:02x}");
+        debug!(
+            "Clear halt, endpoint {endpoint:02x} on {}",
+            self.device.log_id
+        );
 
         let pipe_ref = {
             let endpoints = self.endpoints.lock().unwrap();
@@ -289,12 +441,66 @@ impl MacInterface {
             ))
         }
     }
+
+    pub fn cancel_all(&self, endpoint: u8) -> Result<(), Error> {
+        debug!(
+            "Cancel all transfers, endpoint {endpoint:02x} on {}",
+            self.device.log_id
+        );
+
+        let pipe_ref = {
+            let endpoints = self.endpoints.lock().unwrap();
+            let ep = endpoints
+                .get(&endpoint)
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, "Endpoint not found"))?;
+            ep.pipe_ref
+        };
+
+        unsafe {
+            check_iokit_return(call_iokit_function!(
+                self.interface.raw,
+                AbortPipe(pipe_ref)
+            ))
+        }
+    }
+
+    pub fn alloc_streams(&self, _endpoints: &[u8], _num_streams: u32) -> Result<u32, Error> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "bulk streams not supported on macOS",
+        ))
+    }
+
+    pub fn free_streams(&self, _endpoints: &[u8]) -> Result<(), Error> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "bulk streams not supported on macOS",
+        ))
+    }
+}
+
+impl MacInterface {
+    pub fn release(mut self) -> Result<(), Error> {
+        self.released = true;
+        self.interface.close()
+    }
 }
 
 impl Drop for MacInterface {
     fn drop(&mut self) {
+        self.device
+            .claimed_interfaces
+            .fetch_sub(1, Ordering::SeqCst);
+
+        if self.released {
+            return;
+        }
+
         if let Err(err) = self.interface.close() {
-            error!("Failed to close interface: {err}")
+            error!(
+                "Failed to close interface {} on {}: {err}",
+                self.interface_number, self.device.log_id
+            )
         }
     }
 }
@@ -2,19 +2,27 @@ use std::io::ErrorKind;
 
 use core_foundation::{
     base::{CFType, TCFType},
+    dictionary::CFDictionary,
     number::CFNumber,
     string::CFString,
     ConcreteCFType,
 };
 use io_kit_sys::{
     kIOMasterPortDefault, kIORegistryIterateParents, kIORegistryIterateRecursively,
-    keys::kIOServicePlane, ret::kIOReturnSuccess, usb::lib::kIOUSBDeviceClassName,
-    IORegistryEntryGetChildIterator, IORegistryEntryGetRegistryEntryID,
-    IORegistryEntrySearchCFProperty, IOServiceGetMatchingServices, IOServiceMatching,
+    keys::kIOServicePlane,
+    ret::{kIOReturnSuccess, IOReturn},
+    usb::lib::kIOUSBDeviceClassName,
+    IOObjectConformsTo, IOObjectGetClass, IORegistryEntryCreateCFProperty,
+    IORegistryEntryGetChildIterator, IORegistryEntryGetName, IORegistryEntryGetParentEntry,
+    IORegistryEntryGetRegistryEntryID, IORegistryEntrySearchCFProperty,
+    IOServiceGetMatchingServices, IOServiceMatching,
 };
 use log::debug;
 
-use crate::{DeviceInfo, Error, InterfaceInfo, Speed};
+use crate::{
+    descriptors::{DESCRIPTOR_LEN_DEVICE, DESCRIPTOR_TYPE_DEVICE},
+    DeviceInfo, DeviceSummary, Error, InterfaceInfo, Speed,
+};
 
 use super::iokit::{IoService, IoServiceIterator};
 
@@ -39,6 +47,46 @@ pub fn list_devices() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
     Ok(usb_service_iter()?.filter_map(probe_device))
 }
 
+pub fn list_devices_with_errors(
+    _retries: u8,
+    _lazy_strings: bool,
+    _partial: bool,
+    _configuration_descriptors: bool,
+    _root_hubs: bool,
+) -> Result<impl Iterator<Item = Result<DeviceInfo, Error>>, Error> {
+    Ok(list_devices()?.map(Ok))
+}
+
+pub fn list_devices_with_class(class: u8) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    Ok(list_devices()?
+        .filter(move |d| d.class() == class || d.interfaces().any(|i| i.class() == class)))
+}
+
+pub fn list_devices_on_bus(bus_id: &str) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    let bus_id = bus_id.to_owned();
+    Ok(usb_service_iter()?
+        .filter(move |device| {
+            get_integer_property(device, "locationID")
+                .is_some_and(|l| format!("{:02x}", (l as u32 >> 24) as u8) == bus_id)
+        })
+        .filter_map(probe_device))
+}
+
+pub fn list_device_ids() -> Result<impl Iterator<Item = DeviceSummary>, Error> {
+    Ok(usb_service_iter()?.filter_map(|device| {
+        Some(DeviceSummary {
+            id: crate::DeviceId::new(get_registry_id(&device)?),
+            device_address: get_integer_property(&device, "USB Address")? as u8,
+            vendor_id: get_integer_property(&device, "idVendor")? as u16,
+            product_id: get_integer_property(&device, "idProduct")? as u16,
+        })
+    }))
+}
+
+pub(crate) fn probe_device_id(registry_id: u64) -> Option<DeviceInfo> {
+    probe_device(service_by_registry_id(registry_id).ok()?)
+}
+
 pub(crate) fn service_by_registry_id(registry_id: u64) -> Result<IoService, Error> {
     usb_service_iter()?
         .find(|dev| get_registry_id(dev) == Some(registry_id))
@@ -51,20 +99,41 @@ pub(crate) fn probe_device(device: IoService) -> Option<DeviceInfo> {
 
     let location_id = get_integer_property(&device, "locationID")? as u32;
 
+    let controller = find_controller(&device);
+    let controller_id = controller
+        .as_ref()
+        .and_then(|c| get_name(|buf| unsafe { IORegistryEntryGetName(c.get(), buf) }));
+    let controller_driver = controller
+        .as_ref()
+        .and_then(|c| get_name(|buf| unsafe { IOObjectGetClass(c.get(), buf) }));
+
+    let num_configurations = get_integer_property(&device, "bNumConfigurations")
+        .map(|n| n as u8)
+        .unwrap_or_else(|| {
+            log::debug!("Missing bNumConfigurations for {registry_id:08x}, assuming 1");
+            1
+        });
+
     // Can run `ioreg -p IOUSB -l` to see all properties
     Some(DeviceInfo {
         registry_id,
         location_id,
         bus_id: format!("{:02x}", (location_id >> 24) as u8),
+        controller_id,
+        controller_driver,
         device_address: get_integer_property(&device, "USB Address")? as u8,
         port_chain: parse_location_id(location_id),
+        root_hub: false,
         vendor_id: get_integer_property(&device, "idVendor")? as u16,
         product_id: get_integer_property(&device, "idProduct")? as u16,
         device_version: get_integer_property(&device, "bcdDevice")? as u16,
+        usb_version: get_integer_property(&device, "bcdUSB").map_or(0, |v| v as u16),
         class: get_integer_property(&device, "bDeviceClass")? as u8,
         subclass: get_integer_property(&device, "bDeviceSubClass")? as u8,
         protocol: get_integer_property(&device, "bDeviceProtocol")? as u8,
         max_packet_size_0: get_integer_property(&device, "bMaxPacketSize0")? as u8,
+        num_configurations,
+        device_descriptor: device_descriptor_from_properties(&device),
         speed: get_integer_property(&device, "Device Speed").and_then(map_speed),
         manufacturer_string: get_string_property(&device, "USB Vendor Name"),
         product_string: get_string_property(&device, "USB Product Name"),
@@ -78,13 +147,124 @@ pub(crate) fn probe_device(device: IoService) -> Option<DeviceInfo> {
                     protocol: get_integer_property(&child, "bInterfaceProtocol")? as u8,
                     interface_string: get_string_property(&child, "kUSBString")
                         .or_else(|| get_string_property(&child, "USB Interface Name")),
+                    function: None,
                 })
             })
             .collect()
         }),
+        configuration_descriptors: Vec::new(),
+        #[cfg(feature = "mock")]
+        mock_id: None,
     })
 }
 
+/// Power state of an `IOUSBHostDevice` when it is fully on. Lower states are
+/// suspended or off.
+const USB_HOST_POWER_STATE_ON: i64 = 2;
+
+/// Check the current power state of the device from its `IOPowerManagement`
+/// property.
+pub(crate) fn is_suspended(registry_id: u64) -> Result<bool, Error> {
+    let service = service_by_registry_id(registry_id)?;
+    let state = unsafe {
+        let key = CFString::from_static_string("IOPowerManagement");
+        let raw = IORegistryEntryCreateCFProperty(
+            service.get(),
+            key.as_concrete_TypeRef(),
+            std::ptr::null(),
+            0,
+        );
+        if raw.is_null() {
+            return Err(Error::other("device has no power management state"));
+        }
+        CFType::wrap_under_create_rule(raw)
+            .downcast_into::<CFDictionary>()
+            .and_then(|d| {
+                let key = CFString::from_static_string("CurrentPowerState");
+                let value = d.find(key.as_CFTypeRef().cast())?;
+                CFType::wrap_under_get_rule(*value).downcast_into::<CFNumber>()
+            })
+            .and_then(|n| n.to_i64())
+    };
+
+    let state = state.ok_or_else(|| Error::other("failed to read device power state"))?;
+    Ok(state < USB_HOST_POWER_STATE_ON)
+}
+
+/// IOKit class that host controller drivers are subclasses of.
+const USB_HOST_CONTROLLER_CLASS: &[u8] = b"IOUSBHostController\0";
+
+/// Walk up the service plane to the host controller of `device`.
+fn find_controller(device: &IoService) -> Option<IoService> {
+    let mut entry = get_parent(device)?;
+    loop {
+        if unsafe { IOObjectConformsTo(entry.get(), USB_HOST_CONTROLLER_CLASS.as_ptr() as *mut _) }
+            != 0
+        {
+            return Some(entry);
+        }
+        entry = get_parent(&entry)?;
+    }
+}
+
+/// Read an `io_name_t` string filled in by `f`.
+fn get_name(f: impl FnOnce(*mut std::ffi::c_char) -> IOReturn) -> Option<String> {
+    let mut buf = [0 as std::ffi::c_char; 128];
+    if f(buf.as_mut_ptr()) != kIOReturnSuccess {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) };
+    name.to_str().ok().map(str::to_owned)
+}
+
+/// Probe the hub that `device` is connected to.
+///
+/// Hub ports and drivers sit between a hub and its children in the service
+/// plane, so this walks up until it finds another USB device.
+pub(crate) fn probe_parent(device: &DeviceInfo) -> Option<DeviceInfo> {
+    let mut entry = service_by_registry_id(device.registry_id).ok()?;
+    loop {
+        entry = get_parent(&entry)?;
+        if unsafe { IOObjectConformsTo(entry.get(), kIOUSBDeviceClassName as *mut _) } != 0 {
+            return probe_device(entry);
+        }
+    }
+}
+
+/// Reassemble the device descriptor from the fields IOKit caches as properties.
+fn device_descriptor_from_properties(
+    device: &IoService,
+) -> Option<[u8; DESCRIPTOR_LEN_DEVICE as usize]> {
+    let u8_prop = |name| get_integer_property(device, name).map(|v| v as u8);
+    let u16_prop = |name| get_integer_property(device, name).map(|v| (v as u16).to_le_bytes());
+
+    let [usb_lo, usb_hi] = u16_prop("bcdUSB")?;
+    let [vid_lo, vid_hi] = u16_prop("idVendor")?;
+    let [pid_lo, pid_hi] = u16_prop("idProduct")?;
+    let [dev_lo, dev_hi] = u16_prop("bcdDevice")?;
+
+    Some([
+        DESCRIPTOR_LEN_DEVICE,
+        DESCRIPTOR_TYPE_DEVICE,
+        usb_lo,
+        usb_hi,
+        u8_prop("bDeviceClass")?,
+        u8_prop("bDeviceSubClass")?,
+        u8_prop("bDeviceProtocol")?,
+        u8_prop("bMaxPacketSize0")?,
+        vid_lo,
+        vid_hi,
+        pid_lo,
+        pid_hi,
+        dev_lo,
+        dev_hi,
+        u8_prop("iManufacturer")?,
+        u8_prop("iProduct")?,
+        u8_prop("iSerialNumber")?,
+        u8_prop("bNumConfigurations")?,
+    ])
+}
+
 pub(crate) fn get_registry_id(device: &IoService) -> Option<u64> {
     unsafe {
         let mut out = 0;
@@ -131,7 +311,7 @@ fn get_string_property(device: &IoService, property: &'static str) -> Option<Str
     get_property::<CFString>(device, property).map(|s| s.to_string())
 }
 
-fn get_integer_property(device: &IoService, property: &'static str) -> Option<i64> {
+pub(crate) fn get_integer_property(device: &IoService, property: &'static str) -> Option<i64> {
     let n = get_property::<CFNumber>(device, property)?;
     n.to_i64().or_else(|| {
         debug!("failed to convert {property} value {n:?} to i64");
@@ -153,6 +333,19 @@ fn get_children(device: &IoService) -> Result<IoServiceIterator, Error> {
     }
 }
 
+fn get_parent(device: &IoService) -> Option<IoService> {
+    unsafe {
+        let mut parent = 0;
+        let r = IORegistryEntryGetParentEntry(device.get(), kIOServicePlane as *mut _, &mut parent);
+        if r != kIOReturnSuccess {
+            // The root of the plane has no parent.
+            return None;
+        }
+
+        Some(IoService::new(parent))
+    }
+}
+
 fn map_speed(speed: i64) -> Option<Speed> {
     // https://developer.apple.com/documentation/iokit/1425357-usbdevicespeed
     match speed {
@@ -20,12 +20,15 @@ use io_kit_sys::{
 use log::debug;
 use slab::Slab;
 
-use crate::{hotplug::HotplugEvent, DeviceId, Error};
+use crate::{
+    hotplug::{may_match_any, HotplugEvent, HotplugFilter},
+    DeviceId, Error,
+};
 
 use super::{
-    enumeration::{get_registry_id, probe_device},
+    enumeration::{get_integer_property, get_registry_id, probe_device},
     events::{add_event_source, EventRegistration},
-    iokit::IoServiceIterator,
+    iokit::{IoService, IoServiceIterator},
 };
 
 // Wakers are owned by a global slab to avoid race conditions when freeing them
@@ -58,10 +61,10 @@ pub(crate) struct MacHotplugWatch {
     _notification_port: NotificationPort,
 }
 
-struct NotificationPort(*mut IONotificationPort);
+pub(crate) struct NotificationPort(pub(crate) *mut IONotificationPort);
 
 impl NotificationPort {
-    fn new() -> NotificationPort {
+    pub(crate) fn new() -> NotificationPort {
         unsafe { NotificationPort(IONotificationPortCreate(kIOMasterPortDefault)) }
     }
 }
@@ -116,10 +119,13 @@ impl MacHotplugWatch {
         })
     }
 
-    pub fn poll_next(&mut self, cx: &mut Context) -> Poll<HotplugEvent> {
+    pub fn poll_next(&mut self, cx: &mut Context, filters: &[HotplugFilter]) -> Poll<HotplugEvent> {
         self.waker_id.register(cx.waker());
 
         while let Some(s) = self.matched_iter.next() {
+            if !service_may_match(&s, filters) {
+                continue;
+            }
             if let Some(dev) = probe_device(s) {
                 return Poll::Ready(HotplugEvent::Connected(dev));
             } else {
@@ -127,10 +133,13 @@ impl MacHotplugWatch {
             }
         }
 
-        if let Some(s) = self.terminated_iter.next() {
+        while let Some(s) = self.terminated_iter.next() {
+            if !service_may_match(&s, filters) {
+                continue;
+            }
             if let Some(registry_id) = get_registry_id(&s) {
                 debug!("device {registry_id} disconnected");
-                let id = DeviceId(registry_id);
+                let id = DeviceId::new(registry_id);
                 return Poll::Ready(HotplugEvent::Disconnected(id));
             } else {
                 debug!("failed to get registry ID for disconnected device")
@@ -141,6 +150,19 @@ impl MacHotplugWatch {
     }
 }
 
+/// Check the IDs in the registry entry against the filters before probing the device.
+///
+/// The properties of a terminated service remain readable while we hold a
+/// reference to it, so this works for disconnect events too.
+fn service_may_match(service: &IoService, filters: &[HotplugFilter]) -> bool {
+    let prop = |name| get_integer_property(service, name);
+    may_match_any(
+        filters,
+        prop("idVendor").map(|v| v as u16),
+        prop("idProduct").map(|v| v as u16),
+    )
+}
+
 fn register_notification(
     port: &NotificationPort,
     dictionary: &CFDictionary,
@@ -30,6 +30,9 @@ use io_kit_sys::{
 //
 const SYS_IOKIT: c_int = ((0x38) & 0x3f) << 26;
 const SUB_IOKIT_USB: c_int = ((1) & 0xfff) << 14;
+const SUB_IOKIT_COMMON: c_int = 0;
+
+pub(crate) const kIOMessageServiceIsTerminated: u32 = (SYS_IOKIT | SUB_IOKIT_COMMON | 0x010) as u32;
 
 pub(crate) const kIOUSBUnknownPipeErr: c_int = SYS_IOKIT | SUB_IOKIT_USB | 0x61; // 0xe0004061  Pipe ref not recognized
 pub(crate) const kIOUSBTooManyPipesErr: c_int = SYS_IOKIT | SUB_IOKIT_USB | 0x60; // 0xe0004060  Too many pipes
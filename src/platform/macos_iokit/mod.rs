@@ -1,12 +1,17 @@
 mod transfer;
 use io_kit_sys::ret::{
-    kIOReturnAborted, kIOReturnNoDevice, kIOReturnSuccess, kIOReturnUnderrun, IOReturn,
+    kIOReturnAborted, kIOReturnNoDevice, kIOReturnOverrun, kIOReturnSuccess, kIOReturnUnderrun,
+    IOReturn,
 };
 pub(crate) use transfer::TransferData;
 
 mod enumeration;
 mod events;
-pub use enumeration::list_devices;
+pub(crate) use enumeration::{is_suspended, probe_device_id, probe_parent};
+pub use enumeration::{
+    list_device_ids, list_devices, list_devices_on_bus, list_devices_with_class,
+    list_devices_with_errors,
+};
 
 mod device;
 pub(crate) use device::MacDevice as Device;
@@ -31,6 +36,9 @@ fn status_to_transfer_result(status: IOReturn) -> Result<(), TransferError> {
         kIOReturnSuccess | kIOReturnUnderrun => Ok(()),
         kIOReturnNoDevice => Err(TransferError::Disconnected),
         kIOReturnAborted => Err(TransferError::Cancelled),
+        kIOReturnOverrun => Err(TransferError::Overflow),
+        iokit_c::kIOUSBPipeStalled => Err(TransferError::Stall),
+        iokit_c::kIOUSBTransactionTimeout => Err(TransferError::TimedOut),
         _ => Err(TransferError::Unknown),
     }
 }
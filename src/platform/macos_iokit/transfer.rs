@@ -11,8 +11,9 @@ use log::{error, info};
 use crate::{
     platform::macos_iokit::iokit_c::IOUSBDevRequest,
     transfer::{
-        notify_completion, Completion, ControlIn, ControlOut, PlatformSubmit, PlatformTransfer,
-        RequestBuffer, ResponseBuffer, TransferError,
+        check_disconnected, drop_transfer_buffer_parts, drop_vec_parts, notify_completion,
+        CallerBuffer, Completion, ControlIn, ControlOut, FilledBuffer, PlatformSubmit,
+        PlatformTransfer, RequestBuffer, ResponseBuffer, TransferBuffer, TransferError,
     },
 };
 
@@ -31,7 +32,15 @@ extern "C" fn transfer_callback(refcon: *mut c_void, result: IOReturn, len: *mut
             inner.status = result;
             inner.callback_data
         };
-        notify_completion::<super::TransferData>(callback_data)
+        notify_completion::<crate::platform::TransferData>(callback_data)
+    }
+}
+
+/// Completion callback for a zero-length packet sent after a transfer, whose
+/// result is only logged.
+extern "C" fn zlp_callback(_refcon: *mut c_void, result: IOReturn, _len: *mut c_void) {
+    if result != kIOReturnSuccess {
+        error!("Zero-length packet failed: {result:x}");
     }
 }
 
@@ -40,15 +49,22 @@ pub struct TransferData {
     pipe_ref: u8,
     buf: *mut u8,
     capacity: usize,
+
+    /// Frees `buf`, which is a `Vec` unless a caller-provided buffer is pending.
+    buf_drop: unsafe fn(*mut u8, usize),
     inner: *mut TransferDataInner,
     device: Arc<super::Device>,
     interface: Option<Arc<super::Interface>>,
+
+    /// Send a zero-length packet after OUT transfers that are a multiple of
+    /// the maximum packet size.
+    zero_packet: bool,
 }
 
 impl Drop for TransferData {
     fn drop(&mut self) {
         if !self.buf.is_null() {
-            unsafe { drop(Vec::from_raw_parts(self.buf, 0, self.capacity)) }
+            unsafe { (self.buf_drop)(self.buf, self.capacity) }
         }
         unsafe { drop(Box::from_raw(self.inner)) }
     }
@@ -76,6 +92,7 @@ impl TransferData {
             pipe_ref,
             buf: null_mut(),
             capacity: 0,
+            buf_drop: drop_vec_parts,
             inner: Box::into_raw(Box::new(TransferDataInner {
                 actual_len: 0,
                 callback_data: null_mut(),
@@ -83,15 +100,33 @@ impl TransferData {
             })),
             device,
             interface: Some(interface),
+            zero_packet: false,
         }
     }
 
+    pub(super) fn set_zero_packet(&mut self, zero_packet: bool) {
+        self.zero_packet = zero_packet;
+    }
+
+    /// Whether an OUT transfer of `len` bytes must be followed by a
+    /// zero-length packet.
+    fn needs_zlp(&self, len: usize) -> bool {
+        if !self.zero_packet || len == 0 {
+            return false;
+        }
+        let endpoints = self.interface.as_ref().unwrap().endpoints.lock().unwrap();
+        endpoints
+            .get(&self.endpoint_addr)
+            .is_some_and(|ep| ep.max_packet_size != 0 && len % ep.max_packet_size as usize == 0)
+    }
+
     pub(super) fn new_control(device: Arc<super::Device>) -> TransferData {
         TransferData {
             endpoint_addr: 0,
             pipe_ref: 0,
             buf: null_mut(),
             capacity: 0,
+            buf_drop: drop_vec_parts,
             inner: Box::into_raw(Box::new(TransferDataInner {
                 actual_len: 0,
                 callback_data: null_mut(),
@@ -99,6 +134,7 @@ impl TransferData {
             })),
             device,
             interface: None,
+            zero_packet: false,
         }
     }
 
@@ -127,8 +163,9 @@ impl TransferData {
     unsafe fn check_submit_result(&mut self, res: IOReturn) {
         if res != kIOReturnSuccess {
             error!(
-                "Failed to submit transfer on endpoint {ep}: {res:x}",
-                ep = self.endpoint_addr
+                "Failed to submit transfer on endpoint {ep} of {dev}: {res:x}",
+                ep = self.endpoint_addr,
+                dev = self.device.log_id
             );
             let callback_data = {
                 let inner = &mut *self.inner;
@@ -137,7 +174,7 @@ impl TransferData {
             };
 
             // Complete the transfer in the place of the callback
-            notify_completion::<super::TransferData>(callback_data)
+            notify_completion::<crate::platform::TransferData>(callback_data)
         }
     }
 
@@ -145,7 +182,10 @@ impl TransferData {
     unsafe fn take_status(&mut self) -> (Result<(), TransferError>, usize) {
         let inner = unsafe { &*self.inner };
 
-        (status_to_transfer_result(inner.status), inner.actual_len)
+        let status = check_disconnected(status_to_transfer_result(inner.status), || {
+            self.device.is_disconnected()
+        });
+        (status, inner.actual_len)
     }
 }
 
@@ -156,14 +196,18 @@ impl PlatformTransfer for TransferData {
         if let Some(intf) = self.interface.as_ref() {
             let r = unsafe { call_iokit_function!(intf.interface.raw, AbortPipe(self.pipe_ref)) };
             info!(
-                "Cancelled all transfers on endpoint {ep:02x}. status={r:x}",
-                ep = self.endpoint_addr
+                "Cancelled all transfers on endpoint {ep:02x} of {dev}. status={r:x}",
+                ep = self.endpoint_addr,
+                dev = self.device.log_id
             );
         } else {
             assert!(self.pipe_ref == 0);
             let r =
                 unsafe { call_iokit_function!(self.device.device.raw, USBDeviceAbortPipeZero()) };
-            info!("Cancelled all transfers on control pipe. status={r:x}");
+            info!(
+                "Cancelled all transfers on control pipe of {}. status={r:x}",
+                self.device.log_id
+            );
         }
     }
 }
@@ -172,6 +216,7 @@ impl PlatformSubmit<Vec<u8>> for TransferData {
     unsafe fn submit(&mut self, data: Vec<u8>, callback_data: *mut std::ffi::c_void) {
         assert!(self.endpoint_addr & 0x80 == 0);
         let len = data.len();
+        let zlp = self.needs_zlp(len);
         self.fill(data, callback_data);
 
         // SAFETY: we just properly filled the buffer and it is not already pending
@@ -190,6 +235,23 @@ impl PlatformSubmit<Vec<u8>> for TransferData {
             inner = self.inner,
             ep = self.endpoint_addr
         );
+
+        // IOKit has no flag for this, so queue a separate zero-length write
+        // behind the transfer on the same pipe.
+        if zlp && res == kIOReturnSuccess {
+            let r = call_iokit_function!(
+                self.interface.as_ref().unwrap().interface.raw,
+                WritePipeAsync(self.pipe_ref, null_mut(), 0, zlp_callback, null_mut())
+            );
+            if r != kIOReturnSuccess {
+                error!(
+                    "Failed to submit zero-length packet on endpoint {ep:02x} of {dev}: {r:x}",
+                    ep = self.endpoint_addr,
+                    dev = self.device.log_id
+                );
+            }
+        }
+
         self.check_submit_result(res);
     }
 
@@ -202,6 +264,53 @@ impl PlatformSubmit<Vec<u8>> for TransferData {
     }
 }
 
+impl<B: TransferBuffer> PlatformSubmit<CallerBuffer<B>> for TransferData {
+    unsafe fn submit(&mut self, data: CallerBuffer<B>, callback_data: *mut std::ffi::c_void) {
+        assert!(self.endpoint_addr & 0x80 == 0x80);
+
+        let (ptr, len) = data.into_raw_parts();
+        self.buf = ptr;
+        self.capacity = len;
+        self.buf_drop = drop_transfer_buffer_parts::<B>;
+
+        let inner = &mut *self.inner;
+        inner.actual_len = 0;
+        inner.status = kIOReturnSuccess;
+        inner.callback_data = callback_data;
+
+        // SAFETY: we just properly filled the buffer and it is not already pending
+        let res = call_iokit_function!(
+            self.interface.as_ref().unwrap().interface.raw,
+            ReadPipeAsync(
+                self.pipe_ref,
+                self.buf as *mut c_void,
+                u32::try_from(len).expect("request too large"),
+                transfer_callback,
+                self.inner as *mut c_void
+            )
+        );
+        info!(
+            "Submitted IN transfer {inner:?} into caller buffer on endpoint {ep:02x}",
+            inner = self.inner,
+            ep = self.endpoint_addr
+        );
+
+        self.check_submit_result(res);
+    }
+
+    unsafe fn take_completed(&mut self) -> Completion<FilledBuffer<B>> {
+        let (status, actual_len) = self.take_status();
+
+        let ptr = mem::replace(&mut self.buf, null_mut());
+        let len = mem::replace(&mut self.capacity, 0);
+        self.buf_drop = drop_vec_parts;
+
+        // SAFETY: self is completed (precondition) and the parts came from `submit`
+        let data = unsafe { FilledBuffer::from_raw_parts(ptr, len, actual_len) };
+        Completion { data, status }
+    }
+}
+
 impl PlatformSubmit<RequestBuffer> for TransferData {
     unsafe fn submit(&mut self, data: RequestBuffer, callback_data: *mut std::ffi::c_void) {
         assert!(self.endpoint_addr & 0x80 == 0x80);
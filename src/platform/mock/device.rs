@@ -0,0 +1,409 @@
+use std::{
+    collections::BTreeSet,
+    io::ErrorKind,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use log::debug;
+
+use crate::{
+    descriptors::Configuration,
+    transfer::{Control, EndpointType, TransferError, TransferHandle},
+    DeviceInfo, Error,
+};
+
+use super::{entry_by_id, MockEntry};
+
+pub(crate) struct VirtualDevice {
+    pub(crate) entry: Arc<MockEntry>,
+    pub(crate) log_id: String,
+    active_config: AtomicU8,
+
+    /// Interface numbers of `VirtualInterface`s that have not been dropped.
+    claimed_interfaces: Mutex<BTreeSet<u8>>,
+}
+
+impl VirtualDevice {
+    pub(crate) fn from_device_info(d: &DeviceInfo) -> Result<Arc<VirtualDevice>, Error> {
+        let log_id = d.log_id();
+        log::info!("Opening virtual device {log_id}");
+        let entry = d
+            .mock_id
+            .and_then(entry_by_id)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "device not connected"))?;
+
+        let active_config = entry
+            .configurations
+            .first()
+            .map(|c| Configuration::new(c).configuration_value())
+            .unwrap_or(0);
+
+        Ok(Arc::new(VirtualDevice {
+            entry,
+            log_id,
+            active_config: AtomicU8::new(active_config),
+            claimed_interfaces: Mutex::new(BTreeSet::new()),
+        }))
+    }
+
+    /// Virtual devices have no permissions, so this is the same as
+    /// `from_device_info`.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn from_device_info_readonly(d: &DeviceInfo) -> Result<Arc<VirtualDevice>, Error> {
+        Self::from_device_info(d)
+    }
+
+    /// No usbfs features are supported.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn capabilities(&self) -> u32 {
+        0
+    }
+
+    /// Transfers on a virtual device are never split, so there is no limit.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn max_bulk_transfer_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// The device descriptor followed by the configuration descriptors, as
+    /// read from a usbfs device node.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn descriptors(&self) -> Vec<u8> {
+        let mut buf = self.entry.device_descriptor.clone();
+        for config in &self.entry.configurations {
+            buf.extend_from_slice(config);
+        }
+        buf
+    }
+
+    /// Check whether the device has been disconnected with
+    /// [`crate::mock::MockConnection::disconnect`].
+    pub(crate) fn is_disconnected(&self) -> bool {
+        !self.entry.is_connected()
+    }
+
+    fn check_connected(&self) -> Result<(), Error> {
+        if self.is_disconnected() {
+            return Err(Error::new(ErrorKind::NotConnected, "device disconnected"));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn is_suspended(&self) -> Result<bool, Error> {
+        self.check_connected().map(|()| false)
+    }
+
+    pub(crate) fn active_configuration_value(&self) -> u8 {
+        self.active_config.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn get_configuration(&self) -> Result<u8, Error> {
+        self.check_connected()?;
+        Ok(self.active_configuration_value())
+    }
+
+    pub(crate) fn configuration_descriptors(&self) -> impl Iterator<Item = &[u8]> {
+        self.entry.configurations.iter().map(|c| &c[..])
+    }
+
+    fn active_configuration(&self) -> Option<Configuration<'_>> {
+        let value = self.active_configuration_value();
+        self.configuration_descriptors()
+            .map(Configuration::new)
+            .find(|c| c.configuration_value() == value)
+    }
+
+    pub(crate) fn set_configuration(&self, configuration: u8) -> Result<(), Error> {
+        self.check_connected()?;
+        if !self.claimed_interfaces.lock().unwrap().is_empty() {
+            return Err(Error::other(
+                "cannot set configuration while interfaces are claimed",
+            ));
+        }
+
+        if configuration != 0
+            && !self
+                .configuration_descriptors()
+                .any(|c| Configuration::new(c).configuration_value() == configuration)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "configuration not found",
+            ));
+        }
+
+        debug!("Set configuration {configuration} on {}", self.log_id);
+        self.active_config.store(configuration, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub(crate) fn reset(&self) -> Result<(), Error> {
+        self.check_connected()
+    }
+
+    pub fn control_in_blocking(
+        &self,
+        control: Control,
+        data: &mut [u8],
+        _timeout: Duration,
+    ) -> Result<usize, TransferError> {
+        let length = data.len().try_into().expect("length must fit in u16");
+        let response = self.entry.control_in(control, length)?;
+        data[..response.len()].copy_from_slice(&response);
+        Ok(response.len())
+    }
+
+    pub fn control_out_blocking(
+        &self,
+        control: Control,
+        data: &[u8],
+        _timeout: Duration,
+    ) -> Result<usize, TransferError> {
+        self.entry.control_out(control, data).map(|()| data.len())
+    }
+
+    /// Answer a `GET_DESCRIPTOR` request as the Windows backend does, with
+    /// a standard device request.
+    #[cfg(target_os = "windows")]
+    pub(crate) fn get_descriptor(
+        &self,
+        desc_type: u8,
+        desc_index: u8,
+        language_id: u16,
+    ) -> Result<Vec<u8>, Error> {
+        use crate::transfer::{ControlType, Recipient};
+
+        let control = Control {
+            control_type: ControlType::Standard,
+            recipient: Recipient::Device,
+            request: super::REQUEST_GET_DESCRIPTOR,
+            value: u16::from_be_bytes([desc_type, desc_index]),
+            index: language_id,
+        };
+        Ok(self.entry.control_in(control, u16::MAX)?)
+    }
+
+    pub(crate) fn make_control_transfer(self: &Arc<Self>) -> TransferHandle<super::TransferData> {
+        TransferHandle::new(super::TransferData::new(self.clone(), None, 0))
+    }
+
+    pub(crate) fn claim_interface(
+        self: &Arc<Self>,
+        interface_number: u8,
+    ) -> Result<Arc<VirtualInterface>, Error> {
+        self.check_connected()?;
+
+        let exists = self.active_configuration().is_some_and(|c| {
+            c.interfaces()
+                .any(|i| i.interface_number() == interface_number)
+        });
+        if !exists {
+            return Err(Error::new(ErrorKind::NotFound, "interface not found"));
+        }
+
+        if !self
+            .claimed_interfaces
+            .lock()
+            .unwrap()
+            .insert(interface_number)
+        {
+            return Err(Error::other("interface is already claimed"));
+        }
+
+        debug!("Claimed interface {interface_number} on {}", self.log_id);
+        Ok(Arc::new(VirtualInterface {
+            device: self.clone(),
+            interface_number,
+            alt_setting: AtomicU8::new(0),
+        }))
+    }
+
+    pub(crate) fn detach_and_claim_interface(
+        self: &Arc<Self>,
+        interface: u8,
+    ) -> Result<Arc<VirtualInterface>, Error> {
+        self.claim_interface(interface)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn detach_and_claim_interface_except(
+        self: &Arc<Self>,
+        interface: u8,
+        _except_driver: &str,
+    ) -> Result<Arc<VirtualInterface>, Error> {
+        self.claim_interface(interface)
+    }
+
+    /// Virtual devices have no kernel drivers.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn kernel_driver(&self, _interface: u8) -> Result<Option<String>, Error> {
+        self.check_connected().map(|()| None)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn detach_kernel_driver(&self, _interface: u8) -> Result<(), Error> {
+        self.check_connected()
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn detach_kernel_driver_if_attached(&self, _interface: u8) -> Result<bool, Error> {
+        self.check_connected().map(|()| false)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn attach_kernel_driver(&self, _interface: u8) -> Result<(), Error> {
+        self.check_connected()
+    }
+}
+
+pub(crate) struct VirtualInterface {
+    pub(crate) interface_number: u8,
+    pub(crate) device: Arc<VirtualDevice>,
+
+    /// Alternate setting most recently selected with `set_alt_setting`.
+    alt_setting: AtomicU8,
+}
+
+impl VirtualInterface {
+    pub(crate) fn make_transfer(
+        self: &Arc<Self>,
+        endpoint: u8,
+        ep_type: EndpointType,
+    ) -> TransferHandle<super::TransferData> {
+        if ep_type == EndpointType::Control {
+            assert!(endpoint == 0);
+            self.device.make_control_transfer()
+        } else {
+            TransferHandle::new(super::TransferData::new(
+                self.device.clone(),
+                Some(self.clone()),
+                endpoint,
+            ))
+        }
+    }
+
+    /// Streams can't be allocated on a virtual device, so transfers on a
+    /// nonzero `stream_id` fail.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn make_stream_transfer(
+        self: &Arc<Self>,
+        endpoint: u8,
+        stream_id: u32,
+    ) -> TransferHandle<super::TransferData> {
+        let mut t = super::TransferData::new(self.device.clone(), Some(self.clone()), endpoint);
+        t.stream_id = stream_id;
+        TransferHandle::new(t)
+    }
+
+    /// Transfers on a virtual device are never split, so this has no effect.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn set_bulk_split_size(&self, _size: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Zero-length packets aren't visible to a `MockHandler`, so this is
+    /// an ordinary bulk transfer.
+    pub(crate) fn make_zlp_transfer(
+        self: &Arc<Self>,
+        endpoint: u8,
+    ) -> TransferHandle<super::TransferData> {
+        self.make_transfer(endpoint, EndpointType::Bulk)
+    }
+
+    pub fn control_in_blocking(
+        &self,
+        control: Control,
+        data: &mut [u8],
+        timeout: Duration,
+    ) -> Result<usize, TransferError> {
+        self.device.control_in_blocking(control, data, timeout)
+    }
+
+    pub fn control_out_blocking(
+        &self,
+        control: Control,
+        data: &[u8],
+        timeout: Duration,
+    ) -> Result<usize, TransferError> {
+        self.device.control_out_blocking(control, data, timeout)
+    }
+
+    pub fn set_alt_setting(&self, alt_setting: u8) -> Result<(), Error> {
+        self.device.check_connected()?;
+
+        let exists = self.device.active_configuration().is_some_and(|c| {
+            c.interface_alt_settings().any(|i| {
+                i.interface_number() == self.interface_number
+                    && i.alternate_setting() == alt_setting
+            })
+        });
+        if !exists {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "alternate setting not found",
+            ));
+        }
+
+        debug!(
+            "Set interface {} alt setting to {alt_setting} on {}",
+            self.interface_number, self.device.log_id
+        );
+        self.alt_setting.store(alt_setting, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn get_alt_setting(&self) -> u8 {
+        self.alt_setting.load(Ordering::Relaxed)
+    }
+
+    pub fn clear_halt(&self, endpoint: u8) -> Result<(), Error> {
+        debug!(
+            "Clear halt, endpoint {endpoint:02x} on {}",
+            self.device.log_id
+        );
+        self.device.check_connected()
+    }
+
+    /// Only transfers held by `MockHandler::nak_in` are ever pending.
+    pub fn cancel_all(&self, endpoint: u8) -> Result<(), Error> {
+        debug!(
+            "Cancel all transfers, endpoint {endpoint:02x} on {}",
+            self.device.log_id
+        );
+        self.device.check_connected()?;
+        self.device.entry.cancel_endpoint(endpoint);
+        Ok(())
+    }
+
+    pub fn alloc_streams(&self, _endpoints: &[u8], _num_streams: u32) -> Result<u32, Error> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "bulk streams not supported by the mock backend",
+        ))
+    }
+
+    pub fn free_streams(&self, _endpoints: &[u8]) -> Result<(), Error> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "bulk streams not supported by the mock backend",
+        ))
+    }
+
+    pub fn release(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Drop for VirtualInterface {
+    fn drop(&mut self) {
+        self.device
+            .claimed_interfaces
+            .lock()
+            .unwrap()
+            .remove(&self.interface_number);
+    }
+}
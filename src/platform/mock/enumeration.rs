@@ -0,0 +1,74 @@
+use std::io::ErrorKind;
+
+use crate::{DeviceInfo, DeviceSummary, Error};
+
+use super::{connected_entries, entry_by_id};
+
+pub fn list_devices() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    Ok(connected_entries()
+        .into_iter()
+        .filter(|e| !e.info.root_hub)
+        .map(|e| e.info.clone()))
+}
+
+pub fn list_devices_with_errors(
+    _retries: u8,
+    _lazy_strings: bool,
+    _partial: bool,
+    configuration_descriptors: bool,
+    root_hubs: bool,
+) -> Result<impl Iterator<Item = Result<DeviceInfo, Error>>, Error> {
+    Ok(connected_entries()
+        .into_iter()
+        .filter(move |e| root_hubs || !e.info.root_hub)
+        .map(move |e| {
+            let mut info = e.info.clone();
+            if configuration_descriptors {
+                info.configuration_descriptors = e.configurations.concat();
+            }
+            Ok(info)
+        }))
+}
+
+pub fn list_devices_with_class(class: u8) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    Ok(list_devices()?
+        .filter(move |d| d.class() == class || d.interfaces().any(|i| i.class() == class)))
+}
+
+pub fn list_devices_on_bus(bus_id: &str) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    let bus_id = bus_id.to_owned();
+    Ok(list_devices()?.filter(move |d| d.bus_id() == bus_id))
+}
+
+pub fn list_device_ids() -> Result<impl Iterator<Item = DeviceSummary>, Error> {
+    Ok(list_devices()?.map(|d| DeviceSummary {
+        id: d.id(),
+        device_address: d.device_address(),
+        vendor_id: d.vendor_id(),
+        product_id: d.product_id(),
+    }))
+}
+
+pub(crate) fn probe_device_id(id: u64) -> Option<DeviceInfo> {
+    entry_by_id(id).map(|e| e.info.clone())
+}
+
+/// Virtual devices are all attached directly to a root hub.
+pub(crate) fn probe_parent(_device: &DeviceInfo) -> Option<DeviceInfo> {
+    None
+}
+
+/// Virtual hubs have no downstream ports to list.
+#[cfg(target_os = "linux")]
+pub(crate) fn probe_hub_ports(_hub: &DeviceInfo) -> Result<Vec<(u8, Option<DeviceInfo>)>, Error> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "hub ports are not supported by the mock backend",
+    ))
+}
+
+pub(crate) fn is_suspended(id: u64) -> Result<bool, Error> {
+    entry_by_id(id)
+        .map(|_| false)
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, "device not connected"))
+}
@@ -0,0 +1,64 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    task::{Context, Poll, Waker},
+};
+
+use slab::Slab;
+
+use crate::{
+    hotplug::{HotplugEvent, HotplugFilter},
+    Error,
+};
+
+struct Watcher {
+    events: VecDeque<HotplugEvent>,
+    waker: Option<Waker>,
+}
+
+static WATCHERS: Mutex<Slab<Watcher>> = Mutex::new(Slab::new());
+
+/// Queue an event for every open `VirtualHotplugWatch`.
+pub(super) fn broadcast(event: impl Fn() -> HotplugEvent) {
+    let mut watchers = WATCHERS.lock().unwrap();
+    for (_, watcher) in watchers.iter_mut() {
+        watcher.events.push_back(event());
+        if let Some(waker) = watcher.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+pub(crate) struct VirtualHotplugWatch(usize);
+
+impl VirtualHotplugWatch {
+    pub(crate) fn new() -> Result<Self, Error> {
+        let id = WATCHERS.lock().unwrap().insert(Watcher {
+            events: VecDeque::new(),
+            waker: None,
+        });
+        Ok(VirtualHotplugWatch(id))
+    }
+
+    pub fn poll_next(
+        &mut self,
+        cx: &mut Context,
+        _filters: &[HotplugFilter],
+    ) -> Poll<HotplugEvent> {
+        let mut watchers = WATCHERS.lock().unwrap();
+        let watcher = &mut watchers[self.0];
+        match watcher.events.pop_front() {
+            Some(event) => Poll::Ready(event),
+            None => {
+                watcher.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for VirtualHotplugWatch {
+    fn drop(&mut self) {
+        WATCHERS.lock().unwrap().remove(self.0);
+    }
+}
@@ -0,0 +1,238 @@
+mod transfer;
+pub(crate) use transfer::TransferData;
+
+mod enumeration;
+#[cfg(target_os = "linux")]
+pub(crate) use enumeration::probe_hub_ports;
+pub(crate) use enumeration::{
+    is_suspended, list_device_ids, list_devices, list_devices_on_bus, list_devices_with_class,
+    list_devices_with_errors, probe_device_id, probe_parent,
+};
+
+mod device;
+pub(crate) use device::VirtualDevice as Device;
+pub(crate) use device::VirtualInterface as Interface;
+
+mod hotplug;
+pub(crate) use hotplug::VirtualHotplugWatch as HotplugWatch;
+
+use std::{
+    ffi::c_void,
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::{
+    descriptors::{DESCRIPTOR_TYPE_CONFIGURATION, DESCRIPTOR_TYPE_DEVICE, DESCRIPTOR_TYPE_STRING},
+    hotplug::HotplugEvent,
+    mock::MockHandler,
+    transfer::{notify_completion, Control, ControlType, Recipient, TransferError},
+    DeviceInfo,
+};
+
+/// Device ID is assigned sequentially when a virtual device is connected
+pub(crate) type DeviceId = u64;
+
+/// `bRequest` value for the standard `GET_DESCRIPTOR` request.
+const REQUEST_GET_DESCRIPTOR: u8 = 0x06;
+
+/// A virtual device registered with [`crate::mock::MockDevice::connect`].
+pub(crate) struct MockEntry {
+    pub(crate) info: DeviceInfo,
+    pub(crate) device_descriptor: Vec<u8>,
+    pub(crate) configurations: Vec<Vec<u8>>,
+    pub(crate) strings: Vec<(u8, String)>,
+    pub(crate) handler: Arc<dyn MockHandler>,
+    pub(crate) connected: AtomicBool,
+
+    /// IN transfers left pending because the handler NAKs their endpoint.
+    pub(crate) held: Mutex<Vec<HeldTransfer>>,
+}
+
+/// A pending transfer, completed by notifying `callback_data`.
+pub(crate) struct HeldTransfer {
+    endpoint: u8,
+    callback_data: *mut c_void,
+}
+
+unsafe impl Send for HeldTransfer {}
+
+/// Virtual devices that are currently connected.
+static DEVICES: Mutex<Vec<Arc<MockEntry>>> = Mutex::new(Vec::new());
+
+/// Set when the first virtual device is connected.
+static IN_USE: AtomicBool = AtomicBool::new(false);
+
+/// Whether a virtual device has ever been connected, after which devices
+/// are listed and watched from this backend instead of the OS.
+pub(crate) fn in_use() -> bool {
+    IN_USE.load(Ordering::SeqCst)
+}
+
+pub(crate) fn connect(entry: MockEntry) {
+    let info = entry.info.clone();
+    IN_USE.store(true, Ordering::SeqCst);
+    DEVICES.lock().unwrap().push(Arc::new(entry));
+    hotplug::broadcast(|| HotplugEvent::Connected(info.clone()));
+}
+
+pub(crate) fn disconnect(id: DeviceId) {
+    let removed = {
+        let mut devices = DEVICES.lock().unwrap();
+        let pos = devices.iter().position(|e| e.info.mock_id == Some(id));
+        pos.map(|pos| devices.remove(pos))
+    };
+
+    if let Some(entry) = removed {
+        {
+            // Under the lock so no transfer is held after those below
+            // are completed.
+            let _held = entry.held.lock().unwrap();
+            entry.connected.store(false, Ordering::SeqCst);
+        }
+        entry.complete_held(|_| true);
+        hotplug::broadcast(|| {
+            HotplugEvent::Disconnected(crate::DeviceId(super::DeviceId::Mock(id)))
+        });
+    }
+}
+
+fn connected_entries() -> Vec<Arc<MockEntry>> {
+    DEVICES.lock().unwrap().clone()
+}
+
+fn entry_by_id(id: DeviceId) -> Option<Arc<MockEntry>> {
+    DEVICES
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|e| e.info.mock_id == Some(id))
+        .cloned()
+}
+
+impl MockEntry {
+    fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn naks_in(&self, endpoint: u8) -> bool {
+        self.handler.nak_in(endpoint)
+    }
+
+    /// Leave a transfer pending until it is cancelled or the device is
+    /// disconnected. Returns `false` if the device is already disconnected.
+    pub(crate) fn hold(&self, endpoint: u8, callback_data: *mut c_void) -> bool {
+        let mut held = self.held.lock().unwrap();
+        if !self.is_connected() {
+            return false;
+        }
+        held.push(HeldTransfer {
+            endpoint,
+            callback_data,
+        });
+        true
+    }
+
+    /// Complete the held transfers selected by `f`.
+    pub(crate) fn complete_held(&self, f: impl Fn(&HeldTransfer) -> bool) {
+        let completed: Vec<HeldTransfer> = {
+            let mut held = self.held.lock().unwrap();
+            let (completed, kept) = mem::take(&mut *held).into_iter().partition(f);
+            *held = kept;
+            completed
+        };
+        for t in completed {
+            // SAFETY: removed from `held`, so this is the only completion
+            unsafe { notify_completion::<crate::platform::TransferData>(t.callback_data) }
+        }
+    }
+
+    /// Complete a held transfer on cancellation.
+    pub(crate) fn cancel_held(&self, callback_data: *mut c_void) {
+        self.complete_held(|t| t.callback_data == callback_data);
+    }
+
+    /// Complete the held transfers on an endpoint.
+    pub(crate) fn cancel_endpoint(&self, endpoint: u8) {
+        self.complete_held(|t| t.endpoint == endpoint);
+    }
+
+    /// Answer standard descriptor requests from the registered descriptors,
+    /// and pass everything else to the handler.
+    pub(crate) fn control_in(
+        &self,
+        control: Control,
+        length: u16,
+    ) -> Result<Vec<u8>, TransferError> {
+        if !self.is_connected() {
+            return Err(TransferError::Disconnected);
+        }
+
+        let mut data = if control.control_type == ControlType::Standard
+            && control.recipient == Recipient::Device
+            && control.request == REQUEST_GET_DESCRIPTOR
+        {
+            let [index, desc_type] = control.value.to_le_bytes();
+            match desc_type {
+                DESCRIPTOR_TYPE_DEVICE => self.device_descriptor.clone(),
+                DESCRIPTOR_TYPE_CONFIGURATION => self
+                    .configurations
+                    .get(index as usize)
+                    .cloned()
+                    .ok_or(TransferError::Stall)?,
+                DESCRIPTOR_TYPE_STRING if index == 0 => {
+                    vec![4, DESCRIPTOR_TYPE_STRING, 0x09, 0x04]
+                }
+                DESCRIPTOR_TYPE_STRING => self
+                    .strings
+                    .iter()
+                    .find(|(i, _)| *i == index)
+                    .map(|(_, s)| encode_string_descriptor(s))
+                    .ok_or(TransferError::Stall)?,
+                _ => self.handler.control_in(control, length)?,
+            }
+        } else {
+            self.handler.control_in(control, length)?
+        };
+
+        data.truncate(length as usize);
+        Ok(data)
+    }
+
+    pub(crate) fn control_out(&self, control: Control, data: &[u8]) -> Result<(), TransferError> {
+        if !self.is_connected() {
+            return Err(TransferError::Disconnected);
+        }
+        self.handler.control_out(control, data)
+    }
+
+    pub(crate) fn transfer_in(
+        &self,
+        endpoint: u8,
+        length: usize,
+    ) -> Result<Vec<u8>, TransferError> {
+        if !self.is_connected() {
+            return Err(TransferError::Disconnected);
+        }
+        self.handler.transfer_in(endpoint, length)
+    }
+
+    pub(crate) fn transfer_out(&self, endpoint: u8, data: &[u8]) -> Result<(), TransferError> {
+        if !self.is_connected() {
+            return Err(TransferError::Disconnected);
+        }
+        self.handler.transfer_out(endpoint, data)
+    }
+}
+
+fn encode_string_descriptor(s: &str) -> Vec<u8> {
+    let mut buf = vec![0, DESCRIPTOR_TYPE_STRING];
+    for c in s.encode_utf16().take(126) {
+        buf.extend_from_slice(&c.to_le_bytes());
+    }
+    buf[0] = buf.len() as u8;
+    buf
+}
@@ -0,0 +1,346 @@
+use std::{ffi::c_void, mem, sync::Arc};
+
+use log::debug;
+
+use crate::transfer::{
+    notify_completion, CallerBuffer, Completion, Control, ControlIn, ControlOut, FilledBuffer,
+    IsoOutBuffer, IsoPacket, IsoRequestBuffer, IsoResponse, PlatformSubmit, PlatformTransfer,
+    RequestBuffer, ResponseBuffer, TransferBuffer, TransferError,
+};
+
+/// Frees a caller-provided buffer from its raw parts.
+type DropFn = unsafe fn(*mut u8, usize);
+
+/// Transfers on a virtual device complete during `submit` with the result
+/// returned by the device's `MockHandler`, unless held by
+/// `MockHandler::nak_in`.
+pub struct TransferData {
+    endpoint_addr: u8,
+    pub(super) stream_id: u32,
+    buf: Vec<u8>,
+
+    /// Per-packet results of a completed isochronous transfer.
+    iso_packets: Vec<IsoPacket>,
+
+    /// Set while the transfer is held, so that its status is determined
+    /// when it completes.
+    held: bool,
+    callback_data: *mut c_void,
+
+    /// Caller-provided buffer and the function that frees it, while it is
+    /// held by a completed transfer.
+    caller_buf: Option<(*mut u8, usize, DropFn)>,
+    actual_len: usize,
+    status: Result<(), TransferError>,
+    device: Arc<super::Device>,
+    _interface: Option<Arc<super::Interface>>,
+}
+
+unsafe impl Send for TransferData {}
+
+impl Drop for TransferData {
+    fn drop(&mut self) {
+        if let Some((ptr, len, drop_fn)) = self.caller_buf.take() {
+            unsafe { drop_fn(ptr, len) }
+        }
+    }
+}
+
+impl TransferData {
+    pub(super) fn new(
+        device: Arc<super::Device>,
+        interface: Option<Arc<super::Interface>>,
+        endpoint_addr: u8,
+    ) -> TransferData {
+        TransferData {
+            endpoint_addr,
+            stream_id: 0,
+            buf: Vec::new(),
+            iso_packets: Vec::new(),
+            held: false,
+            callback_data: std::ptr::null_mut(),
+            caller_buf: None,
+            actual_len: 0,
+            status: Ok(()),
+            device,
+            _interface: interface,
+        }
+    }
+
+    /// Request up to `len` bytes from the handler.
+    fn read_in(&mut self, len: usize) -> (Result<(), TransferError>, Vec<u8>) {
+        match self.device.entry.transfer_in(self.endpoint_addr, len) {
+            Ok(mut data) if data.len() > len => {
+                data.truncate(len);
+                (Err(TransferError::Overflow), data)
+            }
+            Ok(data) => (Ok(()), data),
+            Err(e) => (Err(e), Vec::new()),
+        }
+    }
+
+    /// Fail a transfer on a stream, as streams can't be allocated.
+    ///
+    /// SAFETY: `callback_data` must be the pointer passed to `submit`, and
+    /// if this returns `true`, the transfer must not be accessed again.
+    unsafe fn fail_stream(&mut self, callback_data: *mut c_void) -> bool {
+        if self.stream_id == 0 {
+            return false;
+        }
+        self.actual_len = 0;
+        self.complete(Err(TransferError::Unknown), callback_data);
+        true
+    }
+
+    /// Leave an IN transfer pending if the handler NAKs its endpoint.
+    ///
+    /// If this returns `true`, the transfer must not be accessed again
+    /// until it completes.
+    fn hold(&mut self, callback_data: *mut c_void) -> bool {
+        if !self.device.entry.naks_in(self.endpoint_addr) {
+            return false;
+        }
+        self.actual_len = 0;
+        self.held = true;
+        self.callback_data = callback_data;
+        if self.device.entry.hold(self.endpoint_addr, callback_data) {
+            debug!(
+                "Holding transfer on endpoint {ep:02x} of {dev}",
+                ep = self.endpoint_addr,
+                dev = self.device.log_id
+            );
+            true
+        } else {
+            self.held = false;
+            false
+        }
+    }
+
+    /// Status of a completed transfer. A held transfer was completed by
+    /// either cancellation or disconnection.
+    fn take_status(&mut self) -> Result<(), TransferError> {
+        if mem::take(&mut self.held) {
+            if self.device.entry.is_connected() {
+                Err(TransferError::Cancelled)
+            } else {
+                Err(TransferError::Disconnected)
+            }
+        } else {
+            self.status
+        }
+    }
+
+    /// SAFETY: `callback_data` must be the pointer passed to `submit`
+    unsafe fn complete(&mut self, status: Result<(), TransferError>, callback_data: *mut c_void) {
+        debug!(
+            "Completed transfer on endpoint {ep:02x} of {dev}: {status:?}, {len} bytes",
+            ep = self.endpoint_addr,
+            dev = self.device.log_id,
+            len = self.actual_len
+        );
+        self.status = status;
+        notify_completion::<crate::platform::TransferData>(callback_data)
+    }
+}
+
+impl PlatformTransfer for TransferData {
+    fn cancel(&self) {
+        // Other transfers complete before `submit` returns, so only held
+        // transfers are ever pending.
+        self.device.entry.cancel_held(self.callback_data);
+    }
+}
+
+impl PlatformSubmit<Vec<u8>> for TransferData {
+    unsafe fn submit(&mut self, data: Vec<u8>, callback_data: *mut c_void) {
+        assert!(self.endpoint_addr & 0x80 == 0);
+        self.buf = data;
+        if self.fail_stream(callback_data) {
+            return;
+        }
+
+        let status = self
+            .device
+            .entry
+            .transfer_out(self.endpoint_addr, &self.buf);
+        self.actual_len = if status.is_ok() { self.buf.len() } else { 0 };
+        self.complete(status, callback_data);
+    }
+
+    unsafe fn take_completed(&mut self) -> Completion<ResponseBuffer> {
+        let mut buf = mem::take(&mut self.buf);
+        buf.clear();
+        let data = ResponseBuffer::from_vec(buf, self.actual_len);
+        Completion {
+            data,
+            status: self.status,
+        }
+    }
+}
+
+impl<B: TransferBuffer> PlatformSubmit<CallerBuffer<B>> for TransferData {
+    unsafe fn submit(&mut self, data: CallerBuffer<B>, callback_data: *mut c_void) {
+        assert!(self.endpoint_addr & 0x80 == 0x80);
+        let (ptr, len) = data.into_raw_parts();
+        self.caller_buf = Some((ptr, len, crate::transfer::drop_transfer_buffer_parts::<B>));
+        if self.hold(callback_data) {
+            return;
+        }
+
+        let (status, response) = self.read_in(len);
+        // SAFETY: `into_raw_parts` gives a buffer valid for `len` bytes
+        let buf = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
+        buf[..response.len()].copy_from_slice(&response);
+        self.actual_len = response.len();
+        self.complete(status, callback_data);
+    }
+
+    unsafe fn take_completed(&mut self) -> Completion<FilledBuffer<B>> {
+        let (ptr, len, _) = self
+            .caller_buf
+            .take()
+            .expect("transfer has a caller buffer");
+        // SAFETY: the parts came from `submit`
+        let data = unsafe { FilledBuffer::from_raw_parts(ptr, len, self.actual_len) };
+        Completion {
+            data,
+            status: self.take_status(),
+        }
+    }
+}
+
+impl PlatformSubmit<RequestBuffer> for TransferData {
+    unsafe fn submit(&mut self, data: RequestBuffer, callback_data: *mut c_void) {
+        assert!(self.endpoint_addr & 0x80 == 0x80);
+        let (mut buf, len) = data.into_vec();
+        buf.clear();
+        self.buf = buf;
+        if self.fail_stream(callback_data) || self.hold(callback_data) {
+            return;
+        }
+
+        let (status, response) = self.read_in(len);
+        self.buf.extend_from_slice(&response);
+        self.actual_len = self.buf.len();
+        self.complete(status, callback_data);
+    }
+
+    unsafe fn take_completed(&mut self) -> Completion<Vec<u8>> {
+        Completion {
+            data: mem::take(&mut self.buf),
+            status: self.take_status(),
+        }
+    }
+}
+
+impl PlatformSubmit<ControlIn> for TransferData {
+    unsafe fn submit(&mut self, data: ControlIn, callback_data: *mut c_void) {
+        let control = Control {
+            control_type: data.control_type,
+            recipient: data.recipient,
+            request: data.request,
+            value: data.value,
+            index: data.index,
+        };
+        let (status, buf) = match self.device.entry.control_in(control, data.length) {
+            Ok(buf) => (Ok(()), buf),
+            Err(e) => (Err(e), Vec::new()),
+        };
+        self.actual_len = buf.len();
+        self.buf = buf;
+        self.complete(status, callback_data);
+    }
+
+    unsafe fn take_completed(&mut self) -> Completion<Vec<u8>> {
+        Completion {
+            data: mem::take(&mut self.buf),
+            status: self.status,
+        }
+    }
+}
+
+impl PlatformSubmit<ControlOut<'_>> for TransferData {
+    unsafe fn submit(&mut self, data: ControlOut<'_>, callback_data: *mut c_void) {
+        let control = Control {
+            control_type: data.control_type,
+            recipient: data.recipient,
+            request: data.request,
+            value: data.value,
+            index: data.index,
+        };
+        let status = self.device.entry.control_out(control, data.data);
+        self.actual_len = if status.is_ok() { data.data.len() } else { 0 };
+        self.complete(status, callback_data);
+    }
+
+    unsafe fn take_completed(&mut self) -> Completion<ResponseBuffer> {
+        let data = ResponseBuffer::from_vec(mem::take(&mut self.buf), self.actual_len);
+        Completion {
+            data,
+            status: self.status,
+        }
+    }
+}
+
+/// Each packet is a separate call to the handler. Isochronous endpoints
+/// don't NAK, so these transfers are never held.
+impl PlatformSubmit<IsoRequestBuffer> for TransferData {
+    unsafe fn submit(&mut self, data: IsoRequestBuffer, callback_data: *mut c_void) {
+        assert!(self.endpoint_addr & 0x80 == 0x80);
+        let mut buf = data.buf;
+        self.iso_packets.clear();
+        for i in 0..data.num_packets {
+            let offset = i * data.packet_size;
+            let (status, response) = self.read_in(data.packet_size);
+            buf[offset..offset + response.len()].copy_from_slice(&response);
+            self.iso_packets.push(IsoPacket {
+                offset,
+                requested_length: data.packet_size,
+                actual_length: response.len(),
+                status,
+            });
+        }
+        self.buf = buf;
+        self.actual_len = self.iso_packets.iter().map(|p| p.actual_length).sum();
+        self.complete(Ok(()), callback_data);
+    }
+
+    unsafe fn take_completed(&mut self) -> Completion<IsoResponse> {
+        Completion {
+            data: IsoResponse {
+                buf: mem::take(&mut self.buf),
+                packets: mem::take(&mut self.iso_packets),
+            },
+            status: self.status,
+        }
+    }
+}
+
+impl PlatformSubmit<IsoOutBuffer> for TransferData {
+    unsafe fn submit(&mut self, data: IsoOutBuffer, callback_data: *mut c_void) {
+        assert!(self.endpoint_addr & 0x80 == 0);
+        self.iso_packets.clear();
+        for (i, packet) in data.buf.chunks(data.packet_size).enumerate() {
+            let status = self.device.entry.transfer_out(self.endpoint_addr, packet);
+            self.iso_packets.push(IsoPacket {
+                offset: i * data.packet_size,
+                requested_length: packet.len(),
+                actual_length: if status.is_ok() { packet.len() } else { 0 },
+                status,
+            });
+        }
+        self.buf = data.buf;
+        self.actual_len = self.iso_packets.iter().map(|p| p.actual_length).sum();
+        self.complete(Ok(()), callback_data);
+    }
+
+    unsafe fn take_completed(&mut self) -> Completion<IsoResponse> {
+        Completion {
+            data: IsoResponse {
+                buf: mem::take(&mut self.buf),
+                packets: mem::take(&mut self.iso_packets),
+            },
+            status: self.status,
+        }
+    }
+}
@@ -1,17 +1,41 @@
 #[cfg(target_os = "linux")]
-mod linux_usbfs;
+pub(crate) mod linux_usbfs;
 
 #[cfg(target_os = "linux")]
-pub use linux_usbfs::*;
+pub(crate) use linux_usbfs as os;
 
 #[cfg(target_os = "windows")]
-mod windows_winusb;
+pub(crate) mod windows_winusb;
 
 #[cfg(target_os = "windows")]
-pub use windows_winusb::*;
+pub(crate) use windows_winusb as os;
 
 #[cfg(target_os = "macos")]
-mod macos_iokit;
+pub(crate) mod macos_iokit;
 
 #[cfg(target_os = "macos")]
-pub use macos_iokit::*;
+pub(crate) use macos_iokit as os;
+
+#[cfg(not(feature = "mock"))]
+pub use os::*;
+
+#[cfg(feature = "mock")]
+pub(crate) mod mock;
+
+#[cfg(feature = "mock")]
+mod dispatch;
+
+#[cfg(feature = "mock")]
+pub use dispatch::*;
+
+/// The OS backend's device, for methods that expose its raw handles.
+#[cfg(all(target_os = "macos", not(feature = "mock")))]
+pub(crate) fn os_device(device: &Device) -> &Device {
+    device
+}
+
+/// The OS backend's interface, for methods that expose its raw handles.
+#[cfg(all(any(target_os = "windows", target_os = "macos"), not(feature = "mock")))]
+pub(crate) fn os_interface(interface: &Interface) -> &Interface {
+    interface
+}
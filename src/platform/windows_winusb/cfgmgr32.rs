@@ -6,18 +6,20 @@ use windows_sys::{
     Win32::{
         Devices::{
             DeviceAndDriverInstallation::{
-                CM_Get_Child, CM_Get_DevNode_PropertyW, CM_Get_Device_Interface_ListW,
-                CM_Get_Device_Interface_List_SizeW, CM_Get_Device_Interface_PropertyW,
-                CM_Get_Parent, CM_Get_Sibling, CM_Locate_DevNodeW, CM_Open_DevNode_Key,
-                RegDisposition_OpenExisting, CM_GET_DEVICE_INTERFACE_LIST_PRESENT,
-                CM_LOCATE_DEVNODE_PHANTOM, CM_REGISTRY_HARDWARE, CR_BUFFER_SMALL, CR_SUCCESS,
+                CM_Get_Child, CM_Get_DevNode_PropertyW, CM_Get_DevNode_Status,
+                CM_Get_Device_Interface_ListW, CM_Get_Device_Interface_List_SizeW,
+                CM_Get_Device_Interface_PropertyW, CM_Get_Parent, CM_Get_Sibling,
+                CM_Locate_DevNodeW, CM_Open_DevNode_Key, RegDisposition_OpenExisting,
+                CM_GET_DEVICE_INTERFACE_LIST_PRESENT, CM_LOCATE_DEVNODE_PHANTOM,
+                CM_REGISTRY_HARDWARE, CR_BUFFER_SMALL, CR_SUCCESS,
             },
             Properties::{
-                DEVPKEY_Device_InstanceId, DEVPROPKEY, DEVPROPTYPE, DEVPROP_TYPE_STRING,
-                DEVPROP_TYPE_STRING_LIST, DEVPROP_TYPE_UINT32,
+                DEVPKEY_Device_InstanceId, DEVPROPKEY, DEVPROPTYPE, DEVPROP_TYPE_BINARY,
+                DEVPROP_TYPE_STRING, DEVPROP_TYPE_STRING_LIST, DEVPROP_TYPE_UINT32,
             },
         },
         Foundation::INVALID_HANDLE_VALUE,
+        System::Power::CM_POWER_DATA,
         System::Registry::KEY_READ,
     },
 };
@@ -69,6 +71,13 @@ impl DevInst {
             .expect("device should always have instance ID")
     }
 
+    /// Check whether the device node is still present in the device tree.
+    pub fn is_present(&self) -> bool {
+        let mut status = 0;
+        let mut problem = 0;
+        unsafe { CM_Get_DevNode_Status(&mut status, &mut problem, self.0, 0) == CR_SUCCESS }
+    }
+
     pub fn parent(&self) -> Option<DevInst> {
         let mut out = 0;
         let cr = unsafe { CM_Get_Parent(&mut out, self.0, 0) };
@@ -160,6 +169,17 @@ impl PropertyType for u32 {
     }
 }
 
+impl PropertyType for CM_POWER_DATA {
+    const PROPTYPE: DEVPROPTYPE = DEVPROP_TYPE_BINARY;
+    type Buffer = CM_POWER_DATA;
+    fn empty_buffer() -> CM_POWER_DATA {
+        unsafe { mem::zeroed() }
+    }
+    fn from_buffer(b: &Self::Buffer) -> Self {
+        *b
+    }
+}
+
 impl PropertyType for WCString {
     const PROPTYPE: DEVPROPTYPE = DEVPROP_TYPE_STRING;
     type Buffer = [u16; 1024];
@@ -8,22 +8,25 @@ use std::{
         prelude::OwnedHandle,
     },
     ptr::null_mut,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
 
 use log::{debug, error, info, warn};
 use windows_sys::Win32::{
     Devices::Usb::{
-        WinUsb_ControlTransfer, WinUsb_Free, WinUsb_GetAssociatedInterface, WinUsb_Initialize,
-        WinUsb_ResetPipe, WinUsb_SetCurrentAlternateSetting, WinUsb_SetPipePolicy,
-        PIPE_TRANSFER_TIMEOUT, WINUSB_INTERFACE_HANDLE, WINUSB_SETUP_PACKET,
+        WinUsb_AbortPipe, WinUsb_ControlTransfer, WinUsb_Free, WinUsb_GetAssociatedInterface,
+        WinUsb_Initialize, WinUsb_ResetPipe, WinUsb_SetCurrentAlternateSetting,
+        WinUsb_SetPipePolicy, PIPE_TRANSFER_TIMEOUT, WINUSB_INTERFACE_HANDLE, WINUSB_SETUP_PACKET,
     },
-    Foundation::{GetLastError, FALSE, TRUE},
+    Foundation::{GetLastError, ERROR_ACCESS_DENIED, FALSE, TRUE},
 };
 
 use crate::{
-    descriptors::{validate_config_descriptor, DESCRIPTOR_TYPE_CONFIGURATION},
+    descriptors::{validate_config_descriptor, Configuration, DESCRIPTOR_TYPE_CONFIGURATION},
     transfer::{Control, Direction, EndpointType, Recipient, TransferError, TransferHandle},
     DeviceInfo, Error,
 };
@@ -41,18 +44,21 @@ pub(crate) struct WindowsDevice {
     config_descriptors: Vec<Vec<u8>>,
     active_config: u8,
     devinst: DevInst,
+    pub(crate) log_id: String,
     handles: Mutex<BTreeMap<u8, WinusbFileHandle>>,
 }
 
 impl WindowsDevice {
     pub(crate) fn from_device_info(d: &DeviceInfo) -> Result<Arc<WindowsDevice>, Error> {
-        debug!("Creating device for {:?}", d.instance_id);
+        let log_id = d.log_id();
+        debug!("Creating device {log_id} for {:?}", d.instance_id);
 
         // Look up the device again in case the DeviceInfo is stale. In
         // particular, don't trust its `port_number` because another device
         // might now be connected to that port, and we'd get its descriptors
         // instead.
-        let hub_port = HubPort::by_child_devinst(d.devinst)?;
+        let devinst = super::device_devinst(d)?;
+        let hub_port = HubPort::by_child_devinst(devinst)?;
         let connection_info = hub_port.get_info()?;
         let num_configurations = connection_info.device_desc.bNumConfigurations;
 
@@ -62,7 +68,7 @@ impl WindowsDevice {
                 match res {
                     Ok(v) => validate_config_descriptor(&v[..]).map(|_| v),
                     Err(e) => {
-                        error!("Failed to read config descriptor {}: {}", i, e);
+                        error!("Failed to read config descriptor {i} of {log_id}: {e}");
                         None
                     }
                 }
@@ -72,7 +78,8 @@ impl WindowsDevice {
         Ok(Arc::new(WindowsDevice {
             config_descriptors,
             active_config: connection_info.active_config,
-            devinst: d.devinst,
+            devinst,
+            log_id,
             handles: Mutex::new(BTreeMap::new()),
         }))
     }
@@ -81,6 +88,13 @@ impl WindowsDevice {
         self.active_config
     }
 
+    pub(crate) fn get_configuration(&self) -> Result<u8, Error> {
+        // WinUSB doesn't allow standard device requests, but the hub driver
+        // tracks the configuration it set.
+        let connection_info = HubPort::by_child_devinst(self.devinst)?.get_info()?;
+        Ok(connection_info.active_config)
+    }
+
     pub(crate) fn configuration_descriptors(&self) -> impl Iterator<Item = &[u8]> {
         self.config_descriptors.iter().map(|d| &d[..])
     }
@@ -98,14 +112,31 @@ impl WindowsDevice {
         desc_index: u8,
         language_id: u16,
     ) -> Result<Vec<u8>, Error> {
-        HubPort::by_child_devinst(self.devinst)?.get_descriptor(desc_type, desc_index, language_id)
+        HubPort::by_child_devinst(self.devinst)?
+            .get_descriptor(desc_type, desc_index, language_id)
+            .map_err(|e| {
+                // The hub can't read descriptors from a suspended device, and
+                // only reports a generic failure.
+                if super::is_suspended(self.devinst).unwrap_or(false) {
+                    Error::other("device is suspended")
+                } else {
+                    e
+                }
+            })
+    }
+
+    pub(crate) fn is_suspended(&self) -> Result<bool, Error> {
+        super::is_suspended(self.devinst)
+    }
+
+    /// Check whether the device has been removed from the system.
+    pub(crate) fn is_disconnected(&self) -> bool {
+        !self.devinst.is_present()
     }
 
     pub(crate) fn reset(&self) -> Result<(), Error> {
-        Err(io::Error::new(
-            ErrorKind::Unsupported,
-            "reset not supported by WinUSB",
-        ))
+        // WinUSB has no device reset, so cycle the port via the parent hub.
+        HubPort::by_child_devinst(self.devinst)?.cycle()
     }
 
     pub(crate) fn claim_interface(
@@ -204,7 +235,17 @@ pub(crate) struct WinusbFileHandle {
 
 impl WinusbFileHandle {
     fn new(path: &WCStr, first_interface: u8) -> Result<Self, Error> {
-        let handle = create_file(&path)?;
+        let handle = create_file(&path).map_err(|e| {
+            if e.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32) {
+                // WinUSB only allows one handle to the device at a time
+                Error::new(
+                    ErrorKind::PermissionDenied,
+                    "access denied opening WinUSB device; it may be open in another program",
+                )
+            } else {
+                e
+            }
+        })?;
         super::events::register(&handle)?;
 
         let winusb_handle = unsafe {
@@ -273,6 +314,8 @@ impl WinusbFileHandle {
             interface_number,
             first_interface_number: self.first_interface,
             winusb_handle,
+            alt_setting: AtomicU8::new(0),
+            released: false,
         }))
     }
 }
@@ -295,6 +338,12 @@ pub(crate) struct WindowsInterface {
     pub(crate) first_interface_number: u8,
     pub(crate) interface_number: u8,
     pub(crate) winusb_handle: WINUSB_INTERFACE_HANDLE,
+
+    /// Alternate setting most recently selected with `set_alt_setting`.
+    alt_setting: AtomicU8,
+
+    /// Set by `release` so the interface isn't released again on drop.
+    released: bool,
 }
 
 unsafe impl Send for WindowsInterface {}
@@ -302,16 +351,38 @@ unsafe impl Sync for WindowsInterface {}
 
 impl Drop for WindowsInterface {
     fn drop(&mut self) {
+        if !self.released {
+            if let Err(e) = self.release_interface() {
+                error!(
+                    "Failed to release interface {} on {}: {e}",
+                    self.interface_number, self.device.log_id
+                );
+            }
+        }
+    }
+}
+
+impl WindowsInterface {
+    pub fn release(mut self) -> Result<(), Error> {
+        self.released = true;
+        self.release_interface()
+    }
+
+    fn release_interface(&mut self) -> Result<(), Error> {
         // The WinUSB handle for the first interface is owned by WinusbFileHandle
         // because it is used to open subsequent interfaces.
         let is_first_interface = self.interface_number == self.first_interface_number;
+        let mut res = Ok(());
         if !is_first_interface {
             log::debug!(
-                "Closing WinUSB handle for associated interface {}",
-                self.interface_number
+                "Closing WinUSB handle for associated interface {} on {}",
+                self.interface_number,
+                self.device.log_id
             );
             unsafe {
-                WinUsb_Free(self.winusb_handle);
+                if WinUsb_Free(self.winusb_handle) == FALSE {
+                    res = Err(io::Error::last_os_error());
+                }
             }
         }
 
@@ -329,10 +400,13 @@ impl Drop for WindowsInterface {
             entry.remove();
         } else if is_first_interface {
             log::debug!(
-                "Released interface {}, but retaining handle for shared use",
-                self.interface_number
+                "Released interface {} on {}, but retaining handle for shared use",
+                self.interface_number,
+                self.device.log_id
             );
         }
+
+        res
     }
 }
 
@@ -345,6 +419,40 @@ impl WindowsInterface {
         TransferHandle::new(super::TransferData::new(self.clone(), endpoint, ep_type))
     }
 
+    /// Make a bulk OUT transfer that ends with a zero-length packet.
+    pub(crate) fn make_zlp_transfer(
+        self: &Arc<Self>,
+        endpoint: u8,
+    ) -> TransferHandle<super::TransferData> {
+        let mut data = super::TransferData::new(self.clone(), endpoint, EndpointType::Bulk);
+        match self.max_packet_size(endpoint) {
+            Some(size) => data.set_zlp_packet_size(size),
+            None => warn!(
+                "Endpoint {endpoint:02x} not found in descriptors of {}, not sending zero-length packet",
+                self.device.log_id
+            ),
+        }
+        TransferHandle::new(data)
+    }
+
+    /// Look up the maximum packet size of `endpoint` in the interface's
+    /// current alternate setting.
+    fn max_packet_size(&self, endpoint: u8) -> Option<usize> {
+        let alt_setting = self.alt_setting.load(Ordering::Relaxed);
+        self.device
+            .configuration_descriptors()
+            .map(Configuration::new)
+            .find(|c| c.configuration_value() == self.device.active_configuration_value())?
+            .interface_alt_settings()
+            .find(|i| {
+                i.interface_number() == self.interface_number
+                    && i.alternate_setting() == alt_setting
+            })?
+            .endpoints()
+            .find(|e| e.address() == endpoint)
+            .map(|e| e.max_packet_size())
+    }
+
     /// SAFETY: `data` must be valid for `len` bytes to read or write, depending on `Direction`
     unsafe fn control_blocking(
         &self,
@@ -354,7 +462,10 @@ impl WindowsInterface {
         len: usize,
         timeout: Duration,
     ) -> Result<usize, TransferError> {
-        info!("Blocking control {direction:?}, {len} bytes");
+        info!(
+            "Blocking control {direction:?}, {len} bytes on {}",
+            self.device.log_id
+        );
 
         if control.recipient == Recipient::Interface && control.index as u8 != self.interface_number
         {
@@ -400,7 +511,8 @@ impl WindowsInterface {
             Ok(actual_len as usize)
         } else {
             error!(
-                "WinUsb_ControlTransfer failed: {}",
+                "WinUsb_ControlTransfer on {} failed: {}",
+                self.device.log_id,
                 io::Error::last_os_error()
             );
             Err(super::transfer::map_error(GetLastError()))
@@ -457,9 +569,14 @@ impl WindowsInterface {
     }
 
     pub fn set_alt_setting(&self, alt_setting: u8) -> Result<(), Error> {
+        debug!(
+            "Set interface {} alt setting to {alt_setting} on {}",
+            self.interface_number, self.device.log_id
+        );
         unsafe {
             let r = WinUsb_SetCurrentAlternateSetting(self.winusb_handle, alt_setting.into());
             if r == TRUE {
+                self.alt_setting.store(alt_setting, Ordering::Relaxed);
                 Ok(())
             } else {
                 Err(io::Error::last_os_error())
@@ -467,8 +584,15 @@ impl WindowsInterface {
         }
     }
 
+    pub fn get_alt_setting(&self) -> u8 {
+        self.alt_setting.load(Ordering::Relaxed)
+    }
+
     pub fn clear_halt(&self, endpoint: u8) -> Result<(), Error> {
-        debug!("Clear halt, endpoint {endpoint:02x}");
+        debug!(
+            "Clear halt, endpoint {endpoint:02x} on {}",
+            self.device.log_id
+        );
         unsafe {
             let r = WinUsb_ResetPipe(self.winusb_handle, endpoint);
             if r == TRUE {
@@ -478,4 +602,33 @@ impl WindowsInterface {
             }
         }
     }
+
+    pub fn cancel_all(&self, endpoint: u8) -> Result<(), Error> {
+        debug!(
+            "Cancel all transfers, endpoint {endpoint:02x} on {}",
+            self.device.log_id
+        );
+        unsafe {
+            let r = WinUsb_AbortPipe(self.winusb_handle, endpoint);
+            if r == TRUE {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        }
+    }
+
+    pub fn alloc_streams(&self, _endpoints: &[u8], _num_streams: u32) -> Result<u32, Error> {
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "bulk streams not supported by WinUSB",
+        ))
+    }
+
+    pub fn free_streams(&self, _endpoints: &[u8]) -> Result<(), Error> {
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "bulk streams not supported by WinUSB",
+        ))
+    }
 }
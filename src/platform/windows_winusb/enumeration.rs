@@ -8,17 +8,22 @@ use windows_sys::Win32::Devices::{
     Properties::{
         DEVPKEY_Device_Address, DEVPKEY_Device_BusReportedDeviceDesc, DEVPKEY_Device_CompatibleIds,
         DEVPKEY_Device_HardwareIds, DEVPKEY_Device_InstanceId, DEVPKEY_Device_LocationPaths,
-        DEVPKEY_Device_Parent, DEVPKEY_Device_Service,
+        DEVPKEY_Device_Parent, DEVPKEY_Device_PowerData, DEVPKEY_Device_Service,
     },
-    Usb::GUID_DEVINTERFACE_USB_DEVICE,
+    Usb::{GUID_DEVINTERFACE_USB_DEVICE, USB_DEVICE_DESCRIPTOR},
+};
+use windows_sys::Win32::System::Power::{
+    PowerDeviceD1, PowerDeviceD2, PowerDeviceD3, CM_POWER_DATA,
 };
 
 use crate::{
     descriptors::{
         decode_string_descriptor, language_id::US_ENGLISH, validate_config_descriptor,
-        Configuration, DESCRIPTOR_TYPE_CONFIGURATION, DESCRIPTOR_TYPE_STRING,
+        Configuration, DESCRIPTOR_LEN_DEVICE, DESCRIPTOR_TYPE_CONFIGURATION,
+        DESCRIPTOR_TYPE_STRING,
     },
-    DeviceInfo, Error, InterfaceInfo,
+    enumeration::assign_functions,
+    DeviceInfo, DeviceSummary, Error, InterfaceInfo,
 };
 
 use super::{
@@ -37,6 +42,140 @@ pub fn list_devices() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
     Ok(devs.into_iter())
 }
 
+pub fn list_devices_on_bus(bus_id: &str) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    // Check the location path before probing to avoid querying the hub for
+    // devices on other buses.
+    let on_bus = |devinst: &DevInst| {
+        devinst
+            .get_property::<Vec<OsString>>(DEVPKEY_Device_LocationPaths)
+            .unwrap_or_default()
+            .iter()
+            .find_map(|p| parse_location_path(p))
+            .is_some_and(|(id, _)| id == bus_id)
+    };
+
+    let devs: Vec<DeviceInfo> = cfgmgr32::list_interfaces(GUID_DEVINTERFACE_USB_DEVICE, None)
+        .iter()
+        .flat_map(|i| get_device_interface_property::<WCString>(i, DEVPKEY_Device_InstanceId))
+        .flat_map(|d| DevInst::from_instance_id(&d))
+        .filter(on_bus)
+        .flat_map(probe_device)
+        .collect();
+    Ok(devs.into_iter())
+}
+
+pub fn list_devices_with_errors(
+    _retries: u8,
+    _lazy_strings: bool,
+    _partial: bool,
+    configuration_descriptors: bool,
+    _root_hubs: bool,
+) -> Result<impl Iterator<Item = Result<DeviceInfo, Error>>, Error> {
+    Ok(list_devices()?.map(move |mut d| {
+        if configuration_descriptors {
+            d.configuration_descriptors = read_configuration_descriptors(&d);
+        }
+        Ok(d)
+    }))
+}
+
+/// Request each configuration descriptor of `device` from its hub and
+/// concatenate them, stopping at the first that can't be read.
+fn read_configuration_descriptors(device: &DeviceInfo) -> Vec<u8> {
+    let mut descriptors = Vec::new();
+    let Some(hub_port) = device
+        .devinst
+        .and_then(|d| HubPort::by_child_devinst(d).ok())
+    else {
+        return descriptors;
+    };
+    for index in 0..device.num_configurations {
+        let Ok(mut buf) = hub_port.get_descriptor(DESCRIPTOR_TYPE_CONFIGURATION, index, 0) else {
+            break;
+        };
+        let Some(len) = validate_config_descriptor(&buf) else {
+            break;
+        };
+        buf.truncate(len);
+        descriptors.extend_from_slice(&buf);
+    }
+    descriptors
+}
+
+pub fn list_devices_with_class(class: u8) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    Ok(list_devices()?
+        .filter(move |d| d.class() == class || d.interfaces().any(|i| i.class() == class)))
+}
+
+pub fn list_device_ids() -> Result<impl Iterator<Item = DeviceSummary>, Error> {
+    Ok(list_devices()?.map(|d| DeviceSummary {
+        id: d.id(),
+        device_address: d.device_address,
+        vendor_id: d.vendor_id,
+        product_id: d.product_id,
+    }))
+}
+
+pub(crate) fn probe_device_id(devinst: Option<DevInst>) -> Option<DeviceInfo> {
+    probe_device(devinst?)
+}
+
+/// The device node of `device`.
+///
+/// A deserialized `DeviceInfo` doesn't carry a device node, so look it up
+/// again by instance ID.
+pub(crate) fn device_devinst(device: &DeviceInfo) -> Result<DevInst, Error> {
+    device
+        .devinst
+        .or_else(|| DevInst::from_instance_id(&WCString::from(device.instance_id.as_os_str())))
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("device {:?} is not in the device tree", device.instance_id),
+            )
+        })
+}
+
+/// Probe the child of `hub` whose address, the hub port number, is `port`.
+pub(crate) fn probe_hub_child(hub: &DeviceInfo, port: u8) -> Option<DeviceInfo> {
+    device_devinst(hub)
+        .ok()?
+        .children()
+        .find(|c| c.get_property::<u32>(DEVPKEY_Device_Address) == Some(port.into()))
+        .and_then(probe_device)
+}
+
+/// Locate and probe the device with the specified instance ID.
+///
+/// The lookup is case-insensitive, like other PnP APIs.
+pub(crate) fn probe_instance_id(instance_id: &OsStr) -> Option<DeviceInfo> {
+    let devinst = DevInst::from_instance_id(&WCString::from(instance_id))?;
+    probe_device(devinst)
+}
+
+/// Check whether the device's most recent power state is below D0.
+///
+/// USB devices in selective suspend are in D2 or D3.
+pub(crate) fn is_suspended(devinst: DevInst) -> Result<bool, Error> {
+    #![allow(non_upper_case_globals)]
+
+    let power_data = devinst
+        .get_property::<CM_POWER_DATA>(DEVPKEY_Device_PowerData)
+        .ok_or_else(|| Error::other("failed to read device power data"))?;
+    Ok(matches!(
+        power_data.PD_MostRecentPowerState,
+        PowerDeviceD1 | PowerDeviceD2 | PowerDeviceD3
+    ))
+}
+
+/// Probe the hub that `device` is connected to.
+///
+/// Root hubs are children of the host controller rather than a hub port, so
+/// probing them fails and devices connected to a root hub have no parent.
+pub(crate) fn probe_parent(device: &DeviceInfo) -> Option<DeviceInfo> {
+    probe_device(device_devinst(device).ok()?.parent()?)
+}
+
 pub fn probe_device(devinst: DevInst) -> Option<DeviceInfo> {
     let instance_id = devinst.get_property::<OsString>(DEVPKEY_Device_InstanceId)?;
     debug!("Probing device {instance_id:?}");
@@ -69,6 +208,9 @@ pub fn probe_device(devinst: DevInst) -> Option<DeviceInfo> {
 
     let driver = get_driver_name(devinst);
 
+    let config_desc = read_active_config_descriptor(&hub_port, info.active_config);
+    let config = config_desc.as_deref().map(Configuration::new);
+
     let mut interfaces = if driver.eq_ignore_ascii_case("usbccgp") {
         devinst
             .children()
@@ -88,15 +230,28 @@ pub fn probe_device(devinst: DevInst) -> Option<DeviceInfo> {
                     subclass,
                     protocol,
                     interface_string,
+                    function: None,
                 })
             })
             .collect()
     } else {
-        list_interfaces_from_desc(&hub_port, info.active_config).unwrap_or(Vec::new())
+        config
+            .as_ref()
+            .map_or(Vec::new(), list_interfaces_from_desc)
     };
 
     interfaces.sort_unstable_by_key(|i| i.interface_number);
 
+    let openable = is_winusb_driver(&driver)
+        || (driver.eq_ignore_ascii_case("usbccgp")
+            && devinst
+                .children()
+                .any(|child| is_winusb_driver(&get_driver_name(child))));
+
+    if let Some(config) = &config {
+        assign_functions(&mut interfaces, config);
+    }
+
     let location_paths = devinst
         .get_property::<Vec<OsString>>(DEVPKEY_Device_LocationPaths)
         .unwrap_or_default();
@@ -106,33 +261,77 @@ pub fn probe_device(devinst: DevInst) -> Option<DeviceInfo> {
         .find_map(|p| parse_location_path(p))
         .unwrap_or_default();
 
+    let controller = find_controller(devinst);
+    let controller_id = controller
+        .and_then(|c| c.get_property::<OsString>(DEVPKEY_Device_InstanceId))
+        .and_then(|s| s.into_string().ok());
+    let controller_driver = controller.map(get_driver_name).filter(|s| !s.is_empty());
+
     Some(DeviceInfo {
         instance_id,
         location_paths,
         parent_instance_id,
-        devinst,
+        devinst: Some(devinst),
         port_number,
         port_chain,
+        root_hub: false,
         driver: Some(driver).filter(|s| !s.is_empty()),
+        openable,
         bus_id,
+        controller_id,
+        controller_driver,
         device_address: info.address,
         vendor_id: info.device_desc.idVendor,
         product_id: info.device_desc.idProduct,
         device_version: info.device_desc.bcdDevice,
+        usb_version: info.device_desc.bcdUSB,
         class: info.device_desc.bDeviceClass,
         subclass: info.device_desc.bDeviceSubClass,
         protocol: info.device_desc.bDeviceProtocol,
         max_packet_size_0: info.device_desc.bMaxPacketSize0,
+        num_configurations: info.device_desc.bNumConfigurations,
+        device_descriptor: Some(device_descriptor_bytes(&info.device_desc)),
         speed: info.speed,
         manufacturer_string: None,
         product_string,
         serial_number,
         interfaces,
+        configuration_descriptors: Vec::new(),
+        #[cfg(feature = "mock")]
+        mock_id: None,
     })
 }
 
-fn list_interfaces_from_desc(hub_port: &HubPort, active_config: u8) -> Option<Vec<InterfaceInfo>> {
-    let buf = hub_port
+/// Serialize the hub's cached device descriptor back into wire format.
+fn device_descriptor_bytes(desc: &USB_DEVICE_DESCRIPTOR) -> [u8; DESCRIPTOR_LEN_DEVICE as usize] {
+    let [usb_lo, usb_hi] = { desc.bcdUSB }.to_le_bytes();
+    let [vid_lo, vid_hi] = { desc.idVendor }.to_le_bytes();
+    let [pid_lo, pid_hi] = { desc.idProduct }.to_le_bytes();
+    let [dev_lo, dev_hi] = { desc.bcdDevice }.to_le_bytes();
+    [
+        desc.bLength,
+        desc.bDescriptorType,
+        usb_lo,
+        usb_hi,
+        desc.bDeviceClass,
+        desc.bDeviceSubClass,
+        desc.bDeviceProtocol,
+        desc.bMaxPacketSize0,
+        vid_lo,
+        vid_hi,
+        pid_lo,
+        pid_hi,
+        dev_lo,
+        dev_hi,
+        desc.iManufacturer,
+        desc.iProduct,
+        desc.iSerialNumber,
+        desc.bNumConfigurations,
+    ]
+}
+
+fn read_active_config_descriptor(hub_port: &HubPort, active_config: u8) -> Option<Vec<u8>> {
+    let mut buf = hub_port
         .get_descriptor(
             DESCRIPTOR_TYPE_CONFIGURATION,
             active_config.saturating_sub(1),
@@ -140,27 +339,49 @@ fn list_interfaces_from_desc(hub_port: &HubPort, active_config: u8) -> Option<Ve
         )
         .ok()?;
     let len = validate_config_descriptor(&buf)?;
-    let desc = Configuration::new(&buf[..len]);
+    buf.truncate(len);
 
-    if desc.configuration_value() != active_config {
+    if Configuration::new(&buf).configuration_value() != active_config {
         return None;
     }
 
-    Some(
-        desc.interfaces()
-            .map(|i| {
-                let i_desc = i.first_alt_setting();
-
-                InterfaceInfo {
-                    interface_number: i.interface_number(),
-                    class: i_desc.class(),
-                    subclass: i_desc.subclass(),
-                    protocol: i_desc.protocol(),
-                    interface_string: None,
-                }
-            })
-            .collect(),
-    )
+    Some(buf)
+}
+
+fn list_interfaces_from_desc(desc: &Configuration) -> Vec<InterfaceInfo> {
+    desc.interfaces()
+        .map(|i| {
+            let i_desc = i.first_alt_setting();
+
+            InterfaceInfo {
+                interface_number: i.interface_number(),
+                class: i_desc.class(),
+                subclass: i_desc.subclass(),
+                protocol: i_desc.protocol(),
+                interface_string: None,
+                function: None,
+            }
+        })
+        .collect()
+}
+
+/// Find the host controller above `devinst` in the device tree.
+///
+/// The controller is the parent of the root hub, whose instance ID starts
+/// with `USB\ROOT_HUB`.
+fn find_controller(devinst: DevInst) -> Option<DevInst> {
+    let mut node = devinst;
+    loop {
+        let parent = node.parent()?;
+        let instance_id = node.get_property::<OsString>(DEVPKEY_Device_InstanceId)?;
+        if instance_id
+            .to_str()
+            .is_some_and(|id| id.to_ascii_uppercase().starts_with("USB\\ROOT_HUB"))
+        {
+            return Some(parent);
+        }
+        node = parent;
+    }
 }
 
 pub(crate) fn get_driver_name(dev: DevInst) -> String {
@@ -169,6 +390,10 @@ pub(crate) fn get_driver_name(dev: DevInst) -> String {
         .unwrap_or_default()
 }
 
+fn is_winusb_driver(driver: &str) -> bool {
+    driver.eq_ignore_ascii_case("winusb")
+}
+
 /// Get the device path to open for a whole device bound to WinUSB.
 pub(crate) fn get_winusb_device_path(dev: DevInst) -> Result<WCString, Error> {
     let paths = dev.interfaces(GUID_DEVINTERFACE_USB_DEVICE);
@@ -276,6 +501,33 @@ fn test_parse_hardware_id() {
     );
 }
 
+/// Parse vendor and product ID from an instance ID or hardware ID like `USB\VID_1234&PID_5678...`
+pub(crate) fn parse_vid_pid(s: &OsStr) -> Option<(u16, u16)> {
+    let s = s.to_str()?.to_ascii_uppercase();
+    let s = s.strip_prefix("USB\\VID_")?;
+    let vid = u16::from_str_radix(s.get(0..4)?, 16).ok()?;
+    let s = s.get(4..)?.strip_prefix("&PID_")?;
+    let pid = u16::from_str_radix(s.get(0..4)?, 16).ok()?;
+    Some((vid, pid))
+}
+
+#[test]
+fn test_parse_vid_pid() {
+    assert_eq!(parse_vid_pid(OsStr::new("")), None);
+    assert_eq!(
+        parse_vid_pid(OsStr::new("USB\\ROOT_HUB30\\4&1234&0&0")),
+        None
+    );
+    assert_eq!(
+        parse_vid_pid(OsStr::new("USB\\VID_1234&PID_ABCD\\SERIAL")),
+        Some((0x1234, 0xABCD))
+    );
+    assert_eq!(
+        parse_vid_pid(OsStr::new("usb\\vid_59e3&pid_0a23&mi_00\\7&1&0")),
+        Some((0x59E3, 0x0A23))
+    );
+}
+
 /// Parse class, subclass, protocol from a Compatible ID value
 fn parse_compatible_id(s: &OsStr) -> Option<(u8, u8, u8)> {
     let s = s.to_str()?;
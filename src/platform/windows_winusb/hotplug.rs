@@ -1,6 +1,6 @@
 use std::{
     collections::VecDeque,
-    ffi::c_void,
+    ffi::{c_void, OsStr},
     io::ErrorKind,
     mem::size_of,
     ptr::addr_of,
@@ -25,12 +25,15 @@ use windows_sys::Win32::{
 };
 
 use crate::{
-    hotplug::HotplugEvent,
+    hotplug::{may_match_any, HotplugEvent, HotplugFilter},
     platform::windows_winusb::{cfgmgr32::get_device_interface_property, util::WCString},
     DeviceId, Error,
 };
 
-use super::{enumeration::probe_device, util::WCStr};
+use super::{
+    enumeration::{parse_vid_pid, probe_device},
+    util::WCStr,
+};
 
 use super::DevInst;
 
@@ -39,9 +42,12 @@ pub(crate) struct WindowsHotplugWatch {
     registration: HCMNOTIFICATION,
 }
 
+/// Queued notification, with the vendor and product IDs if known.
+type HotplugNotification = (Action, DevInst, Option<(u16, u16)>);
+
 struct HotplugInner {
     waker: AtomicWaker,
-    events: Mutex<VecDeque<(Action, DevInst)>>,
+    events: Mutex<VecDeque<HotplugNotification>>,
 }
 
 #[derive(Debug)]
@@ -97,21 +103,25 @@ impl WindowsHotplugWatch {
         unsafe { &*self.inner }
     }
 
-    pub fn poll_next(&mut self, cx: &mut Context) -> Poll<HotplugEvent> {
+    pub fn poll_next(&mut self, cx: &mut Context, filters: &[HotplugFilter]) -> Poll<HotplugEvent> {
         self.inner().waker.register(cx.waker());
-        let event = self.inner().events.lock().unwrap().pop_front();
-        match event {
-            Some((Action::Connect, devinst)) => {
-                if let Some(dev) = probe_device(devinst) {
-                    return Poll::Ready(HotplugEvent::Connected(dev));
-                };
+        loop {
+            let event = self.inner().events.lock().unwrap().pop_front();
+            match event {
+                Some((_, _, Some((vid, pid)))) if !may_match_any(filters, Some(vid), Some(pid)) => {
+                    debug!("Ignoring hotplug event for {vid:04x}:{pid:04x} not matching filter");
+                }
+                Some((Action::Connect, devinst, _)) => {
+                    if let Some(dev) = probe_device(devinst) {
+                        return Poll::Ready(HotplugEvent::Connected(dev));
+                    };
+                }
+                Some((Action::Disconnect, devinst, _)) => {
+                    return Poll::Ready(HotplugEvent::Disconnected(DeviceId::new(Some(devinst))));
+                }
+                None => return Poll::Pending,
             }
-            Some((Action::Disconnect, devinst)) => {
-                return Poll::Ready(HotplugEvent::Disconnected(DeviceId(devinst)));
-            }
-            None => {}
         }
-        Poll::Pending
     }
 }
 
@@ -154,8 +164,16 @@ unsafe extern "system" fn hotplug_callback(
             .unwrap();
     let devinst = DevInst::from_instance_id(&device_instance).unwrap();
 
+    // The device's properties may no longer be accessible once it has been
+    // removed, so get the IDs for filtering now.
+    let ids = parse_vid_pid(OsStr::new(&device_instance.to_string()));
+
     debug!("Hotplug callback: action={action:?}, instance={device_instance}");
-    inner.events.lock().unwrap().push_back((action, devinst));
+    inner
+        .events
+        .lock()
+        .unwrap()
+        .push_back((action, devinst, ids));
     inner.waker.wake();
     return ERROR_SUCCESS;
 }
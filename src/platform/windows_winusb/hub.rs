@@ -13,12 +13,17 @@ use windows_sys::Win32::{
     Devices::{
         Properties::DEVPKEY_Device_Address,
         Usb::{
+            DeviceCausedOvercurrent, DeviceConnected, DeviceEnumerating, DeviceFailedEnumeration,
+            DeviceGeneralFailure, DeviceHubNestedTooDeeply, DeviceInLegacyHub,
+            DeviceNotEnoughBandwidth, DeviceNotEnoughPower, DeviceReset, NoDeviceConnected,
             UsbFullSpeed, UsbHighSpeed, UsbLowSpeed, GUID_DEVINTERFACE_USB_HUB,
             IOCTL_USB_GET_DESCRIPTOR_FROM_NODE_CONNECTION,
             IOCTL_USB_GET_NODE_CONNECTION_INFORMATION_EX,
-            IOCTL_USB_GET_NODE_CONNECTION_INFORMATION_EX_V2, USB_DESCRIPTOR_REQUEST,
-            USB_DESCRIPTOR_REQUEST_0, USB_DEVICE_DESCRIPTOR, USB_DEVICE_SPEED,
-            USB_NODE_CONNECTION_INFORMATION_EX, USB_NODE_CONNECTION_INFORMATION_EX_V2,
+            IOCTL_USB_GET_NODE_CONNECTION_INFORMATION_EX_V2, IOCTL_USB_GET_NODE_INFORMATION,
+            IOCTL_USB_HUB_CYCLE_PORT, USB_CONNECTION_STATUS, USB_CYCLE_PORT_PARAMS,
+            USB_DESCRIPTOR_REQUEST, USB_DESCRIPTOR_REQUEST_0, USB_DEVICE_DESCRIPTOR,
+            USB_DEVICE_SPEED, USB_NODE_CONNECTION_INFORMATION_EX,
+            USB_NODE_CONNECTION_INFORMATION_EX_V2, USB_NODE_INFORMATION,
         },
     },
     Foundation::{GetLastError, ERROR_GEN_FAILURE, TRUE},
@@ -36,7 +41,7 @@ const DEVICE_IS_SUPER_SPEED_CAPABLE_OR_HIGHER: u32 = 0x02;
 const DEVICE_IS_OPERATING_AT_SUPER_SPEED_PLUS_OR_HIGHER: u32 = 0x04;
 const DEVICE_IS_SUPER_SPEED_PLUS_CAPABLE_OR_HIGHER: u32 = 0x08;
 
-use crate::{Error, Speed};
+use crate::{ConnectionStatus, Error, Speed};
 
 use super::{
     cfgmgr32::DevInst,
@@ -50,14 +55,14 @@ impl HubHandle {
     pub fn by_devinst(devinst: DevInst) -> Option<HubHandle> {
         let paths = devinst.interfaces(GUID_DEVINTERFACE_USB_HUB);
         let Some(path) = paths.iter().next() else {
-            debug!("Failed to find hub interface");
+            debug!("Failed to find hub interface for {}", devinst.instance_id());
             return None;
         };
 
         match create_file(path) {
             Ok(f) => Some(HubHandle(f)),
             Err(e) => {
-                debug!("Failed to open hub: {e}");
+                debug!("Failed to open hub {path}: {e}");
                 None
             }
         }
@@ -86,7 +91,7 @@ impl HubHandle {
                 Ok(info)
             } else {
                 let err = Error::last_os_error();
-                debug!("Hub DeviceIoControl failed: {err:?}");
+                debug!("Hub DeviceIoControl for port {port_number} failed: {err:?}");
                 Err(err)
             }
         }
@@ -117,7 +122,66 @@ impl HubHandle {
                 Ok(info)
             } else {
                 let err = Error::last_os_error();
-                debug!("Hub DeviceIoControl failed: {err:?}");
+                debug!("Hub DeviceIoControl for port {port_number} failed: {err:?}");
+                Err(err)
+            }
+        }
+    }
+
+    /// Get the number of downstream ports from the hub descriptor.
+    pub fn num_ports(&self) -> Result<u8, Error> {
+        unsafe {
+            let mut info: USB_NODE_INFORMATION = mem::zeroed();
+            let mut bytes_returned: u32 = 0;
+            let r = DeviceIoControl(
+                raw_handle(&self.0),
+                IOCTL_USB_GET_NODE_INFORMATION,
+                &info as *const _ as *const c_void,
+                mem::size_of_val(&info) as u32,
+                &mut info as *mut _ as *mut c_void,
+                mem::size_of_val(&info) as u32,
+                &mut bytes_returned,
+                null_mut(),
+            );
+
+            if r == TRUE {
+                Ok(info.u.HubInformation.HubDescriptor.bNumberOfPorts)
+            } else {
+                let err = Error::last_os_error();
+                debug!("IOCTL_USB_GET_NODE_INFORMATION failed: {err:?}");
+                Err(err)
+            }
+        }
+    }
+
+    /// Get the `ConnectionStatus` the hub driver reports for a port.
+    pub fn connection_status(&self, port_number: u32) -> Result<ConnectionStatus, Error> {
+        let info = self.get_node_connection_info(port_number)?;
+        Ok(connection_status(info.ConnectionStatus))
+    }
+
+    /// Power-cycle the port, causing the attached device to re-enumerate.
+    pub fn cycle_port(&self, port_number: u32) -> Result<(), Error> {
+        unsafe {
+            let mut params: USB_CYCLE_PORT_PARAMS = mem::zeroed();
+            params.ConnectionIndex = port_number;
+            let mut bytes_returned: u32 = 0;
+            let r = DeviceIoControl(
+                raw_handle(&self.0),
+                IOCTL_USB_HUB_CYCLE_PORT,
+                &params as *const _ as *const c_void,
+                mem::size_of_val(&params) as u32,
+                &mut params as *mut _ as *mut c_void,
+                mem::size_of_val(&params) as u32,
+                &mut bytes_returned,
+                null_mut(),
+            );
+
+            if r == TRUE {
+                Ok(())
+            } else {
+                let err = Error::last_os_error();
+                debug!("IOCTL_USB_HUB_CYCLE_PORT for port {port_number} failed: {err:?}");
                 Err(err)
             }
         }
@@ -176,7 +240,7 @@ impl HubHandle {
                 Ok(vec)
             } else {
                 let err = GetLastError();
-                debug!("IOCTL_USB_GET_DESCRIPTOR_FROM_NODE_CONNECTION failed: type={descriptor_type} index={descriptor_index} error={err:?}");
+                debug!("IOCTL_USB_GET_DESCRIPTOR_FROM_NODE_CONNECTION for port {port_number} failed: type={descriptor_type} index={descriptor_index} error={err:?}");
                 Err(match err {
                     ERROR_GEN_FAILURE => Error::new(
                         ErrorKind::Other,
@@ -256,6 +320,14 @@ impl HubPort {
         })
     }
 
+    pub fn cycle(&self) -> Result<(), Error> {
+        self.hub_handle.cycle_port(self.port_number)
+    }
+
+    pub fn connection_status(&self) -> Result<ConnectionStatus, Error> {
+        self.hub_handle.connection_status(self.port_number)
+    }
+
     pub fn get_descriptor(
         &self,
         descriptor_type: u8,
@@ -270,3 +342,21 @@ impl HubPort {
         )
     }
 }
+
+fn connection_status(status: USB_CONNECTION_STATUS) -> ConnectionStatus {
+    #![allow(non_upper_case_globals)]
+    match status {
+        NoDeviceConnected => ConnectionStatus::NoDeviceConnected,
+        DeviceConnected => ConnectionStatus::DeviceConnected,
+        DeviceFailedEnumeration => ConnectionStatus::DeviceFailedEnumeration,
+        DeviceGeneralFailure => ConnectionStatus::DeviceGeneralFailure,
+        DeviceCausedOvercurrent => ConnectionStatus::DeviceCausedOvercurrent,
+        DeviceNotEnoughPower => ConnectionStatus::DeviceNotEnoughPower,
+        DeviceNotEnoughBandwidth => ConnectionStatus::DeviceNotEnoughBandwidth,
+        DeviceHubNestedTooDeeply => ConnectionStatus::DeviceHubNestedTooDeeply,
+        DeviceInLegacyHub => ConnectionStatus::DeviceInLegacyHub,
+        DeviceEnumerating => ConnectionStatus::DeviceEnumerating,
+        DeviceReset => ConnectionStatus::DeviceReset,
+        other => ConnectionStatus::Unknown(other),
+    }
+}
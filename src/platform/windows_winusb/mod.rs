@@ -1,5 +1,11 @@
 mod enumeration;
-pub use enumeration::list_devices;
+pub(crate) use enumeration::{
+    device_devinst, is_suspended, probe_device_id, probe_hub_child, probe_instance_id, probe_parent,
+};
+pub use enumeration::{
+    list_device_ids, list_devices, list_devices_on_bus, list_devices_with_class,
+    list_devices_with_errors,
+};
 
 mod events;
 
@@ -12,9 +18,13 @@ pub(crate) use transfer::TransferData;
 
 mod cfgmgr32;
 mod hub;
+pub(crate) use hub::{HubHandle, HubPort};
 mod registry;
 pub(crate) use cfgmgr32::DevInst;
-pub(crate) use DevInst as DeviceId;
+
+/// The device node, or `None` for a deserialized `DeviceInfo` whose device
+/// is no longer in the device tree.
+pub(crate) type DeviceId = Option<DevInst>;
 mod hotplug;
 mod util;
 pub(crate) use hotplug::WindowsHotplugWatch as HotplugWatch;
@@ -20,9 +20,11 @@ use windows_sys::Win32::{
     System::IO::{CancelIoEx, OVERLAPPED},
 };
 
+use crate::transfer::check_disconnected;
 use crate::transfer::{
-    notify_completion, Completion, ControlIn, ControlOut, EndpointType, PlatformSubmit,
-    PlatformTransfer, Recipient, RequestBuffer, ResponseBuffer, TransferError,
+    drop_transfer_buffer_parts, drop_vec_parts, notify_completion, CallerBuffer, Completion,
+    ControlIn, ControlOut, EndpointType, FilledBuffer, PlatformSubmit, PlatformTransfer, Recipient,
+    RequestBuffer, ResponseBuffer, TransferBuffer, TransferError,
 };
 
 #[repr(C)]
@@ -37,9 +39,16 @@ pub struct TransferData {
     event: *mut EventNotify,
     buf: *mut u8,
     capacity: usize,
+
+    /// Frees `buf`, which is a `Vec` unless a caller-provided buffer is pending.
+    buf_drop: unsafe fn(*mut u8, usize),
     endpoint: u8,
     ep_type: EndpointType,
     submit_error: Option<WIN32_ERROR>,
+
+    /// Send a zero-length packet after OUT transfers that are a nonzero
+    /// multiple of this maximum packet size.
+    zlp_packet_size: Option<usize>,
 }
 
 unsafe impl Send for TransferData {}
@@ -55,9 +64,62 @@ impl TransferData {
             event: Box::into_raw(Box::new(unsafe { mem::zeroed() })),
             buf: null_mut(),
             capacity: 0,
+            buf_drop: drop_vec_parts,
             endpoint,
             ep_type,
             submit_error: None,
+            zlp_packet_size: None,
+        }
+    }
+
+    pub(super) fn set_zlp_packet_size(&mut self, max_packet_size: usize) {
+        self.zlp_packet_size = Some(max_packet_size);
+    }
+
+    /// Whether an OUT transfer of `len` bytes must be followed by a
+    /// zero-length packet.
+    fn needs_zlp(&self, len: usize) -> bool {
+        self.zlp_packet_size
+            .is_some_and(|size| size != 0 && len != 0 && len % size == 0)
+    }
+
+    /// Queue a zero-length write behind the transfer just submitted.
+    ///
+    /// WinUSB has a `SHORT_PACKET_TERMINATE` pipe policy, but it applies to
+    /// every transfer on the pipe. WinUSB completes the requests on a pipe in
+    /// order, so a separate write follows the transfer's data instead. Its
+    /// `EventNotify` has a null `ptr`, and is freed by `handle_event` when the
+    /// write completes.
+    ///
+    /// SAFETY: `self.buf` must be valid; no data is read from it.
+    unsafe fn submit_zlp(&self) {
+        let event: *mut EventNotify = Box::into_raw(Box::new(mem::zeroed()));
+
+        debug!(
+            "Submit zero-length packet {:?} on endpoint {:02X}",
+            event, self.endpoint
+        );
+
+        let r = WinUsb_WritePipe(
+            self.interface.winusb_handle,
+            self.endpoint,
+            self.buf,
+            0,
+            null_mut(),
+            event as *mut OVERLAPPED,
+        );
+
+        if r == FALSE {
+            let err = GetLastError();
+            if err != ERROR_IO_PENDING {
+                error!(
+                    "Failed to submit zero-length packet on endpoint {:02x} of {}: {}",
+                    self.endpoint,
+                    self.interface.device.log_id,
+                    io::Error::from_raw_os_error(err as _)
+                );
+                drop(Box::from_raw(event));
+            }
         }
     }
 
@@ -72,18 +134,25 @@ impl TransferData {
     /// SAFETY: user_data must be the callback pointer passed to `submit`
     unsafe fn post_submit(&mut self, r: i32, func: &str, user_data: *mut c_void) {
         if r == TRUE {
-            error!("{func} completed synchronously")
+            error!(
+                "{func} completed synchronously on {}",
+                self.interface.device.log_id
+            )
         }
 
         let err = GetLastError();
 
         if err != ERROR_IO_PENDING {
             self.submit_error = Some(err);
-            error!("{func} failed: {}", io::Error::from_raw_os_error(err as _));
+            error!(
+                "{func} failed on {}: {}",
+                self.interface.device.log_id,
+                io::Error::from_raw_os_error(err as _)
+            );
 
             // Safety: Transfer was not submitted, so we still own it
             // and must complete it in place of the event thread.
-            notify_completion::<TransferData>(user_data);
+            notify_completion::<crate::platform::TransferData>(user_data);
         } else {
             self.submit_error = None;
         }
@@ -93,10 +162,13 @@ impl TransferData {
     unsafe fn get_status(&mut self) -> (usize, Result<(), TransferError>) {
         if let Some(err) = self.submit_error {
             debug!(
-                "Transfer {:?} on endpoint {:02x} failed on submit: {}",
-                self.event, self.endpoint, err
+                "Transfer {:?} on endpoint {:02x} of {} failed on submit: {}",
+                self.event, self.endpoint, self.interface.device.log_id, err
             );
-            return (0, Err(map_error(err)));
+            let status = check_disconnected(Err(map_error(err)), || {
+                self.interface.device.is_disconnected()
+            });
+            return (0, status);
         }
 
         let mut actual_len = 0;
@@ -116,11 +188,12 @@ impl TransferData {
         } else {
             let err = GetLastError();
             debug!(
-                "Transfer {:?} on endpoint {:02x} failed: {}, {} bytes transferred",
-                self.event, self.endpoint, err, actual_len
+                "Transfer {:?} on endpoint {:02x} of {} failed: {}, {} bytes transferred",
+                self.event, self.endpoint, self.interface.device.log_id, err, actual_len
             );
             Err(map_error(err))
         };
+        let status = check_disconnected(status, || self.interface.device.is_disconnected());
 
         (actual_len as usize, status)
     }
@@ -129,7 +202,7 @@ impl TransferData {
 impl Drop for TransferData {
     fn drop(&mut self) {
         if !self.buf.is_null() {
-            unsafe { drop(Vec::from_raw_parts(self.buf, 0, self.capacity)) }
+            unsafe { (self.buf_drop)(self.buf, self.capacity) }
         }
         unsafe { drop(Box::from_raw(self.event)) }
     }
@@ -147,7 +220,8 @@ impl PlatformTransfer for TransferData {
                 let err = GetLastError();
                 if err != ERROR_NOT_FOUND {
                     error!(
-                        "CancelIoEx failed: {}",
+                        "CancelIoEx failed on {}: {}",
+                        self.interface.device.log_id,
                         io::Error::from_raw_os_error(err as i32)
                     );
                 }
@@ -178,7 +252,13 @@ impl PlatformSubmit<Vec<u8>> for TransferData {
             null_mut(),
             self.event as *mut OVERLAPPED,
         );
+        let zlp = self.needs_zlp(len);
         self.post_submit(r, "WinUsb_WritePipe", user_data);
+
+        // Only queue the zero-length packet if the transfer was submitted.
+        if zlp && self.submit_error.is_none() {
+            self.submit_zlp();
+        }
     }
 
     unsafe fn take_completed(&mut self) -> Completion<ResponseBuffer> {
@@ -222,6 +302,41 @@ impl PlatformSubmit<RequestBuffer> for TransferData {
     }
 }
 
+impl<B: TransferBuffer> PlatformSubmit<CallerBuffer<B>> for TransferData {
+    unsafe fn submit(&mut self, data: CallerBuffer<B>, user_data: *mut c_void) {
+        addr_of_mut!((*self.event).ptr).write(user_data);
+
+        let (ptr, len) = data.into_raw_parts();
+        self.buf = ptr;
+        self.capacity = len;
+        self.buf_drop = drop_transfer_buffer_parts::<B>;
+
+        debug!(
+            "Submit transfer {:?} on endpoint {:02X} for {} bytes IN into caller buffer",
+            self.event, self.endpoint, len
+        );
+
+        let r = WinUsb_ReadPipe(
+            self.interface.winusb_handle,
+            self.endpoint,
+            self.buf,
+            len.try_into().expect("transfer size should fit in u32"),
+            null_mut(),
+            self.event as *mut OVERLAPPED,
+        );
+        self.post_submit(r, "WinUsb_ReadPipe", user_data);
+    }
+
+    unsafe fn take_completed(&mut self) -> Completion<FilledBuffer<B>> {
+        let (actual_len, status) = self.get_status();
+        let ptr = mem::replace(&mut self.buf, null_mut());
+        let len = mem::replace(&mut self.capacity, 0);
+        self.buf_drop = drop_vec_parts;
+        let data = FilledBuffer::from_raw_parts(ptr, len, actual_len);
+        Completion { data, status }
+    }
+}
+
 impl PlatformSubmit<ControlIn> for TransferData {
     unsafe fn submit(&mut self, data: ControlIn, user_data: *mut c_void) {
         assert_eq!(self.endpoint, 0);
@@ -329,16 +444,25 @@ pub(super) fn handle_event(completion: *mut OVERLAPPED) {
     debug!("Handling completion for transfer {completion:?}");
     unsafe {
         let p = addr_of_mut!((*completion).ptr).read();
-        notify_completion::<TransferData>(p)
+        if p.is_null() {
+            // A zero-length packet from `submit_zlp`, whose result is only
+            // logged. `Internal` holds its NTSTATUS.
+            let status = (*completion).overlapped.Internal;
+            if status != 0 {
+                error!("Zero-length packet failed: NTSTATUS {status:#x}");
+            }
+            drop(Box::from_raw(completion));
+            return;
+        }
+        notify_completion::<crate::platform::TransferData>(p)
     }
 }
 
 pub(crate) fn map_error(err: WIN32_ERROR) -> TransferError {
     match err {
         ERROR_GEN_FAILURE => TransferError::Stall,
-        ERROR_REQUEST_ABORTED | ERROR_TIMEOUT | ERROR_SEM_TIMEOUT | ERROR_OPERATION_ABORTED => {
-            TransferError::Cancelled
-        }
+        ERROR_REQUEST_ABORTED | ERROR_OPERATION_ABORTED => TransferError::Cancelled,
+        ERROR_TIMEOUT | ERROR_SEM_TIMEOUT => TransferError::TimedOut,
         ERROR_FILE_NOT_FOUND | ERROR_DEVICE_NOT_CONNECTED | ERROR_NO_SUCH_DEVICE => {
             TransferError::Disconnected
         }
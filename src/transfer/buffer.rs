@@ -1,5 +1,6 @@
 use std::fmt::Debug;
 use std::mem::ManuallyDrop;
+use std::sync::{Arc, Mutex};
 
 use super::TransferRequest;
 
@@ -126,3 +127,230 @@ impl Drop for ResponseBuffer {
 impl TransferRequest for Vec<u8> {
     type Response = ResponseBuffer;
 }
+
+/// A pool of transfer buffers of a fixed size, for re-use across transfers.
+///
+/// Buffers taken from the pool with [`get`][Self::get] and returned with
+/// [`recycle`][Self::recycle] keep their allocation, so after the pool
+/// fills up, continued streaming doesn't allocate a new data buffer for
+/// each transfer.
+///
+/// The pool is reference-counted and synchronized internally, so it can be
+/// cloned cheaply to recycle buffers from another thread, such as one
+/// processing the received data.
+///
+/// Usually created with [`Queue::with_buffer_pool`][super::Queue::with_buffer_pool].
+#[derive(Clone)]
+pub struct BufferPool {
+    buffers: Arc<Mutex<Vec<Vec<u8>>>>,
+    capacity: usize,
+    buf_size: usize,
+}
+
+impl BufferPool {
+    /// Create an empty pool keeping up to `capacity` buffers of `buf_size`
+    /// bytes.
+    pub fn new(capacity: usize, buf_size: usize) -> BufferPool {
+        BufferPool {
+            buffers: Arc::new(Mutex::new(Vec::with_capacity(capacity))),
+            capacity,
+            buf_size,
+        }
+    }
+
+    /// Size in bytes of the buffers in the pool.
+    pub fn buf_size(&self) -> usize {
+        self.buf_size
+    }
+
+    /// Take an empty `Vec` with a capacity of at least `buf_size` bytes from
+    /// the pool, or allocate a new one if the pool is empty.
+    pub fn get(&self) -> Vec<u8> {
+        self.buffers
+            .lock()
+            .unwrap()
+            .pop()
+            .unwrap_or_else(|| Vec::with_capacity(self.buf_size))
+    }
+
+    /// Return a buffer to the pool.
+    ///
+    /// The buffer is dropped instead if the pool is full or its capacity is
+    /// smaller than `buf_size`.
+    pub fn recycle(&self, mut buf: Vec<u8>) {
+        if buf.capacity() < self.buf_size {
+            return;
+        }
+        let mut buffers = self.buffers.lock().unwrap();
+        if buffers.len() < self.capacity {
+            buf.clear();
+            buffers.push(buf);
+        }
+    }
+
+    /// Number of buffers currently available in the pool.
+    pub fn available(&self) -> usize {
+        self.buffers.lock().unwrap().len()
+    }
+}
+
+impl Debug for BufferPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("capacity", &self.capacity)
+            .field("buf_size", &self.buf_size)
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+#[test]
+fn test_buffer_pool() {
+    let pool = BufferPool::new(1, 64);
+    let buf = pool.get();
+    assert!(buf.capacity() >= 64);
+    let ptr = buf.as_ptr();
+
+    pool.recycle(buf);
+    pool.recycle(vec![0; 64]);
+    assert_eq!(pool.available(), 1);
+
+    let buf = pool.get();
+    assert_eq!(buf.as_ptr(), ptr);
+    assert!(buf.is_empty());
+
+    pool.recycle(Vec::with_capacity(16));
+    assert_eq!(pool.available(), 0);
+}
+
+/// Memory owned by the caller that can be lent to the OS for a transfer.
+///
+/// Submitting a buffer with [`Queue::submit_buffer`][super::Queue::submit_buffer]
+/// moves it into the transfer, and it is returned in the
+/// [`Completion`][super::Completion] as a [`FilledBuffer`]. This allows a
+/// fixed pool of buffers to be cycled through a queue with no allocation per
+/// transfer.
+///
+/// # Safety
+///
+/// The pointer returned from [`into_raw_parts`][TransferBuffer::into_raw_parts]
+/// must be valid for reads and writes of the returned number of bytes until
+/// it is passed back to [`from_raw_parts`][TransferBuffer::from_raw_parts],
+/// and nothing else may access that memory in the meantime. The OS writes to
+/// the memory while the transfer is pending, even if the `Queue` is dropped
+/// and the buffer is never returned.
+pub unsafe trait TransferBuffer: Send + Sync + Sized {
+    /// Give up ownership of the memory, returning a pointer to it and its length in bytes.
+    fn into_raw_parts(self) -> (*mut u8, usize);
+
+    /// Reconstruct the buffer from the parts returned by `into_raw_parts`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` and `len` must have been returned from `into_raw_parts` on the
+    /// same type, and not already reconstructed.
+    unsafe fn from_raw_parts(ptr: *mut u8, len: usize) -> Self;
+}
+
+unsafe impl TransferBuffer for Box<[u8]> {
+    fn into_raw_parts(self) -> (*mut u8, usize) {
+        let len = self.len();
+        (Box::into_raw(self).cast(), len)
+    }
+
+    unsafe fn from_raw_parts(ptr: *mut u8, len: usize) -> Self {
+        unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)) }
+    }
+}
+
+unsafe impl TransferBuffer for &'static mut [u8] {
+    fn into_raw_parts(self) -> (*mut u8, usize) {
+        (self.as_mut_ptr(), self.len())
+    }
+
+    unsafe fn from_raw_parts(ptr: *mut u8, len: usize) -> Self {
+        unsafe { std::slice::from_raw_parts_mut(ptr, len) }
+    }
+}
+
+/// Request type for an **IN** transfer into a caller-provided [`TransferBuffer`].
+///
+/// Usually created by [`Queue::submit_buffer`][super::Queue::submit_buffer].
+/// The full length of the buffer is requested.
+pub struct CallerBuffer<B: TransferBuffer>(pub B);
+
+impl<B: TransferBuffer> TransferRequest for CallerBuffer<B> {
+    type Response = FilledBuffer<B>;
+}
+
+impl<B: TransferBuffer> CallerBuffer<B> {
+    pub(crate) fn into_raw_parts(self) -> (*mut u8, usize) {
+        self.0.into_raw_parts()
+    }
+}
+
+/// A caller-provided [`TransferBuffer`] returned from a completed transfer.
+///
+/// The first [`actual_length`][FilledBuffer::actual_length] bytes of the
+/// buffer contain the received data. The remainder of the buffer is unchanged.
+pub struct FilledBuffer<B> {
+    buffer: B,
+    actual_length: usize,
+}
+
+impl<B: TransferBuffer> FilledBuffer<B> {
+    /// SAFETY: `ptr` and `len` must be from `B::into_raw_parts`, and no longer in use by the OS
+    pub(crate) unsafe fn from_raw_parts(ptr: *mut u8, len: usize, actual_length: usize) -> Self {
+        assert!(actual_length <= len);
+        FilledBuffer {
+            buffer: unsafe { B::from_raw_parts(ptr, len) },
+            actual_length,
+        }
+    }
+}
+
+impl<B> FilledBuffer<B> {
+    /// Get the number of bytes received into the buffer.
+    pub fn actual_length(&self) -> usize {
+        self.actual_length
+    }
+
+    /// Get the buffer, including any bytes beyond `actual_length`.
+    pub fn buffer(&self) -> &B {
+        &self.buffer
+    }
+
+    /// Extract the buffer to submit it for another transfer.
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+}
+
+impl<B: AsRef<[u8]>> FilledBuffer<B> {
+    /// Get the received data.
+    pub fn data(&self) -> &[u8] {
+        &self.buffer.as_ref()[..self.actual_length]
+    }
+}
+
+impl<B> Debug for FilledBuffer<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FilledBuffer")
+            .field("actual_length", &self.actual_length)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Free a buffer stored in a platform `TransferData` as the parts of a `Vec`.
+///
+/// SAFETY: `ptr` and `capacity` must be from a `Vec<u8>` that is no longer in use by the OS
+pub(crate) unsafe fn drop_vec_parts(ptr: *mut u8, capacity: usize) {
+    unsafe { drop(Vec::from_raw_parts(ptr, 0, capacity)) }
+}
+
+/// Free a buffer stored in a platform `TransferData` as the parts of a [`TransferBuffer`].
+///
+/// SAFETY: `ptr` and `len` must be from `B::into_raw_parts` and no longer in use by the OS
+pub(crate) unsafe fn drop_transfer_buffer_parts<B: TransferBuffer>(ptr: *mut u8, len: usize) {
+    unsafe { drop(B::from_raw_parts(ptr, len)) }
+}
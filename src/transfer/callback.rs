@@ -0,0 +1,158 @@
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+};
+
+use crate::platform;
+
+use super::{Completion, PlatformSubmit, Queue, TransferRequest};
+
+type Callback<T> = Box<dyn FnOnce(Completion<T>) + Send>;
+
+struct NotifyWaker<F>(F);
+
+impl<F: Fn() + Send + Sync + 'static> Wake for NotifyWaker<F> {
+    fn wake(self: Arc<Self>) {
+        (self.0)()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        (self.0)()
+    }
+}
+
+/// A [`Queue`] that calls a callback for each completed transfer, for use
+/// from an event loop without an async runtime.
+///
+/// Transfers are submitted with a callback that is called with the
+/// [`Completion`] when the application calls
+/// [`poll_completions`][Self::poll_completions]. The `notify` function passed
+/// to [`new`][Self::new] is called from the event thread after a transfer
+/// completes, and should signal the application's event loop to call
+/// `poll_completions`, for example by posting a message to it. It may also
+/// be called when there is nothing to do yet, such as on submitting a
+/// transfer to an empty queue. Callbacks are only ever called from
+/// `poll_completions`, on the thread that calls it.
+///
+/// Like a `Queue`, transfers complete in the order they were submitted, and
+/// all pending transfers are cancelled when the `CallbackQueue` is dropped,
+/// without calling their callbacks.
+///
+/// ### Example
+///
+/// ```no_run
+/// use std::sync::mpsc;
+/// use nusb::transfer::{CallbackQueue, RequestBuffer};
+/// # let di = nusb::list_devices().unwrap().next().unwrap();
+/// # let device = di.open().unwrap();
+/// # let interface = device.claim_interface(0).unwrap();
+/// let (notify_tx, notify_rx) = mpsc::channel();
+/// let mut queue = CallbackQueue::new(interface.bulk_in_queue(0x81), move || {
+///     let _ = notify_tx.send(());
+/// });
+///
+/// queue.submit(RequestBuffer::new(64), |completion| {
+///     println!("received {:?}", completion.data);
+/// });
+///
+/// while queue.pending() > 0 {
+///     notify_rx.recv().unwrap();
+///     queue.poll_completions();
+/// }
+/// ```
+pub struct CallbackQueue<R: TransferRequest> {
+    queue: Queue<R>,
+    callbacks: VecDeque<Callback<R::Response>>,
+    waker: Waker,
+}
+
+impl<R> CallbackQueue<R>
+where
+    R: TransferRequest + Send + Sync,
+    platform::TransferData: PlatformSubmit<R>,
+{
+    /// Wrap an empty `queue`, calling `notify` when a transfer completes.
+    ///
+    /// `notify` is called from the event thread and must not block.
+    ///
+    /// Panics if `queue` has pending transfers.
+    pub fn new(queue: Queue<R>, notify: impl Fn() + Send + Sync + 'static) -> CallbackQueue<R> {
+        assert_eq!(
+            queue.pending(),
+            0,
+            "queue should have no pending transfers when creating a CallbackQueue"
+        );
+        CallbackQueue {
+            queue,
+            callbacks: VecDeque::new(),
+            waker: Waker::from(Arc::new(NotifyWaker(notify))),
+        }
+    }
+
+    /// Submit a new transfer on the endpoint, calling `callback` from
+    /// [`poll_completions`][Self::poll_completions] when it completes.
+    ///
+    /// For an `IN` endpoint, pass a [`RequestBuffer`][`super::RequestBuffer`].\
+    /// For an `OUT` endpoint, pass a [`Vec<u8>`].
+    pub fn submit(
+        &mut self,
+        data: R,
+        callback: impl FnOnce(Completion<R::Response>) + Send + 'static,
+    ) {
+        self.queue.submit(data);
+        self.callbacks.push_back(Box::new(callback));
+
+        // The waker is registered by polling the transfer at the front, so
+        // ask the event loop to call `poll_completions` for a new one.
+        if self.callbacks.len() == 1 {
+            self.waker.wake_by_ref();
+        }
+    }
+
+    /// Call the callbacks of all transfers that have completed, in order,
+    /// and return the number of callbacks called.
+    ///
+    /// This never blocks. After it returns, `notify` will be called when the
+    /// next pending transfer completes.
+    pub fn poll_completions(&mut self) -> usize {
+        let mut cx = Context::from_waker(&self.waker);
+        let mut count = 0;
+        while self.queue.pending() > 0 {
+            let Poll::Ready(completion) = self.queue.poll_next(&mut cx) else {
+                break;
+            };
+            let callback = self
+                .callbacks
+                .pop_front()
+                .expect("each pending transfer should have a callback");
+            callback(completion);
+            count += 1;
+        }
+        count
+    }
+
+    /// Get the number of transfers that have been submitted whose callbacks
+    /// have not yet been called.
+    pub fn pending(&self) -> usize {
+        self.queue.pending()
+    }
+
+    /// Request cancellation of all pending transfers.
+    ///
+    /// The callbacks are still called from
+    /// [`poll_completions`][Self::poll_completions], so you can tell which
+    /// transfers were completed, partially-completed, or cancelled.
+    pub fn cancel_all(&mut self) {
+        self.queue.cancel_all()
+    }
+}
+
+impl<R: TransferRequest> Debug for CallbackQueue<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallbackQueue")
+            .field("pending", &self.callbacks.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -1,3 +1,5 @@
+use std::io::{Error, ErrorKind};
+
 use super::{ResponseBuffer, TransferRequest};
 
 /// Transfer direction
@@ -12,6 +14,7 @@ pub enum Direction {
 }
 
 /// Specification defining the request.
+#[doc(alias = "RequestType")]
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[repr(u8)]
 pub enum ControlType {
@@ -207,3 +210,188 @@ fn request_type(direction: Direction, control_type: ControlType, recipient: Reci
 impl TransferRequest for ControlIn {
     type Response = Vec<u8>;
 }
+
+/// Builder for the fields of a SETUP packet, including its direction.
+///
+/// This builds the `bmRequestType` byte from typed fields, and checks that
+/// the direction and `wLength` match when converting to a [`ControlIn`] or
+/// [`ControlOut`].
+///
+/// ### Example
+///
+/// ```
+/// use nusb::transfer::{ControlSetup, ControlType, Direction, Recipient};
+/// let setup = ControlSetup::new(Direction::In)
+///     .recipient(Recipient::Device)
+///     .control_type(ControlType::Vendor)
+///     .request(0x01)
+///     .value(0x0200)
+///     .index(0)
+///     .length(64);
+/// assert_eq!(setup.setup_packet(), [0xC0, 0x01, 0x00, 0x02, 0x00, 0x00, 0x40, 0x00]);
+/// assert_eq!(setup.control_in().unwrap().length, 64);
+/// assert!(setup.control_out(&[1, 2, 3]).is_err());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ControlSetup {
+    direction: Direction,
+    control_type: ControlType,
+    recipient: Recipient,
+    request: u8,
+    value: u16,
+    index: u16,
+    length: u16,
+}
+
+impl ControlSetup {
+    /// Start building a standard request to the device in the specified
+    /// direction, with all other fields zero.
+    pub fn new(direction: Direction) -> ControlSetup {
+        ControlSetup {
+            direction,
+            control_type: ControlType::Standard,
+            recipient: Recipient::Device,
+            request: 0,
+            value: 0,
+            index: 0,
+            length: 0,
+        }
+    }
+
+    /// Set the recipient used for the `bmRequestType` field.
+    pub fn recipient(self, recipient: Recipient) -> ControlSetup {
+        ControlSetup { recipient, ..self }
+    }
+
+    /// Set the request type used for the `bmRequestType` field.
+    #[doc(alias = "request_type")]
+    pub fn control_type(self, control_type: ControlType) -> ControlSetup {
+        ControlSetup {
+            control_type,
+            ..self
+        }
+    }
+
+    /// Set the `bRequest` field.
+    pub fn request(self, request: u8) -> ControlSetup {
+        ControlSetup { request, ..self }
+    }
+
+    /// Set the `wValue` field.
+    pub fn value(self, value: u16) -> ControlSetup {
+        ControlSetup { value, ..self }
+    }
+
+    /// Set the `wIndex` field.
+    pub fn index(self, index: u16) -> ControlSetup {
+        ControlSetup { index, ..self }
+    }
+
+    /// Set the `wLength` field, the number of bytes in the data stage.
+    #[doc(alias = "wLength")]
+    pub fn length(self, length: u16) -> ControlSetup {
+        ControlSetup { length, ..self }
+    }
+
+    /// Direction of the request.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Packed `bmRequestType` byte.
+    #[doc(alias = "bmRequestType")]
+    pub fn request_type_byte(&self) -> u8 {
+        request_type(self.direction, self.control_type, self.recipient)
+    }
+
+    /// Number of bytes in the data stage, from the `wLength` field.
+    pub fn data_length(&self) -> u16 {
+        self.length
+    }
+
+    /// Packed 8-byte SETUP packet.
+    pub fn setup_packet(&self) -> [u8; SETUP_PACKET_SIZE] {
+        pack_setup(
+            self.direction,
+            self.control_type,
+            self.recipient,
+            self.request,
+            self.value,
+            self.index,
+            self.length,
+        )
+    }
+
+    /// Convert to a [`Control`] for the `control_in_blocking` and
+    /// `control_out_blocking` methods, which take the direction from the
+    /// method called.
+    pub fn control(&self) -> Control {
+        Control {
+            control_type: self.control_type,
+            recipient: self.recipient,
+            request: self.request,
+            value: self.value,
+            index: self.index,
+        }
+    }
+
+    /// Convert to a [`ControlIn`] reading up to `wLength` bytes.
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidInput`] if the direction is
+    /// not [`Direction::In`].
+    pub fn control_in(&self) -> Result<ControlIn, Error> {
+        self.check_direction(Direction::In)?;
+        Ok(ControlIn {
+            control_type: self.control_type,
+            recipient: self.recipient,
+            request: self.request,
+            value: self.value,
+            index: self.index,
+            length: self.length,
+        })
+    }
+
+    /// Convert to a [`ControlOut`] sending `data`.
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidInput`] if the direction is
+    /// not [`Direction::Out`], or if the length of `data` is not `wLength`.
+    pub fn control_out<'a>(&self, data: &'a [u8]) -> Result<ControlOut<'a>, Error> {
+        self.check_direction(Direction::Out)?;
+        self.check_length(data.len())?;
+        Ok(ControlOut {
+            control_type: self.control_type,
+            recipient: self.recipient,
+            request: self.request,
+            value: self.value,
+            index: self.index,
+            data,
+        })
+    }
+
+    /// Check that a buffer of `len` bytes matches `wLength`.
+    pub(crate) fn check_length(&self, len: usize) -> Result<(), Error> {
+        if len != usize::from(self.length) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "control transfer buffer is {len} bytes, but wLength is {}",
+                    self.length
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_direction(&self, expected: Direction) -> Result<(), Error> {
+        if self.direction != expected {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "control setup direction is {:?}, expected {expected:?}",
+                    self.direction
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
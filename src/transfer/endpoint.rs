@@ -0,0 +1,169 @@
+use log::warn;
+
+use super::{Direction, TransferError};
+
+/// Get the endpoint number, 0 to 15, from a `bEndpointAddress` value.
+pub fn endpoint_number(address: u8) -> u8 {
+    address & 0x0f
+}
+
+/// Get the direction from a `bEndpointAddress` value, encoded in its top
+/// (`0x80`) bit.
+pub fn endpoint_direction(address: u8) -> Direction {
+    match address & 0x80 {
+        0 => Direction::Out,
+        _ => Direction::In,
+    }
+}
+
+/// An endpoint address, combining an endpoint number and direction.
+///
+/// The transfer methods of [`Interface`][crate::Interface] take an
+/// `EndpointAddress` or a `u8` with the direction in its top (`0x80`) bit.
+/// Constructing it from a number and direction avoids mistakes with that
+/// bit. If the direction doesn't match the method, a transfer fails with
+/// [`TransferError::InvalidEndpoint`] and a queue constructor panics.
+///
+/// ```
+/// use nusb::transfer::{Direction, EndpointAddress};
+/// let ep = EndpointAddress::new_in(1);
+/// assert_eq!(u8::from(ep), 0x81);
+/// assert_eq!(ep.number(), 1);
+/// assert_eq!(ep.direction(), Direction::In);
+/// ```
+#[doc(alias = "bEndpointAddress")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct EndpointAddress(u8);
+
+impl EndpointAddress {
+    /// Construct an address from an endpoint number and direction.
+    ///
+    /// ### Panics
+    /// * If `number` is greater than 15.
+    pub const fn new(number: u8, direction: Direction) -> EndpointAddress {
+        assert!(number <= 0x0f, "endpoint number must be 0 to 15");
+        EndpointAddress(number | (direction as u8) << 7)
+    }
+
+    /// Construct the address of **IN (device-to-host)** endpoint `number`.
+    ///
+    /// ### Panics
+    /// * If `number` is greater than 15.
+    pub const fn new_in(number: u8) -> EndpointAddress {
+        EndpointAddress::new(number, Direction::In)
+    }
+
+    /// Construct the address of **OUT (host-to-device)** endpoint `number`.
+    ///
+    /// ### Panics
+    /// * If `number` is greater than 15.
+    pub const fn new_out(number: u8) -> EndpointAddress {
+        EndpointAddress::new(number, Direction::Out)
+    }
+
+    /// Endpoint number, 0 to 15.
+    pub fn number(&self) -> u8 {
+        endpoint_number(self.0)
+    }
+
+    /// Direction of the endpoint.
+    pub fn direction(&self) -> Direction {
+        endpoint_direction(self.0)
+    }
+
+    /// Address as passed to the transfer methods.
+    pub fn address(&self) -> u8 {
+        self.0
+    }
+}
+
+impl From<u8> for EndpointAddress {
+    fn from(address: u8) -> Self {
+        EndpointAddress(address)
+    }
+}
+
+impl From<EndpointAddress> for u8 {
+    fn from(address: EndpointAddress) -> Self {
+        address.0
+    }
+}
+
+impl std::fmt::Debug for EndpointAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EndpointAddress(0x{:02x})", self.0)
+    }
+}
+
+/// Describe why `endpoint` can't be used with the transfer method `method`,
+/// if it is not an address of the required `direction`.
+fn direction_mismatch(endpoint: u8, direction: Direction, method: &str) -> Option<String> {
+    let (expected, actual) = match direction {
+        Direction::In => ("IN", "an OUT"),
+        Direction::Out => ("OUT", "an IN"),
+    };
+    (endpoint_direction(endpoint) != direction).then(|| {
+        format!("`{method}` requires an {expected} endpoint address, but 0x{endpoint:02x} is {actual} endpoint")
+    })
+}
+
+/// Panic if `endpoint` is not an address of the direction required by the
+/// queue constructor `method`, and return it as a `u8`.
+#[track_caller]
+pub(crate) fn assert_direction(
+    endpoint: EndpointAddress,
+    direction: Direction,
+    method: &str,
+) -> u8 {
+    if let Some(msg) = direction_mismatch(endpoint.0, direction, method) {
+        panic!("{msg}");
+    }
+    endpoint.0
+}
+
+/// Check that `endpoint` is an address of the direction required by the
+/// transfer method `method`, and return it as a `u8`.
+///
+/// A mismatch is logged and returned as [`TransferError::InvalidEndpoint`]
+/// for the method to complete the transfer with.
+pub(crate) fn check_direction(
+    endpoint: EndpointAddress,
+    direction: Direction,
+    method: &str,
+) -> Result<u8, TransferError> {
+    match direction_mismatch(endpoint.0, direction, method) {
+        Some(msg) => {
+            warn!("{msg}");
+            Err(TransferError::InvalidEndpoint)
+        }
+        None => Ok(endpoint.0),
+    }
+}
+
+#[test]
+fn test_endpoint_address() {
+    assert_eq!(endpoint_number(0x83), 3);
+    assert_eq!(endpoint_direction(0x83), Direction::In);
+    assert_eq!(endpoint_direction(0x02), Direction::Out);
+    assert_eq!(EndpointAddress::new_out(2).address(), 0x02);
+    assert_eq!(EndpointAddress::new(15, Direction::In).address(), 0x8f);
+    assert_eq!(EndpointAddress::from(0x81), EndpointAddress::new_in(1));
+
+    assert_eq!(
+        assert_direction(0x81.into(), Direction::In, "bulk_in_queue"),
+        0x81
+    );
+    assert!(std::panic::catch_unwind(|| {
+        assert_direction(0x01.into(), Direction::In, "bulk_in_queue")
+    })
+    .is_err());
+
+    assert_eq!(
+        check_direction(0x02.into(), Direction::Out, "bulk_out"),
+        Ok(0x02)
+    );
+    assert_eq!(
+        check_direction(0x82.into(), Direction::Out, "bulk_out"),
+        Err(TransferError::InvalidEndpoint)
+    );
+}
@@ -8,12 +8,16 @@ use std::{
 
 use atomic_waker::AtomicWaker;
 
-use super::Completion;
+use super::{Completion, Priority};
 
 pub trait PlatformTransfer: Send {
     /// Request cancellation of a transfer that may or may not currently be
     /// pending.
     fn cancel(&self);
+
+    /// Set the priority of subsequent submissions. Ignored by platforms that
+    /// can't prioritize transfers.
+    fn set_priority(&mut self, _priority: Priority) {}
 }
 
 pub trait TransferRequest {
@@ -89,6 +93,23 @@ impl<P: PlatformTransfer> TransferHandle<P> {
         }
     }
 
+    /// Convert the platform data of a transfer that has never been
+    /// submitted, moving it to a new allocation.
+    #[cfg(feature = "mock")]
+    pub(crate) fn map<Q: PlatformTransfer>(self, f: impl FnOnce(P) -> Q) -> TransferHandle<Q> {
+        assert_eq!(
+            self.inner().state.load(Ordering::Relaxed),
+            STATE_IDLE,
+            "Transfer should be idle when converted"
+        );
+
+        // SAFETY: an idle transfer is not shared with the platform, and
+        // forgetting the handle gives this ownership of the allocation.
+        let inner = unsafe { Box::from_raw(self.ptr.as_ptr()) };
+        std::mem::forget(self);
+        TransferHandle::new(f(inner.platform_data.into_inner()))
+    }
+
     fn inner(&self) -> &TransferInner<P> {
         // SAFETY: while `TransferHandle` is alive, its `TransferInner` is alive
         // (it may be shared by `notify_completion` on the event thread, so can't be &mut)
@@ -121,6 +142,20 @@ impl<P: PlatformTransfer> TransferHandle<P> {
         }
     }
 
+    /// Set the priority of subsequent submissions of this transfer.
+    pub(crate) fn set_priority(&mut self, priority: Priority) {
+        let inner = self.inner();
+        assert_eq!(
+            inner.state.load(Ordering::Relaxed),
+            STATE_IDLE,
+            "Transfer should be idle when setting its priority"
+        );
+
+        // SAFETY: while `TransferHandle` is alive, the only mutable access to `platform_data`
+        // is via this `TransferHandle`. Verified that it is idle.
+        unsafe { (*inner.platform_data.get()).set_priority(priority) }
+    }
+
     pub(crate) fn cancel(&mut self) {
         self.platform_data().cancel();
     }
@@ -143,10 +178,7 @@ impl<P: PlatformTransfer> TransferHandle<P> {
         }
     }
 
-    pub fn poll_completion<D: TransferRequest>(
-        &mut self,
-        cx: &Context,
-    ) -> Poll<Completion<D::Response>>
+    pub fn poll_completion<D>(&mut self, cx: &Context) -> Poll<Completion<D::Response>>
     where
         D: TransferRequest,
         P: PlatformSubmit<D>,
@@ -159,11 +191,16 @@ impl<P: PlatformTransfer> TransferHandle<P> {
 
 impl<P: PlatformTransfer> Drop for TransferHandle<P> {
     fn drop(&mut self) {
+        // Cancel before marking the transfer abandoned: once it is abandoned,
+        // the completion handler may free it at any time, so it must not be
+        // accessed afterwards. If it completes in between, the swap below
+        // sees `STATE_COMPLETED` and frees it here instead.
+        if self.inner().state.load(Ordering::Acquire) == STATE_PENDING {
+            self.cancel();
+        }
+
         match self.inner().state.swap(STATE_ABANDONED, Ordering::Acquire) {
-            STATE_PENDING => {
-                self.cancel();
-                /* handler responsible for dropping */
-            }
+            STATE_PENDING => { /* handler responsible for dropping */ }
             STATE_IDLE | STATE_COMPLETED => {
                 // SAFETY: state means there is no concurrent access
                 unsafe { drop(Box::from_raw(self.ptr.as_ptr())) }
@@ -176,7 +213,10 @@ impl<P: PlatformTransfer> Drop for TransferHandle<P> {
 /// Notify that a transfer has completed.
 ///
 /// SAFETY: `transfer` must be a pointer previously passed to `submit`, and
-/// the caller / kernel must no longer dereference it or its buffer.
+/// the caller / kernel must no longer dereference it or its buffer. `P` must
+/// be the type of the `TransferHandle`, which for a backend is
+/// `crate::platform::TransferData` rather than its own type, because that
+/// wraps it when the `mock` backend is also enabled.
 pub(crate) unsafe fn notify_completion<P: PlatformTransfer>(transfer: *mut c_void) {
     unsafe {
         let transfer = transfer as *mut TransferInner<P>;
@@ -194,3 +234,68 @@ pub(crate) unsafe fn notify_completion<P: PlatformTransfer>(transfer: *mut c_voi
         }
     }
 }
+
+#[test]
+fn test_drop_pending_transfer() {
+    use std::sync::{
+        atomic::{AtomicBool, AtomicUsize},
+        Arc,
+    };
+
+    /// Transfer that the OS completes as soon as it is cancelled, as can
+    /// happen on the event thread while a `TransferHandle` is dropped.
+    struct Fake {
+        transfer: AtomicUsize,
+        freed: Arc<AtomicBool>,
+    }
+
+    impl PlatformTransfer for Fake {
+        fn cancel(&self) {
+            let freed = self.freed.clone();
+            let transfer = self.transfer.load(Ordering::SeqCst);
+            std::thread::spawn(move || unsafe {
+                notify_completion::<Fake>(transfer as *mut c_void)
+            })
+            .join()
+            .unwrap();
+            assert!(
+                !freed.load(Ordering::SeqCst),
+                "transfer freed while cancelling"
+            );
+        }
+    }
+
+    impl Drop for Fake {
+        fn drop(&mut self) {
+            self.freed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    struct Request;
+
+    impl TransferRequest for Request {
+        type Response = ();
+    }
+
+    impl PlatformSubmit<Request> for Fake {
+        unsafe fn submit(&mut self, _data: Request, transfer: *mut c_void) {
+            self.transfer.store(transfer as usize, Ordering::SeqCst);
+        }
+
+        unsafe fn take_completed(&mut self) -> Completion<()> {
+            Completion {
+                data: (),
+                status: Ok(()),
+            }
+        }
+    }
+
+    let freed = Arc::new(AtomicBool::new(false));
+    let mut handle = TransferHandle::new(Fake {
+        transfer: AtomicUsize::new(0),
+        freed: freed.clone(),
+    });
+    handle.submit(Request);
+    drop(handle);
+    assert!(freed.load(Ordering::SeqCst));
+}
@@ -0,0 +1,191 @@
+use std::fmt::Debug;
+
+use super::{TransferError, TransferRequest};
+
+/// A buffer for requesting an isochronous IN transfer.
+///
+/// An isochronous transfer is made up of a number of packets, each of which
+/// is scheduled in its own (micro)frame. The buffer is divided into
+/// `num_packets` slots of `packet_size` bytes, and each packet is received
+/// into its own slot. Packets may be shorter than `packet_size`, in which
+/// case the rest of the slot is left zeroed.
+///
+/// The completion contains an [`IsoResponse`] describing each packet.
+#[derive(Debug)]
+#[allow(dead_code)] // only submitted on Linux and by the mock backend
+pub struct IsoRequestBuffer {
+    pub(crate) buf: Vec<u8>,
+    pub(crate) num_packets: usize,
+    pub(crate) packet_size: usize,
+}
+
+impl IsoRequestBuffer {
+    /// Create an `IsoRequestBuffer` for `num_packets` packets of up to
+    /// `packet_size` bytes each.
+    pub fn new(num_packets: usize, packet_size: usize) -> IsoRequestBuffer {
+        IsoRequestBuffer::reuse(Vec::new(), num_packets, packet_size)
+    }
+
+    /// Create an `IsoRequestBuffer` by re-using the allocation of a `Vec`.
+    pub fn reuse(mut v: Vec<u8>, num_packets: usize, packet_size: usize) -> IsoRequestBuffer {
+        let len = num_packets
+            .checked_mul(packet_size)
+            .expect("isochronous buffer size should not overflow");
+        v.clear();
+        v.resize(len, 0);
+        IsoRequestBuffer {
+            buf: v,
+            num_packets,
+            packet_size,
+        }
+    }
+}
+
+impl TransferRequest for IsoRequestBuffer {
+    type Response = IsoResponse;
+}
+
+/// Data for an isochronous OUT transfer.
+///
+/// The data is split into packets of `packet_size` bytes, each of which is
+/// sent in its own (micro)frame. The final packet may be shorter.
+///
+/// The completion contains an [`IsoResponse`] describing each packet, with the
+/// original buffer to re-use.
+#[derive(Debug)]
+pub struct IsoOutBuffer {
+    pub(crate) buf: Vec<u8>,
+    pub(crate) packet_size: usize,
+}
+
+impl IsoOutBuffer {
+    /// Create an `IsoOutBuffer` to send `data` in packets of `packet_size`
+    /// bytes.
+    ///
+    /// ### Panics
+    /// * if `packet_size` is zero.
+    pub fn new(data: Vec<u8>, packet_size: usize) -> IsoOutBuffer {
+        assert!(packet_size > 0, "packet size must be nonzero");
+        IsoOutBuffer {
+            buf: data,
+            packet_size,
+        }
+    }
+
+    /// Number of packets that will be sent.
+    pub fn num_packets(&self) -> usize {
+        self.buf.len().div_ceil(self.packet_size)
+    }
+}
+
+impl TransferRequest for IsoOutBuffer {
+    type Response = IsoResponse;
+}
+
+/// Result of an individual packet within an isochronous transfer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IsoPacket {
+    pub(crate) offset: usize,
+    pub(crate) requested_length: usize,
+    pub(crate) actual_length: usize,
+    pub(crate) status: Result<(), TransferError>,
+}
+
+impl IsoPacket {
+    /// Offset of this packet within the transfer buffer.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Number of bytes requested for this packet.
+    pub fn requested_length(&self) -> usize {
+        self.requested_length
+    }
+
+    /// Number of bytes actually transferred for this packet.
+    pub fn actual_length(&self) -> usize {
+        self.actual_length
+    }
+
+    /// Indicates successful completion or error for this packet.
+    pub fn status(&self) -> Result<(), TransferError> {
+        self.status
+    }
+}
+
+/// Data and per-packet results returned on completion of an isochronous
+/// transfer.
+///
+/// The [`Completion`][super::Completion] status of an isochronous transfer
+/// only reflects whether the transfer as a whole was scheduled. Individual
+/// packets can fail independently, so check [`IsoPacket::status`] for each
+/// packet.
+#[derive(Debug, Clone)]
+pub struct IsoResponse {
+    pub(crate) buf: Vec<u8>,
+    pub(crate) packets: Vec<IsoPacket>,
+}
+
+impl IsoResponse {
+    /// Per-packet results, in the order the packets were scheduled.
+    pub fn packets(&self) -> &[IsoPacket] {
+        &self.packets
+    }
+
+    /// Data transferred in `packet`.
+    pub fn packet_data(&self, packet: &IsoPacket) -> &[u8] {
+        &self.buf[packet.offset..packet.offset + packet.actual_length]
+    }
+
+    /// Iterate over the data of packets that completed successfully.
+    pub fn successful_packets(&self) -> impl Iterator<Item = &[u8]> {
+        self.packets
+            .iter()
+            .filter(|p| p.status.is_ok())
+            .map(|p| self.packet_data(p))
+    }
+
+    /// Number of packets that completed with an error.
+    pub fn error_count(&self) -> usize {
+        self.packets.iter().filter(|p| p.status.is_err()).count()
+    }
+
+    /// Extract the buffer to re-use in another transfer.
+    pub fn reuse(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+#[test]
+fn test_iso_response() {
+    let response = IsoResponse {
+        buf: vec![1, 2, 0, 0, 3, 4, 5, 0, 0, 0, 0, 0],
+        packets: vec![
+            IsoPacket {
+                offset: 0,
+                requested_length: 4,
+                actual_length: 2,
+                status: Ok(()),
+            },
+            IsoPacket {
+                offset: 4,
+                requested_length: 4,
+                actual_length: 3,
+                status: Ok(()),
+            },
+            IsoPacket {
+                offset: 8,
+                requested_length: 4,
+                actual_length: 0,
+                status: Err(TransferError::Fault),
+            },
+        ],
+    };
+
+    assert_eq!(response.error_count(), 1);
+    assert_eq!(
+        response.successful_packets().collect::<Vec<_>>(),
+        [&[1, 2][..], &[3, 4, 5][..]]
+    );
+    assert_eq!(IsoOutBuffer::new(vec![0; 10], 4).num_packets(), 3);
+}
@@ -2,6 +2,23 @@
 //!
 //! Use the methods on an [`Interface`][`super::Interface`] to make individual
 //! transfers or obtain a [`Queue`] to manage multiple transfers.
+//!
+//! ## Ordering and priority
+//!
+//! Transfers on the same endpoint complete in the order they were submitted.
+//! Each endpoint has its own queue, so a long bulk transfer never delays a
+//! transfer on another endpoint in software. The host controller then
+//! schedules the bus: interrupt and isochronous endpoints are serviced at
+//! their reserved intervals before any bulk traffic in each (micro)frame,
+//! so interrupt reports keep their latency while bulk endpoints on the same
+//! device are streaming.
+//!
+//! Submitting a transfer with [`Queue::submit_with_priority`] and
+//! [`Priority::High`] only affects the order in which `nusb` hands transfers
+//! to the OS, not how the host controller schedules them. To keep command
+//! and response latency low alongside bulk streaming, also put the commands
+//! on an interrupt endpoint, or use a separate bulk endpoint with only a few
+//! transfers pending so they are not queued behind bulk data.
 
 use std::{
     fmt::Display,
@@ -9,20 +26,42 @@ use std::{
     io,
     marker::PhantomData,
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
 
 use crate::platform;
 
 mod queue;
-pub use queue::Queue;
+pub use queue::{Queue, TransferId};
+
+mod callback;
+pub use callback::CallbackQueue;
 
 mod buffer;
-pub use buffer::{RequestBuffer, ResponseBuffer};
+pub(crate) use buffer::{drop_transfer_buffer_parts, drop_vec_parts};
+pub use buffer::{
+    BufferPool, CallerBuffer, FilledBuffer, RequestBuffer, ResponseBuffer, TransferBuffer,
+};
 
 mod control;
 #[allow(unused)]
 pub(crate) use control::SETUP_PACKET_SIZE;
-pub use control::{Control, ControlIn, ControlOut, ControlType, Direction, Recipient};
+pub use control::{
+    Control, ControlIn, ControlOut, ControlSetup, ControlType, Direction, Recipient,
+};
+
+mod endpoint;
+pub(crate) use endpoint::{assert_direction, check_direction};
+pub use endpoint::{endpoint_direction, endpoint_number, EndpointAddress};
+
+mod iso;
+pub use iso::{IsoOutBuffer, IsoPacket, IsoRequestBuffer, IsoResponse};
+
+mod stream;
+pub use stream::InterruptStream;
+
+mod timer;
+use timer::Timer;
 
 mod internal;
 pub(crate) use internal::{
@@ -46,8 +85,34 @@ pub enum EndpointType {
     Interrupt = 3,
 }
 
+/// Submission priority of a transfer, set with
+/// [`Queue::submit_with_priority`].
+///
+/// This is a hint that only influences submission order where the platform
+/// allows it.
+///
+/// ### Platform-specific notes
+/// * On Linux, a `High` priority transfer is submitted ahead of the URBs
+///   that other threads have yet to submit for `Normal` priority transfers
+///   on the same device. This matters when a large bulk transfer is split
+///   into several URBs, which are submitted one at a time.
+/// * On Windows and macOS, this has no effect.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Priority {
+    /// The default priority.
+    #[default]
+    Normal,
+
+    /// Submitted ahead of `Normal` priority transfers.
+    High,
+}
+
 /// Transfer error.
+///
+/// New variants may be added in future releases, so matches on this type
+/// need a wildcard arm.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum TransferError {
     /// Transfer was cancelled.
     Cancelled,
@@ -62,11 +127,43 @@ pub enum TransferError {
     Stall,
 
     /// Device disconnected.
+    ///
+    /// When a device is unplugged, all of its pending transfers complete
+    /// with this error, as do any transfers submitted afterwards.
     Disconnected,
 
+    /// Transfer did not complete before its timeout and was cancelled.
+    ///
+    /// This is also used when the OS or host controller times out a
+    /// transfer. Before version 0.2, that was reported as
+    /// [`TransferError::Cancelled`] on Linux and Windows and
+    /// [`TransferError::Unknown`] on macOS.
+    TimedOut,
+
+    /// Device sent more data than was requested, or more than the maximum
+    /// packet size in a packet (babble).
+    ///
+    /// For bulk and interrupt IN transfers, this usually means the length
+    /// requested was not a multiple of the endpoint's maximum packet size.
+    ///
+    /// Before version 0.2, this was reported as [`TransferError::Fault`] on
+    /// Linux and [`TransferError::Unknown`] on macOS.
+    ///
+    /// ### Platform-specific notes
+    /// * On Windows, WinUSB does not distinguish this from other errors, and
+    ///   it is reported as [`TransferError::Unknown`].
+    Overflow,
+
     /// Hardware issue or protocol violation.
     Fault,
 
+    /// The endpoint address passed to a transfer method has the wrong
+    /// direction for the method, e.g. an OUT endpoint for
+    /// [`Interface::bulk_in`][crate::Interface::bulk_in].
+    ///
+    /// The transfer was not submitted.
+    InvalidEndpoint,
+
     /// Unknown or OS-specific error.
     Unknown,
 }
@@ -77,7 +174,15 @@ impl Display for TransferError {
             TransferError::Cancelled => write!(f, "transfer was cancelled"),
             TransferError::Stall => write!(f, "endpoint STALL condition"),
             TransferError::Disconnected => write!(f, "device disconnected"),
+            TransferError::TimedOut => write!(f, "transfer timed out"),
+            TransferError::Overflow => write!(f, "device sent more data than requested"),
             TransferError::Fault => write!(f, "hardware fault or protocol violation"),
+            TransferError::InvalidEndpoint => {
+                write!(
+                    f,
+                    "endpoint address has the wrong direction for the transfer"
+                )
+            }
             TransferError::Unknown => write!(f, "unknown error"),
         }
     }
@@ -91,8 +196,11 @@ impl From<TransferError> for io::Error {
             TransferError::Cancelled => io::Error::new(io::ErrorKind::Interrupted, value),
             TransferError::Stall => io::Error::new(io::ErrorKind::ConnectionReset, value),
             TransferError::Disconnected => io::Error::new(io::ErrorKind::ConnectionAborted, value),
-            TransferError::Fault => io::Error::new(io::ErrorKind::Other, value),
-            TransferError::Unknown => io::Error::new(io::ErrorKind::Other, value),
+            TransferError::TimedOut => io::Error::new(io::ErrorKind::TimedOut, value),
+            TransferError::Overflow => io::Error::new(io::ErrorKind::InvalidData, value),
+            TransferError::Fault => io::Error::other(value),
+            TransferError::InvalidEndpoint => io::Error::new(io::ErrorKind::InvalidInput, value),
+            TransferError::Unknown => io::Error::other(value),
         }
     }
 }
@@ -122,6 +230,79 @@ impl<T> Completion<T> {
     }
 }
 
+/// Report an error from a transfer on a device that has been disconnected as
+/// [`TransferError::Disconnected`].
+///
+/// When a device is unplugged, pending transfers may complete with a
+/// cancellation or generic error depending on the platform, so this checks
+/// `is_disconnected` on any error other than `Disconnected`.
+#[allow(dead_code)] // not used on all platforms
+pub(crate) fn check_disconnected(
+    status: Result<(), TransferError>,
+    is_disconnected: impl FnOnce() -> bool,
+) -> Result<(), TransferError> {
+    match status {
+        Err(e) if e != TransferError::Disconnected && is_disconnected() => {
+            Err(TransferError::Disconnected)
+        }
+        status => status,
+    }
+}
+
+impl Completion<Vec<u8>> {
+    /// Classify how an IN transfer of `requested_len` bytes ended.
+    ///
+    /// A bulk or interrupt IN transfer completes successfully when the
+    /// device sends a packet shorter than the maximum packet size, so a
+    /// successful transfer returning fewer bytes than requested is not an
+    /// error. This distinguishes that case from a transfer that filled the
+    /// whole buffer and from the errors that protocol code usually needs to
+    /// handle specifically.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use nusb::transfer::{Completion, TransferStatus};
+    /// let c = Completion { data: vec![0; 10], status: Ok(()) };
+    /// assert_eq!(c.transfer_status(64), TransferStatus::ShortPacket { len: 10 });
+    /// ```
+    pub fn transfer_status(&self, requested_len: usize) -> TransferStatus {
+        match self.status {
+            Ok(()) if self.data.len() < requested_len => TransferStatus::ShortPacket {
+                len: self.data.len(),
+            },
+            Ok(()) => TransferStatus::Completed,
+            Err(TransferError::Overflow) => TransferStatus::Overflow,
+            Err(TransferError::Stall) => TransferStatus::Stall,
+            Err(e) => TransferStatus::Error(e),
+        }
+    }
+}
+
+/// How a transfer ended, returned by [`Completion::transfer_status`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TransferStatus {
+    /// All of the requested data was transferred.
+    Completed,
+
+    /// The transfer completed successfully when the device sent a short
+    /// packet, with `len` bytes transferred in total.
+    ShortPacket {
+        /// Number of bytes transferred.
+        len: usize,
+    },
+
+    /// The device sent more data than requested. See [`TransferError::Overflow`].
+    Overflow,
+
+    /// The endpoint is halted. See [`TransferError::Stall`].
+    Stall,
+
+    /// The transfer failed with another error.
+    Error(TransferError),
+}
+
 impl TryFrom<Completion<Vec<u8>>> for Vec<u8> {
     type Error = TransferError;
 
@@ -144,25 +325,71 @@ impl TryFrom<Completion<ResponseBuffer>> for ResponseBuffer {
 /// submit an individual transfer and obtain a `TransferFuture`.
 ///
 /// The transfer is cancelled on drop. The buffer and
-/// any partially-completed data are destroyed. This means
+/// any partially-completed data are destroyed once the OS reports that the
+/// cancelled transfer has completed, so dropping a pending `TransferFuture`
+/// is always memory-safe, but this means
 /// that `TransferFuture` is not [cancel-safe] and cannot be used
 /// in `select!{}`, When racing a `TransferFuture` with a timeout
 /// you cannot tell whether data may have been partially transferred on timeout.
 /// Use the [`Queue`] interface if these matter for your application.
 ///
+/// Transfers submitted with a timeout, such as
+/// [`Interface::control_in_timeout`][super::Interface::control_in_timeout],
+/// are instead cancelled when the deadline passes while the future is being
+/// polled. The future then resolves normally with any partial data and a
+/// status of [`TransferError::TimedOut`].
+///
 /// [cancel-safe]: https://docs.rs/tokio/latest/tokio/macro.select.html#cancellation-safety
 pub struct TransferFuture<D: TransferRequest> {
-    transfer: TransferHandle<platform::TransferData>,
+    /// `None` if the transfer was rejected without being submitted.
+    transfer: Option<TransferHandle<platform::TransferData>>,
+
+    /// Completion of a rejected transfer, returned by the first poll.
+    rejected: Option<Completion<D::Response>>,
+
+    deadline: Option<Deadline>,
     ty: PhantomData<D::Response>,
 }
 
+struct Deadline {
+    timer: Timer,
+
+    /// Whether the deadline passed and the transfer has been cancelled.
+    expired: bool,
+}
+
 impl<D: TransferRequest> TransferFuture<D> {
     pub(crate) fn new(transfer: TransferHandle<platform::TransferData>) -> TransferFuture<D> {
         TransferFuture {
-            transfer,
+            transfer: Some(transfer),
+            rejected: None,
+            deadline: None,
+            ty: PhantomData,
+        }
+    }
+
+    /// Create a future that completes immediately with `status`, returning
+    /// `data` from a transfer that was never submitted.
+    pub(crate) fn rejected(data: D::Response, status: TransferError) -> TransferFuture<D> {
+        TransferFuture {
+            transfer: None,
+            rejected: Some(Completion {
+                data,
+                status: Err(status),
+            }),
+            deadline: None,
             ty: PhantomData,
         }
     }
+
+    /// Cancel the transfer if it has not completed within `timeout` from now.
+    pub(crate) fn with_timeout(mut self, timeout: Duration) -> TransferFuture<D> {
+        self.deadline = Some(Deadline {
+            timer: Timer::new(Instant::now() + timeout),
+            expired: false,
+        });
+        self
+    }
 }
 
 impl<D: TransferRequest> Future for TransferFuture<D>
@@ -173,6 +400,83 @@ where
     type Output = Completion<D::Response>;
 
     fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        self.as_mut().transfer.poll_completion::<D>(cx)
+        let this = &mut *self;
+        let res = match &mut this.transfer {
+            Some(transfer) => transfer.poll_completion::<D>(cx),
+            None => Poll::Ready(
+                this.rejected
+                    .take()
+                    .expect("TransferFuture polled after completion"),
+            ),
+        };
+        match res {
+            Poll::Ready(mut completion) => {
+                if let Some(deadline) = &mut this.deadline {
+                    deadline.timer.cancel();
+                    if deadline.expired && completion.status == Err(TransferError::Cancelled) {
+                        completion.status = Err(TransferError::TimedOut);
+                    }
+                }
+                Poll::Ready(completion)
+            }
+            Poll::Pending => {
+                if let Some(deadline) = this.deadline.as_mut().filter(|d| !d.expired) {
+                    if deadline.timer.poll(cx).is_ready() {
+                        // The platform reports completion with the buffer once
+                        // the cancellation takes effect, and we'll be woken then.
+                        deadline.expired = true;
+                        if let Some(transfer) = &mut this.transfer {
+                            transfer.cancel();
+                        }
+                    }
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[test]
+fn test_transfer_status() {
+    let c = |len, status| Completion {
+        data: vec![0; len],
+        status,
+    };
+    assert_eq!(c(64, Ok(())).transfer_status(64), TransferStatus::Completed);
+    assert_eq!(
+        c(3, Ok(())).transfer_status(64),
+        TransferStatus::ShortPacket { len: 3 }
+    );
+    assert_eq!(
+        c(0, Err(TransferError::Overflow)).transfer_status(64),
+        TransferStatus::Overflow
+    );
+    assert_eq!(
+        c(0, Err(TransferError::Disconnected)).transfer_status(64),
+        TransferStatus::Error(TransferError::Disconnected)
+    );
+}
+
+#[test]
+fn test_check_disconnected() {
+    // Pending transfers are completed in whatever way the platform reports
+    // when the device is removed.
+    let pending = [
+        Err(TransferError::Cancelled),
+        Err(TransferError::Unknown),
+        Err(TransferError::Stall),
+        Err(TransferError::Disconnected),
+    ];
+    for status in pending {
+        assert_eq!(
+            check_disconnected(status, || true),
+            Err(TransferError::Disconnected)
+        );
     }
+
+    assert_eq!(check_disconnected(Ok(()), || true), Ok(()));
+    assert_eq!(
+        check_disconnected(Err(TransferError::Cancelled), || false),
+        Err(TransferError::Cancelled)
+    );
 }
@@ -0,0 +1,174 @@
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures_core::Stream;
+
+use super::{timer::Timer, Queue, RequestBuffer, TransferError};
+
+/// Default number of transfers kept pending by an [`InterruptStream`].
+const INTERRUPT_STREAM_TRANSFERS: usize = 4;
+
+/// A stream of reports from an interrupt IN endpoint.
+///
+/// Use [`Interface::interrupt_in_stream`][crate::Interface::interrupt_in_stream]
+/// to obtain an `InterruptStream`.
+///
+/// The stream keeps several transfers pending so the host controller polls
+/// the endpoint in every interval, and re-submits a transfer each time one
+/// completes successfully. On the first error, the remaining transfers are
+/// cancelled and nothing is re-submitted: the error is returned from the
+/// stream, followed by any data that was already received, and then the
+/// stream ends. After a [`TransferError::Stall`], call
+/// [`Interface::clear_halt`][crate::Interface::clear_halt] and create a new
+/// stream to resume.
+///
+/// When the `InterruptStream` is dropped, all pending transfers are
+/// cancelled.
+///
+/// ### Timeout
+///
+/// Devices such as HID keyboards only send reports on user input, so the
+/// stream can go a long time without yielding anything. To make periodic
+/// checks while waiting, set a timeout with
+/// [`with_timeout`][Self::with_timeout]. If no report arrives within the
+/// timeout, the stream yields [`TransferError::TimedOut`], but unlike other
+/// errors this does not cancel the pending transfers, and the stream can
+/// continue to be polled for the next report. If the device was unplugged,
+/// the stream instead yields [`TransferError::Disconnected`] or another
+/// error and then ends.
+///
+/// ### Queue depth
+///
+/// By default, 4 transfers are kept pending. A deeper queue, set with
+/// [`with_depth`][Self::with_depth], gives the device more buffers to
+/// complete while the stream is not being polled, so reports are not
+/// dropped or NAKed during longer delays between polls. The cost is memory
+/// for the extra buffers and more reports lost if the stream is dropped.
+///
+/// On Linux, the event thread reaps all completed transfers at once, with
+/// one `USBDEVFS_REAPURBNDELAY` call for each and one more that finds none.
+/// A depth of 1 always takes 2 calls per report. With a depth of 16, if all
+/// 16 complete before the event thread runs, that falls to 1.06 calls per
+/// report, counted with the real reap loop in a unit test. When the event
+/// thread keeps up with the device, each wakeup reaps one transfer at any
+/// depth, so the reduction only applies to bursts of completions.
+pub struct InterruptStream {
+    queue: Queue<RequestBuffer>,
+    buffer_size: usize,
+    depth: usize,
+    failed: bool,
+    timeout: Option<Duration>,
+
+    /// End of the current timeout window, started on the first poll after
+    /// the previous item.
+    deadline: Option<Timer>,
+}
+
+impl InterruptStream {
+    pub(crate) fn new(queue: Queue<RequestBuffer>, buffer_size: usize) -> InterruptStream {
+        InterruptStream {
+            queue,
+            buffer_size,
+            depth: 0,
+            failed: false,
+            timeout: None,
+            deadline: None,
+        }
+        .with_depth(INTERRUPT_STREAM_TRANSFERS)
+    }
+
+    /// Set the number of transfers kept pending.
+    ///
+    /// Additional transfers are submitted immediately. When reducing the
+    /// depth, transfers already pending are allowed to complete and are not
+    /// re-submitted until fewer than `depth` remain. A `depth` of zero is
+    /// treated as 1. See [Queue depth](#queue-depth) for the tradeoff.
+    pub fn with_depth(mut self, depth: usize) -> InterruptStream {
+        self.depth = depth.max(1);
+        if !self.failed {
+            while self.queue.pending() < self.depth {
+                self.queue.submit(RequestBuffer::new(self.buffer_size));
+            }
+        }
+        self
+    }
+
+    /// Get the number of transfers kept pending.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Yield [`TransferError::TimedOut`] if no report arrives within
+    /// `timeout`, without ending the stream.
+    ///
+    /// The timeout restarts each time the stream is polled after yielding an
+    /// item, including a timeout. See [Timeout](#timeout).
+    pub fn with_timeout(mut self, timeout: Duration) -> InterruptStream {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Get the timeout set with [`with_timeout`][Self::with_timeout].
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn poll_timeout(&mut self, cx: &mut Context<'_>) -> Poll<TransferError> {
+        let Some(timeout) = self.timeout.filter(|_| !self.failed) else {
+            return Poll::Pending;
+        };
+        let deadline = self
+            .deadline
+            .get_or_insert_with(|| Timer::new(Instant::now() + timeout));
+        ready!(deadline.poll(cx));
+        self.deadline = None;
+        Poll::Ready(TransferError::TimedOut)
+    }
+}
+
+impl Stream for InterruptStream {
+    type Item = Result<Vec<u8>, TransferError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.queue.pending() == 0 {
+                return Poll::Ready(None);
+            }
+
+            let completion = match this.queue.poll_next(cx) {
+                Poll::Ready(completion) => completion,
+                Poll::Pending => {
+                    return this.poll_timeout(cx).map(|e| Some(Err(e)));
+                }
+            };
+            this.deadline = None;
+
+            match completion.status {
+                Ok(()) if !this.failed => {
+                    if this.queue.pending() < this.depth {
+                        this.queue.submit(RequestBuffer::new(this.buffer_size));
+                    }
+                }
+                Ok(()) => {}
+                // Transfers cancelled after the first error are not reported.
+                Err(TransferError::Cancelled) if this.failed => continue,
+                Err(e) => {
+                    if !this.failed {
+                        // Other pending transfers would likely fail the same
+                        // way, so don't wait for each of them.
+                        this.failed = true;
+                        this.queue.cancel_all();
+                    }
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+
+            return Poll::Ready(Some(Ok(completion.data)));
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Timer thread for transfer timeouts.
+//!
+//! The platform event loops only wake futures when a transfer completes, so
+//! a future with a deadline holds a [`Timer`] that registers its waker here
+//! to be polled again once the deadline passes. The thread is started when
+//! the first timer is registered and sleeps on a condvar until the earliest
+//! deadline.
+use std::{
+    collections::BTreeMap,
+    sync::{Condvar, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+    time::Instant,
+};
+
+static TIMERS: Mutex<Timers> = Mutex::new(Timers {
+    entries: BTreeMap::new(),
+    next_id: 0,
+    thread_started: false,
+});
+static TIMERS_CHANGED: Condvar = Condvar::new();
+
+struct Timers {
+    entries: BTreeMap<u64, (Instant, Waker)>,
+    next_id: u64,
+    thread_started: bool,
+}
+
+/// A deadline that wakes the task polling it once it passes.
+///
+/// The waker is registered with the timer thread on the first poll, and the
+/// registration is removed when the deadline passes, the timer is
+/// cancelled, or the `Timer` is dropped.
+pub(crate) struct Timer {
+    deadline: Instant,
+
+    /// Key of the entry in `TIMERS` and the waker stored there, if registered.
+    registered: Option<(u64, Waker)>,
+}
+
+impl Timer {
+    pub(crate) fn new(deadline: Instant) -> Timer {
+        Timer {
+            deadline,
+            registered: None,
+        }
+    }
+
+    /// Check whether the deadline has passed, or else arrange for the task
+    /// to be woken when it does.
+    pub(crate) fn poll(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            self.cancel();
+            return Poll::Ready(());
+        }
+
+        if self
+            .registered
+            .as_ref()
+            .is_some_and(|(_, w)| w.will_wake(cx.waker()))
+        {
+            return Poll::Pending;
+        }
+
+        let mut timers = TIMERS.lock().unwrap();
+        let id = match &self.registered {
+            Some((id, _)) => *id,
+            None => {
+                let id = timers.next_id;
+                timers.next_id += 1;
+                id
+            }
+        };
+        // Replaces the entry with the previous waker, if it hasn't fired yet.
+        timers
+            .entries
+            .insert(id, (self.deadline, cx.waker().clone()));
+        self.registered = Some((id, cx.waker().clone()));
+
+        if !timers.thread_started {
+            timers.thread_started = true;
+            thread::spawn(timer_loop);
+        }
+        drop(timers);
+
+        TIMERS_CHANGED.notify_one();
+        Poll::Pending
+    }
+
+    /// Remove the registration with the timer thread, if any.
+    pub(crate) fn cancel(&mut self) {
+        if let Some((id, _)) = self.registered.take() {
+            TIMERS.lock().unwrap().entries.remove(&id);
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+fn timer_loop() {
+    let mut timers = TIMERS.lock().unwrap();
+    loop {
+        let now = Instant::now();
+
+        let mut expired = Vec::new();
+        timers.entries.retain(|_, (deadline, waker)| {
+            if *deadline <= now {
+                expired.push(waker.clone());
+                false
+            } else {
+                true
+            }
+        });
+
+        if !expired.is_empty() {
+            // Wake without holding the lock, as a waker may poll its future
+            // on this thread and re-register.
+            drop(timers);
+            expired.into_iter().for_each(Waker::wake);
+            timers = TIMERS.lock().unwrap();
+            continue;
+        }
+
+        timers = match timers.entries.values().map(|(d, _)| *d).min() {
+            Some(next) => {
+                TIMERS_CHANGED
+                    .wait_timeout(timers, next.saturating_duration_since(now))
+                    .unwrap()
+                    .0
+            }
+            None => TIMERS_CHANGED.wait(timers).unwrap(),
+        };
+    }
+}
+
+#[test]
+fn test_timer() {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        task::Wake,
+        time::Duration,
+    };
+
+    struct Flag(AtomicBool);
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let flag = Arc::new(Flag(AtomicBool::new(false)));
+    let waker = Waker::from(flag.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    // Dropping a pending timer removes its entry.
+    let mut timer = Timer::new(Instant::now() + Duration::from_secs(60));
+    assert!(timer.poll(&mut cx).is_pending());
+    assert!(timer.poll(&mut cx).is_pending());
+    let (id, _) = *timer.registered.as_ref().unwrap();
+    assert!(TIMERS.lock().unwrap().entries.contains_key(&id));
+    drop(timer);
+    assert!(!TIMERS.lock().unwrap().entries.contains_key(&id));
+
+    // An expired timer wakes the task and is then ready.
+    let mut timer = Timer::new(Instant::now() + Duration::from_millis(10));
+    assert!(timer.poll(&mut cx).is_pending());
+    let start = Instant::now();
+    while !flag.0.load(Ordering::SeqCst) {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "timer didn't fire"
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert!(timer.poll(&mut cx).is_ready());
+    assert!(timer.registered.is_none());
+}